@@ -1,3 +1,9 @@
+use std::convert::TryFrom;
+
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::exprs::RulesFile;
 use crate::rules::values::CmpOperator;
 use crate::rules::{path_value::PathAwareValue, EvaluationContext, EvaluationType, Result, Status};
 
@@ -25,3 +31,13 @@ impl EvaluationContext for DummyEval {
 
     fn start_evaluation(&self, _eval_type: EvaluationType, _context: &str) {}
 }
+
+///
+/// The status of evaluating the rules against JSON or YAML data
+///
+pub(crate) fn status(rules: &str, data: &str) -> Result<Status> {
+    let rules = RulesFile::try_from(rules)?;
+    let root = get_path_aware_value_from_data(&data.to_string())?;
+    let mut scope = root_scope(&rules, &root)?;
+    eval_rules_file(&rules, &mut scope)
+}
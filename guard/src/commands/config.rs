@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use clap::App;
+use serde::Deserialize;
+
+use crate::commands::files::read_file_content;
+use crate::commands::{
+    BASELINE, CONFIG, COVERAGE, DATA, DIRECTORY, INPUT_PARAMETERS, RULES, RULES_FILE, TEST,
+    TEST_DATA, VALIDATE, WRITE_BASELINE,
+};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::Result;
+
+pub(crate) const CONFIG_FILE_NAMES: [&str; 2] = ["guard.toml", ".guardrc"];
+
+//
+// Arguments whose values are paths, these are resolved relative to the directory containing
+// the configuration file
+//
+const PATH_ARGS: [&str; 9] = [
+    RULES.0,
+    DATA.0,
+    INPUT_PARAMETERS.0,
+    BASELINE.0,
+    WRITE_BASELINE.0,
+    RULES_FILE.0,
+    TEST_DATA.0,
+    DIRECTORY.0,
+    COVERAGE.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ConfigValue {
+    Flag(bool),
+    Number(i64),
+    Value(String),
+    Values(Vec<String>),
+}
+
+///
+/// Project configuration, `guard.toml` in TOML or `.guardrc` in YAML/JSON. Each section is
+/// named after the command and maps long argument names to their default values, e.g.
+///
+/// ```toml
+/// [validate]
+/// rules = ["rules/"]
+/// data = "templates/"
+/// show-summary = ["fail", "skip"]
+/// verbose = true
+/// ```
+///
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProjectConfig {
+    #[serde(default)]
+    pub(crate) validate: BTreeMap<String, ConfigValue>,
+    #[serde(default)]
+    pub(crate) test: BTreeMap<String, ConfigValue>,
+}
+
+impl ProjectConfig {
+    pub(crate) fn load(path: &Path) -> Result<ProjectConfig> {
+        let content = read_file_content(File::open(path)?)?;
+        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+        if is_toml {
+            toml::from_str(&content).map_err(|e| {
+                Error::new(ErrorKind::ParseError(format!(
+                    "Unable to process config file {}, Error {}",
+                    path.display(),
+                    e
+                )))
+            })
+        } else {
+            Ok(serde_yaml::from_str(&content)?)
+        }
+    }
+}
+
+///
+/// Looks for a project configuration file starting at `start` and moving up through its
+/// parent directories. The first directory containing one of [`CONFIG_FILE_NAMES`] wins.
+///
+pub(crate) fn discover(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find_map(|dir| {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+///
+/// Adds defaults from the project configuration to the command line arguments for `validate`
+/// and `test`. Arguments already present on the command line take precedence. The resulting
+/// arguments are still validated by the command's argument parser.
+///
+pub fn apply_project_config(app: &App<'_, '_>, args: Vec<String>) -> Result<Vec<String>> {
+    let cwd = std::env::current_dir()?;
+    apply_project_config_from(app, args, &cwd)
+}
+
+pub(crate) fn apply_project_config_from(
+    app: &App<'_, '_>,
+    args: Vec<String>,
+    cwd: &Path,
+) -> Result<Vec<String>> {
+    let command = match args.get(1).map(String::as_str) {
+        Some(command @ (VALIDATE | TEST)) => command,
+        _ => return Ok(args),
+    };
+    let subcommand = match app
+        .p
+        .subcommands
+        .iter()
+        .find(|each| each.get_name() == command)
+    {
+        Some(subcommand) => subcommand,
+        None => return Ok(args),
+    };
+    //
+    // Arguments that are required may come from the configuration, those requirements are
+    // checked when the arguments are parsed again with the defaults. Any other error is left
+    // for that parse to report.
+    //
+    let mut lenient = subcommand.clone();
+    lenient.p.required.clear();
+    lenient.p.r_ifs.clear();
+    for each in lenient.p.flags.iter_mut() {
+        each.b.requires = None;
+        each.b.r_unless = None;
+    }
+    for each in lenient.p.opts.iter_mut() {
+        each.b.requires = None;
+        each.b.r_unless = None;
+    }
+    let matches = match lenient.get_matches_from_safe(&args[1..]) {
+        Ok(matches) => matches,
+        Err(_) => return Ok(args),
+    };
+
+    let path = match matches.value_of(CONFIG.0) {
+        Some(file) => {
+            let path = cwd.join(file);
+            if !path.is_file() {
+                return Err(Error::new(ErrorKind::FileNotFoundError(format!(
+                    "Config file {} does not exist",
+                    path.display()
+                ))));
+            }
+            path
+        }
+        None => match discover(cwd) {
+            Some(path) => path,
+            None => return Ok(args),
+        },
+    };
+
+    let config = ProjectConfig::load(&path)?;
+    let base = path.parent().unwrap_or(cwd);
+    let section = if command == VALIDATE {
+        &config.validate
+    } else {
+        &config.test
+    };
+
+    let known = command_args(subcommand);
+    let mut selected = Vec::with_capacity(section.len());
+    for (key, value) in section {
+        let arg = match known
+            .iter()
+            .find(|each| each.long == key && each.name != CONFIG.0)
+        {
+            Some(arg) => arg,
+            None => {
+                return Err(Error::new(ErrorKind::ParseError(format!(
+                    "Unknown setting `{}` in [{}] section of config file {}",
+                    key,
+                    command,
+                    path.display()
+                ))))
+            }
+        };
+        if matches.occurrences_of(arg.name) != 0
+            || arg
+                .conflicts
+                .iter()
+                .any(|conflict| matches.occurrences_of(conflict) != 0)
+            || matches!(value, ConfigValue::Flag(false))
+        {
+            continue;
+        }
+        selected.push((arg, value));
+    }
+    //
+    // A default that requires an argument that is neither on the command line nor defaulted
+    // is dropped, e.g. the test patterns for a directory when testing a single rules file
+    //
+    loop {
+        let before = selected.len();
+        let names = selected.iter().map(|(arg, _)| arg.name).collect::<Vec<_>>();
+        selected.retain(|(arg, _)| {
+            arg.requires
+                .iter()
+                .all(|required| matches.occurrences_of(required) != 0 || names.contains(required))
+        });
+        if selected.len() == before {
+            break;
+        }
+    }
+
+    let mut args = args;
+    for (arg, value) in selected {
+        let values = match value {
+            ConfigValue::Flag(_) => {
+                args.push(format!("--{}", arg.long));
+                continue;
+            }
+            ConfigValue::Number(number) => vec![number.to_string()],
+            ConfigValue::Value(single) => vec![single.clone()],
+            ConfigValue::Values(list) => list.clone(),
+        };
+        for each in values {
+            args.push(format!("--{}", arg.long));
+            args.push(if PATH_ARGS.contains(&arg.name) {
+                format!("{}", base.join(each).display())
+            } else {
+                each
+            });
+        }
+    }
+    Ok(args)
+}
+
+//
+// What deciding on a default needs to know about an argument of the command. clap has no
+// accessors for the arguments of a command, they are read from its parser. Conflicts are
+// declared on either of the two arguments.
+//
+struct CommandArg<'a> {
+    name: &'a str,
+    long: &'a str,
+    conflicts: Vec<&'a str>,
+    requires: Vec<&'a str>,
+}
+
+fn command_args<'a>(app: &'a App<'_, '_>) -> Vec<CommandArg<'a>> {
+    let declared = app
+        .p
+        .flags
+        .iter()
+        .map(|each| (&each.b, each.s.long))
+        .chain(app.p.opts.iter().map(|each| (&each.b, each.s.long)))
+        .collect::<Vec<_>>();
+    declared
+        .iter()
+        .filter_map(|(base, long)| {
+            let conflicts = base
+                .blacklist
+                .iter()
+                .flatten()
+                .copied()
+                .chain(declared.iter().filter_map(|(other, _)| {
+                    other
+                        .blacklist
+                        .iter()
+                        .flatten()
+                        .any(|each| *each == base.name)
+                        .then_some(other.name)
+                }))
+                .collect();
+            let requires = base
+                .requires
+                .iter()
+                .flatten()
+                .filter(|(value, _)| value.is_none())
+                .map(|(_, name)| *name)
+                .collect();
+            Some(CommandArg {
+                name: base.name,
+                long: (*long)?,
+                conflicts,
+                requires,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "config_tests.rs"]
+mod config_tests;
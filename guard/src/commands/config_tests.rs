@@ -0,0 +1,353 @@
+use super::*;
+use crate::command::Command;
+use crate::commands::test::Test;
+use crate::commands::validate::Validate;
+
+struct ProjectDir(PathBuf);
+
+impl ProjectDir {
+    fn new(name: &str) -> ProjectDir {
+        let dir =
+            std::env::temp_dir().join(format!("guard-config-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("nested").join("deeper")).unwrap();
+        ProjectDir(dir)
+    }
+
+    fn write(&self, file: &str, content: &str) -> PathBuf {
+        let path = self.0.join(file);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for ProjectDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("cfn-guard")
+        .subcommand(Validate::new().command())
+        .subcommand(Test::new().command())
+}
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+const GUARD_TOML: &str = r#"
+[validate]
+rules = ["rules/", "more-rules/"]
+data = "templates/"
+output-format = "json"
+show-summary = ["fail", "skip"]
+verbose = true
+print-json = false
+
+[test]
+dir = "rules/"
+"#;
+
+#[test]
+fn test_discover_walks_up() {
+    let project = ProjectDir::new("discover");
+    let nested = project.0.join("nested").join("deeper");
+    assert_eq!(discover(&nested), discover(&project.0));
+
+    let rc = project.write(".guardrc", "validate: {}");
+    assert_eq!(discover(&nested), Some(rc));
+    let toml = project.write("guard.toml", "");
+    assert_eq!(discover(&nested), Some(toml));
+}
+
+#[test]
+fn test_defaults_from_toml() -> Result<()> {
+    let project = ProjectDir::new("toml");
+    project.write("guard.toml", GUARD_TOML);
+    let cwd = project.0.join("nested");
+    let base = project.0.display().to_string();
+
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "validate"]), &cwd)?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "--data",
+            &format!("{}/templates/", base),
+            "--output-format",
+            "json",
+            "--rules",
+            &format!("{}/rules/", base),
+            "--rules",
+            &format!("{}/more-rules/", base),
+            "--show-summary",
+            "fail",
+            "--show-summary",
+            "skip",
+            "--verbose",
+        ])
+    );
+
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "test"]), &cwd)?;
+    assert_eq!(
+        result,
+        args(&["cfn-guard", "test", "--dir", &format!("{}/rules/", base)])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_command_line_overrides() -> Result<()> {
+    let project = ProjectDir::new("overrides");
+    project.write("guard.toml", GUARD_TOML);
+    let base = project.0.display().to_string();
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&[
+            "cfn-guard",
+            "validate",
+            "-r",
+            "mine.guard",
+            "--output-format=yaml",
+            "-vS",
+            "all",
+        ]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "-r",
+            "mine.guard",
+            "--output-format=yaml",
+            "-vS",
+            "all",
+            "--data",
+            &format!("{}/templates/", base),
+        ])
+    );
+
+    // conflicts declared on the argument given on the command line count as well
+    project.write(
+        "guard.toml",
+        "[validate]\nalphabetical = true\nverbose = true\n",
+    );
+    let result = apply_project_config_from(
+        &app(),
+        args(&[
+            "cfn-guard",
+            "validate",
+            "-r",
+            "mine.guard",
+            "--last-modified",
+        ]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "-r",
+            "mine.guard",
+            "--last-modified",
+            "--verbose",
+        ])
+    );
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "test", "-r", "a.guard", "-t", "a_tests.yaml"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&["cfn-guard", "test", "-r", "a.guard", "-t", "a_tests.yaml"])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_values_stuck_to_short_flags() -> Result<()> {
+    let project = ProjectDir::new("clusters");
+    project.write(
+        "guard.toml",
+        "[validate]\njobs = 4\nalphabetical = true\nverbose = true\nprint-json = true\n",
+    );
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "validate", "-ojson", "-Sall", "-vs"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "-ojson",
+            "-Sall",
+            "-vs",
+            "--alphabetical",
+            "--jobs",
+            "4",
+            "--print-json",
+        ])
+    );
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "validate", "-paj2"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&["cfn-guard", "validate", "-paj2", "--verbose"])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explicit_config_file() -> Result<()> {
+    let project = ProjectDir::new("explicit");
+    project.write("guard.toml", GUARD_TOML);
+    project.write(
+        "nested/ci.yaml",
+        r#"
+validate:
+  rules: ci-rules/
+  fail-on: [fail, skip, error]
+"#,
+    );
+    let nested = project.0.join("nested");
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "validate", "--config", "nested/ci.yaml"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "--config",
+            "nested/ci.yaml",
+            "--fail-on",
+            "fail",
+            "--fail-on",
+            "skip",
+            "--fail-on",
+            "error",
+            "--rules",
+            &format!("{}/ci-rules/", nested.display()),
+        ])
+    );
+
+    let missing = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "validate", "-c", "none.toml"]),
+        &project.0,
+    );
+    assert!(missing.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_config() {
+    let project = ProjectDir::new("invalid");
+    project.write("guard.toml", "[validate]\nrule = \"rules/\"\n");
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "validate"]), &project.0);
+    assert!(result.is_err());
+
+    project.write("guard.toml", "[validat]\nrules = \"rules/\"\n");
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "validate"]), &project.0);
+    assert!(result.is_err());
+
+    // other commands do not read the configuration
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "parse-tree"]), &project.0);
+    assert_eq!(result.unwrap(), args(&["cfn-guard", "parse-tree"]));
+}
+
+#[test]
+fn test_every_argument_of_the_command_can_be_configured() -> Result<()> {
+    let project = ProjectDir::new("all-args");
+    project.write(
+        "guard.toml",
+        r#"
+[validate]
+rules = "rules/"
+data = "templates/"
+watch = true
+path = "/Resources"
+
+[test]
+dir = "rules/"
+test-pattern = "{dir}/tests/{stem}.yaml"
+filter = ["KMS", "S3"]
+"#,
+    );
+    let base = project.0.display().to_string();
+
+    // --path requires --explain, which is neither on the command line nor configured
+    let result = apply_project_config_from(&app(), args(&["cfn-guard", "validate"]), &project.0)?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "--data",
+            &format!("{}/templates/", base),
+            "--rules",
+            &format!("{}/rules/", base),
+            "--watch",
+        ])
+    );
+
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "validate", "--explain=R", "--rules=r.guard"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "validate",
+            "--explain=R",
+            "--rules=r.guard",
+            "--data",
+            &format!("{}/templates/", base),
+            "--path",
+            "/Resources",
+        ])
+    );
+
+    // the test patterns require --dir which conflicts with the rules and test files given
+    let result = apply_project_config_from(
+        &app(),
+        args(&["cfn-guard", "test", "-r", "a.guard", "-t", "a_tests.yaml"]),
+        &project.0,
+    )?;
+    assert_eq!(
+        result,
+        args(&[
+            "cfn-guard",
+            "test",
+            "-r",
+            "a.guard",
+            "-t",
+            "a_tests.yaml",
+            "--filter",
+            "KMS",
+            "--filter",
+            "S3",
+        ])
+    );
+    Ok(())
+}
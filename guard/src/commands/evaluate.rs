@@ -0,0 +1,88 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::{root_scope, EventRecord};
+use crate::rules::exprs::RulesFile;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::{NamedStatus, RecordType, Result, Status};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Outcome {
+    Statuses(Vec<(String, Status)>),
+    Error(String),
+    Panic(String),
+}
+
+thread_local! {
+    static EVALUATING: Cell<bool> = const { Cell::new(false) };
+}
+
+static QUIET_HOOK: Once = Once::new();
+
+//
+// Panics while evaluating are returned as outcomes, the panic hook does not print them to
+// stderr. Outside of evaluate the hook in place before works as it did. The flag is reset
+// when the guard drops, also when unwinding.
+//
+struct Evaluating;
+
+impl Evaluating {
+    fn start() -> Evaluating {
+        QUIET_HOOK.call_once(|| {
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if !EVALUATING.with(Cell::get) {
+                    hook(info)
+                }
+            }));
+        });
+        EVALUATING.with(|evaluating| evaluating.set(true));
+        Evaluating
+    }
+}
+
+impl Drop for Evaluating {
+    fn drop(&mut self) {
+        EVALUATING.with(|evaluating| evaluating.set(false));
+    }
+}
+
+///
+/// Evaluates the rules against a single input, catching errors and panics
+///
+pub(crate) fn evaluate(rules: &RulesFile<'_>, root: &PathAwareValue) -> Outcome {
+    let _evaluating = Evaluating::start();
+    let evaluated = panic::catch_unwind(AssertUnwindSafe(|| -> Result<Vec<(String, Status)>> {
+        let mut scope = root_scope(rules, root)?;
+        eval_rules_file(rules, &mut scope)?;
+        Ok(rule_statuses(&scope.reset_recorder().extract()))
+    }));
+    match evaluated {
+        Ok(Ok(statuses)) => Outcome::Statuses(statuses),
+        Ok(Err(e)) => Outcome::Error(format!("{}", e)),
+        Err(payload) => Outcome::Panic(
+            payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string()),
+        ),
+    }
+}
+
+///
+/// The status of each rule from the record of evaluating a rules file
+///
+pub(crate) fn rule_statuses(top: &EventRecord<'_>) -> Vec<(String, Status)> {
+    top.children
+        .iter()
+        .filter_map(|each| match &each.container {
+            Some(RecordType::RuleCheck(NamedStatus { name, status, .. })) => {
+                Some((name.to_string(), *status))
+            }
+            _ => None,
+        })
+        .collect()
+}
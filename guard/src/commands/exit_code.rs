@@ -0,0 +1,133 @@
+use enumflags2::{bitflags, BitFlags};
+
+use crate::commands::{
+    ERROR_STATUS_CODE, EVALUATION_ERROR_STATUS_CODE, PARSING_ERROR_STATUS_CODE, SUCCESS_STATUS_CODE,
+};
+use crate::rules::Status;
+
+pub(crate) const FAIL_ON_VALUES: [&str; 4] = ["fail", "skip", "error", "none"];
+pub(crate) const TEST_FAIL_ON_VALUES: [&str; 5] = ["fail", "skip", "untested", "error", "none"];
+pub(crate) const FAIL_ON_DEFAULT: &str = "fail,error";
+
+#[allow(clippy::upper_case_acronyms)]
+#[bitflags]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, Eq, PartialOrd, PartialEq)]
+pub(crate) enum FailOn {
+    FAIL = 0b0001,
+    SKIP = 0b0010,
+    ERROR = 0b0100,
+    UNTESTED = 0b1000,
+}
+
+///
+/// The `--fail-on` policy from its values, `none` turns off all other values wherever it
+/// appears in the list
+///
+pub(crate) fn fail_on_from<'a>(values: Option<impl Iterator<Item = &'a str>>) -> BitFlags<FailOn> {
+    values.map_or(FailOn::FAIL | FailOn::ERROR, |v| {
+        let (fail_on, none) = v.fold((BitFlags::empty(), false), |(mut fail_on, none), elem| {
+            match elem {
+                "fail" => fail_on.insert(FailOn::FAIL),
+                "skip" => fail_on.insert(FailOn::SKIP),
+                "error" => fail_on.insert(FailOn::ERROR),
+                "untested" => fail_on.insert(FailOn::UNTESTED),
+                "none" => return (fail_on, true),
+                _ => unreachable!(),
+            };
+            (fail_on, none)
+        });
+        if none {
+            BitFlags::empty()
+        } else {
+            fail_on
+        }
+    })
+}
+
+///
+/// Collects outcomes across all rules and data files evaluated by a command and decides the
+/// exit code for the process based on the `--fail-on` policy. Errors take precedence over
+/// failures, with parse errors ranked above evaluation errors, so that broken rules can be
+/// told apart from non-compliant data.
+///
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExitCodeTracker {
+    fail_on: BitFlags<FailOn>,
+    failure_code: i32,
+    parse_error: bool,
+    evaluation_error: bool,
+    error: bool,
+    failed: bool,
+    skipped: bool,
+    untested: bool,
+}
+
+impl ExitCodeTracker {
+    pub(crate) fn new(fail_on: BitFlags<FailOn>, failure_code: i32) -> ExitCodeTracker {
+        ExitCodeTracker {
+            fail_on,
+            failure_code,
+            parse_error: false,
+            evaluation_error: false,
+            error: false,
+            failed: false,
+            skipped: false,
+            untested: false,
+        }
+    }
+
+    pub(crate) fn record_status(&mut self, status: Status) {
+        match status {
+            Status::FAIL => self.failed = true,
+            Status::SKIP => self.skipped = true,
+            Status::PASS => {}
+        }
+    }
+
+    ///
+    /// A rules file without any tests. It fails with `--fail-on untested`, and with
+    /// `--fail-on skip` as these were reported as skipped before they had a status of
+    /// their own.
+    ///
+    pub(crate) fn record_untested(&mut self) {
+        self.untested = true;
+    }
+
+    pub(crate) fn record_parse_error(&mut self) {
+        self.parse_error = true;
+    }
+
+    pub(crate) fn record_evaluation_error(&mut self) {
+        self.evaluation_error = true;
+    }
+
+    pub(crate) fn record_error(&mut self) {
+        self.error = true;
+    }
+
+    pub(crate) fn exit_code(&self) -> i32 {
+        if self.fail_on.contains(FailOn::ERROR) {
+            if self.parse_error {
+                return PARSING_ERROR_STATUS_CODE;
+            }
+            if self.evaluation_error {
+                return EVALUATION_ERROR_STATUS_CODE;
+            }
+            if self.error {
+                return ERROR_STATUS_CODE;
+            }
+        }
+        if (self.failed && self.fail_on.contains(FailOn::FAIL))
+            || (self.skipped && self.fail_on.contains(FailOn::SKIP))
+            || (self.untested && self.fail_on.intersects(FailOn::SKIP | FailOn::UNTESTED))
+        {
+            return self.failure_code;
+        }
+        SUCCESS_STATUS_CODE
+    }
+}
+
+#[cfg(test)]
+#[path = "exit_code_tests.rs"]
+mod exit_code_tests;
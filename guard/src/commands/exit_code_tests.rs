@@ -0,0 +1,81 @@
+use super::*;
+use crate::commands::FAILURE_STATUS_CODE;
+
+#[test]
+fn test_fail_on_from_values() {
+    assert_eq!(
+        fail_on_from(None::<std::vec::IntoIter<&str>>),
+        FailOn::FAIL | FailOn::ERROR
+    );
+    assert_eq!(fail_on_from(Some(vec!["skip"].into_iter())), FailOn::SKIP);
+    assert_eq!(
+        fail_on_from(Some(vec!["fail", "skip", "error"].into_iter())),
+        FailOn::FAIL | FailOn::SKIP | FailOn::ERROR
+    );
+    assert_eq!(
+        fail_on_from(Some(vec!["fail", "skip", "untested", "error"].into_iter())),
+        BitFlags::<FailOn>::all()
+    );
+    assert_eq!(
+        fail_on_from(Some(vec!["fail", "none"].into_iter())),
+        BitFlags::<FailOn>::empty()
+    );
+    assert_eq!(
+        fail_on_from(Some(vec!["none", "fail"].into_iter())),
+        BitFlags::<FailOn>::empty()
+    );
+}
+
+#[test]
+fn test_default_policy() {
+    let mut tracker = ExitCodeTracker::new(FailOn::FAIL | FailOn::ERROR, FAILURE_STATUS_CODE);
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+    tracker.record_status(Status::SKIP);
+    tracker.record_status(Status::PASS);
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+    tracker.record_status(Status::FAIL);
+    assert_eq!(tracker.exit_code(), FAILURE_STATUS_CODE);
+    tracker.record_error();
+    assert_eq!(tracker.exit_code(), ERROR_STATUS_CODE);
+    tracker.record_evaluation_error();
+    assert_eq!(tracker.exit_code(), EVALUATION_ERROR_STATUS_CODE);
+    tracker.record_parse_error();
+    assert_eq!(tracker.exit_code(), PARSING_ERROR_STATUS_CODE);
+}
+
+#[test]
+fn test_fail_on_skip_only() {
+    let mut tracker = ExitCodeTracker::new(FailOn::SKIP.into(), FAILURE_STATUS_CODE);
+    tracker.record_status(Status::FAIL);
+    tracker.record_parse_error();
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+    tracker.record_status(Status::SKIP);
+    assert_eq!(tracker.exit_code(), FAILURE_STATUS_CODE);
+}
+
+#[test]
+fn test_fail_on_none() {
+    let mut tracker = ExitCodeTracker::new(BitFlags::empty(), FAILURE_STATUS_CODE);
+    tracker.record_status(Status::FAIL);
+    tracker.record_status(Status::SKIP);
+    tracker.record_parse_error();
+    tracker.record_evaluation_error();
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+}
+
+#[test]
+fn test_fail_on_untested() {
+    let mut tracker = ExitCodeTracker::new(FailOn::FAIL | FailOn::ERROR, FAILURE_STATUS_CODE);
+    tracker.record_untested();
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+
+    let mut tracker = ExitCodeTracker::new(FailOn::UNTESTED.into(), FAILURE_STATUS_CODE);
+    tracker.record_status(Status::SKIP);
+    assert_eq!(tracker.exit_code(), SUCCESS_STATUS_CODE);
+    tracker.record_untested();
+    assert_eq!(tracker.exit_code(), FAILURE_STATUS_CODE);
+
+    let mut tracker = ExitCodeTracker::new(FailOn::SKIP.into(), FAILURE_STATUS_CODE);
+    tracker.record_untested();
+    assert_eq!(tracker.exit_code(), FAILURE_STATUS_CODE);
+}
@@ -1,12 +1,18 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
 
-use crate::rules::errors::Error;
+use crate::rules::errors::{Error, ErrorKind};
+use globset::GlobBuilder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
 use walkdir::{DirEntry, WalkDir};
 
+pub(crate) const IGNORE_FILE_NAME: &str = ".guardignore";
+
 pub(crate) fn read_file_content(file: File) -> Result<String, std::io::Error> {
     let mut file_content = String::new();
     let mut buf_reader = BufReader::new(file);
@@ -14,56 +20,298 @@ pub(crate) fn read_file_content(file: File) -> Result<String, std::io::Error> {
     Ok(file_content)
 }
 
-pub(crate) fn get_files<F>(file: &str, sort: F) -> Result<Vec<PathBuf>, Error>
+pub(crate) fn get_files<F>(
+    file: &str,
+    sort: F,
+    ignore: &IgnoreFilter,
+) -> Result<Vec<PathBuf>, Error>
 where
     F: FnMut(&walkdir::DirEntry, &walkdir::DirEntry) -> Ordering + Send + Sync + 'static,
 {
-    let path = PathBuf::from_str(file)?;
-    let input_file = File::open(file)?;
-    let metadata = input_file.metadata()?;
-    Ok(if metadata.is_file() {
-        vec![path]
-    } else {
-        let result = get_files_with_filter(file, sort, |entry| {
-            entry
-                .file_name()
-                .to_str()
-                .map(|name| !name.ends_with("/"))
-                .unwrap_or(false)
-        })?;
-        result
+    get_files_with_filter(file, sort, ignore, |entry| {
+        entry
+            .file_name()
+            .to_str()
+            .map(|name| !name.ends_with('/'))
+            .unwrap_or(false)
     })
 }
 
 pub(crate) fn get_files_with_filter<S, F>(
     file: &str,
     sort: S,
+    ignore: &IgnoreFilter,
     filter: F,
 ) -> Result<Vec<PathBuf>, Error>
 where
     S: FnMut(&walkdir::DirEntry, &walkdir::DirEntry) -> Ordering + Send + Sync + 'static,
     F: Fn(&walkdir::DirEntry) -> bool,
 {
-    let mut selected = Vec::with_capacity(10);
-    let walker = WalkDir::new(file).sort_by(sort).into_iter();
+    Ok(select_files(file, sort, ignore, filter)?.files)
+}
+
+///
+/// Files selected for a single `--data`/`--rules` style argument. `base` is the file or
+/// directory that was walked, for a glob pattern it is the longest leading path without
+/// any glob characters.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Selection {
+    pub(crate) base: PathBuf,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+///
+/// Selects files for an argument that is either a file, a directory or a glob pattern like
+/// `templates/**/*.yaml`. A file passed explicitly is always selected. Files and directories
+/// discovered by walking are skipped when `ignore` excludes them, otherwise `filter` decides.
+///
+pub(crate) fn select_files<S, F>(
+    file_or_glob: &str,
+    sort: S,
+    ignore: &IgnoreFilter,
+    filter: F,
+) -> Result<Selection, Error>
+where
+    S: FnMut(&walkdir::DirEntry, &walkdir::DirEntry) -> Ordering + Send + Sync + 'static,
+    F: Fn(&walkdir::DirEntry) -> bool,
+{
+    let path = PathBuf::from_str(file_or_glob)?;
+    let (base, glob) = if path.exists() || !is_glob(file_or_glob) {
+        (path, None)
+    } else {
+        let base = path
+            .components()
+            .take_while(|part| !is_glob(&part.as_os_str().to_string_lossy()))
+            .collect::<PathBuf>();
+        let glob = GlobBuilder::new(file_or_glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                Error::new(ErrorKind::ParseError(format!(
+                    "Invalid glob pattern {}, Error {}",
+                    file_or_glob, e
+                )))
+            })?
+            .compile_matcher();
+        (base, Some(glob))
+    };
+
+    let walk_from = if base == Path::new("") {
+        PathBuf::from(".")
+    } else {
+        base.clone()
+    };
+    if !walk_from.exists() {
+        return Err(Error::new(ErrorKind::FileNotFoundError(
+            file_or_glob.to_string(),
+        )));
+    }
+    if walk_from.is_file() {
+        return Ok(Selection {
+            base,
+            files: vec![walk_from],
+        });
+    }
+
+    let mut rooted = ignore.rooted_at(&walk_from)?;
+    let mut files = Vec::with_capacity(10);
+    let walker = WalkDir::new(&walk_from).sort_by(sort).into_iter();
     let dir_check = |entry: &DirEntry| {
-        // select directories to traverse
-        if entry.path().is_dir() {
+        if entry.depth() == 0 {
             return true;
         }
-        filter(entry)
+        let is_dir = entry.path().is_dir();
+        if rooted.is_ignored(entry.path(), is_dir) {
+            return false;
+        }
+        // select directories to traverse
+        is_dir || filter(entry)
     };
     for each in walker.filter_entry(dir_check) {
         //
         // We are ignoring errors here. TODO fix this later
         //
         if let Ok(entry) = each {
-            if entry.path().is_file() {
-                selected.push(entry.into_path());
+            if !entry.path().is_file() {
+                continue;
+            }
+            let path = if base == Path::new("") {
+                entry
+                    .path()
+                    .strip_prefix(".")
+                    .unwrap_or(entry.path())
+                    .to_path_buf()
+            } else {
+                entry.into_path()
+            };
+            if glob.as_ref().is_none_or(|glob| glob.is_match(&path)) {
+                files.push(path);
             }
         }
     }
-    Ok(selected)
+    rooted.finish()?;
+    Ok(Selection { base, files })
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '[', '{'])
+}
+
+///
+/// Decides which files and directories are skipped when walking directories for data and
+/// rules files. Patterns use gitignore syntax and come from `--exclude` arguments, from a
+/// `.guardignore` file in the current directory and from `.guardignore` files in the walked
+/// directory and the directories below it. `--exclude` patterns are matched relative to the
+/// walked directory and always win, otherwise the `.guardignore` closest to a path takes
+/// precedence, so it can re-include with `!pattern`.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct IgnoreFilter {
+    cwd: PathBuf,
+    excludes: Vec<String>,
+    project: Gitignore,
+}
+
+#[derive(Debug)]
+pub(crate) struct RootedIgnoreFilter<'filter> {
+    filter: &'filter IgnoreFilter,
+    root: PathBuf,
+    excludes: Gitignore,
+    dirs: HashMap<PathBuf, Gitignore>,
+    error: Option<Error>,
+}
+
+impl IgnoreFilter {
+    pub(crate) fn new<'p>(excludes: impl Iterator<Item = &'p str>) -> Result<IgnoreFilter, Error> {
+        IgnoreFilter::new_in(&std::env::current_dir()?, excludes)
+    }
+
+    pub(crate) fn new_in<'p>(
+        cwd: &Path,
+        excludes: impl Iterator<Item = &'p str>,
+    ) -> Result<IgnoreFilter, Error> {
+        let excludes = excludes.map(String::from).collect::<Vec<_>>();
+        // reports invalid patterns before any directory is walked
+        build_excludes(cwd, &excludes)?;
+        let project = load_ignore_file(cwd)?;
+        Ok(IgnoreFilter {
+            cwd: cwd.to_path_buf(),
+            excludes,
+            project,
+        })
+    }
+
+    pub(crate) fn rooted_at(&self, root: &Path) -> Result<RootedIgnoreFilter<'_>, Error> {
+        let root = normalize(&self.cwd.join(root));
+        Ok(RootedIgnoreFilter {
+            filter: self,
+            excludes: build_excludes(&root, &self.excludes)?,
+            root,
+            dirs: HashMap::new(),
+            error: None,
+        })
+    }
+}
+
+impl<'filter> RootedIgnoreFilter<'filter> {
+    ///
+    /// The `.guardignore` files of the directories between the root and the path are read the
+    /// first time they are needed, a file that cannot be read is reported by `finish`
+    ///
+    pub(crate) fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let path = normalize(&self.filter.cwd.join(path));
+        let mut dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+        if path.starts_with(&self.filter.cwd) && !dirs.contains(&self.filter.cwd) {
+            dirs.push(self.filter.cwd.clone());
+        }
+        for dir in &dirs {
+            if !self.dirs.contains_key(dir) {
+                let loaded = if *dir == self.filter.cwd {
+                    Ok(self.filter.project.clone())
+                } else {
+                    load_ignore_file(dir)
+                };
+                let loaded = loaded.unwrap_or_else(|e| {
+                    self.error.get_or_insert(e);
+                    Gitignore::empty()
+                });
+                self.dirs.insert(dir.clone(), loaded);
+            }
+        }
+        std::iter::once(&self.excludes)
+            .chain(dirs.iter().map(|dir| &self.dirs[dir]))
+            .filter(|each| !each.is_empty())
+            .find_map(|each| {
+                let relative = path.strip_prefix(each.path()).ok()?;
+                match each.matched_path_or_any_parents(relative, is_dir) {
+                    Match::Ignore(_) => Some(true),
+                    Match::Whitelist(_) => Some(false),
+                    Match::None => None,
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    ///
+    /// Reports the first `.guardignore` file that could not be read during the walk
+    ///
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+fn build_excludes(root: &Path, excludes: &[String]) -> Result<Gitignore, Error> {
+    let mut builder = GitignoreBuilder::new(root);
+    for each in excludes {
+        builder.add_line(None, each).map_err(ignore_error)?;
+    }
+    builder.build().map_err(ignore_error)
+}
+
+fn load_ignore_file(dir: &Path) -> Result<Gitignore, Error> {
+    let file = dir.join(IGNORE_FILE_NAME);
+    if !file.is_file() {
+        return Ok(Gitignore::empty());
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&file) {
+        return Err(ignore_error(e));
+    }
+    builder.build().map_err(ignore_error)
+}
+
+fn ignore_error(e: ignore::Error) -> Error {
+    Error::new(ErrorKind::ParseError(format!(
+        "Invalid ignore pattern, Error {}",
+        e
+    )))
+}
+
+//
+// Lexically resolves `.` and `..` so that paths can be compared with the ignore file roots
+// without touching the file system
+//
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for part in path.components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            rest => normalized.push(rest.as_os_str()),
+        }
+    }
+    normalized
 }
 
 #[derive(Debug)]
@@ -134,3 +382,7 @@ pub(crate) fn regular_ordering(
 ) -> Ordering {
     Ordering::Equal
 }
+
+#[cfg(test)]
+#[path = "files_tests.rs"]
+mod files_tests;
@@ -0,0 +1,221 @@
+use super::*;
+
+struct Project(PathBuf);
+
+impl Project {
+    fn new(name: &str, files: &[&str]) -> Project {
+        let dir = std::env::temp_dir().join(format!("guard-files-{}-{}", name, std::process::id()));
+        for each in files {
+            let file = dir.join(each);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "{}").unwrap();
+        }
+        Project(dir)
+    }
+
+    fn path(&self, relative: &str) -> String {
+        format!("{}", self.0.join(relative).display())
+    }
+
+    fn write(&self, relative: &str, content: &str) {
+        std::fs::write(self.0.join(relative), content).unwrap();
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+const TEMPLATES: [&str; 6] = [
+    "templates/app.yaml",
+    "templates/nested/db.json",
+    "templates/nested/README.md",
+    "templates/node_modules/pkg/package.json",
+    "templates/cdk.out/asset.template.json",
+    "rules/s3.guard",
+];
+
+fn yaml_or_json(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .is_some_and(|name| name.ends_with(".yaml") || name.ends_with(".json"))
+}
+
+fn relative(project: &Project, selection: &Selection) -> Vec<String> {
+    selection
+        .files
+        .iter()
+        .map(|each| {
+            format!(
+                "{}",
+                each.strip_prefix(&project.0).unwrap_or(each).display()
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_select_directory() -> Result<(), Error> {
+    let project = Project::new("directory", &TEMPLATES);
+    let ignore = IgnoreFilter::new_in(&project.0, std::iter::empty())?;
+    let selection = select_files(
+        &project.path("templates"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(selection.base, project.0.join("templates"));
+    assert_eq!(
+        relative(&project, &selection),
+        vec![
+            "templates/app.yaml",
+            "templates/cdk.out/asset.template.json",
+            "templates/nested/db.json",
+            "templates/node_modules/pkg/package.json",
+        ]
+    );
+
+    let selection = select_files(
+        &project.path("rules/s3.guard"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(relative(&project, &selection), vec!["rules/s3.guard"]);
+
+    assert!(select_files(&project.path("missing"), alpabetical, &ignore, yaml_or_json).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_select_glob() -> Result<(), Error> {
+    let project = Project::new("glob", &TEMPLATES);
+    let ignore = IgnoreFilter::new_in(&project.0, std::iter::empty())?;
+    let selection = select_files(
+        &project.path("templates/**/*.json"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(selection.base, project.0.join("templates"));
+    assert_eq!(
+        relative(&project, &selection),
+        vec![
+            "templates/cdk.out/asset.template.json",
+            "templates/nested/db.json",
+            "templates/node_modules/pkg/package.json",
+        ]
+    );
+
+    let selection = select_files(
+        &project.path("templates/*.yaml"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(relative(&project, &selection), vec!["templates/app.yaml"]);
+
+    let selection = select_files(
+        &project.path("templates/{app,nested/db}.*"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(
+        relative(&project, &selection),
+        vec!["templates/app.yaml", "templates/nested/db.json"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_excludes_and_ignore_files() -> Result<(), Error> {
+    let project = Project::new("ignore", &TEMPLATES);
+    project.write(".guardignore", "node_modules/\n");
+    project.write("templates/.guardignore", "cdk.out/\n*.json\n!db.json\n");
+
+    let ignore = IgnoreFilter::new_in(&project.0, std::iter::empty())?;
+    let selection = select_files(
+        &project.path("templates"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(
+        relative(&project, &selection),
+        vec!["templates/app.yaml", "templates/nested/db.json"]
+    );
+
+    let ignore = IgnoreFilter::new_in(&project.0, vec!["nested/"].into_iter())?;
+    let selection = select_files(
+        &project.path("templates/**/*"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(relative(&project, &selection), vec!["templates/app.yaml"]);
+
+    // explicitly passed files are not subject to ignore patterns
+    let selection = select_files(
+        &project.path("templates/node_modules/pkg/package.json"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(
+        relative(&project, &selection),
+        vec!["templates/node_modules/pkg/package.json"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_nested_ignore_files_and_excludes_outside_the_current_directory() -> Result<(), Error> {
+    let project = Project::new("nested-ignore", &TEMPLATES);
+    project.write("templates/nested/.guardignore", "*.json\n");
+    let elsewhere = Project::new("nested-ignore-cwd", &[]);
+
+    let ignore = IgnoreFilter::new_in(&elsewhere.0, vec!["node_modules/"].into_iter())?;
+    let selection = select_files(
+        &project.path("templates"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(
+        relative(&project, &selection),
+        vec![
+            "templates/app.yaml",
+            "templates/cdk.out/asset.template.json"
+        ]
+    );
+
+    // anchored patterns are relative to the walked directory
+    let ignore = IgnoreFilter::new_in(&elsewhere.0, vec!["/cdk.out", "/nested"].into_iter())?;
+    let selection = select_files(
+        &project.path("templates"),
+        alpabetical,
+        &ignore,
+        yaml_or_json,
+    )?;
+    assert_eq!(
+        relative(&project, &selection),
+        vec![
+            "templates/app.yaml",
+            "templates/node_modules/pkg/package.json"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(
+        normalize(Path::new("/a/./b/../c/d")),
+        PathBuf::from("/a/c/d")
+    );
+    assert_eq!(normalize(Path::new("a/../../b")), PathBuf::from("b"));
+}
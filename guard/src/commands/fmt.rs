@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::Read;
+
+use clap::{App, Arg, ArgMatches};
+use serde_json::Value;
+use similar::TextDiff;
+
+use crate::command::Command;
+use crate::commands::files::{get_files_with_filter, read_file_content, IgnoreFilter};
+use crate::commands::{
+    CHECK, FAILURE_STATUS_CODE, FMT, PARSING_ERROR_STATUS_CODE, RULES,
+    RULE_FILE_SUPPORTED_EXTENSIONS, SUCCESS_STATUS_CODE,
+};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::parser::{rules_file, rules_file_exprs, Span};
+use crate::rules::Result;
+
+pub(crate) mod printer;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Fmt {}
+
+impl Fmt {
+    pub fn new() -> Self {
+        Fmt {}
+    }
+}
+
+impl Command for Fmt {
+    fn name(&self) -> &'static str {
+        FMT
+    }
+
+    fn command(&self) -> App<'static, 'static> {
+        App::new(FMT)
+            .about(r#"Formats Guard rules files in a canonical form. Keywords are lower case, blocks
+are indented with four spaces and comments are kept. Files are rewritten in place, with
+--check nothing is written and the command fails if any file is not formatted. Without
+--rules the rules are read from stdin and the formatted rules printed to stdout.
+"#)
+            .arg(Arg::with_name(RULES.0)
+                .long(RULES.0)
+                .short(RULES.1)
+                .takes_value(true)
+                .multiple(true)
+                .help("Provide a rules file or a directory of rules files"))
+            .arg(Arg::with_name(CHECK.0)
+                .long(CHECK.0)
+                .short(CHECK.1)
+                .required(false)
+                .help("Print the difference for files that are not formatted, without changing them. Exits with 5 if any are not"))
+    }
+
+    fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
+        let check = app.is_present(CHECK.0);
+        let list_of_file_or_dir = match app.values_of(RULES.0) {
+            Some(list) => list,
+            None => {
+                let mut content = String::new();
+                std::io::stdin().read_to_string(&mut content)?;
+                return match format_rules("", &content) {
+                    Ok(formatted) if check => {
+                        if formatted == content {
+                            return Ok(SUCCESS_STATUS_CODE);
+                        }
+                        print_diff("stdin", &content, &formatted);
+                        Ok(FAILURE_STATUS_CODE)
+                    }
+                    Ok(formatted) => {
+                        print!("{}", formatted);
+                        Ok(SUCCESS_STATUS_CODE)
+                    }
+                    Err(e) => {
+                        eprintln!("Error formatting rules from stdin, {}", e);
+                        Ok(PARSING_ERROR_STATUS_CODE)
+                    }
+                };
+            }
+        };
+
+        let ignore = IgnoreFilter::new(std::iter::empty())?;
+        let mut status = SUCCESS_STATUS_CODE;
+        for file_or_dir in list_of_file_or_dir {
+            let files = get_files_with_filter(
+                file_or_dir,
+                crate::commands::files::alpabetical,
+                &ignore,
+                |entry| {
+                    entry.file_name().to_str().is_some_and(|name| {
+                        RULE_FILE_SUPPORTED_EXTENSIONS
+                            .iter()
+                            .any(|ext| name.ends_with(ext))
+                    })
+                },
+            )?;
+            for file in files {
+                let name = file.display().to_string();
+                let content = read_file_content(File::open(&file)?)?;
+                let formatted = match format_rules(&name, &content) {
+                    Ok(formatted) => formatted,
+                    Err(e) => {
+                        eprintln!("Error formatting rules file {}, {}", name, e);
+                        status = PARSING_ERROR_STATUS_CODE;
+                        continue;
+                    }
+                };
+                if formatted == content {
+                    continue;
+                }
+                if check {
+                    print_diff(&name, &content, &formatted);
+                    if status == SUCCESS_STATUS_CODE {
+                        status = FAILURE_STATUS_CODE;
+                    }
+                } else {
+                    std::fs::write(&file, formatted)?;
+                    println!("Formatted {}", name);
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+fn print_diff(name: &str, content: &str, formatted: &str) {
+    println!("Would reformat {}", name);
+    print!(
+        "{}",
+        TextDiff::from_lines(content, formatted)
+            .unified_diff()
+            .header(name, name)
+    );
+}
+
+///
+/// Formats the rules in `content`. The formatted rules are parsed again and must be the same
+/// rules apart from their locations, a file that would change otherwise is reported as an
+/// error and left as is.
+///
+pub(crate) fn format_rules(name: &str, content: &str) -> Result<String> {
+    let span = Span::new_extra(content, name);
+    let exprs = rules_file_exprs(span)?;
+    let formatted = printer::print(content, &exprs);
+    if exprs.is_empty() {
+        return Ok(formatted);
+    }
+
+    let original = rules_file(span)?;
+    let reparsed = rules_file(Span::new_extra(&formatted, name));
+    let same = match reparsed {
+        Ok(reparsed) => {
+            without_locations(serde_json::to_value(&original)?)
+                == without_locations(serde_json::to_value(&reparsed)?)
+        }
+        Err(_) => false,
+    };
+    if !same {
+        return Err(Error::new(ErrorKind::IncompatibleError(format!(
+            "the formatted rules in {} do not match the original rules, the file was left unchanged",
+            name
+        ))));
+    }
+    Ok(formatted)
+}
+
+pub(crate) fn without_locations(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| key != "location")
+                .map(|(key, each)| (key, without_locations(each)))
+                .collect(),
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(without_locations).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+#[path = "fmt_tests.rs"]
+mod fmt_tests;
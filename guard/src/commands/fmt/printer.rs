@@ -0,0 +1,601 @@
+use crate::rules::exprs::{
+    Block, FileLocation, GuardClause, LetExpr, LetValue, QueryPart, QueryPartsDisplay, Rule,
+    RuleClause, TypeBlock, WhenConditions, WhenGuardClause,
+};
+use crate::rules::parser::Exprs;
+
+const INDENT: &str = "    ";
+const MAX_WIDTH: usize = 100;
+
+type Position = (u32, u32);
+
+///
+/// A comment in the source. It is trailing when code precedes it on the same line.
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Comment {
+    line: u32,
+    text: String,
+    trailing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Brace {
+    open: Position,
+    close: Position,
+}
+
+///
+/// What the parsed expressions do not keep about the source: comments, blank lines and where
+/// blocks open and close. Positions are 1 based lines and columns in characters, the same as
+/// the locations the parser records.
+///
+struct Source<'s> {
+    lines: Vec<&'s str>,
+    comments: Vec<Comment>,
+    braces: Vec<Brace>,
+}
+
+impl<'s> Source<'s> {
+    fn scan(content: &'s str) -> Source<'s> {
+        let mut comments = vec![];
+        let mut braces = vec![];
+        let mut open = vec![];
+        let (mut line, mut column) = (1u32, 0u32);
+        let mut code_on_line = false;
+        let mut chars = content.chars().peekable();
+        let mut within: Option<char> = None;
+        let mut escaped = false;
+        while let Some(ch) = chars.next() {
+            column += 1;
+            if ch == '\n' {
+                line += 1;
+                column = 0;
+                code_on_line = false;
+                escaped = false;
+                continue;
+            }
+
+            //
+            // Inside a string, regex or custom message, these end at the first closing
+            // delimiter that is not escaped
+            //
+            if let Some(end) = within {
+                if end == '>' {
+                    if ch == '>' && chars.peek() == Some(&'>') {
+                        chars.next();
+                        column += 1;
+                        within = None;
+                    }
+                } else if ch == end && !escaped {
+                    within = None;
+                }
+                escaped = ch == '\\';
+                continue;
+            }
+
+            match ch {
+                '#' => {
+                    let mut text = String::from("#");
+                    while let Some(next) = chars.peek() {
+                        if *next == '\n' {
+                            break;
+                        }
+                        text.push(*next);
+                        chars.next();
+                    }
+                    comments.push(Comment {
+                        line,
+                        text: text.trim_end().to_string(),
+                        trailing: code_on_line,
+                    });
+                    continue;
+                }
+                '\'' | '"' | '/' => within = Some(ch),
+                '<' if chars.peek() == Some(&'<') => {
+                    chars.next();
+                    column += 1;
+                    within = Some('>');
+                }
+                '{' => open.push((line, column)),
+                '}' => {
+                    if let Some(start) = open.pop() {
+                        braces.push(Brace {
+                            open: start,
+                            close: (line, column),
+                        });
+                    }
+                }
+                _ => {}
+            }
+            if !ch.is_whitespace() {
+                code_on_line = true;
+            }
+        }
+        braces.sort_by_key(|brace| brace.open);
+        Source {
+            lines: content.lines().collect(),
+            comments,
+            braces,
+        }
+    }
+
+    fn is_blank(&self, line: u32) -> bool {
+        line > 0
+            && self
+                .lines
+                .get(line as usize - 1)
+                .is_some_and(|text| text.trim().is_empty())
+    }
+
+    fn innermost(&self, position: Position) -> Option<Brace> {
+        self.braces
+            .iter()
+            .rev()
+            .find(|brace| brace.open < position && position < brace.close)
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    depth: usize,
+    text: String,
+    comment: Option<String>,
+}
+
+///
+/// Prints the expressions of a rules file in their canonical form. Comments in `content` are
+/// kept, each before the expression that followed it or at the end of the line it trailed.
+///
+pub(crate) fn print(content: &str, exprs: &[(FileLocation, Exprs)]) -> String {
+    let mut printer = Printer {
+        source: Source::scan(content),
+        next_comment: 0,
+        lines: vec![],
+        depth: 0,
+    };
+    let mut previous: Option<&Exprs> = None;
+    for (location, each) in exprs {
+        if let Some(previous) = previous {
+            //
+            // A blank line separates expressions, runs of assignments and of default clauses
+            // keep the spacing they had
+            //
+            let same_kind = matches!(
+                (previous, each),
+                (Exprs::Assignment(_), Exprs::Assignment(_))
+                    | (Exprs::DefaultClause(_), Exprs::DefaultClause(_))
+            );
+            if !same_kind {
+                printer.blank();
+            }
+        }
+        printer.comments_before(location.line);
+        printer.gap(location.line);
+        printer.expr((location.line, location.column), each);
+        previous = Some(each);
+    }
+    printer.comments_before(u32::MAX);
+    printer.render()
+}
+
+struct Printer<'s> {
+    source: Source<'s>,
+    next_comment: usize,
+    lines: Vec<Line>,
+    depth: usize,
+}
+
+enum Item<'b, T> {
+    Assignment(&'b LetExpr<'b>),
+    Clauses(&'b [T]),
+}
+
+impl<'s> Printer<'s> {
+    fn render(&self) -> String {
+        let mut rendered = String::new();
+        for each in &self.lines {
+            let mut line = String::new();
+            if !each.text.is_empty() || each.comment.is_some() {
+                line.push_str(&INDENT.repeat(each.depth));
+            }
+            line.push_str(&each.text);
+            if let Some(comment) = &each.comment {
+                if !each.text.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(comment);
+            }
+            rendered.push_str(line.trim_end());
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    fn line(&mut self, text: String) {
+        self.line_at(self.depth, text);
+    }
+
+    fn line_at(&mut self, depth: usize, text: String) {
+        self.lines.push(Line {
+            depth,
+            text,
+            comment: None,
+        });
+    }
+
+    fn append(&mut self, text: &str) {
+        if let Some(last) = self.lines.last_mut() {
+            last.text.push_str(text);
+        }
+    }
+
+    fn blank(&mut self) {
+        if let Some(last) = self.lines.last() {
+            if !last.text.is_empty() || last.comment.is_some() {
+                self.line_at(0, String::new());
+            }
+        }
+    }
+
+    //
+    // Keeps a blank line that preceded `line` in the source, except at the start of a block
+    //
+    fn gap(&mut self, line: u32) {
+        if !self.source.is_blank(line.saturating_sub(1)) {
+            return;
+        }
+        if let Some(last) = self.lines.last() {
+            if !last.text.ends_with('{') && !last.text.ends_with('[') {
+                self.blank();
+            }
+        }
+    }
+
+    fn comments_before(&mut self, line: u32) {
+        while let Some(comment) = self.source.comments.get(self.next_comment) {
+            if comment.line >= line {
+                break;
+            }
+            let comment = comment.clone();
+            self.next_comment += 1;
+            if comment.trailing {
+                let last = self
+                    .lines
+                    .iter_mut()
+                    .rev()
+                    .find(|each| !each.text.is_empty() || each.comment.is_some());
+                if let Some(last) = last {
+                    if last.comment.is_none() && !last.text.is_empty() {
+                        last.comment = Some(comment.text);
+                        continue;
+                    }
+                }
+            }
+            self.gap(comment.line);
+            self.lines.push(Line {
+                depth: self.depth,
+                text: String::new(),
+                comment: Some(comment.text),
+            });
+        }
+    }
+
+    fn expr(&mut self, position: Position, expr: &Exprs) {
+        match expr {
+            Exprs::Assignment(assignment) => self.assignment(assignment),
+            Exprs::DefaultClause(disjunctions) => self.disjunctions(disjunctions),
+            Exprs::DefaultTypeBlock(disjunctions) => self.disjunctions(disjunctions),
+            Exprs::DefaultWhenBlock(conditions, block) => {
+                let header = when_header("", conditions);
+                self.block(header, position, block);
+            }
+            Exprs::Rule(rule) => self.rule(rule, None, position),
+            Exprs::ParameterizedRule(parameterized) => self.rule(
+                &parameterized.rule,
+                Some(
+                    parameterized
+                        .parameter_names
+                        .iter()
+                        .map(String::as_str)
+                        .collect(),
+                ),
+                position,
+            ),
+        }
+    }
+
+    //
+    // An assignment from a query with a filter that has several clauses, blocks or does not fit
+    // on the line has the filter's clauses printed on lines of their own
+    //
+    fn assignment(&mut self, assignment: &LetExpr) {
+        let text = assignment.to_string();
+        let query = match &assignment.value {
+            LetValue::AccessClause(query) => query,
+            _ => return self.line(text),
+        };
+        let too_long = self.depth * INDENT.len() + text.len() > MAX_WIDTH;
+        let filter = query
+            .query
+            .iter()
+            .enumerate()
+            .find_map(|(idx, part)| match part {
+                QueryPart::Filter(name, conjunctions)
+                    if too_long
+                        || conjunctions.len() > 1
+                        || conjunctions
+                            .iter()
+                            .flatten()
+                            .any(|each| each.inline().is_none()) =>
+                {
+                    Some((idx, name, conjunctions))
+                }
+                _ => None,
+            });
+        let (idx, name, conjunctions) = match filter {
+            Some(filter) => filter,
+            None => return self.line(text),
+        };
+
+        self.line(format!(
+            "let {} = {}[{}",
+            assignment.var,
+            QueryPartsDisplay(query, 0..idx),
+            name.as_ref()
+                .map_or(String::new(), |name| format!(" {} |", name))
+        ));
+        self.depth += 1;
+        for each in conjunctions {
+            if let Some(first) = each.first() {
+                let line = first.position().0;
+                self.comments_before(line);
+                self.gap(line);
+            }
+            self.disjunctions(each);
+        }
+        self.depth -= 1;
+        self.line(format!(
+            "]{}",
+            QueryPartsDisplay(query, idx + 1..query.query.len())
+        ));
+    }
+
+    fn rule(&mut self, rule: &Rule, parameters: Option<Vec<&str>>, position: Position) {
+        let mut name = format!("rule {}", rule.rule_name);
+        if let Some(parameters) = parameters {
+            name.push_str(&format!("({})", parameters.join(", ")));
+        }
+        let header = match &rule.conditions {
+            Some(conditions) => when_header(&format!("{} ", name), conditions),
+            None => vec![name],
+        };
+        self.block(header, position, &rule.block);
+    }
+
+    fn disjunctions<T: Node>(&mut self, disjunctions: &[T]) {
+        let inline = disjunctions
+            .iter()
+            .map(Node::inline)
+            .collect::<Option<Vec<String>>>();
+        if let Some(inline) = inline {
+            let joined = inline.join(" or ");
+            if inline.len() == 1 || self.depth * INDENT.len() + joined.len() <= MAX_WIDTH {
+                self.line(joined);
+                return;
+            }
+        }
+        for (idx, each) in disjunctions.iter().enumerate() {
+            if idx != 0 {
+                self.append(" or");
+                self.comments_before(each.position().0);
+            }
+            each.print(self);
+        }
+    }
+
+    fn block<T: Node>(&mut self, header: Vec<String>, position: Position, block: &Block<T>) {
+        let count = header.len();
+        for (idx, each) in header.into_iter().enumerate() {
+            let each = if idx + 1 == count {
+                format!("{} {{", each)
+            } else {
+                each
+            };
+            if idx == 0 {
+                self.line(each);
+            } else {
+                self.line_at(self.depth + 2, each);
+            }
+        }
+
+        //
+        // The block's braces are the innermost ones around its first clause, for a block of
+        // only assignments the first ones after its start
+        //
+        let brace = match block.conjunctions.first().and_then(|each| each.first()) {
+            Some(first) => self.source.innermost(first.position()),
+            None => self
+                .source
+                .braces
+                .iter()
+                .find(|brace| brace.open > position)
+                .copied(),
+        };
+
+        let mut items = vec![];
+        for each in &block.assignments {
+            items.push((at(&each.location), Item::Assignment(each)));
+        }
+        for each in &block.conjunctions {
+            let at = each.first().map_or(position, Node::position);
+            items.push((at, Item::Clauses(each.as_slice())));
+        }
+        items.sort_by_key(|(at, _)| *at);
+
+        self.depth += 1;
+        for (at, item) in items {
+            self.comments_before(at.0);
+            self.gap(at.0);
+            match item {
+                Item::Assignment(assignment) => self.assignment(assignment),
+                Item::Clauses(disjunctions) => self.disjunctions(disjunctions),
+            }
+        }
+        if let Some(brace) = brace {
+            self.comments_before(brace.close.0);
+        }
+        self.depth -= 1;
+        self.line("}".to_string());
+    }
+}
+
+///
+/// A clause that can appear in a disjunction inside a block
+///
+trait Node {
+    fn position(&self) -> Position;
+
+    ///
+    /// The clause on a single line, None for clauses printed as blocks
+    ///
+    fn inline(&self) -> Option<String>;
+
+    fn print(&self, printer: &mut Printer);
+}
+
+impl<'loc> Node for GuardClause<'loc> {
+    fn position(&self) -> Position {
+        match self {
+            GuardClause::Clause(clause) => at(&clause.access_clause.location),
+            GuardClause::NamedRule(named) => at(&named.location),
+            GuardClause::ParameterizedNamedRule(parameterized) => {
+                at(&parameterized.named_rule.location)
+            }
+            GuardClause::BlockClause(block) => at(&block.location),
+            GuardClause::WhenBlock(conditions, _) => conditions_position(conditions),
+        }
+    }
+
+    fn inline(&self) -> Option<String> {
+        match self {
+            GuardClause::BlockClause(_) | GuardClause::WhenBlock(..) => None,
+            _ => Some(self.to_string()),
+        }
+    }
+
+    fn print(&self, printer: &mut Printer) {
+        match self {
+            GuardClause::BlockClause(block) => {
+                let mut header = block.query.to_string();
+                if block.not_empty {
+                    header.push_str(" !empty");
+                }
+                printer.block(vec![header], at(&block.location), &block.block);
+            }
+            GuardClause::WhenBlock(conditions, block) => printer.block(
+                when_header("", conditions),
+                conditions_position(conditions),
+                block,
+            ),
+            _ => printer.line(self.to_string()),
+        }
+    }
+}
+
+impl<'loc> Node for RuleClause<'loc> {
+    fn position(&self) -> Position {
+        match self {
+            RuleClause::Clause(clause) => clause.position(),
+            RuleClause::WhenBlock(conditions, _) => conditions_position(conditions),
+            RuleClause::TypeBlock(type_block) => type_block.position(),
+        }
+    }
+
+    fn inline(&self) -> Option<String> {
+        match self {
+            RuleClause::Clause(clause) => clause.inline(),
+            _ => None,
+        }
+    }
+
+    fn print(&self, printer: &mut Printer) {
+        match self {
+            RuleClause::Clause(clause) => clause.print(printer),
+            RuleClause::WhenBlock(conditions, block) => printer.block(
+                when_header("", conditions),
+                conditions_position(conditions),
+                block,
+            ),
+            RuleClause::TypeBlock(type_block) => type_block.print(printer),
+        }
+    }
+}
+
+impl<'loc> Node for TypeBlock<'loc> {
+    fn position(&self) -> Position {
+        //
+        // The type name's location is kept on the clause that selects resources of the type
+        //
+        match self.query.get(2) {
+            Some(QueryPart::Filter(_, conjunctions)) => conjunctions
+                .first()
+                .and_then(|each| each.first())
+                .map_or((0, 0), Node::position),
+            _ => (0, 0),
+        }
+    }
+
+    fn inline(&self) -> Option<String> {
+        None
+    }
+
+    fn print(&self, printer: &mut Printer) {
+        let header = match &self.conditions {
+            Some(conditions) => when_header(&format!("{} ", self.type_name), conditions),
+            None => vec![self.type_name.clone()],
+        };
+        printer.block(header, self.position(), &self.block);
+    }
+}
+
+fn at(location: &FileLocation) -> Position {
+    (location.line, location.column)
+}
+
+fn conditions_position(conditions: &WhenConditions) -> Position {
+    conditions
+        .first()
+        .and_then(|each| each.first())
+        .map_or((0, 0), |first| match first {
+            WhenGuardClause::Clause(clause) => at(&clause.access_clause.location),
+            WhenGuardClause::NamedRule(named) => at(&named.location),
+            WhenGuardClause::ParameterizedNamedRule(parameterized) => {
+                at(&parameterized.named_rule.location)
+            }
+        })
+}
+
+//
+// `when` with its conditions, each conjunction after the first on its own line. A named rule
+// must be followed by a new line, an `or` or the opening brace, so they can not share one.
+//
+fn when_header(prefix: &str, conditions: &WhenConditions) -> Vec<String> {
+    conditions
+        .iter()
+        .enumerate()
+        .map(|(idx, disjunctions)| {
+            let joined = disjunctions
+                .iter()
+                .map(|each| each.to_string())
+                .collect::<Vec<_>>()
+                .join(" or ");
+            if idx == 0 {
+                format!("{}when {}", prefix, joined)
+            } else {
+                joined
+            }
+        })
+        .collect()
+}
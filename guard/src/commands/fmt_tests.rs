@@ -0,0 +1,125 @@
+use super::*;
+
+#[test]
+fn test_format_canonical_form() -> Result<()> {
+    let rules = r#"let buckets := Resources.*[ Type == "AWS::S3::Bucket"  ]
+let allowed = [ "aws:kms",
+    "AES256" ]
+rule s3_encrypted WHEN %buckets NOT EMPTY
+{
+  %buckets.Properties.BucketEncryption EXISTS
+  %buckets.Properties.BucketEncryption.ServerSideEncryptionConfiguration[*].ServerSideEncryptionByDefault.SSEAlgorithm IN %allowed
+}
+rule s3_tagged when s3_encrypted {
+  AWS::S3::Bucket Properties.Tags[*].Key != /^aws:/
+  %buckets[*].Properties.VersioningConfiguration.Status == 'Enabled' OR
+      %buckets[*].Properties.ObjectLockEnabled == true
+}
+"#;
+    let expected = r#"let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+let allowed = ['aws:kms', 'AES256']
+
+rule s3_encrypted when %buckets !empty {
+    %buckets.Properties.BucketEncryption exists
+    %buckets.Properties.BucketEncryption.ServerSideEncryptionConfiguration[*].ServerSideEncryptionByDefault.SSEAlgorithm in %allowed
+}
+
+rule s3_tagged when s3_encrypted {
+    AWS::S3::Bucket {
+        Properties.Tags[*].Key != /^aws:/
+    }
+    %buckets.Properties.VersioningConfiguration.Status == 'Enabled' or
+    %buckets.Properties.ObjectLockEnabled == true
+}
+"#;
+    assert_eq!(format_rules("s3.guard", rules)?, expected);
+    assert_eq!(format_rules("s3.guard", expected)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_format_keeps_comments() -> Result<()> {
+    let rules = r#"#
+# Buckets in the template
+#
+let buckets = Resources.*[
+    Type == 'AWS::S3::Bucket'   # only buckets
+    # that are not retained
+    DeletionPolicy != 'Retain'
+]
+
+
+rule s3_logging when %buckets !empty {   # skips templates without buckets
+    # logging must be on
+    %buckets.Properties.LoggingConfiguration exists
+
+    # and go to a bucket
+    %buckets.Properties.LoggingConfiguration.DestinationBucketName !empty
+    # nothing else to check
+}
+# end
+"#;
+    let expected = r#"#
+# Buckets in the template
+#
+let buckets = Resources.*[
+    Type == 'AWS::S3::Bucket' # only buckets
+    # that are not retained
+    DeletionPolicy != 'Retain'
+]
+
+rule s3_logging when %buckets !empty { # skips templates without buckets
+    # logging must be on
+    %buckets.Properties.LoggingConfiguration exists
+
+    # and go to a bucket
+    %buckets.Properties.LoggingConfiguration.DestinationBucketName !empty
+    # nothing else to check
+}
+# end
+"#;
+    let formatted = format_rules("logging.guard", rules)?;
+    assert_eq!(formatted, expected);
+    assert_eq!(format_rules("logging.guard", &formatted)?, formatted);
+
+    let only_comments = "# nothing here yet   \n\n# later\n";
+    assert_eq!(
+        format_rules("empty.guard", only_comments)?,
+        "# nothing here yet\n\n# later\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_format_values_and_queries_round_trip() -> Result<()> {
+    let rules = r#"let values = [1, -2, 2.5, 1.0e+30, "it's", /^arn:aws:s3:::[^\/]+/, {Key: 'a', 'odd key': null}, r[1,10)]
+let keys = Resources[ name ].Properties.'Fn::GetAtt'[0][ k | keys not in ['a'] ]
+rule parameterized(resources, allowed) {
+    some %resources.Properties.Tags[*] {
+        let key = Key
+        %key in %allowed <<tag keys must be allowed>>
+    }
+    NOT %resources.Properties.Name IS_STRING or %resources[ Type == 'x' ] !empty
+}
+rule calls {
+    parameterized(%keys, ['a', 'b'])
+    !calls_again <<must not>>
+    when parameterized(%keys, 'c') { this.Region == 'us-east-1' }
+}
+"#;
+    let formatted = format_rules("values.guard", rules)?;
+    assert!(formatted.contains(
+        r#"let values = [1, -2, 2.5, 1e+30, "it's", /^arn:aws:s3:::[^\/]+/, {Key: 'a', 'odd key': null}, r[1,10)]"#
+    ));
+    assert!(formatted.contains(
+        "let keys = Resources[ name ].Properties['Fn::GetAtt'][0][ k | keys not in ['a'] ]"
+    ));
+    assert!(formatted
+        .contains("not %resources.Properties.Name is_string or %resources[ Type == 'x' ] !empty"));
+    assert!(formatted.contains("    not calls_again <<must not>>\n"));
+    assert!(formatted.contains("    when parameterized(%keys, 'c') {\n        this.Region"));
+    assert_eq!(format_rules("values.guard", &formatted)?, formatted);
+
+    assert!(format_rules("broken.guard", "rule broken {\n    Resources.* {\n").is_err());
+    Ok(())
+}
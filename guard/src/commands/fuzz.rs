@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use clap::{App, Arg, ArgMatches};
+
+use crate::command::Command;
+use crate::commands::evaluate::{evaluate, Outcome};
+use crate::commands::exit_code::{fail_on_from, ExitCodeTracker};
+use crate::commands::files::{
+    get_files_with_filter, iterate_over, read_file_content, IgnoreFilter,
+};
+use crate::commands::fuzz::mutation::{mutants, query_keys};
+use crate::commands::test::TestSpec;
+use crate::commands::validate::validate_path;
+use crate::commands::{FUZZ, MAX_INPUTS, RULES_FILE, TEST_DATA, TEST_FAILURE_STATUS_CODE, VERBOSE};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::RulesFile;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::{Result, Status};
+
+mod mutation;
+
+const DEFAULT_MAX_INPUTS: usize = 500;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Fuzz {}
+
+impl Fuzz {
+    pub fn new() -> Self {
+        Fuzz {}
+    }
+}
+
+impl Command for Fuzz {
+    fn name(&self) -> &'static str {
+        FUZZ
+    }
+
+    fn command(&self) -> App<'static, 'static> {
+        App::new(FUZZ)
+            .about(r#"Evaluates a Guard rules file against variations of the inputs in its unit tests to
+find inputs that cause evaluation errors, panics, or rule statuses that contradict the
+fuzz expectations declared for a test case. Variations remove, empty and change the type
+of the values at keys that the rules query.
+"#)
+            .arg(Arg::with_name(RULES_FILE.0)
+                .long(RULES_FILE.0)
+                .short(RULES_FILE.1)
+                .takes_value(true)
+                .required(true)
+                .help("Provide a rules file"))
+            .arg(Arg::with_name(TEST_DATA.0)
+                .long(TEST_DATA.0)
+                .short(TEST_DATA.1)
+                .takes_value(true)
+                .required(true)
+                .help("Provide a file or dir for unit test files whose inputs are fuzzed"))
+            .arg(Arg::with_name(MAX_INPUTS.0)
+                .long(MAX_INPUTS.0)
+                .short(MAX_INPUTS.1)
+                .takes_value(true)
+                .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Maximum number of generated inputs evaluated per test case, defaults to 500"))
+            .arg(Arg::with_name(VERBOSE.0)
+                .long(VERBOSE.0)
+                .short(VERBOSE.1)
+                .required(false)
+                .help("Print the rule statuses for every generated input"))
+    }
+
+    fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
+        let mut exit_code = ExitCodeTracker::new(
+            fail_on_from(None::<std::iter::Empty<&str>>),
+            TEST_FAILURE_STATUS_CODE,
+        );
+        let file = app.value_of(RULES_FILE.0).unwrap();
+        let data = app.value_of(TEST_DATA.0).unwrap();
+        let max_inputs = app
+            .value_of(MAX_INPUTS.0)
+            .map_or(Ok(DEFAULT_MAX_INPUTS), str::parse::<usize>)
+            .map_err(|e| Error::new(ErrorKind::ParseError(e.to_string())))?;
+        let verbose = app.is_present(VERBOSE.0);
+
+        validate_path(file)?;
+        let ignore = IgnoreFilter::new(std::iter::empty())?;
+        let test_files = get_files_with_filter(
+            data,
+            crate::commands::files::regular_ordering,
+            &ignore,
+            |entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .map(|name| {
+                        [".json", ".yaml", ".JSON", ".YAML", ".yml", ".jsn"]
+                            .iter()
+                            .any(|ext| name.ends_with(ext))
+                    })
+                    .unwrap_or(false)
+            },
+        )?;
+
+        let content = read_file_content(File::open(file)?)?;
+        let span = crate::rules::parser::Span::new_extra(&content, file);
+        let rules = match crate::rules::parser::rules_file(span) {
+            Ok(rules) => rules,
+            Err(e) => {
+                eprintln!("Parse Error on ruleset file {}", e);
+                exit_code.record_parse_error();
+                return Ok(exit_code.exit_code());
+            }
+        };
+
+        fuzz_with_data(&test_files, &rules, max_inputs, verbose, &mut exit_code)?;
+        Ok(exit_code.exit_code())
+    }
+}
+
+fn fuzz_with_data(
+    test_files: &[PathBuf],
+    rules: &RulesFile<'_>,
+    max_inputs: usize,
+    verbose: bool,
+    exit_code: &mut ExitCodeTracker,
+) -> Result<()> {
+    let keys = query_keys(rules);
+    let mut test_counter = 1;
+    for specs in iterate_over(test_files, |data, path| {
+        let dir = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
+        match serde_yaml::from_str::<Vec<TestSpec>>(&data) {
+            Ok(specs) => Ok((specs, dir)),
+            Err(_) => match serde_json::from_str::<Vec<TestSpec>>(&data) {
+                Ok(specs) => Ok((specs, dir)),
+                Err(e) => Err(Error::new(ErrorKind::ParseError(format!(
+                    "Unable to process data in file {}, Error {},",
+                    path.display(),
+                    e
+                )))),
+            },
+        }
+    }) {
+        let (specs, dir) = match specs {
+            Ok(specs) => specs,
+            Err(e) => {
+                eprintln!("Error processing {}", e);
+                exit_code.record_error();
+                continue;
+            }
+        };
+        for mut each in specs {
+            println!("Fuzzing Test Case #{}", test_counter);
+            test_counter += 1;
+            if let Some(name) = &each.name {
+                println!("Name: {}", name);
+            }
+            let root = each.resolve_input(&dir);
+            let allowed = each
+                .fuzz
+                .rules
+                .iter()
+                .map(|(rule, statuses)| Ok((rule.as_str(), statuses.statuses()?)))
+                .collect::<Result<HashMap<&str, Vec<Status>>>>();
+            let (root, allowed) = match (root, allowed) {
+                (Ok(root), Ok(allowed)) => (root, allowed),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("Error processing {}", e);
+                    exit_code.record_error();
+                    continue;
+                }
+            };
+
+            let document: serde_json::Value = match (&root).try_into() {
+                Ok((_, document)) => document,
+                Err(e) => {
+                    eprintln!("Error processing {}", e);
+                    exit_code.record_error();
+                    continue;
+                }
+            };
+            let generated = mutants(&document, &keys).take(max_inputs).map(|mutant| {
+                (
+                    format!("{}", mutant),
+                    PathAwareValue::try_from(mutant.apply(&document)),
+                )
+            });
+            let mut evaluated = 0;
+            let mut findings = vec![];
+            for (description, input) in
+                std::iter::once(("unmodified input".to_string(), Ok(root))).chain(generated)
+            {
+                evaluated += 1;
+                let input = match input {
+                    Ok(input) => input,
+                    Err(e) => {
+                        findings.push(format!("{}: unable to build input {}", description, e));
+                        continue;
+                    }
+                };
+                match evaluate(rules, &input) {
+                    Outcome::Statuses(statuses) => {
+                        if verbose {
+                            println!("  {}", description);
+                            for (rule, status) in &statuses {
+                                println!("    {}: {}", rule, status);
+                            }
+                        }
+                        for (rule, status) in statuses {
+                            if let Some(expected) = allowed.get(rule.as_str()) {
+                                if !expected.contains(&status) {
+                                    findings.push(format!(
+                                        "{}: rule {} status {}, expected one of {}",
+                                        description,
+                                        rule,
+                                        status,
+                                        expected
+                                            .iter()
+                                            .map(|each| format!("{}", each))
+                                            .collect::<Vec<_>>()
+                                            .join(", ")
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    Outcome::Error(e) => {
+                        findings.push(format!("{}: evaluation error {}", description, e))
+                    }
+                    Outcome::Panic(message) => {
+                        findings.push(format!("{}: panicked {}", description, message))
+                    }
+                }
+            }
+
+            println!(
+                "  Evaluated {} inputs, {} findings",
+                evaluated,
+                findings.len()
+            );
+            for finding in &findings {
+                println!("  {}", finding);
+            }
+            exit_code.record_status(if findings.is_empty() {
+                Status::PASS
+            } else {
+                Status::FAIL
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+use std::fmt::Formatter;
+
+use serde_json::{Map, Value};
+
+use crate::rules::exprs::{
+    AccessClause, Block, GuardClause, LetExpr, LetValue, QueryPart, RuleClause, RulesFile,
+    WhenConditions, WhenGuardClause,
+};
+
+///
+/// The keys referenced by the queries in a rules file, these are the parts of the input the
+/// rules look at and the ones worth mutating. Variables are not included, the queries they
+/// are assigned from are.
+///
+pub(crate) fn query_keys(rules: &RulesFile<'_>) -> HashSet<String> {
+    let mut keys = HashSet::new();
+    assignments(&rules.assignments, &mut keys);
+    for rule in rules
+        .guard_rules
+        .iter()
+        .chain(rules.parameterized_rules.iter().map(|each| &each.rule))
+    {
+        if let Some(conditions) = &rule.conditions {
+            when_conditions(conditions, &mut keys);
+        }
+        assignments(&rule.block.assignments, &mut keys);
+        for clause in rule.block.conjunctions.iter().flatten() {
+            match clause {
+                RuleClause::Clause(clause) => guard_clause(clause, &mut keys),
+                RuleClause::WhenBlock(conditions, block) => {
+                    when_conditions(conditions, &mut keys);
+                    guard_block(block, &mut keys);
+                }
+                RuleClause::TypeBlock(type_block) => {
+                    query(&type_block.query, &mut keys);
+                    if let Some(conditions) = &type_block.conditions {
+                        when_conditions(conditions, &mut keys);
+                    }
+                    guard_block(&type_block.block, &mut keys);
+                }
+            }
+        }
+    }
+    keys
+}
+
+fn assignments(assignments: &[LetExpr<'_>], keys: &mut HashSet<String>) {
+    for each in assignments {
+        let_value(&each.value, keys);
+    }
+}
+
+fn let_value(value: &LetValue<'_>, keys: &mut HashSet<String>) {
+    match value {
+        LetValue::Value(_) => {}
+        LetValue::AccessClause(access) => query(&access.query, keys),
+        LetValue::FunctionCall(function) => {
+            for each in &function.parameters {
+                let_value(each, keys);
+            }
+        }
+    }
+}
+
+fn query(parts: &[QueryPart<'_>], keys: &mut HashSet<String>) {
+    for part in parts {
+        match part {
+            QueryPart::Key(key) if !part.is_variable() => {
+                keys.insert(key.clone());
+            }
+            QueryPart::MapKeyFilter(_, filter) => let_value(&filter.compare_with, keys),
+            QueryPart::Filter(_, conjunctions) => {
+                for each in conjunctions.iter().flatten() {
+                    guard_clause(each, keys);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn access_clause(clause: &AccessClause<'_>, keys: &mut HashSet<String>) {
+    query(&clause.query.query, keys);
+    if let Some(compare_with) = &clause.compare_with {
+        let_value(compare_with, keys);
+    }
+}
+
+fn when_conditions(conditions: &WhenConditions<'_>, keys: &mut HashSet<String>) {
+    for each in conditions.iter().flatten() {
+        match each {
+            WhenGuardClause::Clause(gac) => access_clause(&gac.access_clause, keys),
+            WhenGuardClause::NamedRule(_) => {}
+            WhenGuardClause::ParameterizedNamedRule(prc) => {
+                for parameter in &prc.parameters {
+                    let_value(parameter, keys);
+                }
+            }
+        }
+    }
+}
+
+fn guard_block(block: &Block<'_, GuardClause<'_>>, keys: &mut HashSet<String>) {
+    assignments(&block.assignments, keys);
+    for each in block.conjunctions.iter().flatten() {
+        guard_clause(each, keys);
+    }
+}
+
+fn guard_clause(clause: &GuardClause<'_>, keys: &mut HashSet<String>) {
+    match clause {
+        GuardClause::Clause(gac) => access_clause(&gac.access_clause, keys),
+        GuardClause::NamedRule(_) => {}
+        GuardClause::ParameterizedNamedRule(prc) => {
+            for parameter in &prc.parameters {
+                let_value(parameter, keys);
+            }
+        }
+        GuardClause::BlockClause(block_clause) => {
+            query(&block_clause.query.query, keys);
+            guard_block(&block_clause.block, keys);
+        }
+        GuardClause::WhenBlock(conditions, block) => {
+            when_conditions(conditions, keys);
+            guard_block(block, keys);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Mutation {
+    Remove,
+    Replace(Value),
+}
+
+///
+/// A single change to an input document, the value at `path` is either removed or replaced
+///
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Mutant {
+    pub(crate) path: Vec<Segment>,
+    pub(crate) mutation: Mutation,
+}
+
+impl Mutant {
+    pub(crate) fn apply(&self, document: &Value) -> Value {
+        let mut mutated = document.clone();
+        if let Some((last, parents)) = self.path.split_last() {
+            let parent = parents
+                .iter()
+                .try_fold(&mut mutated, |current, segment| child(current, segment));
+            if let Some(parent) = parent {
+                match (&self.mutation, parent) {
+                    (Mutation::Remove, Value::Object(map)) => {
+                        if let Segment::Key(key) = last {
+                            map.remove(key);
+                        }
+                    }
+                    (Mutation::Remove, Value::Array(values)) => {
+                        if let Segment::Index(index) = last {
+                            if *index < values.len() {
+                                values.remove(*index);
+                            }
+                        }
+                    }
+                    (Mutation::Replace(replacement), parent) => {
+                        if let Some(current) = child(parent, last) {
+                            *current = replacement.clone();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        mutated
+    }
+}
+
+fn child<'v>(value: &'v mut Value, segment: &Segment) -> Option<&'v mut Value> {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => map.get_mut(key),
+        (Segment::Index(index), Value::Array(values)) => values.get_mut(*index),
+        _ => None,
+    }
+}
+
+impl std::fmt::Display for Mutant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pointer = self
+            .path
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+                Segment::Index(index) => format!("/{}", index),
+            })
+            .collect::<String>();
+        match &self.mutation {
+            Mutation::Remove => write!(f, "removed {}", pointer),
+            Mutation::Replace(value) => {
+                let value = value.to_string();
+                match value.char_indices().nth(40) {
+                    Some((end, _)) => write!(f, "replaced {} with {}...", pointer, &value[..end]),
+                    None => write!(f, "replaced {} with {}", pointer, value),
+                }
+            }
+        }
+    }
+}
+
+///
+/// All single changes to `document` at the values of the keys in `keys`. Each such value is
+/// removed, set to null, emptied, wrapped in a list or unwrapped from one, and changed to a
+/// scalar of another type. Mutants are generated as they are iterated, in document order.
+///
+pub(crate) fn mutants<'d>(document: &'d Value, keys: &'d HashSet<String>) -> Mutants<'d> {
+    Mutants {
+        keys,
+        pending: vec![(vec![], document)],
+        ready: vec![].into_iter(),
+    }
+}
+
+pub(crate) struct Mutants<'d> {
+    keys: &'d HashSet<String>,
+    //
+    // The values still to visit with their paths, the next one to visit is last
+    //
+    pending: Vec<(Vec<Segment>, &'d Value)>,
+    //
+    // The mutants of the last value visited that are not returned yet
+    //
+    ready: std::vec::IntoIter<Mutant>,
+}
+
+impl<'d> Iterator for Mutants<'d> {
+    type Item = Mutant;
+
+    fn next(&mut self) -> Option<Mutant> {
+        loop {
+            if let Some(mutant) = self.ready.next() {
+                return Some(mutant);
+            }
+            let (path, value) = self.pending.pop()?;
+            match value {
+                Value::Object(map) => {
+                    for (key, each) in map.iter().rev() {
+                        let mut path = path.clone();
+                        path.push(Segment::Key(key.clone()));
+                        self.pending.push((path, each));
+                    }
+                }
+                Value::Array(values) => {
+                    for (index, each) in values.iter().enumerate().rev() {
+                        let mut path = path.clone();
+                        path.push(Segment::Index(index));
+                        self.pending.push((path, each));
+                    }
+                }
+                _ => {}
+            }
+            if let Some(Segment::Key(key)) = path.last() {
+                if self.keys.contains(key) {
+                    let mut mutants = vec![Mutant {
+                        path: path.clone(),
+                        mutation: Mutation::Remove,
+                    }];
+                    mutants.extend(replacements(value).into_iter().map(|replacement| Mutant {
+                        path: path.clone(),
+                        mutation: Mutation::Replace(replacement),
+                    }));
+                    self.ready = mutants.into_iter();
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn replacements(value: &Value) -> Vec<Value> {
+    let mut replacements = vec![Value::Null];
+    match value {
+        Value::Object(map) if !map.is_empty() => replacements.push(Value::Object(Map::new())),
+        Value::Array(values) => {
+            if let Some(first) = values.first() {
+                replacements.push(first.clone());
+                replacements.push(Value::Array(vec![]));
+            }
+        }
+        Value::String(string) if !string.is_empty() => {
+            replacements.push(Value::String(String::new()))
+        }
+        _ => {}
+    }
+    if !value.is_array() {
+        replacements.push(Value::Array(vec![value.clone()]));
+    }
+    replacements.push(match value {
+        Value::String(_) => Value::from(0),
+        Value::Number(number) => Value::String(number.to_string()),
+        Value::Bool(flag) => Value::String(flag.to_string()),
+        Value::Null | Value::Object(_) | Value::Array(_) => Value::String("fuzz".to_string()),
+    });
+    replacements.retain(|each| each != value);
+    replacements
+}
+
+#[cfg(test)]
+#[path = "mutation_tests.rs"]
+mod mutation_tests;
@@ -0,0 +1,100 @@
+use super::*;
+use crate::rules::Result;
+use serde_json::json;
+use std::convert::TryFrom;
+
+const RULES: &str = r#"let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+
+rule S3_ENCRYPTION when %buckets !empty {
+    %buckets.Properties.BucketEncryption exists
+    %buckets.Properties.Tags[*] {
+        Key != /secret/
+    }
+}
+"#;
+
+#[test]
+fn test_query_keys() -> Result<()> {
+    let rules = RulesFile::try_from(RULES)?;
+    let mut keys = query_keys(&rules).into_iter().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "BucketEncryption",
+            "Key",
+            "Properties",
+            "Resources",
+            "Tags",
+            "Type"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_replacements() {
+    assert_eq!(
+        replacements(&json!(["a", "b"])),
+        vec![json!(null), json!("a"), json!([]), json!("fuzz")]
+    );
+    assert_eq!(
+        replacements(&json!({"a": 1})),
+        vec![json!(null), json!({}), json!([{"a": 1}]), json!("fuzz")]
+    );
+    assert_eq!(
+        replacements(&json!("AWS::S3::Bucket")),
+        vec![json!(null), json!(""), json!(["AWS::S3::Bucket"]), json!(0)]
+    );
+    assert_eq!(
+        replacements(&json!(null)),
+        vec![json!([null]), json!("fuzz")]
+    );
+}
+
+#[test]
+fn test_mutants_only_touch_queried_keys() {
+    let document = json!({
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Metadata": {"Owner": "team"},
+                "Properties": {"Tags": [{"Key": "a/b", "Value": "c"}]}
+            }
+        }
+    });
+    let keys = ["Resources", "Tags", "Key"]
+        .iter()
+        .map(|each| each.to_string())
+        .collect::<HashSet<String>>();
+    let mutants = mutants(&document, &keys).collect::<Vec<_>>();
+    let described = mutants
+        .iter()
+        .map(|each| format!("{}", each))
+        .collect::<Vec<_>>();
+
+    assert!(described.contains(&"removed /Resources".to_string()));
+    assert!(described.contains(&"replaced /Resources/Bucket/Properties/Tags with []".to_string()));
+    assert!(described.contains(&"removed /Resources/Bucket/Properties/Tags/0/Key".to_string()));
+    assert!(mutants.iter().all(|each| {
+        !each.path.contains(&Segment::Key("Metadata".to_string()))
+            && !each.path.contains(&Segment::Key("Type".to_string()))
+    }));
+
+    let emptied = mutants
+        .iter()
+        .find(|each| format!("{}", each) == "replaced /Resources/Bucket/Properties/Tags with []")
+        .unwrap()
+        .apply(&document);
+    assert_eq!(
+        emptied["Resources"]["Bucket"]["Properties"]["Tags"],
+        json!([])
+    );
+    assert_eq!(
+        emptied["Resources"]["Bucket"]["Metadata"],
+        json!({"Owner": "team"})
+    );
+
+    let removed = mutants[0].apply(&document);
+    assert_eq!(removed, json!({}));
+}
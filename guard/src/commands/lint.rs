@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use clap::{App, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::command::Command;
+use crate::commands::files::{get_files_with_filter, read_file_content, IgnoreFilter};
+use crate::commands::lint::checks::{definitions, lint, Finding, Severity, CHECKS};
+use crate::commands::{
+    APP_NAME, APP_VERSION, FAILURE_STATUS_CODE, LINT, OUTPUT_FORMAT, PARSING_ERROR_STATUS_CODE,
+    RULES, RULE_FILE_SUPPORTED_EXTENSIONS, SUCCESS_STATUS_CODE,
+};
+use crate::rules::parser::{rules_file, rules_file_exprs, Span};
+use crate::rules::Result;
+
+pub(crate) mod checks;
+
+lazy_static! {
+    static ref DISABLE: Regex =
+        Regex::new(r"#\s*guard-lint:\s*disable(?:=([A-Za-z0-9_\-,\s]+))?").unwrap();
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Lint {}
+
+impl Lint {
+    pub fn new() -> Self {
+        Lint {}
+    }
+}
+
+#[derive(Serialize)]
+struct Reported<'f> {
+    file: &'f str,
+    #[serde(flatten)]
+    finding: &'f Finding,
+}
+
+impl Command for Lint {
+    fn name(&self) -> &'static str {
+        LINT
+    }
+
+    fn command(&self) -> App<'static, 'static> {
+        App::new(LINT)
+            .about(
+                r#"Checks Guard rules files for common mistakes without evaluating them, such as
+variables that are never used, references to rules that are not defined and regexes
+compared with > or <. Findings on a line are turned off with a comment on that line, or
+on its own line before it, like `# guard-lint: disable=G001,G004`. Without a list all
+checks are turned off for the line. Exits with 5 if any finding is an error.
+"#,
+            )
+            .arg(
+                Arg::with_name(RULES.0)
+                    .long(RULES.0)
+                    .short(RULES.1)
+                    .takes_value(true)
+                    .multiple(true)
+                    .required(true)
+                    .help("Provide a rules file or a directory of rules files"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_FORMAT.0)
+                    .long(OUTPUT_FORMAT.0)
+                    .short(OUTPUT_FORMAT.1)
+                    .takes_value(true)
+                    .possible_values(&["text", "json", "sarif"])
+                    .default_value("text")
+                    .help("Specify the format in which the findings should be displayed"),
+            )
+    }
+
+    fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
+        let ignore = IgnoreFilter::new(std::iter::empty())?;
+        let mut status = SUCCESS_STATUS_CODE;
+        let mut linted = vec![];
+        for file_or_dir in app.values_of(RULES.0).unwrap() {
+            let files = get_files_with_filter(
+                file_or_dir,
+                crate::commands::files::alpabetical,
+                &ignore,
+                |entry| {
+                    entry.file_name().to_str().is_some_and(|name| {
+                        RULE_FILE_SUPPORTED_EXTENSIONS
+                            .iter()
+                            .any(|ext| name.ends_with(ext))
+                    })
+                },
+            )?;
+            for file in files {
+                let name = file.display().to_string();
+                let content = read_file_content(File::open(&file)?)?;
+                match lint_rules(&name, &content) {
+                    Ok(findings) => linted.push((name, findings)),
+                    Err(e) => {
+                        eprintln!("Error parsing rules file {}, {}", name, e);
+                        status = PARSING_ERROR_STATUS_CODE;
+                    }
+                }
+            }
+        }
+
+        match app.value_of(OUTPUT_FORMAT.0) {
+            Some("json") => {
+                let reported = linted
+                    .iter()
+                    .flat_map(|(file, findings)| {
+                        findings
+                            .iter()
+                            .map(move |finding| Reported { file, finding })
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string_pretty(&reported)?);
+            }
+            Some("sarif") => println!("{}", serde_json::to_string_pretty(&sarif(&linted))?),
+            _ => {
+                for (file, findings) in &linted {
+                    for each in findings {
+                        println!(
+                            "{}:{}:{}: {}[{}] {}",
+                            file, each.line, each.column, each.severity, each.id, each.message
+                        );
+                    }
+                }
+            }
+        }
+
+        let errors = linted
+            .iter()
+            .flat_map(|(_, findings)| findings.iter())
+            .any(|each| each.severity == Severity::Error);
+        if errors && status == SUCCESS_STATUS_CODE {
+            status = FAILURE_STATUS_CODE;
+        }
+        Ok(status)
+    }
+}
+
+///
+/// Lints the rules in `content`, leaving out findings turned off by disable comments
+///
+pub(crate) fn lint_rules(name: &str, content: &str) -> Result<Vec<Finding>> {
+    let span = Span::new_extra(content, name);
+    let exprs = rules_file_exprs(span)?;
+    if exprs.is_empty() {
+        return Ok(vec![]);
+    }
+    let rules = rules_file(span)?;
+    let definitions = definitions(&exprs);
+    let turned_off = disabled(content);
+    Ok(lint(&rules, &definitions)
+        .into_iter()
+        .filter(|each| match turned_off.get(&each.line) {
+            Some(None) => false,
+            Some(Some(checks)) => !checks
+                .iter()
+                .any(|check| check == each.id || check == each.name),
+            None => true,
+        })
+        .collect())
+}
+
+//
+// Lines with findings turned off, a list of check ids or names or None for all checks. A
+// comment on a line of its own applies to the next line.
+//
+fn disabled(content: &str) -> HashMap<u32, Option<Vec<String>>> {
+    let mut disabled = HashMap::new();
+    for (idx, line) in content.lines().enumerate() {
+        let found = match DISABLE.captures(line) {
+            Some(found) => found,
+            None => continue,
+        };
+        let own_line = line[..found.get(0).unwrap().start()].trim().is_empty();
+        let line_number = idx as u32 + if own_line { 2 } else { 1 };
+        let checks = found.get(1).map(|checks| {
+            checks
+                .as_str()
+                .split(',')
+                .map(|each| each.trim().to_string())
+                .filter(|each| !each.is_empty())
+                .collect()
+        });
+        disabled.insert(line_number, checks);
+    }
+    disabled
+}
+
+fn sarif(linted: &[(String, Vec<Finding>)]) -> serde_json::Value {
+    let rules = CHECKS
+        .iter()
+        .map(|check| {
+            json!({
+                "id": check.id,
+                "name": check.name,
+                "shortDescription": { "text": check.description },
+                "defaultConfiguration": { "level": check.severity.to_string() },
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = linted
+        .iter()
+        .flat_map(|(file, findings)| {
+            findings.iter().map(move |each| {
+                json!({
+                    "ruleId": each.id,
+                    "ruleIndex": CHECKS.iter().position(|check| check.id == each.id),
+                    "level": each.severity.to_string(),
+                    "message": { "text": each.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file },
+                            "region": { "startLine": each.line, "startColumn": each.column },
+                        }
+                    }],
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": APP_NAME,
+                    "version": APP_VERSION,
+                    "informationUri": "https://github.com/aws-cloudformation/cloudformation-guard",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+#[path = "lint_tests.rs"]
+mod lint_tests;
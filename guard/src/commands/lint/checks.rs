@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::commands::fmt::without_locations;
+use crate::rules::exprs::{
+    AccessClause, AccessQuery, Block, FileLocation, GuardClause, GuardNamedRuleClause, LetExpr,
+    LetValue, QueryPart, Rule, RuleClause, RulesFile, WhenConditions, WhenGuardClause,
+};
+use crate::rules::parser::Exprs;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::values::CmpOperator;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+///
+/// A check in the catalog. Findings and disable comments refer to it by `id` or `name`.
+///
+#[derive(Debug, PartialEq)]
+pub(crate) struct Check {
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) severity: Severity,
+    pub(crate) description: &'static str,
+}
+
+pub(crate) const UNUSED_VARIABLE: Check = Check {
+    id: "G001",
+    name: "unused-variable",
+    severity: Severity::Warning,
+    description: "A let or rule parameter that is never referenced",
+};
+
+pub(crate) const UNDEFINED_RULE: Check = Check {
+    id: "G002",
+    name: "undefined-rule",
+    severity: Severity::Error,
+    description: "A named rule reference to a rule that is not defined in the file, or that is called with or without parameters when it takes the other",
+};
+
+pub(crate) const REGEX_ORDERING: Check = Check {
+    id: "G003",
+    name: "regex-ordering",
+    severity: Severity::Error,
+    description: "A regex compared with >, >=, < or <=, which never matches",
+};
+
+pub(crate) const NULL_COMPARISON: Check = Check {
+    id: "G004",
+    name: "null-comparison",
+    severity: Severity::Warning,
+    description: "A comparison with null, which only matches explicit null values. Use exists or !exists to check whether a value is set",
+};
+
+pub(crate) const ALWAYS_TRUE_CONDITION: Check = Check {
+    id: "G005",
+    name: "always-true-condition",
+    severity: Severity::Warning,
+    description: "A when condition joining a clause and its negation with or, which always holds",
+};
+
+pub(crate) const DUPLICATE_RULE: Check = Check {
+    id: "G006",
+    name: "duplicate-rule",
+    severity: Severity::Error,
+    description: "A rule with the same name as a rule defined before it",
+};
+
+pub(crate) const SHADOWED_VARIABLE: Check = Check {
+    id: "G007",
+    name: "shadowed-variable",
+    severity: Severity::Warning,
+    description: "A let or rule parameter with the same name as one already in scope",
+};
+
+pub(crate) const UNDEFINED_VARIABLE: Check = Check {
+    id: "G008",
+    name: "undefined-variable",
+    severity: Severity::Error,
+    description: "A %variable reference with no let or rule parameter of that name in scope",
+};
+
+pub(crate) const CHECKS: [&Check; 8] = [
+    &UNUSED_VARIABLE,
+    &UNDEFINED_RULE,
+    &REGEX_ORDERING,
+    &NULL_COMPARISON,
+    &ALWAYS_TRUE_CONDITION,
+    &DUPLICATE_RULE,
+    &SHADOWED_VARIABLE,
+    &UNDEFINED_VARIABLE,
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Finding {
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+}
+
+type Position = (u32, u32);
+
+///
+/// Where a rule is defined. Rules do not record their location, these come from the top
+/// level expressions of the file.
+///
+#[derive(Debug)]
+pub(crate) struct Definition<'r> {
+    name: &'r str,
+    parameterized: bool,
+    at: Position,
+}
+
+pub(crate) fn definitions<'r>(exprs: &'r [(FileLocation<'_>, Exprs<'_>)]) -> Vec<Definition<'r>> {
+    exprs
+        .iter()
+        .filter_map(|(location, each)| {
+            let (name, parameterized) = match each {
+                Exprs::Rule(rule) => (rule.rule_name.as_str(), false),
+                Exprs::ParameterizedRule(rule) => (rule.rule.rule_name.as_str(), true),
+                _ => return None,
+            };
+            Some(Definition {
+                name,
+                parameterized,
+                at: (location.line, location.column),
+            })
+        })
+        .collect()
+}
+
+struct Variable<'r> {
+    name: &'r str,
+    at: Position,
+    parameter_of: Option<&'r str>,
+    used: bool,
+}
+
+struct Linter<'r> {
+    rules: HashMap<&'r str, &'r Definition<'r>>,
+    scopes: Vec<Vec<Variable<'r>>>,
+    findings: Vec<Finding>,
+}
+
+///
+/// Runs every check in [CHECKS] over `rules_file`, findings are ordered by where they are.
+///
+pub(crate) fn lint<'r>(
+    rules_file: &'r RulesFile<'r>,
+    definitions: &'r [Definition<'r>],
+) -> Vec<Finding> {
+    let mut linter = Linter {
+        rules: HashMap::new(),
+        scopes: vec![],
+        findings: vec![],
+    };
+    for each in definitions {
+        match linter.rules.get(each.name) {
+            Some(first) => linter.report(
+                &DUPLICATE_RULE,
+                each.at,
+                format!(
+                    "rule `{}` is already defined at line {}",
+                    each.name, first.at.0
+                ),
+            ),
+            None => {
+                linter.rules.insert(each.name, each);
+            }
+        }
+    }
+
+    linter.enter(rules_file.assignments.iter().map(Variable::from));
+    linter.assignments(&rules_file.assignments);
+    for each in &rules_file.guard_rules {
+        linter.rule(each);
+    }
+    for each in &rules_file.parameterized_rules {
+        let name = each.rule.rule_name.as_str();
+        let at = definitions
+            .iter()
+            .find(|definition| definition.parameterized && definition.name == name)
+            .map_or((1, 1), |definition| definition.at);
+        linter.enter(each.parameter_names.iter().map(|parameter| Variable {
+            name: parameter,
+            at,
+            parameter_of: Some(name),
+            used: false,
+        }));
+        linter.rule(&each.rule);
+        linter.leave();
+    }
+    linter.leave();
+
+    linter.findings.sort_by_key(|each| (each.line, each.column));
+    linter.findings
+}
+
+impl<'r> From<&'r LetExpr<'r>> for Variable<'r> {
+    fn from(assignment: &'r LetExpr<'r>) -> Self {
+        Variable {
+            name: &assignment.var,
+            at: at(&assignment.location),
+            parameter_of: None,
+            used: false,
+        }
+    }
+}
+
+impl<'r> Linter<'r> {
+    fn report(&mut self, check: &'static Check, at: Position, message: String) {
+        self.findings.push(Finding {
+            id: check.id,
+            name: check.name,
+            severity: check.severity,
+            message,
+            line: at.0,
+            column: at.1,
+        });
+    }
+
+    fn enter<I: Iterator<Item = Variable<'r>>>(&mut self, variables: I) {
+        self.scopes.push(vec![]);
+        for each in variables {
+            let previous = self
+                .scopes
+                .iter()
+                .flat_map(|scope| scope.iter())
+                .rev()
+                .find(|variable| variable.name == each.name)
+                .map(|variable| variable.at);
+            if let Some(previous) = previous {
+                self.report(
+                    &SHADOWED_VARIABLE,
+                    each.at,
+                    format!(
+                        "`{}` shadows the variable at line {}",
+                        each.name, previous.0
+                    ),
+                );
+            }
+            self.scopes.last_mut().unwrap().push(each);
+        }
+    }
+
+    fn leave(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for each in scope.into_iter().filter(|each| !each.used) {
+            let message = match each.parameter_of {
+                Some(rule) => format!("parameter `{}` of rule `{}` is never used", each.name, rule),
+                None => format!("`{}` is assigned but never used", each.name),
+            };
+            self.report(&UNUSED_VARIABLE, each.at, message);
+        }
+    }
+
+    fn reference(&mut self, name: &str, at: Position) {
+        let found = self
+            .scopes
+            .iter_mut()
+            .flat_map(|scope| scope.iter_mut())
+            .rev()
+            .find(|variable| variable.name == name);
+        match found {
+            Some(variable) => variable.used = true,
+            None => self.report(
+                &UNDEFINED_VARIABLE,
+                at,
+                format!("variable `%{}` is not defined", name),
+            ),
+        }
+    }
+
+    fn assignments(&mut self, assignments: &'r [LetExpr<'r>]) {
+        for each in assignments {
+            self.value(&each.value, at(&each.location));
+        }
+    }
+
+    fn value(&mut self, value: &'r LetValue<'r>, location: Position) {
+        match value {
+            LetValue::Value(_) => {}
+            LetValue::AccessClause(query) => self.query(query, location),
+            LetValue::FunctionCall(call) => {
+                for each in &call.parameters {
+                    self.value(each, at(&call.location));
+                }
+            }
+        }
+    }
+
+    fn query(&mut self, query: &'r AccessQuery<'r>, location: Position) {
+        for part in &query.query {
+            match part {
+                QueryPart::Key(key) => {
+                    if let Some(name) = key.strip_prefix('%') {
+                        self.reference(name, location);
+                    }
+                }
+                QueryPart::MapKeyFilter(_, filter) => self.value(&filter.compare_with, location),
+                QueryPart::Filter(_, conjunctions) => {
+                    for each in conjunctions.iter().flatten() {
+                        self.guard_clause(each);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn access_clause(&mut self, clause: &'r AccessClause<'r>) {
+        let location = at(&clause.location);
+        self.query(&clause.query, location);
+        let compare_with = match &clause.compare_with {
+            Some(compare_with) => compare_with,
+            None => return,
+        };
+        self.value(compare_with, location);
+
+        let (operator, _) = clause.comparator;
+        match compare_with {
+            LetValue::Value(PathAwareValue::Regex(_))
+                if matches!(
+                    operator,
+                    CmpOperator::Gt | CmpOperator::Ge | CmpOperator::Lt | CmpOperator::Le
+                ) =>
+            {
+                self.report(
+                    &REGEX_ORDERING,
+                    location,
+                    format!(
+                        "`{}` is compared with a regex using {}, which never matches",
+                        clause.query,
+                        match operator {
+                            CmpOperator::Gt => ">",
+                            CmpOperator::Ge => ">=",
+                            CmpOperator::Lt => "<",
+                            _ => "<=",
+                        }
+                    ),
+                )
+            }
+            LetValue::Value(PathAwareValue::Null(_)) if operator == CmpOperator::Eq => self.report(
+                &NULL_COMPARISON,
+                location,
+                format!(
+                    "`{}` is compared with null, use exists or !exists to check whether it is set",
+                    clause.query
+                ),
+            ),
+            _ => {}
+        }
+    }
+
+    fn named_rule(&mut self, clause: &GuardNamedRuleClause<'_>, parameterized: bool) {
+        let name = clause.dependent_rule.as_str();
+        let message = match self.rules.get(name) {
+            None => format!("rule `{}` is not defined", name),
+            Some(rule) if rule.parameterized && !parameterized => {
+                format!("rule `{}` takes parameters", name)
+            }
+            Some(rule) if !rule.parameterized && parameterized => {
+                format!("rule `{}` does not take parameters", name)
+            }
+            Some(_) => return,
+        };
+        self.report(&UNDEFINED_RULE, at(&clause.location), message);
+    }
+
+    fn guard_clause(&mut self, clause: &'r GuardClause<'r>) {
+        match clause {
+            GuardClause::Clause(clause) => self.access_clause(&clause.access_clause),
+            GuardClause::NamedRule(clause) => self.named_rule(clause, false),
+            GuardClause::ParameterizedNamedRule(clause) => {
+                self.named_rule(&clause.named_rule, true);
+                for each in &clause.parameters {
+                    self.value(each, at(&clause.named_rule.location));
+                }
+            }
+            GuardClause::BlockClause(clause) => {
+                self.query(&clause.query, at(&clause.location));
+                self.block(&clause.block, Self::guard_clause);
+            }
+            GuardClause::WhenBlock(conditions, block) => {
+                self.when(conditions);
+                self.block(block, Self::guard_clause);
+            }
+        }
+    }
+
+    fn when_clause(&mut self, clause: &'r WhenGuardClause<'r>) {
+        match clause {
+            WhenGuardClause::Clause(clause) => self.access_clause(&clause.access_clause),
+            WhenGuardClause::NamedRule(clause) => self.named_rule(clause, false),
+            WhenGuardClause::ParameterizedNamedRule(clause) => {
+                self.named_rule(&clause.named_rule, true);
+                for each in &clause.parameters {
+                    self.value(each, at(&clause.named_rule.location));
+                }
+            }
+        }
+    }
+
+    fn when(&mut self, conditions: &'r WhenConditions<'r>) {
+        for disjunctions in conditions {
+            for each in disjunctions {
+                self.when_clause(each);
+            }
+            let complements = disjunctions.iter().enumerate().find_map(|(idx, first)| {
+                disjunctions[idx + 1..]
+                    .iter()
+                    .find(|second| complement(first, second))
+                    .map(|second| (first, second))
+            });
+            if let Some((first, second)) = complements {
+                self.report(
+                    &ALWAYS_TRUE_CONDITION,
+                    position(second),
+                    format!(
+                        "condition is always true, `{}` is joined with its negation `{}`",
+                        describe(first),
+                        describe(second)
+                    ),
+                );
+            }
+        }
+    }
+
+    fn block<T>(&mut self, block: &'r Block<'r, T>, clause: fn(&mut Self, &'r T)) {
+        self.enter(block.assignments.iter().map(Variable::from));
+        self.assignments(&block.assignments);
+        for each in block.conjunctions.iter().flatten() {
+            clause(self, each);
+        }
+        self.leave();
+    }
+
+    fn rule(&mut self, rule: &'r Rule<'r>) {
+        if let Some(conditions) = &rule.conditions {
+            self.when(conditions);
+        }
+        self.block(&rule.block, Self::rule_clause);
+    }
+
+    fn rule_clause(&mut self, clause: &'r RuleClause<'r>) {
+        match clause {
+            RuleClause::Clause(clause) => self.guard_clause(clause),
+            RuleClause::WhenBlock(conditions, block) => {
+                self.when(conditions);
+                self.block(block, Self::guard_clause);
+            }
+            RuleClause::TypeBlock(type_block) => {
+                if let Some(conditions) = &type_block.conditions {
+                    self.when(conditions);
+                }
+                self.block(&type_block.block, Self::guard_clause);
+            }
+        }
+    }
+}
+
+fn at(location: &FileLocation<'_>) -> Position {
+    (location.line, location.column)
+}
+
+fn position(clause: &WhenGuardClause<'_>) -> Position {
+    match clause {
+        WhenGuardClause::Clause(clause) => at(&clause.access_clause.location),
+        WhenGuardClause::NamedRule(clause) => at(&clause.location),
+        WhenGuardClause::ParameterizedNamedRule(clause) => at(&clause.named_rule.location),
+    }
+}
+
+fn describe(clause: &WhenGuardClause<'_>) -> String {
+    match clause {
+        WhenGuardClause::Clause(clause) => {
+            let access = &clause.access_clause;
+            let (operator, negated) = access.comparator;
+            format!(
+                "{}{} {}{}",
+                if clause.negation { "not " } else { "" },
+                access.query,
+                if negated { "not " } else { "" },
+                operator.to_string().to_lowercase()
+            )
+        }
+        WhenGuardClause::NamedRule(clause) => format!(
+            "{}{}",
+            if clause.negation { "not " } else { "" },
+            clause.dependent_rule
+        ),
+        WhenGuardClause::ParameterizedNamedRule(clause) => clause.to_string(),
+    }
+}
+
+//
+// Two clauses are complements when one is the other with `not` in front, or they test the
+// same unary operator one negated and one not, like `exists` and `!exists`. `==` and `!=`
+// are not, both fail when the query selects nothing.
+//
+fn complement(first: &WhenGuardClause<'_>, second: &WhenGuardClause<'_>) -> bool {
+    match (first, second) {
+        (WhenGuardClause::Clause(first), WhenGuardClause::Clause(second)) => {
+            let (lhs, rhs) = (&first.access_clause, &second.access_clause);
+            let (operator, negated) = lhs.comparator;
+            let negations = if negated == rhs.comparator.1 {
+                first.negation != second.negation
+            } else {
+                first.negation == second.negation && operator.is_unary()
+            };
+            operator == rhs.comparator.0
+                && negations
+                && same(&lhs.query, &rhs.query)
+                && same(&lhs.compare_with, &rhs.compare_with)
+        }
+        (WhenGuardClause::NamedRule(first), WhenGuardClause::NamedRule(second)) => {
+            first.dependent_rule == second.dependent_rule && first.negation != second.negation
+        }
+        _ => false,
+    }
+}
+
+fn same<T: Serialize>(first: &T, second: &T) -> bool {
+    match (serde_json::to_value(first), serde_json::to_value(second)) {
+        (Ok(first), Ok(second)) => without_locations(first) == without_locations(second),
+        _ => false,
+    }
+}
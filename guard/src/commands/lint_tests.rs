@@ -0,0 +1,128 @@
+use super::*;
+
+fn ids(findings: &[Finding]) -> Vec<(&str, u32)> {
+    findings.iter().map(|each| (each.id, each.line)).collect()
+}
+
+#[test]
+fn test_lint_reports_each_check() -> Result<()> {
+    let rules = r#"let unused = 10
+let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+
+rule encrypted when %buckets exists or %buckets !exists {
+    let buckets = %buckets.Properties
+    %buckets.Name > /^prod/
+    %buckets.Name == null
+    versioned
+    tagged(%buckets)
+    %missing exists
+}
+
+rule encrypted {
+    Resources exists
+}
+
+rule tagged(resources, keys) {
+    %resources.Tags !empty
+    when not encrypted or encrypted {
+        Resources exists
+    }
+}
+"#;
+    let findings = lint_rules("lint.guard", rules)?;
+    assert_eq!(
+        ids(&findings),
+        vec![
+            ("G001", 1),
+            ("G005", 4),
+            ("G007", 5),
+            ("G003", 6),
+            ("G004", 7),
+            ("G002", 8),
+            ("G008", 10),
+            ("G006", 13),
+            ("G001", 17),
+            ("G005", 19),
+        ]
+    );
+    assert_eq!(findings[0].severity, Severity::Warning);
+    assert_eq!(findings[0].column, 1);
+    assert_eq!(findings[5].message, "rule `versioned` is not defined");
+    assert_eq!(
+        findings[8].message,
+        "parameter `keys` of rule `tagged` is never used"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_lint_clean_rules_and_disable_comments() -> Result<()> {
+    let clean = r#"let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+let allowed = ['aws:kms', 'AES256']
+
+rule encrypted when %buckets !empty {
+    AWS::S3::Bucket {
+        let algorithm = Properties.BucketEncryption.ServerSideEncryptionConfiguration[*].ServerSideEncryptionByDefault.SSEAlgorithm
+        %algorithm in %allowed
+    }
+}
+
+rule named(resources) {
+    encrypted
+    %resources.Properties.BucketName == /^prod-/
+}
+
+rule all {
+    named(%buckets)
+}
+"#;
+    assert!(lint_rules("clean.guard", clean)?.is_empty());
+    assert!(lint_rules("empty.guard", "# nothing yet\n")?.is_empty());
+
+    let disabled = r#"let unused = 10 # guard-lint: disable=G001
+# guard-lint: disable=null-comparison,G003
+let other = Resources.*[ Name == null ]
+rule checks {
+    # guard-lint: disable
+    Resources.Name == null
+    Resources.Name == null # guard-lint: disable=G001
+    %other exists
+}
+"#;
+    assert_eq!(
+        ids(&lint_rules("disabled.guard", disabled)?),
+        vec![("G004", 7)]
+    );
+
+    assert!(lint_rules("broken.guard", "rule broken {\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_lint_sarif_report() -> Result<()> {
+    let findings = lint_rules(
+        "rules/s3.guard",
+        "let unused = 10\nrule r {\n    missing\n}\n",
+    )?;
+    let report = sarif(&[("rules/s3.guard".to_string(), findings)]);
+    let run = &report["runs"][0];
+    assert_eq!(report["version"], "2.1.0");
+    assert_eq!(run["tool"]["driver"]["name"], APP_NAME);
+    assert_eq!(
+        run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+        CHECKS.len()
+    );
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "G001");
+    assert_eq!(results[0]["ruleIndex"], 0);
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(results[1]["ruleId"], "G002");
+    assert_eq!(results[1]["level"], "error");
+    let location = &results[1]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "rules/s3.guard");
+    assert_eq!(location["region"]["startLine"], 3);
+    assert_eq!(location["region"]["startColumn"], 5);
+    Ok(())
+}
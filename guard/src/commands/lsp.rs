@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use clap::{App, ArgMatches};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _,
+};
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbolParams,
+    DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url,
+};
+use serde::de::DeserializeOwned;
+
+use crate::command::Command;
+use crate::commands::lsp::analysis::{completions, definition, diagnostics, hover, symbols};
+use crate::commands::{LSP, SUCCESS_STATUS_CODE};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::Result;
+
+mod analysis;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Lsp {}
+
+impl Lsp {
+    pub fn new() -> Self {
+        Lsp {}
+    }
+}
+
+impl Command for Lsp {
+    fn name(&self) -> &'static str {
+        LSP
+    }
+
+    fn command(&self) -> App<'static, 'static> {
+        App::new(LSP).about(
+            r#"Runs a Language Server Protocol server for Guard rules files over stdin and stdout,
+for use by editors. It reports parse errors, goes to the definitions of variables and
+rules, shows the queries variables resolve to on hover, completes keywords and
+operators, and lists the rules in a file.
+"#,
+        )
+    }
+
+    fn execute(&self, _app: &ArgMatches<'_>) -> Result<i32> {
+        let (connection, io_threads) = Connection::stdio();
+        let capabilities = serde_json::to_value(capabilities())?;
+        connection
+            .initialize(capabilities)
+            .map_err(protocol_error)?;
+        serve(&connection)?;
+        drop(connection);
+        io_threads.join()?;
+        Ok(SUCCESS_STATUS_CODE)
+    }
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["%".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+fn protocol_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::new(ErrorKind::IncompatibleError(format!(
+        "Language server protocol error {}",
+        e
+    )))
+}
+
+///
+/// Handles messages from the editor after initialization until it asks to shut down. Open
+/// documents are kept in full and re-analyzed on each request, rules files are small.
+///
+pub(crate) fn serve(connection: &Connection) -> Result<()> {
+    let mut documents: HashMap<Url, String> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection
+                    .handle_shutdown(&request)
+                    .map_err(protocol_error)?
+                {
+                    return Ok(());
+                }
+                let response = respond(&documents, request);
+                connection
+                    .sender
+                    .send(Message::Response(response))
+                    .map_err(protocol_error)?;
+            }
+            Message::Notification(notification) => {
+                //
+                // A notification the server cannot read is logged to stderr, the editor shows
+                // the server's stderr, and the session goes on with the documents it has
+                //
+                let method = notification.method.clone();
+                let changed = match notified(&mut documents, notification) {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        eprintln!("Skipping notification {}, Error {}", method, e);
+                        None
+                    }
+                };
+                if let Some(uri) = changed {
+                    let diagnostics = match documents.get(&uri) {
+                        Some(text) => diagnostics(uri.path(), text),
+                        None => vec![],
+                    };
+                    connection
+                        .sender
+                        .send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            PublishDiagnosticsParams::new(uri, diagnostics, None),
+                        )))
+                        .map_err(protocol_error)?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+///
+/// Updates the open documents for a notification, returning the document that changed
+///
+fn notified(
+    documents: &mut HashMap<Url, String>,
+    notification: Notification,
+) -> Result<Option<Url>> {
+    Ok(match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: DidOpenTextDocumentParams = params(notification.params)?;
+            documents.insert(params.text_document.uri.clone(), params.text_document.text);
+            Some(params.text_document.uri)
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: DidChangeTextDocumentParams = params(notification.params)?;
+            //
+            // Full sync, the last change has the whole document
+            //
+            if let Some(change) = params.content_changes.into_iter().last() {
+                documents.insert(params.text_document.uri.clone(), change.text);
+            }
+            Some(params.text_document.uri)
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: DidCloseTextDocumentParams = params(notification.params)?;
+            documents.remove(&params.text_document.uri);
+            Some(params.text_document.uri)
+        }
+        _ => None,
+    })
+}
+
+fn params<P: DeserializeOwned>(value: serde_json::Value) -> Result<P> {
+    Ok(serde_json::from_value(value)?)
+}
+
+fn respond(documents: &HashMap<Url, String>, request: Request) -> Response {
+    let id = request.id.clone();
+    let result = match request.method.as_str() {
+        GotoDefinition::METHOD => params::<GotoDefinitionParams>(request.params).map(|params| {
+            let position = params.text_document_position_params;
+            let uri = position.text_document.uri;
+            let position = position.position;
+            documents
+                .get(&uri)
+                .and_then(|text| definition(text, position))
+                .map(|declaration| {
+                    GotoDefinitionResponse::Scalar(Location::new(uri, declaration.range))
+                })
+                .map_or(serde_json::Value::Null, |response| {
+                    serde_json::to_value(response).unwrap()
+                })
+        }),
+        HoverRequest::METHOD => params::<HoverParams>(request.params).map(|params| {
+            let position = params.text_document_position_params;
+            documents
+                .get(&position.text_document.uri)
+                .and_then(|text| hover(text, position.position))
+                .map(|value| Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value,
+                    }),
+                    range: None,
+                })
+                .map_or(serde_json::Value::Null, |response| {
+                    serde_json::to_value(response).unwrap()
+                })
+        }),
+        Completion::METHOD => params::<CompletionParams>(request.params).map(|params| {
+            let position = params.text_document_position;
+            let items = documents
+                .get(&position.text_document.uri)
+                .map(|text| completions(text, position.position))
+                .unwrap_or_default();
+            serde_json::to_value(CompletionResponse::Array(items)).unwrap()
+        }),
+        DocumentSymbolRequest::METHOD => {
+            params::<DocumentSymbolParams>(request.params).map(|params| {
+                let items = documents
+                    .get(&params.text_document.uri)
+                    .map(|text| symbols(text))
+                    .unwrap_or_default();
+                serde_json::to_value(DocumentSymbolResponse::Nested(items)).unwrap()
+            })
+        }
+        _ => {
+            return Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported method {}", request.method),
+            )
+        }
+    };
+    match result {
+        Ok(result) => Response::new_ok(id, result),
+        Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[path = "lsp_tests.rs"]
+mod lsp_tests;
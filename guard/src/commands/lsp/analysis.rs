@@ -0,0 +1,752 @@
+use lazy_static::lazy_static;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, DocumentSymbol, Position,
+    Range, SymbolKind,
+};
+use regex::Regex;
+
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::{Block, FileLocation, GuardClause, LetExpr, Rule, RuleClause};
+use crate::rules::parser::{
+    let_assignment_expr, let_value, rules_file, rules_file_exprs, Exprs, Span,
+};
+
+lazy_static! {
+    static ref DECLARATION: Regex = Regex::new(r"\b(let|rule)\s+([A-Za-z][A-Za-z0-9_]*)").unwrap();
+    static ref PARAMETERS: Regex = Regex::new(r"^\s*\(([^)]*)\)").unwrap();
+    static ref ERROR_LOCATION: Regex = Regex::new(r"at line (\d+) at column (\d+)").unwrap();
+}
+
+const KEYWORDS: [&str; 21] = [
+    "rule",
+    "let",
+    "when",
+    "or",
+    "not",
+    "some",
+    "this",
+    "keys",
+    "exists",
+    "empty",
+    "in",
+    "is_string",
+    "is_list",
+    "is_struct",
+    "is_bool",
+    "is_int",
+    "true",
+    "false",
+    "null",
+    "<<",
+    ">>",
+];
+
+const OPERATORS: [&str; 9] = ["==", "!=", "<", "<=", ">", ">=", "IN", "EXISTS", "EMPTY"];
+
+const FUNCTIONS: [&str; 8] = [
+    "count",
+    "json_parse",
+    "regex_replace",
+    "substring",
+    "to_upper",
+    "to_lower",
+    "join",
+    "url_decode",
+];
+
+//
+// Variables assigned from other variables are resolved at most this deep, which also stops
+// cycles between assignments
+//
+const MAX_RESOLVE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeclarationKind {
+    Variable,
+    Rule,
+    Parameter,
+}
+
+///
+/// A `let` assignment, a named or parameterized rule, or a parameter of a parameterized rule.
+/// The parse tree records where each rule and assignment starts, the names and blocks are
+/// found in the text from there.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Declaration {
+    pub(crate) kind: DeclarationKind,
+    pub(crate) name: String,
+    pub(crate) range: Range,
+    //
+    // Byte offsets of the `let` or `rule` keyword and of the name
+    //
+    start: usize,
+    offset: usize,
+    //
+    // Byte offsets of the block of the rule that this is declared in, or of the rule's own
+    // block for rules
+    //
+    scope: Option<(usize, usize)>,
+}
+
+///
+/// Parse errors for the rules file, reported at the line and column the parser stopped at
+///
+pub(crate) fn diagnostics(name: &str, text: &str) -> Vec<Diagnostic> {
+    if code_lines(text).all(|(_, _, code)| code.trim().is_empty()) {
+        return vec![];
+    }
+    match rules_file(Span::new_extra(text, name)) {
+        Ok(_) => vec![],
+        Err(Error(ErrorKind::ParseError(message))) => vec![parse_error(text, &message)],
+        Err(e) => vec![parse_error(text, &e.to_string())],
+    }
+}
+
+fn parse_error(text: &str, message: &str) -> Diagnostic {
+    let position = ERROR_LOCATION
+        .captures(message)
+        .and_then(|captures| {
+            let line = captures[1].parse::<usize>().ok()?;
+            let column = captures[2].parse::<usize>().ok()?;
+            let line_text = text.split('\n').nth(line.checked_sub(1)?)?;
+            let column = floor_char_boundary(line_text, column.saturating_sub(1));
+            Some(position_in_line(line - 1, line_text, column))
+        })
+        .unwrap_or_default();
+    //
+    // The parser includes the rest of the file after the error, which is not useful in an
+    // editor that shows the diagnostic at that position
+    //
+    let message = message
+        .split(", fragment ")
+        .next()
+        .and_then(|message| message.split(", remaining ").next())
+        .unwrap_or(message);
+    Diagnostic {
+        range: Range::new(position, position),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(crate::commands::APP_NAME.to_string()),
+        message: message.to_string(),
+        ..Diagnostic::default()
+    }
+}
+
+///
+/// The declarations in the rules file in the order they appear. These come from the parse
+/// tree, a file that does not parse while it is being edited is scanned for them instead.
+///
+pub(crate) fn declarations(text: &str) -> Vec<Declaration> {
+    let mut all = match rules_file_exprs(Span::new_extra(text, "")) {
+        Ok(exprs) => parsed(text, &exprs),
+        Err(_) => scanned(text),
+    };
+    all.sort_by_key(|each| each.offset);
+    all
+}
+
+fn parsed(text: &str, exprs: &[(FileLocation<'_>, Exprs<'_>)]) -> Vec<Declaration> {
+    let mut all = vec![];
+    for (location, each) in exprs {
+        let mut assignments = vec![];
+        let scope = match each {
+            Exprs::Assignment(assignment) => {
+                assignments.push(assignment);
+                None
+            }
+            Exprs::DefaultTypeBlock(type_blocks) => {
+                for each in type_blocks {
+                    block(&each.block, guard_clause, &mut assignments);
+                }
+                None
+            }
+            Exprs::DefaultWhenBlock(_, when_block) => {
+                block(when_block, guard_clause, &mut assignments);
+                None
+            }
+            Exprs::DefaultClause(clauses) => {
+                for each in clauses {
+                    guard_clause(each, &mut assignments);
+                }
+                None
+            }
+            Exprs::Rule(rule) => {
+                block(&rule.block, rule_clause, &mut assignments);
+                Some(rule_declarations(text, location, rule, &[], &mut all))
+            }
+            Exprs::ParameterizedRule(parameterized) => {
+                let parameters = parameterized.parameter_names.iter().collect::<Vec<_>>();
+                block(&parameterized.rule.block, rule_clause, &mut assignments);
+                Some(rule_declarations(
+                    text,
+                    location,
+                    &parameterized.rule,
+                    &parameters,
+                    &mut all,
+                ))
+            }
+        };
+        all.extend(assignments.into_iter().filter_map(|assignment| {
+            let start = skip_whitespace(text, offset_of(text, &assignment.location));
+            let offset = find_name(text, start + "let".len(), text.len(), &assignment.var)?;
+            Some(declared(
+                text,
+                DeclarationKind::Variable,
+                &assignment.var,
+                start,
+                offset,
+                scope,
+            ))
+        }));
+    }
+    all
+}
+
+//
+// Adds the rule and its parameters, returning the range of the rule's block
+//
+fn rule_declarations(
+    text: &str,
+    location: &FileLocation<'_>,
+    rule: &Rule<'_>,
+    parameters: &[&String],
+    found: &mut Vec<Declaration>,
+) -> (usize, usize) {
+    let start = offset_of(text, location);
+    let offset =
+        find_name(text, start + "rule".len(), text.len(), &rule.rule_name).unwrap_or(start);
+    let scope = block_range(text, offset + rule.rule_name.len());
+    found.push(declared(
+        text,
+        DeclarationKind::Rule,
+        &rule.rule_name,
+        start,
+        offset,
+        Some(scope),
+    ));
+    let mut at = offset + rule.rule_name.len();
+    for name in parameters {
+        if let Some(begin) = find_name(text, at, scope.0, name) {
+            found.push(declared(
+                text,
+                DeclarationKind::Parameter,
+                name,
+                begin,
+                begin,
+                Some(scope),
+            ));
+            at = begin + name.len();
+        }
+    }
+    scope
+}
+
+fn declared(
+    text: &str,
+    kind: DeclarationKind,
+    name: &str,
+    start: usize,
+    offset: usize,
+    scope: Option<(usize, usize)>,
+) -> Declaration {
+    Declaration {
+        kind,
+        name: name.to_string(),
+        range: Range::new(
+            position_at(text, offset),
+            position_at(text, offset + name.len()),
+        ),
+        start,
+        offset,
+        scope,
+    }
+}
+
+//
+// The assignments in a block and in the blocks nested in its clauses
+//
+fn block<'r, T>(
+    block: &'r Block<'r, T>,
+    clause: fn(&'r T, &mut Vec<&'r LetExpr<'r>>),
+    found: &mut Vec<&'r LetExpr<'r>>,
+) {
+    found.extend(&block.assignments);
+    for each in block.conjunctions.iter().flatten() {
+        clause(each, found);
+    }
+}
+
+fn rule_clause<'r>(clause: &'r RuleClause<'r>, found: &mut Vec<&'r LetExpr<'r>>) {
+    match clause {
+        RuleClause::Clause(clause) => guard_clause(clause, found),
+        RuleClause::WhenBlock(_, when_block) => block(when_block, guard_clause, found),
+        RuleClause::TypeBlock(type_block) => block(&type_block.block, guard_clause, found),
+    }
+}
+
+fn guard_clause<'r>(clause: &'r GuardClause<'r>, found: &mut Vec<&'r LetExpr<'r>>) {
+    match clause {
+        GuardClause::BlockClause(block_clause) => block(&block_clause.block, guard_clause, found),
+        GuardClause::WhenBlock(_, when_block) => block(when_block, guard_clause, found),
+        _ => {}
+    }
+}
+
+//
+// Finds the declarations by matching `let` and `rule` in the code outside of comments
+//
+fn scanned(text: &str) -> Vec<Declaration> {
+    let mut rules = vec![];
+    let mut others = vec![];
+    for (line, line_start, code) in code_lines(text) {
+        for captures in DECLARATION.captures_iter(code) {
+            let keyword = captures.get(1).unwrap();
+            let name = captures.get(2).unwrap();
+            let start = line_start + keyword.start();
+            let offset = line_start + name.start();
+            let range = Range::new(
+                position_in_line(line, code, name.start()),
+                position_in_line(line, code, name.end()),
+            );
+            if keyword.as_str() == "let" {
+                others.push(Declaration {
+                    kind: DeclarationKind::Variable,
+                    name: name.as_str().to_string(),
+                    range,
+                    start,
+                    offset,
+                    scope: None,
+                });
+                continue;
+            }
+
+            let block = block_range(text, line_start + name.end());
+            rules.push(Declaration {
+                kind: DeclarationKind::Rule,
+                name: name.as_str().to_string(),
+                range,
+                start,
+                offset,
+                scope: Some(block),
+            });
+            if let Some(parameters) = PARAMETERS.captures(&code[name.end()..]) {
+                let list = parameters.get(1).unwrap();
+                let mut at = name.end() + list.start();
+                for parameter in list.as_str().split(',') {
+                    let trimmed = parameter.trim();
+                    if !trimmed.is_empty() {
+                        let begin = at + parameter.find(trimmed).unwrap_or(0);
+                        others.push(Declaration {
+                            kind: DeclarationKind::Parameter,
+                            name: trimmed.to_string(),
+                            range: Range::new(
+                                position_in_line(line, code, begin),
+                                position_in_line(line, code, begin + trimmed.len()),
+                            ),
+                            start: line_start + begin,
+                            offset: line_start + begin,
+                            scope: Some(block),
+                        });
+                    }
+                    at += parameter.len() + 1;
+                }
+            }
+        }
+    }
+
+    //
+    // Variables declared inside a rule's block are only visible in that block
+    //
+    for each in others.iter_mut() {
+        if each.kind == DeclarationKind::Variable {
+            each.scope = rules
+                .iter()
+                .filter_map(|rule| rule.scope)
+                .find(|(begin, end)| *begin <= each.offset && each.offset < *end);
+        }
+    }
+    rules.extend(others);
+    rules
+}
+
+///
+/// The declaration of the `%variable`, parameter or rule name at the position
+///
+pub(crate) fn definition(text: &str, position: Position) -> Option<Declaration> {
+    let offset = offset_at(text, position)?;
+    let (name, is_variable) = word_at(text, offset)?;
+    let declarations = declarations(text);
+    if let Some(declared) = declarations
+        .iter()
+        .find(|each| each.offset <= offset && offset <= each.offset + each.name.len())
+    {
+        return Some(declared.clone());
+    }
+    if is_variable {
+        visible(&declarations, &name, offset).cloned()
+    } else {
+        declarations
+            .into_iter()
+            .find(|each| each.kind == DeclarationKind::Rule && each.name == name)
+    }
+}
+
+fn visible<'d>(
+    declarations: &'d [Declaration],
+    name: &str,
+    offset: usize,
+) -> Option<&'d Declaration> {
+    let candidates = declarations
+        .iter()
+        .filter(|each| each.kind != DeclarationKind::Rule && each.name == name)
+        .filter(|each| {
+            each.scope
+                .is_none_or(|(begin, end)| begin <= offset && offset < end)
+        })
+        .collect::<Vec<_>>();
+    //
+    // The nearest preceding declaration wins, assignments in a rule shadow the global ones
+    //
+    candidates
+        .iter()
+        .rev()
+        .find(|each| each.offset <= offset)
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+///
+/// Markdown describing the declaration of the name at the position. For variables this is
+/// the assigned value and the query it resolves to when it is assigned from other variables.
+///
+pub(crate) fn hover(text: &str, position: Position) -> Option<String> {
+    let declaration = definition(text, position)?;
+    match declaration.kind {
+        DeclarationKind::Variable => {
+            let value = assigned(text, &declaration)?;
+            let resolved = resolve(text, &declaration, 0)?;
+            let mut hover = format!("```\nlet {} = {}\n```", declaration.name, value);
+            if resolved != value {
+                hover.push_str(&format!("\n\nResolved query: `{}`", resolved));
+            }
+            Some(hover)
+        }
+        DeclarationKind::Rule => {
+            let header = match declaration.scope {
+                Some((begin, _)) => {
+                    &text[declaration.start..begin.saturating_sub(1).max(declaration.start)]
+                }
+                None => &text[declaration.start..],
+            };
+            Some(format!(
+                "```\n{}\n```",
+                header.split_whitespace().collect::<Vec<_>>().join(" ")
+            ))
+        }
+        DeclarationKind::Parameter => {
+            let rule = declarations(text).into_iter().find(|each| {
+                each.kind == DeclarationKind::Rule && each.scope == declaration.scope
+            })?;
+            Some(format!(
+                "Parameter `{}` of rule `{}`",
+                declaration.name, rule.name
+            ))
+        }
+    }
+}
+
+//
+// The text of the value assigned by a `let`, parsed with the same combinators the rules file
+// parser uses so that it ends where the assignment ends
+//
+fn assigned<'t>(text: &'t str, declaration: &Declaration) -> Option<&'t str> {
+    let rest = &text[declaration.start..];
+    let (after_assignment, _) = let_assignment_expr(Span::new_extra(rest, "")).ok()?;
+    let (after_value, _) = let_value(after_assignment).ok()?;
+    Some(rest[after_assignment.location_offset()..after_value.location_offset()].trim())
+}
+
+fn resolve(text: &str, declaration: &Declaration, depth: usize) -> Option<String> {
+    let value = assigned(text, declaration)?;
+    if depth >= MAX_RESOLVE_DEPTH {
+        return Some(value.to_string());
+    }
+    let name = match value.strip_prefix('%') {
+        Some(rest) => rest
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or(""),
+        None => return Some(value.to_string()),
+    };
+    let declarations = declarations(text);
+    match visible(&declarations, name, declaration.start) {
+        Some(source)
+            if source.kind == DeclarationKind::Variable && source.offset != declaration.offset =>
+        {
+            let resolved = resolve(text, source, depth + 1)?;
+            Some(format!("{}{}", resolved, &value[name.len() + 1..]))
+        }
+        _ => Some(value.to_string()),
+    }
+}
+
+///
+/// Keywords, operators, functions, and the variables and rules declared in the rules file.
+/// Variables are completed with their `%` unless it was already typed.
+///
+pub(crate) fn completions(text: &str, position: Position) -> Vec<CompletionItem> {
+    let after_percent = offset_at(text, position)
+        .map(|offset| text[..offset].trim_end_matches(is_name_char).ends_with('%'))
+        .unwrap_or(false);
+    let mut items = vec![];
+    let mut add = |label: String, insert: String, kind: CompletionItemKind| {
+        items.push(CompletionItem {
+            label,
+            insert_text: Some(insert),
+            kind: Some(kind),
+            ..CompletionItem::default()
+        })
+    };
+    for keyword in KEYWORDS {
+        add(
+            keyword.to_string(),
+            keyword.to_string(),
+            CompletionItemKind::KEYWORD,
+        );
+    }
+    for operator in OPERATORS {
+        add(
+            operator.to_string(),
+            operator.to_string(),
+            CompletionItemKind::OPERATOR,
+        );
+    }
+    for function in FUNCTIONS {
+        add(
+            function.to_string(),
+            format!("{}(", function),
+            CompletionItemKind::FUNCTION,
+        );
+    }
+    let mut seen = std::collections::HashSet::new();
+    for each in declarations(text) {
+        if !seen.insert((each.kind == DeclarationKind::Rule, each.name.clone())) {
+            continue;
+        }
+        match each.kind {
+            DeclarationKind::Rule => add(each.name.clone(), each.name, CompletionItemKind::CLASS),
+            DeclarationKind::Variable | DeclarationKind::Parameter => add(
+                format!("%{}", each.name),
+                if after_percent {
+                    each.name
+                } else {
+                    format!("%{}", each.name)
+                },
+                CompletionItemKind::VARIABLE,
+            ),
+        }
+    }
+    items
+}
+
+///
+/// The rules in the rules file, with the variables assigned in each rule as its children
+///
+#[allow(deprecated)]
+pub(crate) fn symbols(text: &str) -> Vec<DocumentSymbol> {
+    let declarations = declarations(text);
+    declarations
+        .iter()
+        .filter(|each| each.kind == DeclarationKind::Rule)
+        .map(|rule| {
+            let (_, end) = rule.scope.unwrap_or((rule.offset, text.len()));
+            let children = declarations
+                .iter()
+                .filter(|each| each.kind == DeclarationKind::Variable && each.scope == rule.scope)
+                .map(|each| DocumentSymbol {
+                    name: each.name.clone(),
+                    detail: assigned(text, each).map(str::to_string),
+                    kind: SymbolKind::VARIABLE,
+                    tags: None,
+                    deprecated: None,
+                    range: each.range,
+                    selection_range: each.range,
+                    children: None,
+                })
+                .collect::<Vec<_>>();
+            DocumentSymbol {
+                name: rule.name.clone(),
+                detail: None,
+                kind: SymbolKind::FUNCTION,
+                tags: None,
+                deprecated: None,
+                range: Range::new(position_at(text, rule.start), position_at(text, end)),
+                selection_range: rule.range,
+                children: if children.is_empty() {
+                    None
+                } else {
+                    Some(children)
+                },
+            }
+        })
+        .collect()
+}
+
+//
+// Each line with its number, its byte offset, and its text up to a comment
+//
+fn code_lines(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut line_start = 0;
+    text.split('\n').enumerate().map(move |(line, content)| {
+        let start = line_start;
+        line_start += content.len() + 1;
+        (line, start, strip_comment(content))
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+//
+// The byte range of the block that starts after `from`, strings, `<<` `>>` messages and
+// comments are skipped when matching braces
+//
+fn block_range(text: &str, from: usize) -> (usize, usize) {
+    let mut depth = 0;
+    let mut begin = None;
+    let mut quote = None;
+    let mut comment = false;
+    let mut message = false;
+    for (index, c) in text[from..].char_indices() {
+        let index = from + index;
+        if message {
+            message = !text[..index + c.len_utf8()].ends_with(">>");
+            continue;
+        }
+        match (comment, quote, c) {
+            (false, None, '<') if text[index..].starts_with("<<") => message = true,
+            (true, _, '\n') => comment = false,
+            (true, _, _) => {}
+            (false, None, '#') => comment = true,
+            (false, None, '"') | (false, None, '\'') => quote = Some(c),
+            (false, Some(q), _) if q == c => quote = None,
+            (false, Some(_), _) => {}
+            (false, None, '{') => {
+                depth += 1;
+                begin.get_or_insert(index + 1);
+            }
+            (false, None, '}') if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return (begin.unwrap_or(from), index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    (begin.unwrap_or(from), text.len())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+//
+// The byte offset of the first whole word `name` between `from` and `to`
+//
+fn find_name(text: &str, from: usize, to: usize, name: &str) -> Option<usize> {
+    let within = text.get(from..to)?;
+    within
+        .match_indices(name)
+        .map(|(index, _)| from + index)
+        .find(|begin| {
+            !text[..*begin].ends_with(is_name_char)
+                && !text[begin + name.len()..].starts_with(is_name_char)
+        })
+}
+
+fn skip_whitespace(text: &str, offset: usize) -> usize {
+    offset + (text[offset..].len() - text[offset..].trim_start().len())
+}
+
+//
+// The parser locates expressions by line and by column in characters, both counted from one
+//
+fn offset_of(text: &str, location: &FileLocation<'_>) -> usize {
+    let line_start = text
+        .split('\n')
+        .take((location.line as usize).saturating_sub(1))
+        .map(|line| line.len() + 1)
+        .sum::<usize>()
+        .min(text.len());
+    line_start
+        + text[line_start..]
+            .chars()
+            .take((location.column as usize).saturating_sub(1))
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+fn word_at(text: &str, offset: usize) -> Option<(String, bool)> {
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_name_char(*c))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = text[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_name_char(*c))
+        .map_or(text.len(), |(index, _)| offset + index);
+    if start == end {
+        return None;
+    }
+    Some((text[start..end].to_string(), text[..start].ends_with('%')))
+}
+
+//
+// Positions count UTF-16 code units within a line, as the protocol defaults to
+//
+fn position_in_line(line: usize, line_text: &str, byte: usize) -> Position {
+    Position::new(line as u32, line_text[..byte].encode_utf16().count() as u32)
+}
+
+fn position_at(text: &str, offset: usize) -> Position {
+    let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = text[..line_start].matches('\n').count();
+    position_in_line(line, &text[line_start..], offset - line_start)
+}
+
+pub(crate) fn offset_at(text: &str, position: Position) -> Option<usize> {
+    let (_, line_start, _) = code_lines(text).nth(position.line as usize)?;
+    let line = text[line_start..].split('\n').next()?;
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return Some(line_start + index);
+        }
+        units += c.len_utf16();
+    }
+    Some(line_start + line.len())
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|each| text.is_char_boundary(*each))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+#[path = "analysis_tests.rs"]
+mod analysis_tests;
@@ -0,0 +1,164 @@
+use super::*;
+
+const RULES: &str = r#"let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+let versioning = %buckets.Properties.VersioningConfiguration
+
+rule s3_versioning_enabled when %buckets !empty {
+    let status = %versioning.Status
+    %status == 'Enabled'
+}
+
+rule s3_checks {
+    s3_versioning_enabled
+    check_tags(%buckets)
+}
+
+rule check_tags(resources) {
+    %resources.Properties.Tags !empty
+}
+"#;
+
+fn position_of(text: &str, needle: &str, nth: usize) -> Position {
+    let offset = text.match_indices(needle).nth(nth).unwrap().0;
+    position_at(text, offset)
+}
+
+#[test]
+fn test_parse_error_diagnostic_position() {
+    assert!(diagnostics("rules.guard", RULES).is_empty());
+    assert!(diagnostics("empty.guard", "# nothing yet\n\n").is_empty());
+
+    let broken = "rule s3_checks {\n    Resources.* {\n        Type ==\n    }\n";
+    let found = diagnostics("broken.guard", broken);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert!(!found[0].message.contains("fragment"));
+    assert!(found[0].range.start.line > 0);
+}
+
+#[test]
+fn test_definition_of_variables_rules_and_parameters() {
+    //
+    // `status` is declared inside the first rule, its use resolves to that declaration
+    //
+    let used = position_of(RULES, "%status", 0);
+    let found = definition(RULES, Position::new(used.line, used.character + 2)).unwrap();
+    assert_eq!(found.kind, DeclarationKind::Variable);
+    assert_eq!(found.range.start, Position::new(4, 8));
+
+    let used = position_of(RULES, "%buckets", 1);
+    let found = definition(RULES, Position::new(used.line, used.character + 1)).unwrap();
+    assert_eq!(found.name, "buckets");
+    assert_eq!(found.range.start, Position::new(0, 4));
+
+    let used = position_of(RULES, "s3_versioning_enabled", 1);
+    let found = definition(RULES, used).unwrap();
+    assert_eq!(found.kind, DeclarationKind::Rule);
+    assert_eq!(found.range.start, Position::new(3, 5));
+
+    let used = position_of(RULES, "%resources", 0);
+    let found = definition(RULES, Position::new(used.line, used.character + 3)).unwrap();
+    assert_eq!(found.kind, DeclarationKind::Parameter);
+    assert_eq!(found.range.start, position_of(RULES, "resources)", 0));
+
+    let used = position_of(RULES, "Properties", 0);
+    assert_eq!(definition(RULES, used), None);
+}
+
+#[test]
+fn test_hover_resolves_queries() {
+    let used = position_of(RULES, "%status", 0);
+    let hovered = hover(RULES, Position::new(used.line, used.character + 1)).unwrap();
+    assert!(hovered.contains("let status = %versioning.Status"));
+    assert!(hovered.contains(
+        "Resolved query: `Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.VersioningConfiguration.Status`"
+    ));
+
+    let used = position_of(RULES, "%buckets", 0);
+    let hovered = hover(RULES, Position::new(used.line, used.character + 1)).unwrap();
+    assert!(hovered.contains("let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]"));
+    assert!(!hovered.contains("Resolved query"));
+
+    let used = position_of(RULES, "check_tags", 0);
+    let hovered = hover(RULES, used).unwrap();
+    assert!(hovered.contains("rule check_tags(resources)"));
+}
+
+#[test]
+fn test_completions_and_symbols() {
+    let end = position_of(RULES, "%status ==", 0);
+    let items = completions(RULES, Position::new(end.line, end.character + 1));
+    let labels = items
+        .iter()
+        .map(|each| each.label.as_str())
+        .collect::<Vec<_>>();
+    for expected in [
+        "rule",
+        "when",
+        "EXISTS",
+        "==",
+        "count",
+        "%buckets",
+        "check_tags",
+    ] {
+        assert!(labels.contains(&expected), "missing {}", expected);
+    }
+    let buckets = items.iter().find(|each| each.label == "%buckets").unwrap();
+    assert_eq!(buckets.insert_text.as_deref(), Some("buckets"));
+
+    let found = symbols(RULES);
+    assert_eq!(
+        found
+            .iter()
+            .map(|each| each.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["s3_versioning_enabled", "s3_checks", "check_tags"]
+    );
+    assert_eq!(found[0].range.start, Position::new(3, 0));
+    assert_eq!(found[0].range.end, Position::new(6, 1));
+    let children = found[0].children.as_ref().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].name, "status");
+    assert!(found[1].children.is_none());
+}
+
+#[test]
+fn test_declarations_come_from_the_parse_tree() {
+    let rules = r#"rule s3_checks {
+    Resources.*[ Type == 'AWS::S3::Bucket' ] {
+        let tags = Properties.Tags
+        %tags !empty <<rule s3_tags must pass {with tags}>>
+    }
+}
+
+rule other { Resources exists }
+"#;
+    let found = declarations(rules);
+    assert_eq!(
+        found
+            .iter()
+            .map(|each| (each.kind, each.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (DeclarationKind::Rule, "s3_checks"),
+            (DeclarationKind::Variable, "tags"),
+            (DeclarationKind::Rule, "other"),
+        ]
+    );
+    assert_eq!(found[1].range.start, Position::new(2, 12));
+    assert_eq!(found[1].scope, found[0].scope);
+    assert_eq!(symbols(rules)[0].range.end, Position::new(5, 1));
+
+    //
+    // While the file does not parse the declarations are found in its text
+    //
+    let editing = "let buckets = Resources.*\n\nrule s3_checks {\n    %buckets !empty\n";
+    let found = declarations(editing);
+    assert_eq!(
+        found
+            .iter()
+            .map(|each| each.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["buckets", "s3_checks"]
+    );
+}
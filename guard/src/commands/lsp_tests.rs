@@ -0,0 +1,134 @@
+use super::*;
+use lsp_server::RequestId;
+use lsp_types::{
+    PartialResultParams, Position, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, VersionedTextDocumentIdentifier, WorkDoneProgressParams,
+};
+
+fn request<P: serde::Serialize>(id: i32, method: &str, params: P) -> Message {
+    Message::Request(Request::new(
+        RequestId::from(id),
+        method.to_string(),
+        params,
+    ))
+}
+
+fn notification<P: serde::Serialize>(method: &str, params: P) -> Message {
+    Message::Notification(Notification::new(method.to_string(), params))
+}
+
+#[test]
+fn test_serve_publishes_diagnostics_and_answers_requests() -> Result<()> {
+    let (server, client) = Connection::memory();
+    let uri = Url::parse("file:///rules/s3.guard").unwrap();
+    let handle = std::thread::spawn(move || serve(&server));
+
+    client
+        .sender
+        .send(notification(
+            DidOpenTextDocument::METHOD,
+            DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "guard".to_string(),
+                    1,
+                    "rule r {\n    Resources.* {\n".to_string(),
+                ),
+            },
+        ))
+        .unwrap();
+    let published = match client.receiver.recv().unwrap() {
+        Message::Notification(published) => published,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(published.method, PublishDiagnostics::METHOD);
+    let published: PublishDiagnosticsParams = params(published.params)?;
+    assert_eq!(published.diagnostics.len(), 1);
+
+    client
+        .sender
+        .send(notification(
+            DidChangeTextDocument::METHOD,
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+                content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "let a = Resources.*\nrule r {\n    %a !empty\n}\n".to_string(),
+                }],
+            },
+        ))
+        .unwrap();
+    let published = match client.receiver.recv().unwrap() {
+        Message::Notification(published) => published,
+        other => panic!("unexpected {:?}", other),
+    };
+    let published: PublishDiagnosticsParams = params(published.params)?;
+    assert!(published.diagnostics.is_empty());
+
+    client
+        .sender
+        .send(request(
+            1,
+            GotoDefinition::METHOD,
+            GotoDefinitionParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(2, 6),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            },
+        ))
+        .unwrap();
+    let response = match client.receiver.recv().unwrap() {
+        Message::Response(response) => response,
+        other => panic!("unexpected {:?}", other),
+    };
+    let location: Location = params(response.result.unwrap())?;
+    assert_eq!(location.uri, uri);
+    assert_eq!(location.range.start, Position::new(0, 4));
+
+    client
+        .sender
+        .send(request(
+            2,
+            "textDocument/formatting",
+            serde_json::Value::Null,
+        ))
+        .unwrap();
+    let response = match client.receiver.recv().unwrap() {
+        Message::Response(response) => response,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(
+        response.error.unwrap().code,
+        ErrorCode::MethodNotFound as i32
+    );
+
+    //
+    // A notification that cannot be read is skipped, nothing is published for it and the
+    // server keeps answering
+    //
+    client
+        .sender
+        .send(notification(
+            DidChangeTextDocument::METHOD,
+            serde_json::json!({ "textDocument": { "uri": uri.as_str() } }),
+        ))
+        .unwrap();
+    client
+        .sender
+        .send(request(3, "shutdown", serde_json::Value::Null))
+        .unwrap();
+    assert!(matches!(
+        client.receiver.recv().unwrap(),
+        Message::Response(Response { error: None, .. })
+    ));
+    client
+        .sender
+        .send(notification("exit", serde_json::Value::Null))
+        .unwrap();
+    handle.join().unwrap()?;
+    Ok(())
+}
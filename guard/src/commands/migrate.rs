@@ -1,13 +1,27 @@
 use clap::{App, Arg, ArgMatches};
 
 use crate::command::Command;
-use crate::commands::files::read_file_content;
-use crate::commands::{MIGRATE, OUTPUT, RULES};
-use crate::migrate::parser::{parse_rules_file, Clause, Rule, RuleLineType, TypeName};
+use crate::commands::files::{alpabetical, get_files_with_filter, read_file_content, IgnoreFilter};
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::commands::{
+    DATA_FILE_SUPPORTED_EXTENSIONS, FAILURE_STATUS_CODE, MIGRATE, OUTPUT, RULES,
+    SUCCESS_STATUS_CODE, TYPE, VERIFY,
+};
+use crate::migrate::parser::{Clause, Rule, RuleLineType, TypeName};
+use crate::migrate::report::{
+    report_lines, report_text, todo_markers, LineReport, LineStatus, Reported,
+};
+use crate::migrate::violation::violation_rules;
+use crate::migrate::{cfn_nag, evaluate, rego};
 use crate::rules::errors::{Error, ErrorKind};
-use crate::rules::Result;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::exprs::RulesFile;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::{Result, Status};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt::Write as FmtWrite;
 use std::fs::File;
 use std::io::Write as IoWrite;
@@ -35,7 +49,11 @@ impl Command for Migrate {
     fn command(&self) -> App<'static, 'static> {
         App::new(MIGRATE)
             .about(
-                r#"Migrates 1.0 rules to 2.0 compatible rules.
+                r#"Migrates 1.0 rules to 2.0 compatible rules. Each line is reported as migrated, migrated with caveats
+or unsupported, and unsupported lines are left as # TODO comments in the migrated rules. With --verify
+the 1.0 rules and the migrated rules are evaluated against templates and any differences are reported.
+With --type Rego or --type CfnNag the deny rules of a Rego policy, or cfn-nag rules in JSON with jq
+expressions, are migrated the same way.
 "#,
             )
             .arg(
@@ -54,6 +72,23 @@ impl Command for Migrate {
                     .help("Write migrated rules to output file")
                     .required(false),
             )
+            .arg(
+                Arg::with_name(TYPE.0)
+                    .long(TYPE.0)
+                    .takes_value(true)
+                    .possible_values(&["Guard1", "Rego", "CfnNag"])
+                    .default_value("Guard1")
+                    .help("Specify the language of the rules file"),
+            )
+            .arg(
+                Arg::with_name(VERIFY.0)
+                    .long(VERIFY.0)
+                    .short(VERIFY.1)
+                    .takes_value(true)
+                    .multiple(true)
+                    .help("Provide templates, or directories of templates, to compare the 1.0 rules and the migrated rules against")
+                    .required(false),
+            )
     }
 
     fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
@@ -71,31 +106,171 @@ impl Command for Migrate {
                 println!("Unable read content from file {}", e);
                 Err(Error::new(ErrorKind::IoError(e)))
             }
-            Ok(file_content) => match parse_rules_file(&file_content, &file_name) {
-                Err(e) => {
-                    println!("Could not parse 1.0 rule file: {}. Please ensure the file is valid with the old version of the tool and try again.", file_name);
-                    Err(e)
+            Ok(file_content) => {
+                let language = app.value_of(TYPE.0).unwrap();
+                if language != "Guard1" && app.is_present(VERIFY.0) {
+                    return Err(Error::new(ErrorKind::IncompatibleError(format!(
+                        "--verify compares with 1.0 rules, it is not supported for --type {}",
+                        language
+                    ))));
                 }
-                Ok(rules) => {
-                    let migrated_rules = migrate_rules(rules)?;
-                    let span = crate::rules::parser::Span::new_extra(&migrated_rules, "");
-                    match crate::rules::parser::rules_file(span) {
-                        Ok(_rules) => {
-                            write!(out, "{}", migrated_rules)?;
-                            Ok(0 as i32)
-                        }
-                        Err(e) => {
-                            println!(
-                                "Could not parse migrated ruleset for file: '{}': {}",
-                                &file_name, e
-                            );
-                            Err(e)
-                        }
+                let policies = match language {
+                    "Rego" => Some(rego::report_policies(&file_content)),
+                    "CfnNag" => Some(cfn_nag::report_policies(&file_content).map_err(|e| {
+                        Error::new(ErrorKind::ParseError(format!("{}, {}", file_name, e)))
+                    })?),
+                    _ => None,
+                };
+                if let Some(policies) = policies {
+                    eprint!("{}", report_text(&file_name, &policies));
+                    let migrated_rules =
+                        format!("{}{}", todo_markers(&policies), violation_rules(&policies)?);
+                    check_and_write(&migrated_rules, &file_name, &mut out)?;
+                    return Ok(exit_status(&policies));
+                }
+
+                let reports = report_lines(&file_content, &file_name);
+                eprint!("{}", report_text(&file_name, &reports));
+                let rules = reports
+                    .iter()
+                    .filter(|report| report.is_migrated())
+                    .filter_map(|report| report.parsed.clone())
+                    .collect::<Vec<_>>();
+                let migrated_rules = format!("{}{}", todo_markers(&reports), migrate_rules(rules)?);
+                check_and_write(&migrated_rules, &file_name, &mut out)?;
+
+                let mut status = exit_status(&reports);
+                if let Some(templates) = app.values_of(VERIFY.0) {
+                    let (verified, differences) = verify(&reports, templates)?;
+                    for each in &differences {
+                        eprintln!("{}", each);
+                    }
+                    eprintln!(
+                        "{} templates verified, {} differences",
+                        verified,
+                        differences.len()
+                    );
+                    if !differences.is_empty() {
+                        status = FAILURE_STATUS_CODE;
                     }
                 }
-            },
+                Ok(status)
+            }
+        }
+    }
+}
+
+//
+// Writes the migrated rules, once they are known to parse
+//
+fn check_and_write(
+    migrated_rules: &str,
+    file_name: &str,
+    out: &mut Box<dyn std::io::Write>,
+) -> Result<()> {
+    let span = crate::rules::parser::Span::new_extra(migrated_rules, "");
+    if let Err(e) = crate::rules::parser::rules_file(span) {
+        println!(
+            "Could not parse migrated ruleset for file: '{}': {}",
+            file_name, e
+        );
+        return Err(e);
+    }
+    write!(out, "{}", migrated_rules)?;
+    Ok(())
+}
+
+//
+// Success when everything was migrated, with or without caveats
+//
+fn exit_status<R: Reported>(reports: &[R]) -> i32 {
+    if reports
+        .iter()
+        .all(|report| !matches!(report.status(), LineStatus::Unsupported(_)))
+    {
+        SUCCESS_STATUS_CODE
+    } else {
+        FAILURE_STATUS_CODE
+    }
+}
+
+//
+// Evaluates each migrated line against each template, with the 1.0 semantics and as the rules it
+// was migrated to, and returns the number of templates and the lines where one of them fails
+// and the other doesn't
+//
+fn verify<'a>(
+    reports: &[LineReport],
+    templates: impl Iterator<Item = &'a str>,
+) -> Result<(usize, Vec<String>)> {
+    let lines = reports
+        .iter()
+        .filter(|report| report.is_migrated())
+        .filter_map(|report| report.parsed.as_ref().map(|line| (report, line)))
+        .collect::<Vec<_>>();
+    let assignments = lines
+        .iter()
+        .filter(|(_, line)| matches!(line, RuleLineType::Assignment(_)))
+        .map(|(_, line)| (*line).clone())
+        .collect::<Vec<_>>();
+    let variables = evaluate::variables(&assignments);
+
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let mut verified = 0;
+    let mut differences = vec![];
+    for file_or_dir in templates {
+        let files = get_files_with_filter(file_or_dir, alpabetical, &ignore, |entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                DATA_FILE_SUPPORTED_EXTENSIONS
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+            })
+        })?;
+        for file in files {
+            let template = get_path_aware_value_from_data(&read_file_content(File::open(&file)?)?)
+                .map_err(|e| {
+                    Error::new(ErrorKind::ParseError(format!("{}, {}", file.display(), e)))
+                })?;
+            verified += 1;
+            for (report, line) in &lines {
+                if !matches!(line, RuleLineType::Clause(_)) {
+                    continue;
+                }
+                let old = evaluate::evaluate_line(line, &variables, &template);
+                let migrated = match migrated_status(&assignments, line, &template) {
+                    Ok(status) => status.to_string(),
+                    Err(e) => format!("ERROR ({})", e),
+                };
+                if (old == Status::FAIL) != (migrated == Status::FAIL.to_string()) {
+                    differences.push(format!(
+                        "{}: line {}, 1.0 {}, migrated {}: {}",
+                        file.display(),
+                        report.line,
+                        old,
+                        migrated,
+                        report.text
+                    ));
+                }
+            }
         }
     }
+    Ok((verified, differences))
+}
+
+//
+// The status of the rules one line is migrated to, with the assignments it can use
+//
+fn migrated_status(
+    assignments: &[RuleLineType],
+    line: &RuleLineType,
+    template: &PathAwareValue,
+) -> Result<Status> {
+    let mut lines = assignments.to_vec();
+    lines.push(line.clone());
+    let migrated_rules = migrate_rules(lines)?;
+    let rules = RulesFile::try_from(migrated_rules.as_str())?;
+    let mut scope = root_scope(&rules, template)?;
+    eval_rules_file(&rules, &mut scope)
 }
 
 pub(crate) fn migrated_rules_by_type(
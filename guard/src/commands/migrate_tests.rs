@@ -1,6 +1,7 @@
 use super::*;
 use crate::migrate::parser::{
-    BaseRule, Clause, CmpOperator, ConditionalRule, OldGuardValues, PropertyComparison, TypeName,
+    parse_rules_file, BaseRule, Clause, CmpOperator, ConditionalRule, OldGuardValues,
+    PropertyComparison, TypeName,
 };
 use crate::rules::parser::rules_file;
 use crate::rules::values::Value;
@@ -258,3 +259,44 @@ rule aws_ec2_instance_checks WHEN %aws_ec2_instance NOT EMPTY {
     );
     Ok(())
 }
+
+#[test]
+fn test_migrated_status_against_1_0_semantics() -> Result<()> {
+    let template = get_path_aware_value_from_data(&String::from(
+        r#"
+        Resources:
+          Data:
+            Type: AWS::EC2::Volume
+            Properties:
+              Size: "100"
+              AvailabilityZone: us-east-1a
+        "#,
+    ))?;
+    let rules = r#"let zones = [us-east-1a, us-east-1b]
+AWS::EC2::Volume AvailabilityZone IN %zones
+AWS::EC2::Volume Size == 100
+AWS::EC2::Volume Size == "100"
+AWS::S3::Bucket BucketName == logs
+"#;
+    let lines = parse_rules_file(&String::from(rules), &String::from("rules.ruleset"))?;
+    let variables = evaluate::variables(&lines);
+    let statuses = lines[1..]
+        .iter()
+        .map(|line| {
+            Ok((
+                evaluate::evaluate_line(line, &variables, &template),
+                migrated_status(&lines[..1], line, &template)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        statuses,
+        vec![
+            (Status::PASS, Status::PASS),
+            (Status::PASS, Status::FAIL),
+            (Status::PASS, Status::PASS),
+            (Status::SKIP, Status::SKIP),
+        ]
+    );
+    Ok(())
+}
@@ -1,13 +1,24 @@
+pub mod config;
+pub(crate) mod evaluate;
+pub(crate) mod exit_code;
 pub(crate) mod files;
+pub mod fmt;
+pub mod fuzz;
 pub(crate) mod helper;
+pub mod lint;
+pub mod lsp;
 pub(crate) mod migrate;
+pub(crate) mod parallel;
 pub(crate) mod parse_tree;
+pub mod repl;
 pub(crate) mod rulegen;
 pub mod test;
 pub mod validate;
+pub(crate) mod watch;
 
 mod aws_meta_appender;
-mod common_test_helpers;
+#[cfg(test)]
+pub(crate) mod common_test_helpers;
 mod tracker;
 
 //
@@ -17,42 +28,78 @@ mod tracker;
 pub const APP_NAME: &str = "cfn-guard";
 pub const APP_VERSION: &'static str = env!("CARGO_PKG_VERSION");
 // Commands
+pub const FMT: &str = "fmt";
+pub const FUZZ: &str = "fuzz";
+pub const LINT: &str = "lint";
+pub const LSP: &str = "lsp";
 pub(crate) const MIGRATE: &str = "migrate";
 pub(crate) const PARSE_TREE: &str = "parse-tree";
+pub const REPL: &str = "repl";
 pub(crate) const RULEGEN: &str = "rulegen";
 pub const TEST: &str = "test";
 pub const VALIDATE: &str = "validate";
 // Arguments for validate
 pub(crate) const ALPHABETICAL: (&str, &str) = ("alphabetical", "a");
+pub const BASELINE: (&str, &str) = ("baseline", "b");
+pub(crate) const CONFIG: (&str, &str) = ("config", "c");
+pub const COVERAGE: (&str, &str) = ("coverage", "C");
 pub const DATA: (&str, &str) = ("data", "d");
+pub const EXCLUDE: (&str, &str) = ("exclude", "x");
+pub(crate) const EXPLAIN: (&str, &str) = ("explain", "e");
+pub const FAIL_ON: (&str, &str) = ("fail-on", "F");
 pub(crate) const LAST_MODIFIED: (&str, &str) = ("last-modified", "m");
+pub const MAX_INPUTS: (&str, &str) = ("max-inputs", "M");
 pub(crate) const OUTPUT_FORMAT: (&str, &str) = ("output-format", "o");
 pub const INPUT_PARAMETERS: (&str, &str) = ("input-parameters", "i");
+pub const JOBS: (&str, &str) = ("jobs", "j");
+pub(crate) const PATH: (&str, &str) = ("path", "l");
 pub(crate) const PAYLOAD: (&str, &str) = ("payload", "P");
 pub(crate) const PREVIOUS_ENGINE: (&str, &str) = ("previous-engine", "E");
 pub(crate) const PRINT_JSON: (&str, &str) = ("print-json", "p");
 pub(crate) const SHOW_CLAUSE_FAILURES: (&str, &str) = ("show-clause-failures", "s");
 pub(crate) const SHOW_SUMMARY: (&str, &str) = ("show-summary", "S");
+pub const SNAPSHOT: (&str, &str) = ("snapshot", "n");
 pub(crate) const TYPE: (&str, &str) = ("type", "t");
+pub const UPDATE_SNAPSHOTS: (&str, &str) = ("update-snapshots", "u");
 pub(crate) const VERBOSE: (&str, &str) = ("verbose", "v");
+pub(crate) const WATCH: (&str, &str) = ("watch", "W");
+pub const WRITE_BASELINE: (&str, &str) = ("write-baseline", "w");
 // Arguments for validate, migrate, parse tree
 pub const RULES: (&str, &str) = ("rules", "r");
 // Arguments for migrate, parse-tree, rulegen
 pub(crate) const OUTPUT: (&str, &str) = ("output", "o");
+// Arguments for migrate
+pub(crate) const VERIFY: (&str, &str) = ("verify", "v");
 // Arguments for parse-tree
+pub(crate) const FROM_TREE: (&str, &str) = ("from-tree", "t");
+pub(crate) const PRINT_SCHEMA: (&str, &str) = ("print-schema", "S");
 pub(crate) const PRINT_YAML: (&str, &str) = ("print-yaml", "y");
+pub(crate) const SCHEMA_VERSION: (&str, &str) = ("schema-version", "s");
 // Arguments for test
 pub(crate) const RULES_FILE: (&str, &str) = ("rules-file", "r");
 pub const TEST_DATA: (&str, &str) = ("test-data", "t");
+pub const TEST_FILTER: (&str, &str) = ("filter", "f");
+pub const TEST_PATTERN: (&str, &str) = ("test-pattern", "T");
 pub(crate) const DIRECTORY: (&str, &str) = ("dir", "d");
+// Arguments for fmt
+pub const CHECK: (&str, &str) = ("check", "k");
 // Arguments for rulegen
 pub(crate) const TEMPLATE: (&str, &str) = ("template", "t");
+pub(crate) const THRESHOLD: (&str, &str) = ("threshold", "T");
 // Arg group for validate
 pub(crate) const REQUIRED_FLAGS: &str = "required_flags";
 // Arg group for test
 pub(crate) const RULES_AND_TEST_FILE: &str = "rules-and-test-file";
 pub(crate) const DIRECTORY_ONLY: &str = "directory-only";
 
+// Exit codes
+pub const SUCCESS_STATUS_CODE: i32 = 0;
+pub const ERROR_STATUS_CODE: i32 = 1;
+pub const PARSING_ERROR_STATUS_CODE: i32 = 2;
+pub const EVALUATION_ERROR_STATUS_CODE: i32 = 3;
+pub const FAILURE_STATUS_CODE: i32 = 5;
+pub const TEST_FAILURE_STATUS_CODE: i32 = 7;
+
 pub(crate) const DATA_FILE_SUPPORTED_EXTENSIONS: [&'static str; 5] =
     [".yaml", ".yml", ".json", ".jsn", ".template"];
 pub(crate) const RULE_FILE_SUPPORTED_EXTENSIONS: [&'static str; 2] = [".guard", ".ruleset"];
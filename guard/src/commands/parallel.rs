@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use crate::rules::Result;
+
+///
+/// Resolves the `--jobs` argument, `0` selects the number of CPUs available to the process
+///
+pub(crate) fn jobs_from(value: Option<&str>) -> usize {
+    match value.and_then(|v| v.parse::<usize>().ok()) {
+        Some(0) => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        Some(jobs) => jobs,
+        None => 1,
+    }
+}
+
+pub(crate) fn validate_jobs(value: String) -> std::result::Result<(), String> {
+    value
+        .parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a number of jobs, found {}", value))
+}
+
+///
+/// Runs `work` over all items using up to `jobs` threads, and hands each result to `emit` on
+/// the calling thread in the same order as the items. Results are emitted as soon as all the
+/// items before them are done, so output is streamed while being deterministic. With a single
+/// job everything runs on the calling thread. The first error from `emit` stops the run.
+///
+pub(crate) fn map_ordered<T, R, W, E>(jobs: usize, items: &[T], work: W, mut emit: E) -> Result<()>
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    E: FnMut(&T, R) -> Result<()>,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        for each in items {
+            emit(each, work(each))?;
+        }
+        return Ok(());
+    }
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            let sender = sender.clone();
+            let (next, work) = (&next, &work);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() || sender.send((index, work(&items[index]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut emitted = 0;
+        for (index, result) in receiver {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&emitted) {
+                //
+                // Returning drops the receiver, which stops the workers at their next send
+                //
+                emit(&items[emitted], result)?;
+                emitted += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+#[path = "parallel_tests.rs"]
+mod parallel_tests;
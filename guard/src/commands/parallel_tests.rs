@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn test_map_ordered_keeps_item_order() {
+    let items: Vec<u64> = (0..64).collect();
+    for jobs in [1, 2, 8] {
+        let mut seen = Vec::with_capacity(items.len());
+        map_ordered(
+            jobs,
+            &items,
+            |each| {
+                // finish later items first to shake out ordering issues
+                std::thread::sleep(std::time::Duration::from_micros((64 - *each) * 50));
+                each * 2
+            },
+            |each, doubled| {
+                seen.push((*each, doubled));
+                Ok(())
+            },
+        )
+        .unwrap();
+        let expected: Vec<(u64, u64)> = items.iter().map(|each| (*each, each * 2)).collect();
+        assert_eq!(seen, expected);
+    }
+}
+
+#[test]
+fn test_jobs_from() {
+    assert_eq!(jobs_from(None), 1);
+    assert_eq!(jobs_from(Some("4")), 4);
+    assert!(jobs_from(Some("0")) >= 1);
+    assert!(validate_jobs("two".to_string()).is_err());
+    assert!(validate_jobs("2".to_string()).is_ok());
+}
+
+#[test]
+fn test_map_ordered_stops_on_error() {
+    let items: Vec<u64> = (0..64).collect();
+    let mut seen = 0;
+    let result = map_ordered(
+        4,
+        &items,
+        |each| *each,
+        |_, value| {
+            seen += 1;
+            if value == 10 {
+                return Err(crate::rules::errors::Error::new(
+                    crate::rules::errors::ErrorKind::IncompatibleError("stop".to_string()),
+                ));
+            }
+            Ok(())
+        },
+    );
+    assert!(result.is_err());
+    assert_eq!(seen, 11);
+}
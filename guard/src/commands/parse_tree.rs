@@ -1,8 +1,17 @@
 use crate::command::Command;
-use crate::commands::{OUTPUT, PARSE_TREE, PRINT_JSON, PRINT_YAML, RULES};
+use crate::commands::fmt::{printer, without_locations};
+use crate::commands::{
+    FROM_TREE, OUTPUT, PARSE_TREE, PRINT_JSON, PRINT_SCHEMA, PRINT_YAML, RULES, SCHEMA_VERSION,
+};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::{FileLocation, RuleClause, RulesFile};
+use crate::rules::parser::{rules_file, rules_file_exprs, Exprs, Span};
 use crate::rules::Result;
 use clap::{App, Arg, ArgMatches};
 use std::fs::File;
+use std::io::Write;
+
+mod ast;
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub(crate) struct ParseTree {}
@@ -21,7 +30,10 @@ impl Command for ParseTree {
     fn command(&self) -> App<'static, 'static> {
         App::new(PARSE_TREE)
             .about(
-                r#"Prints out the parse tree for the rules defined in the file.
+                r#"Prints out the parse tree for the rules defined in the file. With --from-tree
+the input is a parse tree in JSON or YAML and the rules it describes are printed instead.
+The default tree follows the internal types of cfn-guard and can change between releases,
+--schema-version prints a versioned tree described by the JSON Schema from --print-schema.
 "#,
             )
             .arg(
@@ -54,6 +66,31 @@ impl Command for ParseTree {
                     .required(false)
                     .help("Print output in YAML format"),
             )
+            .arg(
+                Arg::with_name(FROM_TREE.0)
+                    .long(FROM_TREE.0)
+                    .short(FROM_TREE.1)
+                    .required(false)
+                    .conflicts_with_all(&[PRINT_JSON.0, PRINT_YAML.0, SCHEMA_VERSION.0])
+                    .help("Read a parse tree in JSON or YAML and print the rules it describes"),
+            )
+            .arg(
+                Arg::with_name(SCHEMA_VERSION.0)
+                    .long(SCHEMA_VERSION.0)
+                    .short(SCHEMA_VERSION.1)
+                    .takes_value(true)
+                    .possible_values(&["1"])
+                    .required(false)
+                    .help("Print the versioned tree of this schema version"),
+            )
+            .arg(
+                Arg::with_name(PRINT_SCHEMA.0)
+                    .long(PRINT_SCHEMA.0)
+                    .short(PRINT_SCHEMA.1)
+                    .required(false)
+                    .conflicts_with_all(&[RULES.0, FROM_TREE.0, PRINT_YAML.0])
+                    .help("Print the JSON Schema of the versioned tree and exit"),
+            )
     }
 
     fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
@@ -66,10 +103,31 @@ impl Command for ParseTree {
             Some(file) => Box::new(File::create(file)?) as Box<dyn std::io::Write>,
             None => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
         };
+        if app.is_present(PRINT_SCHEMA.0) {
+            let mut out = out;
+            write!(out, "{}", ast::SCHEMA)?;
+            return Ok(0);
+        }
 
         let yaml = !app.is_present(PRINT_JSON.0);
         let mut content = String::new();
         file.read_to_string(&mut content)?;
+        if app.is_present(FROM_TREE.0) {
+            let mut out = out;
+            write!(out, "{}", rules_from_tree(&content)?)?;
+            return Ok(0);
+        }
+        let name = app.value_of(RULES.0).unwrap_or("");
+        if app.is_present(SCHEMA_VERSION.0) {
+            let tree = versioned_tree(name, &content)?;
+            if yaml {
+                serde_yaml::to_writer(out, &tree)?;
+            } else {
+                serde_json::to_writer_pretty(out, &tree)?;
+            }
+            return Ok(0);
+        }
+
         let span = crate::rules::parser::Span::new_extra(&content, "");
         match crate::rules::parser::rules_file(span) {
             Err(e) => {
@@ -79,7 +137,11 @@ impl Command for ParseTree {
 
             Ok(rules) => {
                 if yaml {
-                    serde_yaml::to_writer(out, &rules)?;
+                    //
+                    // serde_yaml does not write enums nested in others, as a JSON value they
+                    // are maps keyed by the variant the same as in the JSON tree
+                    //
+                    serde_yaml::to_writer(out, &serde_json::to_value(&rules)?)?;
                 } else {
                     serde_json::to_writer_pretty(out, &rules)?;
                 }
@@ -89,3 +151,110 @@ impl Command for ParseTree {
         Ok(0 as i32)
     }
 }
+
+///
+/// Prints the rules described by a parse tree in JSON or YAML. The printed rules are parsed
+/// again and must give the same tree apart from locations, which generated trees can leave out.
+///
+pub(crate) fn rules_from_tree(content: &str) -> Result<String> {
+    let tree: RulesFile = if content.trim_start().starts_with('{') {
+        serde_json::from_str(content)?
+    } else {
+        serde_json::from_value(serde_yaml::from_str(content)?)?
+    };
+    let exprs = top_level_exprs(tree.clone())?;
+    if exprs.is_empty() {
+        return Ok(String::new());
+    }
+
+    let rules = printer::print("", &exprs);
+    let reparsed = rules_file(Span::new_extra(&rules, ""))?;
+    if without_locations(serde_json::to_value(&tree)?)
+        != without_locations(serde_json::to_value(&reparsed)?)
+    {
+        return Err(Error::new(ErrorKind::IncompatibleError(
+            "the parse tree can not be written as Guard rules that parse back to the same tree"
+                .to_string(),
+        )));
+    }
+    Ok(rules)
+}
+
+///
+/// The versioned tree for the rules in `content` read from the file `name`, see
+/// `schema/parse-tree-v1.json` for its layout
+///
+pub(crate) fn versioned_tree(name: &str, content: &str) -> Result<serde_json::Value> {
+    let span = Span::new_extra(content, name);
+    let rules = rules_file(span)?;
+    let exprs = rules_file_exprs(span)?;
+    Ok(serde_json::to_value(ast::document(name, &rules, &exprs))?)
+}
+
+//
+// The parser gathers clauses outside of any rule into a rule named "default" ahead of the
+// others, these are written back as clauses at the top of the file
+//
+fn top_level_exprs(tree: RulesFile) -> Result<Vec<(FileLocation, Exprs)>> {
+    let mut exprs = vec![];
+    let mut guard_rules = tree.guard_rules.into_iter().peekable();
+    let default = guard_rules.next_if(|rule| {
+        rule.rule_name == "default"
+            && rule.conditions.is_none()
+            && rule.block.assignments.is_empty()
+    });
+    let mut defaults = vec![];
+    for disjunctions in default.into_iter().flat_map(|rule| rule.block.conjunctions) {
+        let expr = match disjunctions.first() {
+            Some(RuleClause::Clause(_)) => disjunctions
+                .into_iter()
+                .map(|each| match each {
+                    RuleClause::Clause(clause) => Some(clause),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Exprs::DefaultClause),
+            Some(RuleClause::TypeBlock(_)) => disjunctions
+                .into_iter()
+                .map(|each| match each {
+                    RuleClause::TypeBlock(type_block) => Some(type_block),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Exprs::DefaultTypeBlock),
+            Some(RuleClause::WhenBlock(..)) if disjunctions.len() == 1 => {
+                match disjunctions.into_iter().next() {
+                    Some(RuleClause::WhenBlock(conditions, block)) => {
+                        Some(Exprs::DefaultWhenBlock(conditions, block))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match expr {
+            Some(expr) => defaults.push(expr),
+            None => return Err(Error::new(ErrorKind::IncompatibleError(
+                "clauses outside of a rule must be joined with or only to clauses of the same kind"
+                    .to_string(),
+            ))),
+        }
+    }
+
+    exprs.extend(tree.assignments.into_iter().map(Exprs::Assignment));
+    exprs.extend(defaults);
+    exprs.extend(guard_rules.map(Exprs::Rule));
+    exprs.extend(
+        tree.parameterized_rules
+            .into_iter()
+            .map(Exprs::ParameterizedRule),
+    );
+    Ok(exprs
+        .into_iter()
+        .map(|each| (FileLocation::default(), each))
+        .collect())
+}
+
+#[cfg(test)]
+#[path = "parse_tree_tests.rs"]
+mod parse_tree_tests;
@@ -0,0 +1,602 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::rules::exprs::{
+    AccessQuery, Block as BlockExpr, Conjunctions, FileLocation, GuardClause, GuardNamedRuleClause,
+    LetExpr, LetValue, QueryPart, Rule as RuleExpr, RuleClause, RulesFile, TypeBlock,
+    WhenConditions, WhenGuardClause,
+};
+use crate::rules::parser::Exprs;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::values::{CmpOperator, RangeType, LOWER_INCLUSIVE, UPPER_INCLUSIVE};
+
+///
+/// The version of the tree written by [document]. It changes only when a change to the tree
+/// could break tools reading it, adding a field does not change it.
+///
+pub(crate) const VERSION: u32 = 1;
+
+///
+/// JSON Schema for version [VERSION] of the tree
+///
+pub(crate) const SCHEMA: &str = include_str!("../../../schema/parse-tree-v1.json");
+
+//
+// Every node carries its `kind`. Positions that take more than one kind of node use the
+// untagged enums below, the `kind` of the node in them tells which it is.
+//
+
+#[derive(Serialize)]
+pub(crate) struct Document<'a> {
+    schema_version: u32,
+    kind: &'static str,
+    file: &'a str,
+    assignments: Vec<Let<'a>>,
+    rules: Vec<Rule<'a>>,
+}
+
+///
+/// Where a node starts in the rules file. The parser does not record where nodes end.
+///
+#[derive(Serialize)]
+pub(crate) struct Span<'a> {
+    file: &'a str,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Let<'a> {
+    kind: &'static str,
+    name: &'a str,
+    value: Expr<'a>,
+    span: Span<'a>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Rule<'a> {
+    kind: &'static str,
+    name: &'a str,
+    parameters: Vec<&'a str>,
+    conditions: Option<Vec<Vec<Clause<'a>>>>,
+    block: Block<'a>,
+    span: Option<Span<'a>>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Block<'a> {
+    kind: &'static str,
+    assignments: Vec<Let<'a>>,
+    clauses: Vec<Vec<Clause<'a>>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Clause<'a> {
+    Comparison {
+        kind: &'static str,
+        negated: bool,
+        query: Query<'a>,
+        operator: &'static str,
+        operator_negated: bool,
+        value: Option<Expr<'a>>,
+        message: Option<&'a str>,
+        span: Span<'a>,
+    },
+    RuleReference {
+        kind: &'static str,
+        negated: bool,
+        rule: &'a str,
+        arguments: Option<Vec<Expr<'a>>>,
+        message: Option<&'a str>,
+        span: Span<'a>,
+    },
+    Block {
+        kind: &'static str,
+        query: Query<'a>,
+        not_empty: bool,
+        block: Block<'a>,
+        span: Span<'a>,
+    },
+    WhenBlock {
+        kind: &'static str,
+        conditions: Vec<Vec<Clause<'a>>>,
+        block: Block<'a>,
+    },
+    TypeBlock {
+        kind: &'static str,
+        type_name: &'a str,
+        conditions: Option<Vec<Vec<Clause<'a>>>>,
+        block: Block<'a>,
+        span: Option<Span<'a>>,
+    },
+}
+
+#[derive(Serialize)]
+pub(crate) struct Query<'a> {
+    kind: &'static str,
+    quantifier: &'static str,
+    parts: Vec<Part<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Part<'a> {
+    This {
+        kind: &'static str,
+    },
+    Key {
+        kind: &'static str,
+        name: &'a str,
+    },
+    Index {
+        kind: &'static str,
+        index: i32,
+    },
+    AllValues {
+        kind: &'static str,
+        variable: Option<&'a str>,
+    },
+    Filter {
+        kind: &'static str,
+        variable: Option<&'a str>,
+        conditions: Vec<Vec<Clause<'a>>>,
+    },
+    MapKeyFilter {
+        kind: &'static str,
+        variable: Option<&'a str>,
+        operator: &'static str,
+        operator_negated: bool,
+        value: Expr<'a>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Expr<'a> {
+    Literal(Literal),
+    Query(Query<'a>),
+    FunctionCall {
+        kind: &'static str,
+        name: &'a str,
+        arguments: Vec<Expr<'a>>,
+        span: Span<'a>,
+    },
+}
+
+#[derive(Serialize)]
+pub(crate) struct Literal {
+    kind: &'static str,
+    #[serde(flatten)]
+    value: LiteralValue,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum LiteralValue {
+    Null,
+    String {
+        value: String,
+    },
+    Regex {
+        value: String,
+    },
+    Bool {
+        value: bool,
+    },
+    Int {
+        value: i64,
+    },
+    Float {
+        value: f64,
+    },
+    Char {
+        value: char,
+    },
+    List {
+        value: Vec<Literal>,
+    },
+    Map {
+        value: IndexMap<String, Literal>,
+    },
+    Range {
+        element: &'static str,
+        lower: serde_json::Value,
+        upper: serde_json::Value,
+        lower_inclusive: bool,
+        upper_inclusive: bool,
+    },
+}
+
+///
+/// The tree for `rules`, `exprs` are the top level expressions of the same file and give
+/// where its rules start
+///
+pub(crate) fn document<'a>(
+    file: &'a str,
+    rules: &'a RulesFile<'a>,
+    exprs: &[(FileLocation<'_>, Exprs<'_>)],
+) -> Document<'a> {
+    let starts = |parameterized: bool| {
+        exprs
+            .iter()
+            .filter_map(move |(location, each)| match each {
+                Exprs::Rule(_) if !parameterized => Some((location.line, location.column)),
+                Exprs::ParameterizedRule(_) if parameterized => {
+                    Some((location.line, location.column))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    };
+    let (mut rule_starts, mut parameterized_starts) = (starts(false), starts(true));
+
+    //
+    // The parser gathers clauses outside of any rule into a first rule named "default", the
+    // rules in the file follow it and then the parameterized rules, in the order written
+    //
+    let has_default = exprs.iter().any(|(_, each)| {
+        matches!(
+            each,
+            Exprs::DefaultClause(_) | Exprs::DefaultTypeBlock(_) | Exprs::DefaultWhenBlock(..)
+        )
+    });
+    let named = rules.guard_rules.iter().enumerate().map(|(idx, each)| {
+        let start = if idx == 0 && has_default {
+            None
+        } else {
+            rule_starts.next()
+        };
+        rule(file, each, vec![], start)
+    });
+    let mut all = named.collect::<Vec<_>>();
+    for each in &rules.parameterized_rules {
+        let parameters = each.parameter_names.iter().map(String::as_str).collect();
+        all.push(rule(
+            file,
+            &each.rule,
+            parameters,
+            parameterized_starts.next(),
+        ));
+    }
+
+    Document {
+        schema_version: VERSION,
+        kind: "RulesFile",
+        file,
+        assignments: rules
+            .assignments
+            .iter()
+            .map(|each| let_node(file, each))
+            .collect(),
+        rules: all,
+    }
+}
+
+fn span<'a>(file: &'a str, location: &FileLocation<'_>) -> Span<'a> {
+    Span {
+        file,
+        line: location.line,
+        column: location.column,
+    }
+}
+
+fn rule<'a>(
+    file: &'a str,
+    rule: &'a RuleExpr<'a>,
+    parameters: Vec<&'a str>,
+    start: Option<(u32, u32)>,
+) -> Rule<'a> {
+    Rule {
+        kind: "Rule",
+        name: &rule.rule_name,
+        parameters,
+        conditions: rule
+            .conditions
+            .as_ref()
+            .map(|conditions| when_conditions(file, conditions)),
+        block: block(file, &rule.block, rule_clause),
+        span: start.map(|(line, column)| Span { file, line, column }),
+    }
+}
+
+fn let_node<'a>(file: &'a str, assignment: &'a LetExpr<'a>) -> Let<'a> {
+    Let {
+        kind: "Let",
+        name: &assignment.var,
+        value: expr(file, &assignment.value),
+        span: span(file, &assignment.location),
+    }
+}
+
+fn block<'a, T>(
+    file: &'a str,
+    block: &'a BlockExpr<'a, T>,
+    clause: fn(&'a str, &'a T) -> Clause<'a>,
+) -> Block<'a> {
+    Block {
+        kind: "Block",
+        assignments: block
+            .assignments
+            .iter()
+            .map(|each| let_node(file, each))
+            .collect(),
+        clauses: block
+            .conjunctions
+            .iter()
+            .map(|each| each.iter().map(|each| clause(file, each)).collect())
+            .collect(),
+    }
+}
+
+fn conjunctions<'a>(
+    file: &'a str,
+    conjunctions: &'a Conjunctions<GuardClause<'a>>,
+) -> Vec<Vec<Clause<'a>>> {
+    conjunctions
+        .iter()
+        .map(|each| each.iter().map(|each| guard_clause(file, each)).collect())
+        .collect()
+}
+
+fn when_conditions<'a>(file: &'a str, conditions: &'a WhenConditions<'a>) -> Vec<Vec<Clause<'a>>> {
+    conditions
+        .iter()
+        .map(|each| each.iter().map(|each| when_clause(file, each)).collect())
+        .collect()
+}
+
+fn rule_clause<'a>(file: &'a str, clause: &'a RuleClause<'a>) -> Clause<'a> {
+    match clause {
+        RuleClause::Clause(clause) => guard_clause(file, clause),
+        RuleClause::WhenBlock(conditions, when_block) => Clause::WhenBlock {
+            kind: "WhenBlock",
+            conditions: when_conditions(file, conditions),
+            block: block(file, when_block, guard_clause),
+        },
+        RuleClause::TypeBlock(type_block) => type_block_clause(file, type_block),
+    }
+}
+
+fn type_block_clause<'a>(file: &'a str, type_block: &'a TypeBlock<'a>) -> Clause<'a> {
+    //
+    // The parser expands a type block to a query for resources of that type, the filter in it
+    // starts where the type name does
+    //
+    let start = type_block.query.iter().find_map(|part| match part {
+        QueryPart::Filter(_, conjunctions) => match conjunctions.first()?.first()? {
+            GuardClause::Clause(clause) => Some(span(file, &clause.access_clause.location)),
+            _ => None,
+        },
+        _ => None,
+    });
+    Clause::TypeBlock {
+        kind: "TypeBlock",
+        type_name: &type_block.type_name,
+        conditions: type_block
+            .conditions
+            .as_ref()
+            .map(|conditions| when_conditions(file, conditions)),
+        block: block(file, &type_block.block, guard_clause),
+        span: start,
+    }
+}
+
+fn guard_clause<'a>(file: &'a str, clause: &'a GuardClause<'a>) -> Clause<'a> {
+    match clause {
+        GuardClause::Clause(clause) => {
+            let access = &clause.access_clause;
+            Clause::Comparison {
+                kind: "Comparison",
+                negated: clause.negation,
+                query: query(file, &access.query),
+                operator: operator(access.comparator.0),
+                operator_negated: access.comparator.1,
+                value: access.compare_with.as_ref().map(|value| expr(file, value)),
+                message: access.custom_message.as_deref(),
+                span: span(file, &access.location),
+            }
+        }
+        GuardClause::NamedRule(named) => rule_reference(file, named, None),
+        GuardClause::ParameterizedNamedRule(call) => rule_reference(
+            file,
+            &call.named_rule,
+            Some(
+                call.parameters
+                    .iter()
+                    .map(|each| expr(file, each))
+                    .collect(),
+            ),
+        ),
+        GuardClause::BlockClause(clause) => Clause::Block {
+            kind: "BlockClause",
+            query: query(file, &clause.query),
+            not_empty: clause.not_empty,
+            block: block(file, &clause.block, guard_clause),
+            span: span(file, &clause.location),
+        },
+        GuardClause::WhenBlock(conditions, when_block) => Clause::WhenBlock {
+            kind: "WhenBlock",
+            conditions: when_conditions(file, conditions),
+            block: block(file, when_block, guard_clause),
+        },
+    }
+}
+
+fn when_clause<'a>(file: &'a str, clause: &'a WhenGuardClause<'a>) -> Clause<'a> {
+    match clause {
+        WhenGuardClause::Clause(clause) => {
+            let access = &clause.access_clause;
+            Clause::Comparison {
+                kind: "Comparison",
+                negated: clause.negation,
+                query: query(file, &access.query),
+                operator: operator(access.comparator.0),
+                operator_negated: access.comparator.1,
+                value: access.compare_with.as_ref().map(|value| expr(file, value)),
+                message: access.custom_message.as_deref(),
+                span: span(file, &access.location),
+            }
+        }
+        WhenGuardClause::NamedRule(named) => rule_reference(file, named, None),
+        WhenGuardClause::ParameterizedNamedRule(call) => rule_reference(
+            file,
+            &call.named_rule,
+            Some(
+                call.parameters
+                    .iter()
+                    .map(|each| expr(file, each))
+                    .collect(),
+            ),
+        ),
+    }
+}
+
+fn rule_reference<'a>(
+    file: &'a str,
+    named: &'a GuardNamedRuleClause<'a>,
+    arguments: Option<Vec<Expr<'a>>>,
+) -> Clause<'a> {
+    Clause::RuleReference {
+        kind: "RuleReference",
+        negated: named.negation,
+        rule: &named.dependent_rule,
+        arguments,
+        message: named.custom_message.as_deref(),
+        span: span(file, &named.location),
+    }
+}
+
+fn query<'a>(file: &'a str, query: &'a AccessQuery<'a>) -> Query<'a> {
+    Query {
+        kind: "Query",
+        quantifier: if query.match_all { "all" } else { "some" },
+        parts: query.query.iter().map(|each| part(file, each)).collect(),
+    }
+}
+
+fn part<'a>(file: &'a str, part: &'a QueryPart<'a>) -> Part<'a> {
+    match part {
+        QueryPart::This => Part::This { kind: "This" },
+        QueryPart::Key(key) => match key.strip_prefix('%') {
+            Some(name) => Part::Key {
+                kind: "Variable",
+                name,
+            },
+            None => Part::Key {
+                kind: "Key",
+                name: key,
+            },
+        },
+        QueryPart::Index(index) => Part::Index {
+            kind: "Index",
+            index: *index,
+        },
+        QueryPart::AllValues(variable) => Part::AllValues {
+            kind: "AllValues",
+            variable: variable.as_deref(),
+        },
+        QueryPart::AllIndices(variable) => Part::AllValues {
+            kind: "AllIndices",
+            variable: variable.as_deref(),
+        },
+        QueryPart::Filter(variable, filter) => Part::Filter {
+            kind: "Filter",
+            variable: variable.as_deref(),
+            conditions: conjunctions(file, filter),
+        },
+        QueryPart::MapKeyFilter(variable, filter) => Part::MapKeyFilter {
+            kind: "MapKeyFilter",
+            variable: variable.as_deref(),
+            operator: operator(filter.comparator.0),
+            operator_negated: filter.comparator.1,
+            value: expr(file, &filter.compare_with),
+        },
+    }
+}
+
+fn expr<'a>(file: &'a str, value: &'a LetValue<'a>) -> Expr<'a> {
+    match value {
+        LetValue::Value(value) => Expr::Literal(literal(value)),
+        LetValue::AccessClause(access) => Expr::Query(query(file, access)),
+        LetValue::FunctionCall(call) => Expr::FunctionCall {
+            kind: "FunctionCall",
+            name: &call.name,
+            arguments: call
+                .parameters
+                .iter()
+                .map(|each| expr(file, each))
+                .collect(),
+            span: span(file, &call.location),
+        },
+    }
+}
+
+fn literal(value: &PathAwareValue) -> Literal {
+    let value = match value {
+        PathAwareValue::Null(_) => LiteralValue::Null,
+        PathAwareValue::String((_, value)) => LiteralValue::String {
+            value: value.clone(),
+        },
+        PathAwareValue::Regex((_, value)) => LiteralValue::Regex {
+            value: value.clone(),
+        },
+        PathAwareValue::Bool((_, value)) => LiteralValue::Bool { value: *value },
+        PathAwareValue::Int((_, value)) => LiteralValue::Int { value: *value },
+        PathAwareValue::Float((_, value)) => LiteralValue::Float { value: *value },
+        PathAwareValue::Char((_, value)) => LiteralValue::Char { value: *value },
+        PathAwareValue::List((_, list)) => LiteralValue::List {
+            value: list.iter().map(literal).collect(),
+        },
+        PathAwareValue::Map((_, map)) => LiteralValue::Map {
+            value: map
+                .values
+                .iter()
+                .map(|(key, each)| (key.clone(), literal(each)))
+                .collect(),
+        },
+        PathAwareValue::RangeInt((_, range)) => range_value("int", range),
+        PathAwareValue::RangeFloat((_, range)) => range_value("float", range),
+        PathAwareValue::RangeChar((_, range)) => range_value("char", range),
+    };
+    Literal {
+        kind: "Literal",
+        value,
+    }
+}
+
+fn range_value<T: PartialOrd + Serialize>(
+    element: &'static str,
+    range: &RangeType<T>,
+) -> LiteralValue {
+    LiteralValue::Range {
+        element,
+        lower: serde_json::to_value(&range.lower).unwrap_or_default(),
+        upper: serde_json::to_value(&range.upper).unwrap_or_default(),
+        lower_inclusive: range.inclusive & LOWER_INCLUSIVE != 0,
+        upper_inclusive: range.inclusive & UPPER_INCLUSIVE != 0,
+    }
+}
+
+fn operator(operator: CmpOperator) -> &'static str {
+    match operator {
+        CmpOperator::Eq => "eq",
+        CmpOperator::In => "in",
+        CmpOperator::Gt => "gt",
+        CmpOperator::Lt => "lt",
+        CmpOperator::Le => "le",
+        CmpOperator::Ge => "ge",
+        CmpOperator::Exists => "exists",
+        CmpOperator::Empty => "empty",
+        CmpOperator::IsString => "is_string",
+        CmpOperator::IsList => "is_list",
+        CmpOperator::IsMap => "is_map",
+        CmpOperator::IsBool => "is_bool",
+        CmpOperator::IsInt => "is_int",
+    }
+}
+
+#[cfg(test)]
+#[path = "ast_tests.rs"]
+mod ast_tests;
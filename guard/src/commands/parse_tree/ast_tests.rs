@@ -0,0 +1,235 @@
+use super::*;
+
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::commands::parse_tree::versioned_tree;
+use crate::rules::Result;
+
+//
+// Checks `value` against the parts of JSON Schema that the tree schema uses
+//
+fn conforms(schema: &Value, root: &Value, value: &Value) -> bool {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let name = reference.trim_start_matches("#/$defs/");
+        return conforms(&root["$defs"][name], root, value);
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return false;
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            return false;
+        }
+    }
+    if let Some(types) = schema.get("type") {
+        let types = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            other => vec![other.as_str().unwrap()],
+        };
+        let matched = types.iter().any(|each| match *each {
+            "null" => value.is_null(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        });
+        if !matched {
+            return false;
+        }
+    }
+    if let Some(Value::Array(choices)) = schema.get("oneOf") {
+        let matched = choices
+            .iter()
+            .filter(|each| conforms(each, root, value))
+            .count();
+        if matched != 1 {
+            return false;
+        }
+    }
+    if let (Some(items), Value::Array(values)) = (schema.get("items"), value) {
+        if !values.iter().all(|each| conforms(items, root, each)) {
+            return false;
+        }
+    }
+    if let Value::Object(map) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            if !required
+                .iter()
+                .all(|each| map.contains_key(each.as_str().unwrap()))
+            {
+                return false;
+            }
+        }
+        let properties = schema.get("properties");
+        for (key, each) in map {
+            let valid = match properties.and_then(|properties| properties.get(key)) {
+                Some(property) => conforms(property, root, each),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(allowed)) => *allowed,
+                    Some(additional) => conforms(additional, root, each),
+                    None => true,
+                },
+            };
+            if !valid {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[test]
+fn test_versioned_tree_matches_schema_for_corpus() -> Result<()> {
+    let schema: Value = serde_json::from_str(SCHEMA)?;
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut count = 0;
+    for dir in [root.join("../guard-examples"), root.join("resources")] {
+        for entry in walkdir::WalkDir::new(dir) {
+            let entry = entry.unwrap();
+            if !entry.file_name().to_string_lossy().ends_with(".guard") {
+                continue;
+            }
+            let content = std::fs::read_to_string(entry.path())?;
+            let name = entry.path().display().to_string();
+            let tree = match versioned_tree(&name, &content) {
+                Ok(tree) => tree,
+                Err(_) => continue,
+            };
+            assert!(conforms(&schema, &schema, &tree), "{}", name);
+            count += 1;
+        }
+    }
+    assert!(count > 20);
+
+    //
+    // The checks above must be able to fail
+    //
+    let mut tree = versioned_tree("t.guard", "Resources exists\n")?;
+    assert!(conforms(&schema, &schema, &tree));
+    tree["rules"][0]["block"]["clauses"][0][0]["operator"] = json!("EQUALS");
+    assert!(!conforms(&schema, &schema, &tree));
+    Ok(())
+}
+
+#[test]
+fn test_versioned_tree_nodes() -> Result<()> {
+    let rules = r#"let allowed = ['aws:kms', /^arn:/]
+rule encrypted when %allowed !empty {
+    some Resources.*[ Type == 'AWS::S3::Bucket' ].Properties {
+        Algorithm in %allowed <<must be encrypted>>
+        Size in r[1, 10)
+    }
+    not tagged(%allowed)
+}
+rule tagged(keys) {
+    %keys !empty
+}
+"#;
+    let tree = versioned_tree("s3.guard", rules)?;
+    assert_eq!(tree["schema_version"], 1);
+    assert_eq!(tree["kind"], "RulesFile");
+    assert_eq!(tree["file"], "s3.guard");
+    assert_eq!(
+        tree["assignments"][0]["value"],
+        json!({
+            "kind": "Literal",
+            "type": "list",
+            "value": [
+                { "kind": "Literal", "type": "string", "value": "aws:kms" },
+                { "kind": "Literal", "type": "regex", "value": "^arn:" }
+            ]
+        })
+    );
+
+    let encrypted = &tree["rules"][0];
+    assert_eq!(encrypted["name"], "encrypted");
+    assert_eq!(
+        encrypted["span"],
+        json!({ "file": "s3.guard", "line": 2, "column": 1 })
+    );
+    assert_eq!(encrypted["conditions"][0][0]["operator"], "empty");
+    assert_eq!(encrypted["conditions"][0][0]["operator_negated"], true);
+
+    let block = &encrypted["block"]["clauses"][0][0];
+    assert_eq!(block["kind"], "BlockClause");
+    assert_eq!(block["query"]["quantifier"], "some");
+    let kinds = block["query"]["parts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|each| each["kind"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec!["Key", "AllValues", "Filter", "Key"]);
+
+    let comparison = &block["block"]["clauses"][0][0];
+    assert_eq!(comparison["operator"], "in");
+    assert_eq!(comparison["value"]["parts"][0]["kind"], "Variable");
+    assert_eq!(comparison["value"]["parts"][0]["name"], "allowed");
+    assert_eq!(comparison["message"], "must be encrypted");
+    assert_eq!(comparison["span"]["line"], 4);
+    assert_eq!(
+        block["block"]["clauses"][1][0]["value"],
+        json!({
+            "kind": "Literal",
+            "type": "range",
+            "element": "int",
+            "lower": 1,
+            "upper": 10,
+            "lower_inclusive": true,
+            "upper_inclusive": false
+        })
+    );
+
+    let reference = &encrypted["block"]["clauses"][1][0];
+    assert_eq!(reference["kind"], "RuleReference");
+    assert_eq!(reference["negated"], true);
+    assert_eq!(reference["rule"], "tagged");
+    assert_eq!(reference["arguments"][0]["kind"], "Query");
+
+    let tagged = &tree["rules"][1];
+    assert_eq!(tagged["parameters"], json!(["keys"]));
+    assert_eq!(tagged["span"]["line"], 9);
+    Ok(())
+}
+
+#[test]
+fn test_versioned_tree_default_rule_and_type_blocks() -> Result<()> {
+    let rules = r#"Resources exists
+AWS::S3::Bucket when Properties exists {
+    Properties.BucketName == /^prod-/
+}
+rule named {
+    when Resources exists {
+        Resources !empty
+    }
+}
+"#;
+    let tree = versioned_tree("", rules)?;
+    let default = &tree["rules"][0];
+    assert_eq!(default["name"], "default");
+    assert!(default["span"].is_null());
+
+    let type_block = &default["block"]["clauses"][1][0];
+    assert_eq!(type_block["kind"], "TypeBlock");
+    assert_eq!(type_block["type_name"], "AWS::S3::Bucket");
+    assert_eq!(
+        type_block["span"],
+        json!({ "file": "", "line": 2, "column": 1 })
+    );
+    assert_eq!(type_block["conditions"][0][0]["operator"], "exists");
+
+    let named = &tree["rules"][1];
+    assert_eq!(named["span"]["line"], 5);
+    let when = &named["block"]["clauses"][0][0];
+    assert_eq!(when["kind"], "WhenBlock");
+    assert_eq!(when["block"]["clauses"][0][0]["span"]["line"], 7);
+
+    assert!(versioned_tree("", "rule broken {\n").is_err());
+    Ok(())
+}
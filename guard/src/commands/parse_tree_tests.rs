@@ -0,0 +1,125 @@
+use super::*;
+
+use std::path::Path;
+
+#[test]
+fn test_rules_from_tree_round_trips_corpus() -> Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut count = 0;
+    for dir in [root.join("../guard-examples"), root.join("resources")] {
+        for entry in walkdir::WalkDir::new(dir) {
+            let entry = entry.unwrap();
+            if !entry.file_name().to_string_lossy().ends_with(".guard") {
+                continue;
+            }
+            let content = std::fs::read_to_string(entry.path())?;
+            let rules = match rules_file(Span::new_extra(&content, "")) {
+                Ok(rules) => rules,
+                Err(_) => continue,
+            };
+            let expected = without_locations(serde_json::to_value(&rules)?);
+
+            let json = serde_json::to_string(&rules)?;
+            let yaml = serde_yaml::to_string(&serde_json::to_value(&rules)?)?;
+            for tree in [json, yaml] {
+                let printed = rules_from_tree(&tree)?;
+                let reparsed = rules_file(Span::new_extra(&printed, ""))?;
+                assert_eq!(
+                    without_locations(serde_json::to_value(&reparsed)?),
+                    expected,
+                    "{}",
+                    entry.path().display()
+                );
+            }
+            count += 1;
+        }
+    }
+    assert!(count > 20);
+    Ok(())
+}
+
+#[test]
+fn test_rules_from_generated_tree() -> Result<()> {
+    let tree = r#"{
+  "assignments": [
+    { "var": "allowed", "value": { "Value": { "value": ["aws:kms", "AES256"] } } },
+    { "var": "prefix", "value": { "Value": { "value": "/^prod-/", "literal": "/^prod-/" } } }
+  ],
+  "guard_rules": [
+    {
+      "rule_name": "encrypted",
+      "conditions": null,
+      "block": {
+        "assignments": [],
+        "conjunctions": [[{ "Clause": { "Clause": {
+          "access_clause": {
+            "query": { "query": [{ "Key": "Properties" }, { "Key": "Algorithm" }], "match_all": true },
+            "comparator": ["In", false],
+            "compare_with": { "AccessClause": { "query": [{ "Key": "%allowed" }], "match_all": true } },
+            "custom_message": "must be encrypted"
+          },
+          "negation": false
+        } } }]]
+      }
+    },
+    {
+      "rule_name": "named",
+      "conditions": [[{ "NamedRule": { "dependent_rule": "encrypted", "negation": false, "custom_message": null } }]],
+      "block": {
+        "assignments": [],
+        "conjunctions": [[{ "Clause": { "Clause": {
+          "access_clause": {
+            "query": { "query": [{ "Key": "Properties" }, { "Key": "BucketName" }], "match_all": true },
+            "comparator": ["Eq", false],
+            "compare_with": { "AccessClause": { "query": [{ "Key": "%prefix" }], "match_all": true } },
+            "custom_message": null
+          },
+          "negation": false
+        } } }]]
+      }
+    }
+  ],
+  "parameterized_rules": []
+}"#;
+    let expected = r#"let allowed = ['aws:kms', 'AES256']
+let prefix = /^prod-/
+
+rule encrypted {
+    Properties.Algorithm in %allowed <<must be encrypted>>
+}
+
+rule named when encrypted {
+    Properties.BucketName == %prefix
+}
+"#;
+    assert_eq!(rules_from_tree(tree)?, expected);
+
+    let empty = r#"{ "assignments": [], "guard_rules": [], "parameterized_rules": [] }"#;
+    assert_eq!(rules_from_tree(empty)?, "");
+    Ok(())
+}
+
+#[test]
+fn test_rules_from_tree_errors() -> Result<()> {
+    let rules = "Resources exists\nrule r {\n    Resources !empty\n}\n";
+    let mut tree = serde_json::to_value(rules_file(Span::new_extra(rules, ""))?)?;
+
+    //
+    // A clause and a type block joined with or outside of a rule can not be written
+    //
+    let type_block = serde_json::json!({ "TypeBlock": {
+        "type_name": "AWS::S3::Bucket",
+        "conditions": null,
+        "block": { "assignments": [], "conjunctions": [] },
+        "query": []
+    } });
+    tree["guard_rules"][0]["block"]["conjunctions"][0]
+        .as_array_mut()
+        .unwrap()
+        .push(type_block);
+    assert!(rules_from_tree(&tree.to_string()).is_err());
+
+    assert!(rules_from_tree(r#"{ "assignments": "none" }"#).is_err());
+    assert!(rules_from_tree("assignments: [").is_err());
+    Ok(())
+}
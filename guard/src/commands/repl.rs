@@ -0,0 +1,391 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufRead, IsTerminal, Write};
+
+use clap::{App, Arg, ArgMatches};
+
+use crate::command::Command;
+use crate::commands::files::read_file_content;
+use crate::commands::validate::explain::Explain;
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::commands::{DATA, REPL, RULES, SUCCESS_STATUS_CODE};
+use crate::rules::display::ValueOnlyDisplay;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::exprs::{AccessQuery, LetExpr, RulesFile};
+use crate::rules::parser::{access, from_str2, rules_file, Span};
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::{EvalContext, QueryResult, RecordType, Result};
+
+#[cfg(test)]
+#[path = "repl_tests.rs"]
+mod repl_tests;
+
+//
+// Clauses typed in are evaluated as the only clause of a rule with this name
+//
+const CLAUSE_RULE: &str = "repl";
+
+const HELP: &str = r#"Type a query to see the values it resolves to with their paths, e.g.
+    Resources.*[ Type == /S3/ ].Properties
+a clause to see how it evaluates against the data, e.g.
+    Resources.*[ Type == /S3/ ].Properties.BucketName exists
+or a let to define a variable for the queries and clauses that follow, e.g.
+    let buckets = Resources.*[ Type == /S3/ ]
+Lines with unclosed brackets continue on the next line.
+
+Commands:
+    :load <file>    load a rules file
+    :rules          list the rules in the loaded rules files
+    :rule <name>    evaluate a rule from the loaded rules files and trace its evaluation
+    :lets           list the variables defined
+    :help           show this help
+    :quit           exit, as does end of input"#;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Repl {}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {}
+    }
+}
+
+impl Command for Repl {
+    fn name(&self) -> &'static str {
+        REPL
+    }
+
+    fn command(&self) -> App<'static, 'static> {
+        App::new(REPL)
+            .about(
+                r#"Interactively resolves queries and evaluates clauses against a data file, to see how
+they work while authoring rules. Variables can be defined with let, and rules files
+loaded to evaluate their named rules against the data. Type :help for the commands.
+"#,
+            )
+            .arg(
+                Arg::with_name(DATA.0)
+                    .long(DATA.0)
+                    .short(DATA.1)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Provide a data file in JSON or YAML to resolve queries against"),
+            )
+            .arg(
+                Arg::with_name(RULES.0)
+                    .long(RULES.0)
+                    .short(RULES.1)
+                    .takes_value(true)
+                    .multiple(true)
+                    .help("Provide rules files to load at the start, more can be loaded with :load"),
+            )
+    }
+
+    fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
+        let data_file = app.value_of(DATA.0).unwrap();
+        let content = read_file_content(File::open(data_file)?)?;
+        let mut session = Session::new(data_file, get_path_aware_value_from_data(&content)?);
+        let mut stdout = std::io::stdout();
+        for file in app.values_of(RULES.0).into_iter().flatten() {
+            session.eval(&format!(":load {}", file), &mut stdout)?;
+        }
+
+        let stdin = std::io::stdin();
+        let interactive = stdin.is_terminal();
+        if interactive {
+            writeln!(stdout, "Type :help for help, :quit to exit")?;
+        }
+        let mut input = stdin.lock();
+        let mut pending = String::new();
+        loop {
+            if interactive {
+                write!(
+                    stdout,
+                    "{}",
+                    if pending.is_empty() {
+                        "guard> "
+                    } else {
+                        "...> "
+                    }
+                )?;
+                stdout.flush()?;
+            }
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            pending.push_str(&line);
+            if !complete(&pending) {
+                continue;
+            }
+            let entered = std::mem::take(&mut pending);
+            match session.eval(&entered, &mut stdout) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => writeln!(stdout, "Error occurred {}", e)?,
+            }
+        }
+        Ok(SUCCESS_STATUS_CODE)
+    }
+}
+
+///
+/// Whether the text entered so far closes all its brackets, outside of strings
+///
+pub(crate) fn complete(text: &str) -> bool {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for ch in text.chars() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if ch == '\\' => escaped = true,
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None => match ch {
+                '\'' | '"' => quote = Some(ch),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+    depth <= 0
+}
+
+///
+/// The data queries are resolved against, with the variables defined and the rules files loaded
+///
+pub(crate) struct Session {
+    data_file_name: String,
+    data: PathAwareValue,
+    /// The let statements by variable name, in the order the variables were first defined
+    lets: Vec<(String, String)>,
+    /// The rules files loaded by file name, with their content
+    rules: Vec<(String, String)>,
+}
+
+impl Session {
+    pub(crate) fn new(data_file_name: &str, data: PathAwareValue) -> Self {
+        Session {
+            data_file_name: data_file_name.to_string(),
+            data,
+            lets: vec![],
+            rules: vec![],
+        }
+    }
+
+    ///
+    /// Evaluates a line, or lines continued for unclosed brackets, writing the outcome. Returns
+    /// false when the session ends.
+    ///
+    pub(crate) fn eval(&mut self, entered: &str, writer: &mut dyn Write) -> Result<bool> {
+        let entered = entered.trim();
+        if entered.is_empty() || entered.starts_with('#') {
+            return Ok(true);
+        }
+        if let Some(command) = entered.strip_prefix(':') {
+            return self.command(command, writer);
+        }
+        if entered.starts_with("let ") {
+            self.define(entered)?;
+        } else if let Some(query) = whole_query(entered) {
+            self.resolve(&query, writer)?;
+        } else {
+            self.check(entered, writer)?;
+        }
+        Ok(true)
+    }
+
+    fn command(&mut self, command: &str, writer: &mut dyn Write) -> Result<bool> {
+        let (name, argument) = match command.trim().split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, Some(argument.trim())),
+            None => (command.trim(), None),
+        };
+        match (name, argument) {
+            ("quit", None) | ("q", None) | ("exit", None) => return Ok(false),
+            ("help", None) => writeln!(writer, "{}", HELP)?,
+            ("lets", None) => {
+                for (_, text) in &self.lets {
+                    writeln!(writer, "{}", text)?;
+                }
+            }
+            ("load", Some(file)) => {
+                let content = read_file_content(File::open(file)?)?;
+                let names = self.load(file, content)?;
+                writeln!(writer, "Loaded {}: {}", file, names.join(", "))?;
+            }
+            ("rules", None) => {
+                for (file, content) in &self.rules {
+                    let rules = rules_file(Span::new_extra(content, file))?;
+                    writeln!(writer, "{}: {}", file, rule_names(&rules).join(", "))?;
+                }
+            }
+            ("rule", Some(name)) => self.trace(name, writer)?,
+            _ => writeln!(
+                writer,
+                "Unknown command :{}, type :help for the commands",
+                command.trim()
+            )?,
+        }
+        Ok(true)
+    }
+
+    ///
+    /// Loads a rules file, replacing one loaded before with the same name, and returns the names
+    /// of its rules
+    ///
+    pub(crate) fn load(&mut self, file_name: &str, content: String) -> Result<Vec<String>> {
+        let names = rule_names(&rules_file(Span::new_extra(&content, file_name))?);
+        self.rules.retain(|(name, _)| name != file_name);
+        self.rules.push((file_name.to_string(), content));
+        Ok(names)
+    }
+
+    //
+    // Defines or redefines a variable, checking the variables still parse together
+    //
+    fn define(&mut self, text: &str) -> Result<()> {
+        let name = LetExpr::try_from(text)?.var;
+        let mut lets = self.lets.clone();
+        match lets.iter_mut().find(|(each, _)| *each == name) {
+            Some(existing) => existing.1 = text.to_string(),
+            None => lets.push((name, text.to_string())),
+        }
+        parse_entered(&lets_text(&lets))?;
+        self.lets = lets;
+        Ok(())
+    }
+
+    fn resolve(&self, query: &AccessQuery<'_>, writer: &mut dyn Write) -> Result<()> {
+        let lets = lets_text(&self.lets);
+        let rules = parse_entered(&lets)?;
+        let mut scope = root_scope(&rules, &self.data)?;
+        let results = scope.query(&query.query)?;
+        if results.is_empty() {
+            writeln!(writer, "no values")?;
+        }
+        for each in &results {
+            writeln!(writer, "{}", result_text(each))?;
+        }
+        Ok(())
+    }
+
+    //
+    // Evaluates a clause as the only clause of a rule, and traces the evaluation
+    //
+    fn check(&self, clause: &str, writer: &mut dyn Write) -> Result<()> {
+        let text = format!(
+            "{}\nrule {} {{\n{}\n}}\n",
+            lets_text(&self.lets),
+            CLAUSE_RULE,
+            clause
+        );
+        let rules = parse_entered(&text)?;
+        let mut scope = root_scope(&rules, &self.data)?;
+        scope.explain_checks();
+        let status = eval_rules_file(&rules, &mut scope)?;
+        let record = scope.reset_recorder().extract();
+        writeln!(writer, "{}", status)?;
+        let explain = Explain {
+            rule: CLAUSE_RULE.to_string(),
+            path: None,
+        };
+        for rule in &record.children {
+            if let Some(RecordType::RuleCheck(_)) = &rule.container {
+                explain.write_rule(writer, rule)?;
+            }
+        }
+        Ok(())
+    }
+
+    //
+    // Evaluates the loaded rules files that have the rule, and traces its evaluation
+    //
+    fn trace(&self, name: &str, writer: &mut dyn Write) -> Result<()> {
+        let explain = Explain {
+            rule: name.to_string(),
+            path: None,
+        };
+        let parsed = self
+            .rules
+            .iter()
+            .map(|(file, content)| Ok((file, rules_file(Span::new_extra(content, file))?)))
+            .collect::<Result<Vec<_>>>()?;
+        if let Err(message) = explain.check_defined(parsed.iter().map(|(_, rules)| rules)) {
+            writeln!(writer, "{}", message)?;
+            return Ok(());
+        }
+        for (file, rules) in parsed.iter().filter(|(_, rules)| explain.defined_in(rules)) {
+            let mut scope = root_scope(rules, &self.data)?;
+            scope.explain_checks();
+            eval_rules_file(rules, &mut scope)?;
+            let record = scope.reset_recorder().extract();
+            explain.write_trace(writer, file, &self.data_file_name, &record)?;
+        }
+        Ok(())
+    }
+}
+
+//
+// The query when the whole text is one, and not the start of a clause
+//
+fn whole_query(text: &str) -> Option<AccessQuery<'_>> {
+    match access(from_str2(text)) {
+        Ok((rest, query)) if rest.fragment().trim().is_empty() => Some(query),
+        _ => None,
+    }
+}
+
+//
+// The lets and clauses entered, parsed as a rules file named after the command
+//
+fn parse_entered(text: &str) -> Result<RulesFile<'_>> {
+    if text.trim().is_empty() {
+        return Ok(RulesFile {
+            assignments: vec![],
+            guard_rules: vec![],
+            parameterized_rules: vec![],
+        });
+    }
+    rules_file(Span::new_extra(text, REPL))
+}
+
+fn lets_text(lets: &[(String, String)]) -> String {
+    lets.iter()
+        .map(|(_, text)| text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn rule_names(rules: &RulesFile<'_>) -> Vec<String> {
+    rules
+        .guard_rules
+        .iter()
+        .map(|rule| rule.rule_name.clone())
+        .chain(
+            rules
+                .parameterized_rules
+                .iter()
+                .map(|rule| rule.rule.rule_name.clone()),
+        )
+        .collect()
+}
+
+fn result_text(result: &QueryResult<'_>) -> String {
+    match result {
+        QueryResult::Literal(value) => format!("literal {}", ValueOnlyDisplay(value)),
+        QueryResult::Resolved(value) => {
+            format!("{} = {}", value.self_path().0, ValueOnlyDisplay(value))
+        }
+        QueryResult::UnResolved(ur) => format!(
+            "UnResolved at {} (remaining query {}){}",
+            ur.traversed_to.self_path().0,
+            ur.remaining_query,
+            ur.reason
+                .as_ref()
+                .map_or(String::new(), |reason| format!(": {}", reason))
+        ),
+    }
+}
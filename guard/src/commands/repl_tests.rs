@@ -0,0 +1,111 @@
+use super::*;
+use crate::rules::Status;
+
+const DATA: &str = r#"
+Resources:
+  bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketName: logs
+  volume:
+    Type: AWS::EC2::Volume
+    Properties:
+      Size: 200
+      KmsKeyId: key
+"#;
+
+const RULES: &str = r#"
+rule volumes_encrypted {
+    Resources.*[ Type == 'AWS::EC2::Volume' ].Properties.KmsKeyId exists
+}
+
+rule buckets_named {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.BucketName == 'data'
+}
+"#;
+
+fn session() -> Result<Session> {
+    let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(DATA)?)?;
+    Ok(Session::new("template.yaml", value))
+}
+
+fn eval(session: &mut Session, entered: &str) -> Result<String> {
+    let mut output = Vec::new();
+    session.eval(entered, &mut output)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_query_shows_values_with_paths() -> Result<()> {
+    let mut session = session()?;
+    let output = eval(&mut session, "Resources.*.Properties.KmsKeyId")?;
+    assert!(
+        output.contains("UnResolved at /Resources/bucket/Properties (remaining query KmsKeyId)")
+    );
+    assert!(output.contains("/Resources/volume/Properties/KmsKeyId = \"key\""));
+
+    let output = eval(&mut session, "Resources.*[ Type == /Lambda/ ]")?;
+    assert_eq!(output, "no values\n");
+    Ok(())
+}
+
+#[test]
+fn test_let_and_clause_are_traced() -> Result<()> {
+    let mut session = session()?;
+    assert_eq!(
+        eval(
+            &mut session,
+            "let volumes = Resources.*[ Type == /Volume/ ]"
+        )?,
+        ""
+    );
+    assert_eq!(
+        eval(&mut session, ":lets")?,
+        "let volumes = Resources.*[ Type == /Volume/ ]\n"
+    );
+
+    let output = eval(&mut session, "%volumes.Properties.Size > 500")?;
+    assert!(output.starts_with(&format!("{}\n", Status::FAIL)));
+    assert!(output.contains(&format!(
+        "/Resources/volume/Properties/Size = 200 GREATER THAN 500: {}",
+        Status::FAIL
+    )));
+
+    let output = eval(&mut session, "%volumes.Properties.Size")?;
+    assert_eq!(output, "/Resources/volume/Properties/Size = 200\n");
+
+    assert!(eval(&mut session, "let volumes = ").is_err());
+    assert!(eval(&mut session, "%buckets").is_err());
+    assert_eq!(
+        eval(&mut session, ":unknown")?,
+        "Unknown command :unknown, type :help for the commands\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_loaded_rules_are_evaluated_by_name() -> Result<()> {
+    let mut session = session()?;
+    let names = session.load("volumes.guard", RULES.to_string())?;
+    assert_eq!(names, vec!["volumes_encrypted", "buckets_named"]);
+    assert_eq!(
+        eval(&mut session, ":rules")?,
+        "volumes.guard: volumes_encrypted, buckets_named\n"
+    );
+
+    let output = eval(&mut session, ":rule buckets_named")?;
+    assert!(output.starts_with(&format!(
+        "Rule buckets_named in volumes.guard against template.yaml: {}\n",
+        Status::FAIL
+    )));
+    assert!(output.contains("/Resources/bucket/Properties/BucketName = \"logs\""));
+    assert_eq!(
+        eval(&mut session, ":rule missing")?,
+        "There is no rule named missing to explain in the rules files that parsed\n"
+    );
+
+    assert!(!complete("Resources.*[ Type == /S3/"));
+    assert!(complete("Resources.*[ Type == '[' ]"));
+    assert!(!session.eval(":quit", &mut Vec::new())?);
+    Ok(())
+}
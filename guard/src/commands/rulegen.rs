@@ -1,15 +1,24 @@
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::process;
 
 use crate::command::Command;
-use crate::commands::{OUTPUT, RULEGEN, TEMPLATE};
+use crate::commands::files::{alpabetical, get_files_with_filter, read_file_content, IgnoreFilter};
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::commands::{
+    DATA_FILE_SUPPORTED_EXTENSIONS, OUTPUT, RULEGEN, SUCCESS_STATUS_CODE, TEMPLATE, THRESHOLD, TYPE,
+};
+use crate::rules::display::quoted;
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::{
+    AccessClause, AccessQuery, GuardAccessClause, GuardClause, LetValue, QueryPart,
+};
+use crate::rules::path_value::{Path, PathAwareValue};
+use crate::rules::values::{CmpOperator, RangeType, LOWER_INCLUSIVE, UPPER_INCLUSIVE};
 use crate::rules::Result;
 use clap::{App, Arg, ArgMatches};
-use itertools::Itertools;
-use serde_json::Value;
-use std::collections::{HashMap, HashSet};
-use string_builder::Builder;
+
+mod generic;
+mod terraform;
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub(crate) struct Rulegen {}
@@ -27,207 +36,677 @@ impl Command for Rulegen {
 
     fn command(&self) -> App<'static, 'static> {
         App::new(RULEGEN)
-            .about(r#"Autogenerate rules from an existing JSON- or YAML- formatted data. (Currently works with only CloudFormation templates)
+            .about(r#"Autogenerate rules from an existing JSON- or YAML- formatted data. CloudFormation templates get rules for the
+properties of each resource type, as do Terraform plans in JSON with --type TerraformPlan. With --type Generic
+any other data gets a rule for its structure.
 "#)
-            .arg(Arg::with_name(TEMPLATE.0).long(TEMPLATE.0).short(TEMPLATE.1).takes_value(true).help("Provide path to a CloudFormation template file in JSON or YAML").required(true))
+            .arg(Arg::with_name(TEMPLATE.0).long(TEMPLATE.0).short(TEMPLATE.1).takes_value(true).multiple(true)
+                .help("Provide path to a CloudFormation template file in JSON or YAML, or a directory of templates to generate rules from all of them").required(true))
+            .arg(Arg::with_name(TYPE.0).long(TYPE.0).takes_value(true).possible_values(&["CFNTemplate", "TerraformPlan", "Generic"]).default_value("CFNTemplate")
+                .help("Specify the type of data the rules are generated from"))
             .arg(Arg::with_name(OUTPUT.0).long(OUTPUT.0).short(OUTPUT.1).takes_value(true).help("Write to output file").required(false))
+            .arg(Arg::with_name(THRESHOLD.0).long(THRESHOLD.0).short(THRESHOLD.1).takes_value(true).default_value("100").validator(validate_threshold)
+                .help("The percentage of resources of a type a check must hold for to be generated, from 1 to 100"))
     }
 
     fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
-        let file = app.value_of(TEMPLATE.0).unwrap();
-        let template_contents = fs::read_to_string(file)?;
+        let threshold = app.value_of(THRESHOLD.0).unwrap().parse::<usize>().unwrap();
+        let ignore = IgnoreFilter::new(std::iter::empty())?;
+        let data_type = app.value_of(TYPE.0).unwrap();
+        let mut rule_map = BTreeMap::new();
+        let mut documents = ResourceType::default();
+        for file_or_dir in app.values_of(TEMPLATE.0).unwrap() {
+            let files = get_files_with_filter(file_or_dir, alpabetical, &ignore, |entry| {
+                entry.file_name().to_str().is_some_and(|name| {
+                    DATA_FILE_SUPPORTED_EXTENSIONS
+                        .iter()
+                        .any(|ext| name.ends_with(ext))
+                })
+            })?;
+            for file in files {
+                let template_contents = read_file_content(File::open(&file)?)?;
+                let generated = match data_type {
+                    "Generic" => generic::gen_rules_from_data(&template_contents, &mut documents),
+                    "TerraformPlan" => {
+                        terraform::gen_rules_from_plan(&template_contents, &mut rule_map)
+                    }
+                    _ => gen_rules_from_template(&template_contents, &mut rule_map),
+                };
+                generated.map_err(|e| {
+                    Error::new(ErrorKind::ParseError(format!("{}, {}", file.display(), e)))
+                })?;
+            }
+        }
 
         let out = match app.value_of(OUTPUT.0) {
             Some(file) => Box::new(File::create(file)?) as Box<dyn std::io::Write>,
             None => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
         };
+        let generated_rules = match data_type {
+            "Generic" => generic::rules_text(&documents, threshold),
+            "TerraformPlan" => rules_text(&terraform::PLAN_LAYOUT, &rule_map, threshold),
+            _ => rules_text(&TEMPLATE_LAYOUT, &rule_map, threshold),
+        };
+        print_rules(&generated_rules, out)?;
+
+        Ok(SUCCESS_STATUS_CODE)
+    }
+}
+
+fn validate_threshold(value: String) -> std::result::Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(1..=100) => Ok(()),
+        _ => Err(format!(
+            "expected a percentage from 1 to 100, found {}",
+            value
+        )),
+    }
+}
+
+///
+/// Where the resources of each type are found in the data rules are generated from
+///
+pub(crate) struct Layout {
+    /// The query for all the resources, e.g. `Resources.*`
+    pub(crate) resources: &'static str,
+    /// The key of a resource with its type
+    pub(crate) type_key: &'static str,
+    /// A clause a resource must also satisfy for its properties to be checked
+    pub(crate) condition: Option<&'static str>,
+    /// The path from a resource to its properties
+    pub(crate) properties: &'static str,
+    /// What a file with resources is called in the generated comments
+    pub(crate) file: &'static str,
+}
 
-        let result = parse_template_and_call_gen(&template_contents);
-        print_rules(result, out)?;
+pub(crate) const TEMPLATE_LAYOUT: Layout = Layout {
+    resources: "Resources.*",
+    type_key: "Type",
+    condition: None,
+    properties: "Properties",
+    file: "template",
+};
+
+///
+/// What was seen for the resources of one type
+///
+#[derive(Debug, Default)]
+pub(crate) struct ResourceType {
+    /// The number of templates, or plans, with resources of the type
+    pub(crate) templates: usize,
+    /// The number of resources of the type
+    pub(crate) resources: usize,
+    /// Each property path below the properties, e.g. `Tags[*].Key`, and what was seen at it
+    pub(crate) properties: BTreeMap<String, Observed>,
+}
+
+///
+/// What was seen at one property path across the resources of a type
+///
+#[derive(Debug, Default)]
+pub(crate) struct Observed {
+    /// The query parts of the path below the properties
+    pub(crate) path: Vec<QueryPart<'static>>,
+    /// The distinct scalar values seen, in the order they were first seen
+    pub(crate) values: Vec<PathAwareValue>,
+    /// For each resource the path was present in, the values it had as indices into `values`.
+    /// None when a value could not be known from the template alone, e.g. a `Ref`.
+    pub(crate) seen: Vec<Option<BTreeSet<usize>>>,
+    /// The number of resources with each kind of value, by the unary operator checking for it
+    pub(crate) kinds: BTreeMap<&'static str, usize>,
+    /// The length of each list seen at the path
+    pub(crate) lengths: Vec<usize>,
+}
 
-        Ok(0 as i32)
+impl Observed {
+    ///
+    /// The number of resources the path was present in
+    ///
+    pub(crate) fn resources(&self) -> usize {
+        self.seen.len()
     }
 }
 
-pub fn parse_template_and_call_gen(
+//
+// What one resource had at a property path
+//
+#[derive(Default)]
+struct Found<'v> {
+    path: Vec<QueryPart<'static>>,
+    values: Vec<&'v PathAwareValue>,
+    kinds: BTreeSet<&'static str>,
+    lengths: Vec<usize>,
+    unresolved: bool,
+}
+
+///
+/// Adds what was seen in the resources of a template to `rule_map`
+///
+pub(crate) fn gen_rules_from_template(
     template_contents: &str,
-) -> HashMap<String, HashMap<String, HashSet<String>>> {
-    let cfn_template: HashMap<String, Value> = match serde_yaml::from_str(template_contents) {
-        Ok(s) => s,
-        Err(e) => {
-            println!("Parsing error handling template file, Error = {}", e);
-            process::exit(1);
+    rule_map: &mut BTreeMap<String, ResourceType>,
+) -> Result<()> {
+    let template = get_path_aware_value_from_data(&template_contents.to_string())?;
+    match template
+        .as_map()
+        .and_then(|map| map.values.get("Resources"))
+    {
+        Some(PathAwareValue::Map((_, resources))) => {
+            gen_rules(resources.values.values(), &TEMPLATE_LAYOUT, rule_map);
+            Ok(())
         }
-    };
+        Some(_) => Err(Error::new(ErrorKind::ParseError(
+            "Template Resources section has an invalid structure".to_string(),
+        ))),
+        None => Err(Error::new(ErrorKind::ParseError(
+            "Template lacks a Resources section".to_string(),
+        ))),
+    }
+}
 
-    let cfn_resources_clone = match cfn_template.get("Resources") {
-        Some(y) => y.clone(),
-        None => {
-            println!("Template lacks a Resources section");
-            process::exit(1);
-        }
-    };
+pub(crate) fn parse_template_and_call_gen(
+    template_contents: &str,
+) -> Result<BTreeMap<String, ResourceType>> {
+    let mut rule_map = BTreeMap::new();
+    gen_rules_from_template(template_contents, &mut rule_map)?;
+    Ok(rule_map)
+}
 
-    let cfn_resources: HashMap<String, Value> = match serde_json::from_value(cfn_resources_clone) {
-        Ok(y) => y,
-        Err(e) => {
-            println!("Template Resources section has an invalid structure: {}", e);
-            process::exit(1);
+//
+// Gathers, for each resource type, the values seen at every property path. For example, the
+// following template:
+//
+//        {
+//            "Resources": {
+//                "NewVolume" : {
+//                    "Type" : "AWS::EC2::Volume",
+//                    "Properties" : {
+//                        "Size" : 500,
+//                        "Encrypted": false,
+//                        "AvailabilityZone" : "us-west-2b",
+//                        "Tags": [{ "Key": "team", "Value": "storage" }]
+//                    }
+//                },
+//                "NewVolume2" : {
+//                    "Type" : "AWS::EC2::Volume",
+//                    "Properties" : {
+//                        "Size" : 50,
+//                        "Encrypted": false,
+//                        "AvailabilityZone" : "us-west-2c"
+//                    }
+//                }
+//            }
+//        }
+//
+// gives, for AWS::EC2::Volume seen 2 times:
+//
+//     AvailabilityZone   2 times  "us-west-2b", "us-west-2c"
+//     Encrypted          2 times  false
+//     Size               2 times  500, 50
+//     Tags               1 time   a list
+//     Tags[*]            1 time   a map
+//     Tags[*].Key        1 time   "team"
+//     Tags[*].Value      1 time   "storage"
+//
+fn gen_rules<'r, I>(resources: I, layout: &Layout, rule_map: &mut BTreeMap<String, ResourceType>)
+where
+    I: IntoIterator<Item = &'r PathAwareValue>,
+{
+    let mut types = BTreeSet::new();
+    for resource in resources {
+        let properties = layout
+            .properties
+            .split('.')
+            .try_fold(resource, |value, key| value.as_map()?.values.get(key));
+        let resource = match resource.as_map() {
+            Some(resource) => resource,
+            None => continue,
+        };
+        let (resource_type, properties) = match (
+            resource
+                .values
+                .get(layout.type_key)
+                .and_then(PathAwareValue::as_string),
+            properties,
+        ) {
+            (Some(resource_type), Some(properties @ PathAwareValue::Map(_))) => {
+                (resource_type, properties)
+            }
+            _ => continue,
+        };
+
+        let seen = rule_map.entry(resource_type.to_string()).or_default();
+        if types.insert(resource_type) {
+            seen.templates += 1;
         }
-    };
+        record(properties, seen);
+    }
+}
 
-    gen_rules(cfn_resources)
+//
+// Adds what one resource, or document, has below `root` to what was seen for its kind
+//
+fn record(root: &PathAwareValue, seen: &mut ResourceType) {
+    seen.resources += 1;
+    let mut found = BTreeMap::new();
+    observe(root, &[], &mut found);
+    for (text, found) in found {
+        let observed = seen.properties.entry(text).or_insert_with(|| Observed {
+            path: found.path.clone(),
+            ..Observed::default()
+        });
+        for kind in found.kinds {
+            *observed.kinds.entry(kind).or_default() += 1;
+        }
+        observed.lengths.extend(found.lengths);
+        if found.unresolved {
+            observed.seen.push(None);
+            continue;
+        }
+        let mut indices = BTreeSet::new();
+        for value in found.values {
+            let idx = match observed.values.iter().position(|each| each == value) {
+                Some(idx) => idx,
+                None => {
+                    observed.values.push(value.clone());
+                    observed.values.len() - 1
+                }
+            };
+            indices.insert(idx);
+        }
+        observed.seen.push(Some(indices));
+    }
 }
 
-fn gen_rules(
-    cfn_resources: HashMap<String, Value>,
-) -> HashMap<String, HashMap<String, HashSet<String>>> {
-    // Create hashmap of resource name, property name and property values
-    // For example, the following template:
+//
+// Records what one resource has at `path`, and below it for lists and maps
+//
+fn observe<'v>(
+    value: &'v PathAwareValue,
+    path: &[QueryPart<'static>],
+    found: &mut BTreeMap<String, Found<'v>>,
+) {
     //
-    //        {
-    //            "Resources": {
-    //                "NewVolume" : {
-    //                    "Type" : "AWS::EC2::Volume",
-    //                    "Properties" : {
-    //                        "Size" : 500,
-    //                        "Encrypted": false,
-    //                        "AvailabilityZone" : "us-west-2b"
-    //                    }
-    //                },
-    //                "NewVolume2" : {
-    //                    "Type" : "AWS::EC2::Volume",
-    //                    "Properties" : {
-    //                        "Size" : 50,
-    //                        "Encrypted": false,
-    //                        "AvailabilityZone" : "us-west-2c"
-    //                    }
-    //                }
-    //            }
-    //        }
+    // Null values, e.g. the unset attributes in a Terraform plan, are left out as if absent
     //
-    //
-    // The data structure would contain:
-    // <AWS::EC2::Volume> <Encrypted> <false>
-    //                    <Size> <500, 50>
-    //                    <AvailabilityZone> <us-west-2c, us-west-2b>
-    //
-    //
-    //
-    let mut rule_map: HashMap<String, HashMap<String, HashSet<String>>> = HashMap::new();
-    for (_name, cfn_resource) in cfn_resources {
-        let props: HashMap<String, Value> =
-            match serde_json::from_value(cfn_resource["Properties"].clone()) {
-                Ok(s) => s,
-                Err(_) => continue,
-            };
+    if matches!(value, PathAwareValue::Null(_)) {
+        return;
+    }
+    if !path.is_empty() {
+        let at = found.entry(query_text(path)).or_insert_with(|| Found {
+            path: path.to_vec(),
+            ..Found::default()
+        });
+        if is_intrinsic_function(value) {
+            at.unresolved = true;
+            return;
+        }
+        if let Some(kind) = kind(value) {
+            at.kinds.insert(kind);
+        }
+        match value {
+            PathAwareValue::List((_, list)) => at.lengths.push(list.len()),
+            PathAwareValue::Map(_) => {}
+            scalar => at.values.push(scalar),
+        }
+    }
 
-        for (prop_name, prop_val) in props {
-            let stripped_val = match prop_val.as_str() {
-                Some(v) => String::from(v),
-                None => prop_val.to_string(),
-            };
+    match value {
+        PathAwareValue::Map((_, map)) => {
+            for (key, each) in &map.values {
+                observe(each, &joined(path, [QueryPart::Key(key.clone())]), found);
+            }
+        }
+        PathAwareValue::List((_, list)) => {
+            let path = joined(path, [QueryPart::AllIndices(None)]);
+            for each in list {
+                observe(each, &path, found);
+            }
+        }
+        _ => {}
+    }
+}
 
-            let mut no_newline_stripped_val = stripped_val.trim().replace("\n", "");
+//
+// Maps with a single key like `Ref`, `Fn::GetAtt` or `Fn::Sub` are only known once the template
+// is deployed
+//
+fn is_intrinsic_function(value: &PathAwareValue) -> bool {
+    match value {
+        PathAwareValue::Map((_, map)) if map.values.len() == 1 => map
+            .values
+            .keys()
+            .all(|key| key == "Ref" || key == "Condition" || key.starts_with("Fn::")),
+        _ => false,
+    }
+}
 
-            // Preserve double quotes for strings.
-            if prop_val.is_string() {
-                let test_str = format!("{}{}{}", "\"", no_newline_stripped_val, "\"");
-                no_newline_stripped_val = test_str;
-            }
-            let resource_name = format!("{}", &cfn_resource["Type"].as_str().unwrap());
+fn kind(value: &PathAwareValue) -> Option<&'static str> {
+    match value {
+        PathAwareValue::String(_) => Some("is_string"),
+        PathAwareValue::Bool(_) => Some("is_bool"),
+        PathAwareValue::Int(_) => Some("is_int"),
+        PathAwareValue::List(_) => Some("is_list"),
+        PathAwareValue::Map(_) => Some("is_map"),
+        _ => None,
+    }
+}
 
-            if !rule_map.contains_key(&resource_name) {
-                let value_set: HashSet<String> =
-                    vec![no_newline_stripped_val].into_iter().collect();
+fn joined<I>(path: &[QueryPart<'static>], parts: I) -> Vec<QueryPart<'static>>
+where
+    I: IntoIterator<Item = QueryPart<'static>>,
+{
+    path.iter().cloned().chain(parts).collect()
+}
 
-                let mut property_map = HashMap::new();
-                property_map.insert(prop_name, value_set);
-                rule_map.insert(resource_name, property_map);
-            } else {
-                let property_map = rule_map.get_mut(&resource_name).unwrap();
-
-                if !property_map.contains_key(&prop_name) {
-                    let value_set: HashSet<String> =
-                        vec![no_newline_stripped_val].into_iter().collect();
-                    property_map.insert(prop_name, value_set);
-                } else {
-                    let value_set = property_map.get_mut(&prop_name).unwrap();
-                    value_set.insert(no_newline_stripped_val);
-                }
-            };
+//
+// The parts of a dotted path like `Properties` or `change.after`
+//
+fn keys(dotted: &str) -> Vec<QueryPart<'static>> {
+    dotted
+        .split('.')
+        .map(|key| QueryPart::Key(key.to_string()))
+        .collect()
+}
+
+fn query_text(path: &[QueryPart<'static>]) -> String {
+    AccessQuery {
+        query: path.to_vec(),
+        match_all: true,
+    }
+    .to_string()
+}
+
+//
+// The clause checking the values at `path`, with `compare_with` for binary operators
+//
+fn clause(
+    path: &[QueryPart<'static>],
+    comparator: (CmpOperator, bool),
+    compare_with: Option<PathAwareValue>,
+) -> GuardClause<'static> {
+    GuardClause::Clause(GuardAccessClause {
+        access_clause: AccessClause {
+            query: AccessQuery {
+                query: path.to_vec(),
+                match_all: true,
+            },
+            comparator,
+            compare_with: compare_with.map(LetValue::Value),
+            ..AccessClause::default()
+        },
+        negation: false,
+    })
+}
+
+//
+// The unary operator checking for a kind of value
+//
+fn kind_operator(kind: &str) -> CmpOperator {
+    match kind {
+        "is_string" => CmpOperator::IsString,
+        "is_bool" => CmpOperator::IsBool,
+        "is_int" => CmpOperator::IsInt,
+        "is_list" => CmpOperator::IsList,
+        _ => CmpOperator::IsMap,
+    }
+}
+
+//
+// A generated clause and the number of resources, out of those it applies to, it holds for
+//
+struct Clause {
+    text: String,
+    support: usize,
+    of: usize,
+}
+
+//
+// The clauses checking what was seen at one property path, for `query` selecting the values
+// at that path in `of` resources. Each must hold for at least `needed` of them. Values of a
+// single kind are checked with `==` when one was needed, a range when they are numbers, or
+// `in` a list of those needed, taking the most common values first. Lists are checked to be
+// lists, and `required` paths, those present in enough resources, to exist when there is no
+// other check.
+//
+fn clauses(
+    path: &[QueryPart<'static>],
+    observed: &Observed,
+    required: bool,
+    of: usize,
+    needed: usize,
+) -> Vec<Clause> {
+    let check = |comparator, compare_with, support| Clause {
+        text: clause(path, comparator, compare_with).to_string(),
+        support,
+        of,
+    };
+    if let Some((operator, expected, support)) = expected_values(observed, needed) {
+        return vec![check((operator, false), Some(expected), support)];
+    }
+    match observed.kinds.get("is_list") {
+        Some(lists) if *lists >= needed => {
+            return vec![check((CmpOperator::IsList, false), None, *lists)];
+        }
+        _ => {}
+    }
+    if required {
+        return vec![check(
+            (CmpOperator::Exists, false),
+            None,
+            observed.resources(),
+        )];
+    }
+    vec![]
+}
+
+fn expected_values(
+    observed: &Observed,
+    needed: usize,
+) -> Option<(CmpOperator, PathAwareValue, usize)> {
+    let resolved = observed
+        .seen
+        .iter()
+        .flatten()
+        .filter(|each| !each.is_empty())
+        .collect::<Vec<_>>();
+    let mut counts = vec![0; observed.values.len()];
+    for each in &resolved {
+        for idx in each.iter() {
+            counts[*idx] += 1;
         }
     }
+    let mut common = (0..observed.values.len()).collect::<Vec<_>>();
+    common.sort_by_key(|idx| std::cmp::Reverse(counts[*idx]));
 
-    return rule_map;
+    let mut chosen = BTreeSet::new();
+    for idx in common {
+        if let Some(first) = chosen.iter().next() {
+            if kind(&observed.values[idx]) != kind(&observed.values[*first]) {
+                return None;
+            }
+        }
+        chosen.insert(idx);
+        let support = resolved
+            .iter()
+            .filter(|each| each.is_subset(&chosen))
+            .count();
+        if support < needed {
+            continue;
+        }
+
+        let values = chosen
+            .iter()
+            .map(|idx| observed.values[*idx].clone())
+            .collect::<Vec<_>>();
+        if let Some(range) = range(&values) {
+            let support = resolved
+                .iter()
+                .filter(|each| each.iter().all(|idx| observed.values[*idx] == range))
+                .count();
+            return Some((CmpOperator::In, range, support));
+        }
+        return Some(match values.len() {
+            1 => (CmpOperator::Eq, values[0].clone(), support),
+            _ => (
+                CmpOperator::In,
+                PathAwareValue::List((Path::root(), values)),
+                support,
+            ),
+        });
+    }
+    None
+}
+
+//
+// The range from the smallest to the largest of two or more numbers
+//
+fn range(values: &[PathAwareValue]) -> Option<PathAwareValue> {
+    if values.len() < 2 {
+        return None;
+    }
+    let numbers = values
+        .iter()
+        .map(|each| match each {
+            PathAwareValue::Int((_, number)) => Some(*number as f64),
+            PathAwareValue::Float((_, number)) => Some(*number),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let lower = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+    let upper = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let inclusive = LOWER_INCLUSIVE | UPPER_INCLUSIVE;
+    Some(
+        if values
+            .iter()
+            .all(|each| matches!(each, PathAwareValue::Int(_)))
+        {
+            PathAwareValue::RangeInt((
+                Path::root(),
+                RangeType {
+                    lower: lower as i64,
+                    upper: upper as i64,
+                    inclusive,
+                },
+            ))
+        } else {
+            PathAwareValue::RangeFloat((
+                Path::root(),
+                RangeType {
+                    lower,
+                    upper,
+                    inclusive,
+                },
+            ))
+        },
+    )
 }
 
-// Prints the generated rules data structure to stdout. If there are properties mapping to
-// multiple values in the template, the rules are put in one statement using the IN keyword so that
-// the generated rules are interpreted as ALL by default.
+fn counted(count: usize, what: &str) -> String {
+    format!("{} {}{}", count, what, if count == 1 { "" } else { "s" })
+}
+
+//
+// Prints the generated rules, failing when they do not parse
+//
+fn print_rules(generated_rules: &str, mut writer: Box<dyn std::io::Write>) -> Result<()> {
+    if generated_rules.is_empty() {
+        return Ok(());
+    }
+
+    // validate rules generated
+    let span = crate::rules::parser::Span::new_extra(generated_rules, "");
+    if let Err(e) = crate::rules::parser::rules_file(span) {
+        return Err(Error::new(ErrorKind::ParseError(format!(
+            "generated rules do not parse, {}",
+            e
+        ))));
+    }
+    write!(writer, "{}", generated_rules)?;
+    Ok(())
+}
+
+// The rules for the resource types seen, found in the data as given by `layout`. Each check is followed by the number of resources
+// it holds for. Paths present in fewer resources than the threshold are only checked on the
+// resources that have them, with a filter.
 // Using the same example in the comment above, the rules printed for the template will be:
+//     # AWS::EC2::Volume: 2 resources in 1 template
 //     let aws_ec2_volume_resources = Resources.*[ Type == 'AWS::EC2::Volume' ]
 //     rule aws_ec2_volume when %aws_ec2_volume_resources !empty {
-//          %aws_ec2_volume_resources.Properties.Size IN [500, 50]
-//          %aws_ec2_volume_resources.Properties.AvailabilityZone IN ["us-west-2b", "us-west-2c"]
-//          %aws_ec2_volume_resources.Properties.Encrypted == false
+//       %aws_ec2_volume_resources.Properties.AvailabilityZone in ['us-west-2b', 'us-west-2c'] # 2 of 2 resources
+//       %aws_ec2_volume_resources.Properties.Encrypted == false # 2 of 2 resources
+//       %aws_ec2_volume_resources.Properties.Size in r[50,500] # 2 of 2 resources
+//       %aws_ec2_volume_resources[ Properties.Tags exists ].Properties.Tags is_list # 1 of 1 resource
+//       ...
 //     }
-fn print_rules(
-    rule_map: HashMap<String, HashMap<String, HashSet<String>>>,
-    mut writer: Box<dyn std::io::Write>,
-) -> Result<()> {
-    let mut str = Builder::default();
+fn rules_text(
+    layout: &Layout,
+    rule_map: &BTreeMap<String, ResourceType>,
+    threshold: usize,
+) -> String {
+    let needed = |of: usize| (of * threshold).div_ceil(100);
+    let mut generated_rules = String::new();
 
-    for (resource, properties) in &rule_map {
+    for (resource, seen) in rule_map {
         let resource_name_underscore = resource.replace("::", "_").to_lowercase();
         let variable_name = format!("{}_resources", resource_name_underscore);
 
-        str.append(format!(
-            "let {} = Resources.*[ Type == '{}' ]\n",
-            variable_name, resource
-        ));
-        str.append(format!(
-            "rule {} when %{} !empty {{\n",
-            resource_name_underscore, variable_name
-        ));
-
-        for (property, values) in properties {
-            if values.len() > 1 {
-                str.append(format!(
-                    "  %{}.Properties.{} IN [{}]\n",
-                    variable_name,
-                    property,
-                    values.iter().join(", ")
-                ));
+        let mut lines = vec![];
+        for observed in seen.properties.values() {
+            let required = observed.resources() >= needed(seen.resources);
+            let properties = joined(&keys(layout.properties), observed.path.iter().cloned());
+            let variable = QueryPart::Key(format!("%{}", variable_name));
+            let (path, of) = if required {
+                (joined(&[variable], properties), seen.resources)
             } else {
-                str.append(format!(
-                    "  %{}.Properties.{} == {}\n",
-                    variable_name,
-                    property,
-                    values.iter().next().unwrap()
+                let present = clause(&properties, (CmpOperator::Exists, false), None);
+                (
+                    joined(
+                        &[variable, QueryPart::Filter(None, vec![vec![present]])],
+                        properties,
+                    ),
+                    observed.resources(),
+                )
+            };
+            for each in clauses(&path, observed, required, of, needed(of)) {
+                lines.push(format!(
+                    "  {} # {} of {}\n",
+                    each.text,
+                    each.support,
+                    counted(each.of, "resource")
                 ));
             }
         }
-
-        str.append("}\n");
-    }
-
-    // validate rules generated
-    let generated_rules = str.string().unwrap();
-
-    let span = crate::rules::parser::Span::new_extra(&generated_rules, "");
-    match crate::rules::parser::rules_file(span) {
-        Ok(_rules) => {
-            //
-            // TODO fix with Error return
-            //
-            write!(writer, "{}", generated_rules)?;
-        }
-        Err(e) => {
-            println!("Parsing error with generated rules file, Error = {}", e);
+        if lines.is_empty() {
+            continue;
         }
+
+        generated_rules.push_str(&format!(
+            "# {}: {} in {}\n",
+            resource,
+            counted(seen.resources, "resource"),
+            counted(seen.templates, layout.file)
+        ));
+        generated_rules.push_str(&format!(
+            "let {} = {}[ {} == {}{} ]\n",
+            variable_name,
+            layout.resources,
+            layout.type_key,
+            quoted(resource),
+            layout
+                .condition
+                .map_or(String::new(), |condition| format!(" {}", condition))
+        ));
+        generated_rules.push_str(&format!(
+            "rule {} when %{} !empty {{\n",
+            resource_name_underscore, variable_name
+        ));
+        lines
+            .into_iter()
+            .for_each(|line| generated_rules.push_str(&line));
+        generated_rules.push_str("}\n");
     }
-    Ok(())
+    generated_rules
 }
 
 #[cfg(test)]
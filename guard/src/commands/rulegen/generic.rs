@@ -0,0 +1,123 @@
+use super::{clause, counted, expected_values, joined, kind_operator, record, ResourceType};
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::{ConjunctionsDisplay, QueryPart};
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::values::CmpOperator;
+use crate::rules::Result;
+
+//
+// A path with more distinct values than this, or with a different value in every document,
+// is only checked for its type
+//
+const MAX_ENUM_VALUES: usize = 10;
+
+///
+/// Adds what was seen in a JSON or YAML document to `documents`, which are treated as the
+/// resources of a single type with the document as their properties
+///
+pub(crate) fn gen_rules_from_data(content: &str, documents: &mut ResourceType) -> Result<()> {
+    let document = get_path_aware_value_from_data(&content.to_string())?;
+    if !matches!(document, PathAwareValue::Map(_)) {
+        return Err(Error::new(ErrorKind::ParseError(
+            "expected a map at the top of the document".to_string(),
+        )));
+    }
+    record(&document, documents);
+    Ok(())
+}
+
+//
+// The rule for the structure seen across the documents. Each path is checked for
+//
+//   * the values seen when there are only a few of them, like `kind` or `apiVersion`, the same
+//     as for templates
+//   * otherwise its type, with `is_string`, `is_int`, `is_bool` or `is_list`
+//   * otherwise to exist, when it is present in enough documents
+//   * the fewest and most entries seen in lists, e.g. `containers[0] exists` and
+//     `containers[3] !exists` for lists of 1 to 3 entries
+//
+// Paths present in fewer documents than the threshold are checked only when present, e.g.
+// `metadata.labels !exists or metadata.labels is_map`.
+//
+pub(crate) fn rules_text(documents: &ResourceType, threshold: usize) -> String {
+    let needed = |of: usize| (of * threshold).div_ceil(100);
+    let mut lines = vec![];
+    for observed in documents.properties.values() {
+        let path = &observed.path;
+        let required = observed.resources() >= needed(documents.resources);
+        let of = if required {
+            documents.resources
+        } else {
+            observed.resources()
+        };
+        let mut checks = vec![];
+
+        let enum_like = observed.values.len() <= MAX_ENUM_VALUES
+            && observed.values.len() < observed.resources();
+        let expected = enum_like
+            .then(|| expected_values(observed, needed(of)))
+            .flatten();
+        let kind = observed
+            .kinds
+            .iter()
+            .find(|(kind, count)| **kind != "is_map" && **count >= needed(of));
+        if let Some((operator, expected, support)) = expected {
+            checks.push((clause(path, (operator, false), Some(expected)), support));
+        } else if let Some((kind, count)) = kind {
+            checks.push((clause(path, (kind_operator(kind), false), None), *count));
+        } else if required {
+            checks.push((
+                clause(path, (CmpOperator::Exists, false), None),
+                observed.resources(),
+            ));
+        }
+
+        if let (Some(fewest), Some(most)) =
+            (observed.lengths.iter().min(), observed.lengths.iter().max())
+        {
+            if *fewest > 0 {
+                let entry = joined(path, [QueryPart::Index(*fewest as i32 - 1)]);
+                checks.push((
+                    clause(&entry, (CmpOperator::Exists, false), None),
+                    observed.resources(),
+                ));
+            }
+            let entry = joined(path, [QueryPart::Index(*most as i32)]);
+            checks.push((
+                clause(&entry, (CmpOperator::Exists, true), None),
+                observed.resources(),
+            ));
+        }
+
+        for (check, support) in checks {
+            let absent = clause(path, (CmpOperator::Exists, true), None);
+            let check = if required {
+                vec![vec![check]]
+            } else {
+                vec![vec![absent, check]]
+            };
+            lines.push(format!(
+                "  {} # {} of {}\n",
+                ConjunctionsDisplay(&check),
+                support,
+                counted(of, "document")
+            ));
+        }
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    let mut generated_rules = format!("# {}\n", counted(documents.resources, "document"));
+    generated_rules.push_str("rule generated {\n");
+    lines
+        .into_iter()
+        .for_each(|line| generated_rules.push_str(&line));
+    generated_rules.push_str("}\n");
+    generated_rules
+}
+
+#[cfg(test)]
+#[path = "generic_tests.rs"]
+mod generic_tests;
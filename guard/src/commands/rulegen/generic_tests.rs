@@ -0,0 +1,113 @@
+use super::*;
+
+use crate::commands::common_test_helpers::status;
+use crate::rules::Status;
+
+fn documents(contents: &[&str]) -> Result<ResourceType> {
+    let mut documents = ResourceType::default();
+    for each in contents {
+        gen_rules_from_data(each, &mut documents)?;
+    }
+    Ok(documents)
+}
+
+#[test]
+fn test_generic_rules_for_structure() -> Result<()> {
+    let web = r#"
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  labels: { app: web }
+spec:
+  replicas: 3
+  containers:
+    - { name: web, image: 'nginx:1.25', ports: [80, 443] }
+"#;
+    let api = r#"
+apiVersion: apps/v1
+kind: Service
+metadata:
+  name: api
+spec:
+  replicas: 3
+  containers:
+    - { name: api, image: 'api:2' }
+    - { name: envoy, image: 'envoy:1' }
+"#;
+    let generated = rules_text(&documents(&[web, api])?, 100);
+    let expected = r#"# 2 documents
+rule generated {
+  apiVersion == 'apps/v1' # 2 of 2 documents
+  kind is_string # 2 of 2 documents
+  metadata exists # 2 of 2 documents
+  metadata.labels.app !exists or metadata.labels.app is_string # 1 of 1 document
+  metadata.name is_string # 2 of 2 documents
+  spec exists # 2 of 2 documents
+  spec.containers is_list # 2 of 2 documents
+  spec.containers[0] exists # 2 of 2 documents
+  spec.containers[2] !exists # 2 of 2 documents
+  spec.containers[*] exists # 2 of 2 documents
+  spec.containers[*].image is_string # 2 of 2 documents
+  spec.containers[*].name is_string # 2 of 2 documents
+  spec.containers[*].ports !exists or spec.containers[*].ports is_list # 1 of 1 document
+  spec.containers[*].ports !exists or spec.containers[*].ports[1] exists # 1 of 1 document
+  spec.containers[*].ports !exists or spec.containers[*].ports[2] !exists # 1 of 1 document
+  spec.containers[*].ports[*] !exists or spec.containers[*].ports[*] is_int # 1 of 1 document
+  spec.replicas == 3 # 2 of 2 documents
+}
+"#;
+    assert_eq!(generated, expected);
+    assert_eq!(status(&generated, web)?, Status::PASS);
+    assert_eq!(status(&generated, api)?, Status::PASS);
+
+    let three = api.replace(
+        "    - { name: envoy",
+        "    - { name: a, image: b }\n    - { name: envoy",
+    );
+    assert_ne!(status(&generated, &three)?, Status::PASS);
+    let untyped = api.replace("name: api\n", "name: 10\n");
+    assert_ne!(status(&generated, &untyped)?, Status::PASS);
+    Ok(())
+}
+
+#[test]
+fn test_generic_rules_thresholds_and_enum_values() -> Result<()> {
+    let mut samples = vec![];
+    for idx in 0..12 {
+        let replicas = if idx == 0 { 5 } else { 1 };
+        let zone = ["a", "b", "c"][idx % 3];
+        let extra = if idx < 10 { "debug: false\n" } else { "" };
+        samples.push(format!(
+            "name: service-{}\nzone: {}\nreplicas: {}\n{}",
+            idx, zone, replicas, extra
+        ));
+    }
+    let samples = samples.iter().map(String::as_str).collect::<Vec<_>>();
+    let seen = documents(&samples)?;
+
+    let generated = rules_text(&seen, 100);
+    assert!(generated.contains("  debug !exists or debug == false # 10 of 10 documents\n"));
+    assert!(generated.contains("  name is_string # 12 of 12 documents\n"));
+    assert!(generated.contains("  replicas in r[1,5] # 12 of 12 documents\n"));
+    assert!(generated.contains("  zone in ['a', 'b', 'c'] # 12 of 12 documents\n"));
+
+    let generated = rules_text(&seen, 80);
+    assert!(generated.contains("  debug == false # 10 of 12 documents\n"));
+    assert!(generated.contains("  replicas == 1 # 11 of 12 documents\n"));
+    assert!(samples[1..].iter().all(|each| {
+        status(&generated, each).unwrap() == Status::PASS || !each.contains("debug")
+    }));
+    assert_ne!(status(&generated, samples[0])?, Status::PASS);
+    Ok(())
+}
+
+#[test]
+fn test_generic_rules_errors() {
+    let mut seen = ResourceType::default();
+    assert!(gen_rules_from_data("[1, 2]\n", &mut seen).is_err());
+    assert!(gen_rules_from_data("   \n", &mut seen).is_err());
+    assert!(gen_rules_from_data("key: [\n", &mut seen).is_err());
+    assert_eq!(seen.resources, 0);
+    assert_eq!(rules_text(&seen, 100), "");
+}
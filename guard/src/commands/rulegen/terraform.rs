@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use super::{gen_rules, Layout, ResourceType};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::Result;
+
+pub(crate) const PLAN_LAYOUT: Layout = Layout {
+    resources: "resource_changes",
+    type_key: "type",
+    //
+    // Resources being deleted have no properties after the change
+    //
+    condition: Some("change.after is_struct"),
+    properties: "change.after",
+    file: "plan",
+};
+
+///
+/// Adds what was seen in the resources of a Terraform plan, as written by
+/// `terraform show -json`, to `rule_map`
+///
+pub(crate) fn gen_rules_from_plan(
+    plan_contents: &str,
+    rule_map: &mut BTreeMap<String, ResourceType>,
+) -> Result<()> {
+    //
+    // Read as JSON, rather than with the YAML loader, so that unset attributes, which are null
+    // in a plan, are known to be null and left out
+    //
+    let plan = PathAwareValue::try_from(serde_json::from_str::<serde_json::Value>(plan_contents)?)?;
+    match plan
+        .as_map()
+        .and_then(|map| map.values.get("resource_changes"))
+    {
+        Some(PathAwareValue::List((_, changes))) => {
+            gen_rules(changes, &PLAN_LAYOUT, rule_map);
+            Ok(())
+        }
+        Some(_) => Err(Error::new(ErrorKind::ParseError(
+            "Plan resource_changes section has an invalid structure".to_string(),
+        ))),
+        None => Err(Error::new(ErrorKind::ParseError(
+            "Plan lacks a resource_changes section".to_string(),
+        ))),
+    }
+}
+
+#[cfg(test)]
+#[path = "terraform_tests.rs"]
+mod terraform_tests;
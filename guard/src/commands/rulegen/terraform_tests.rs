@@ -0,0 +1,123 @@
+use super::*;
+
+use crate::commands::common_test_helpers::status;
+use crate::commands::rulegen::rules_text;
+use crate::rules::Status;
+
+const PLAN: &str = r#"{
+  "format_version": "1.2",
+  "terraform_version": "1.5.7",
+  "resource_changes": [
+    {
+      "address": "aws_s3_bucket.logs",
+      "type": "aws_s3_bucket",
+      "change": {
+        "actions": ["create"],
+        "before": null,
+        "after": { "bucket": "logs", "force_destroy": false, "tags": { "team": "platform" } }
+      }
+    },
+    {
+      "address": "aws_s3_bucket.assets",
+      "type": "aws_s3_bucket",
+      "change": {
+        "actions": ["create"],
+        "before": null,
+        "after": { "bucket": "assets", "force_destroy": false, "tags": null }
+      }
+    },
+    {
+      "address": "aws_ebs_volume.data",
+      "type": "aws_ebs_volume",
+      "change": {
+        "actions": ["create"],
+        "before": null,
+        "after": { "size": 100, "encrypted": true, "timeouts": null }
+      }
+    },
+    {
+      "address": "aws_ebs_volume.old",
+      "type": "aws_ebs_volume",
+      "change": { "actions": ["delete"], "before": { "size": 8 }, "after": null }
+    }
+  ]
+}"#;
+
+#[test]
+fn test_rules_from_plan() -> Result<()> {
+    let mut rule_map = BTreeMap::new();
+    gen_rules_from_plan(PLAN, &mut rule_map)?;
+    let generated = rules_text(&PLAN_LAYOUT, &rule_map, 100);
+    let expected = r#"# aws_ebs_volume: 1 resource in 1 plan
+let aws_ebs_volume_resources = resource_changes[ type == 'aws_ebs_volume' change.after is_struct ]
+rule aws_ebs_volume when %aws_ebs_volume_resources !empty {
+  %aws_ebs_volume_resources.change.after.encrypted == true # 1 of 1 resource
+  %aws_ebs_volume_resources.change.after.size == 100 # 1 of 1 resource
+}
+# aws_s3_bucket: 2 resources in 1 plan
+let aws_s3_bucket_resources = resource_changes[ type == 'aws_s3_bucket' change.after is_struct ]
+rule aws_s3_bucket when %aws_s3_bucket_resources !empty {
+  %aws_s3_bucket_resources.change.after.bucket in ['logs', 'assets'] # 2 of 2 resources
+  %aws_s3_bucket_resources.change.after.force_destroy == false # 2 of 2 resources
+  %aws_s3_bucket_resources[ change.after.tags.team exists ].change.after.tags.team == 'platform' # 1 of 1 resource
+}
+"#;
+    assert_eq!(generated, expected);
+    assert_eq!(status(&generated, PLAN)?, Status::PASS);
+    assert_ne!(
+        status(
+            &generated,
+            &PLAN.replace(r#""encrypted": true"#, r#""encrypted": false"#)
+        )?,
+        Status::PASS
+    );
+    assert_ne!(
+        status(
+            &generated,
+            &PLAN.replace(r#""team": "platform""#, r#""team": "data""#)
+        )?,
+        Status::PASS
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rules_from_plans_with_threshold() -> Result<()> {
+    let mut rule_map = BTreeMap::new();
+    gen_rules_from_plan(PLAN, &mut rule_map)?;
+    let larger = PLAN
+        .replace(r#""size": 100"#, r#""size": 500"#)
+        .replace(r#""encrypted": true"#, r#""encrypted": false"#);
+    gen_rules_from_plan(&larger, &mut rule_map)?;
+    gen_rules_from_plan(PLAN, &mut rule_map)?;
+
+    let volumes = &rule_map["aws_ebs_volume"];
+    assert_eq!((volumes.resources, volumes.templates), (3, 3));
+    assert!(!volumes.properties.contains_key("timeouts"));
+    let buckets = &rule_map["aws_s3_bucket"];
+    assert_eq!(
+        (buckets.properties["tags"].resources(), buckets.resources),
+        (3, 6)
+    );
+
+    let generated = rules_text(&PLAN_LAYOUT, &rule_map, 60);
+    assert!(generated.contains("# aws_ebs_volume: 3 resources in 3 plans\n"));
+    assert!(generated.contains(
+        "  %aws_ebs_volume_resources.change.after.encrypted == true # 2 of 3 resources\n"
+    ));
+    assert!(generated
+        .contains("  %aws_ebs_volume_resources.change.after.size == 100 # 2 of 3 resources\n"));
+    assert_eq!(status(&generated, PLAN)?, Status::PASS);
+    assert_ne!(status(&generated, &larger)?, Status::PASS);
+    Ok(())
+}
+
+#[test]
+fn test_rules_from_plan_errors() {
+    let mut rule_map = BTreeMap::new();
+    assert!(gen_rules_from_plan(r#"{ "format_version": "1.2" }"#, &mut rule_map).is_err());
+    assert!(gen_rules_from_plan(r#"{ "resource_changes": {} }"#, &mut rule_map).is_err());
+    assert!(gen_rules_from_plan("resource_changes: []\n", &mut rule_map).is_err());
+    assert!(gen_rules_from_plan(r#"{ "resource_changes": [] }"#, &mut rule_map).is_ok());
+    assert!(rule_map.is_empty());
+}
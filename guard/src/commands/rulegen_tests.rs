@@ -1,4 +1,8 @@
+use std::collections::BTreeMap;
+
+use crate::commands::common_test_helpers::status;
 use crate::commands::rulegen;
+use crate::rules::Status;
 
 #[test]
 fn test_rulegen() {
@@ -27,12 +31,12 @@ fn test_rulegen() {
         "#,
     );
 
-    let generated_rules = rulegen::parse_template_and_call_gen(&data);
+    let generated_rules = rulegen::parse_template_and_call_gen(&data).unwrap();
 
     assert_eq!(1, generated_rules.len());
     assert!(generated_rules.contains_key("AWS::EC2::Volume"));
 
-    let property_map = &generated_rules["AWS::EC2::Volume"];
+    let property_map = &generated_rules["AWS::EC2::Volume"].properties;
 
     assert_eq!(3, property_map.len());
     assert!(property_map.contains_key("Encrypted"));
@@ -57,7 +61,149 @@ fn test_rulegen_no_properties() {
         "#,
     );
 
-    let generated_rules = rulegen::parse_template_and_call_gen(&data);
+    let generated_rules = rulegen::parse_template_and_call_gen(&data).unwrap();
 
     assert_eq!(0, generated_rules.len());
 }
+
+#[test]
+fn test_rulegen_typed_and_nested_properties() -> crate::rules::Result<()> {
+    let template = r#"
+Resources:
+  NewVolume:
+    Type: AWS::EC2::Volume
+    Properties:
+      Size: 500
+      Encrypted: false
+      AvailabilityZone: us-west-2b
+      Tags:
+        - Key: team
+          Value: storage
+        - Key: 'aws:cdk:path'
+          Value: volume
+  NewVolume2:
+    Type: AWS::EC2::Volume
+    Properties:
+      Size: 50
+      Encrypted: false
+      AvailabilityZone: us-west-2c
+"#;
+    let generated = rulegen::rules_text(
+        &rulegen::TEMPLATE_LAYOUT,
+        &rulegen::parse_template_and_call_gen(template)?,
+        100,
+    );
+    let expected = r#"# AWS::EC2::Volume: 2 resources in 1 template
+let aws_ec2_volume_resources = Resources.*[ Type == 'AWS::EC2::Volume' ]
+rule aws_ec2_volume when %aws_ec2_volume_resources !empty {
+  %aws_ec2_volume_resources.Properties.AvailabilityZone in ['us-west-2b', 'us-west-2c'] # 2 of 2 resources
+  %aws_ec2_volume_resources.Properties.Encrypted == false # 2 of 2 resources
+  %aws_ec2_volume_resources.Properties.Size in r[50,500] # 2 of 2 resources
+  %aws_ec2_volume_resources[ Properties.Tags exists ].Properties.Tags is_list # 1 of 1 resource
+  %aws_ec2_volume_resources[ Properties.Tags[*].Key exists ].Properties.Tags[*].Key in ['team', 'aws:cdk:path'] # 1 of 1 resource
+  %aws_ec2_volume_resources[ Properties.Tags[*].Value exists ].Properties.Tags[*].Value in ['storage', 'volume'] # 1 of 1 resource
+}
+"#;
+    assert_eq!(generated, expected);
+    assert_eq!(status(&generated, template)?, Status::PASS);
+
+    let changed = template.replace("Size: 50\n", "Size: 5000\n");
+    assert_ne!(status(&generated, &changed)?, Status::PASS);
+    Ok(())
+}
+
+#[test]
+fn test_rulegen_unresolved_values_and_quoted_keys() -> crate::rules::Result<()> {
+    let template = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketName: !Ref Name
+      'aws:meta': [1, "one"]
+      VersioningConfiguration:
+        Status: Enabled
+  Other:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketName: !Sub '${AWS::StackName}-logs'
+      VersioningConfiguration:
+        Status: Enabled
+"#;
+    let generated_rules = rulegen::parse_template_and_call_gen(template)?;
+    let bucket = &generated_rules["AWS::S3::Bucket"];
+    assert_eq!(bucket.resources, 2);
+    assert_eq!(bucket.templates, 1);
+    assert_eq!(bucket.properties["BucketName"].seen, vec![None, None]);
+    assert_eq!(bucket.properties["'aws:meta'[*]"].values.len(), 2);
+
+    let generated = rulegen::rules_text(&rulegen::TEMPLATE_LAYOUT, &generated_rules, 100);
+    let expected = r#"# AWS::S3::Bucket: 2 resources in 1 template
+let aws_s3_bucket_resources = Resources.*[ Type == 'AWS::S3::Bucket' ]
+rule aws_s3_bucket when %aws_s3_bucket_resources !empty {
+  %aws_s3_bucket_resources[ Properties['aws:meta'] exists ].Properties['aws:meta'] is_list # 1 of 1 resource
+  %aws_s3_bucket_resources.Properties.BucketName exists # 2 of 2 resources
+  %aws_s3_bucket_resources.Properties.VersioningConfiguration exists # 2 of 2 resources
+  %aws_s3_bucket_resources.Properties.VersioningConfiguration.Status == 'Enabled' # 2 of 2 resources
+}
+"#;
+    assert_eq!(generated, expected);
+    assert_eq!(status(&generated, template)?, Status::PASS);
+    Ok(())
+}
+
+#[test]
+fn test_rulegen_corpus_thresholds() -> crate::rules::Result<()> {
+    let first = r#"
+Resources:
+  First:
+    Type: AWS::EC2::Volume
+    Properties: { Encrypted: true, Size: 100 }
+  Second:
+    Type: AWS::EC2::Volume
+    Properties: { Encrypted: true, Size: 100, Iops: 3000 }
+"#;
+    let second = r#"
+Resources:
+  Third:
+    Type: AWS::EC2::Volume
+    Properties: { Encrypted: true, Size: 200 }
+  Fourth:
+    Type: AWS::EC2::Volume
+    Properties: { Encrypted: false, Size: 100 }
+  Fifth:
+    Type: AWS::EC2::Volume
+    Properties: { Encrypted: true, Size: 100 }
+"#;
+    let mut rule_map = BTreeMap::new();
+    rulegen::gen_rules_from_template(first, &mut rule_map)?;
+    rulegen::gen_rules_from_template(second, &mut rule_map)?;
+    assert_eq!(rule_map["AWS::EC2::Volume"].templates, 2);
+    assert_eq!(rule_map["AWS::EC2::Volume"].resources, 5);
+
+    let expected = r#"# AWS::EC2::Volume: 5 resources in 2 templates
+let aws_ec2_volume_resources = Resources.*[ Type == 'AWS::EC2::Volume' ]
+rule aws_ec2_volume when %aws_ec2_volume_resources !empty {
+  %aws_ec2_volume_resources.Properties.Encrypted == true # 4 of 5 resources
+  %aws_ec2_volume_resources[ Properties.Iops exists ].Properties.Iops == 3000 # 1 of 1 resource
+  %aws_ec2_volume_resources.Properties.Size == 100 # 4 of 5 resources
+}
+"#;
+    assert_eq!(
+        rulegen::rules_text(&rulegen::TEMPLATE_LAYOUT, &rule_map, 80),
+        expected
+    );
+
+    let all = rulegen::rules_text(&rulegen::TEMPLATE_LAYOUT, &rule_map, 100);
+    assert!(all.contains("Properties.Encrypted in [true, false] # 5 of 5 resources\n"));
+    assert!(all.contains("Properties.Size in r[100,200] # 5 of 5 resources\n"));
+    assert_eq!(status(&all, first)?, Status::PASS);
+    assert_eq!(status(&all, second)?, Status::PASS);
+
+    assert!(rulegen::parse_template_and_call_gen("Parameters: {}\n").is_err());
+    assert!(rulegen::parse_template_and_call_gen("Resources: [1, 2]\n").is_err());
+    assert!(rulegen::validate_threshold("80".to_string()).is_ok());
+    assert!(rulegen::validate_threshold("0".to_string()).is_err());
+    assert!(rulegen::validate_threshold("101".to_string()).is_err());
+    Ok(())
+}
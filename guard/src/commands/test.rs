@@ -1,7 +1,7 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::{App, Arg, ArgGroup, ArgMatches};
 use serde::{Deserialize, Serialize};
@@ -10,23 +10,43 @@ use walkdir::DirEntry;
 use validate::validate_path;
 
 use crate::command::Command;
+use crate::commands::evaluate::{rule_statuses, Outcome};
+use crate::commands::exit_code::{
+    fail_on_from, ExitCodeTracker, FAIL_ON_DEFAULT, TEST_FAIL_ON_VALUES,
+};
 use crate::commands::files::{
     alpabetical, get_files_with_filter, iterate_over, last_modified, read_file_content,
-    regular_ordering,
+    regular_ordering, IgnoreFilter,
 };
+use crate::commands::test::coverage::{write_lcov, FileCoverage};
+use crate::commands::test::discovery::{selected, TestPatterns};
+use crate::commands::test::expectations::RuleExpectation;
+use crate::commands::test::fixtures::{FileReferences, FuzzExpectations, TestInput};
+use crate::commands::test::snapshot::Snapshots;
+use crate::commands::test::watch::{TestFileResults, TestSession, Tests};
 use crate::commands::tracker::StackTracker;
+use crate::commands::watch::watch;
 use crate::commands::{
-    validate, ALPHABETICAL, DIRECTORY, DIRECTORY_ONLY, LAST_MODIFIED, PREVIOUS_ENGINE,
-    RULES_AND_TEST_FILE, RULES_FILE, TEST, TEST_DATA, VERBOSE,
+    validate, ALPHABETICAL, CONFIG, COVERAGE, DIRECTORY, DIRECTORY_ONLY, EXCLUDE, FAIL_ON,
+    LAST_MODIFIED, PREVIOUS_ENGINE, RULES_AND_TEST_FILE, RULES_FILE, SNAPSHOT, TEST, TEST_DATA,
+    TEST_FAILURE_STATUS_CODE, TEST_FILTER, TEST_PATTERN, UPDATE_SNAPSHOTS, VERBOSE, WATCH,
 };
 use crate::rules::errors::{Error, ErrorKind};
 use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::simplifed_json_from_root;
 use crate::rules::evaluate::RootScope;
 use crate::rules::exprs::RulesFile;
 use crate::rules::path_value::PathAwareValue;
 use crate::rules::Status::SKIP;
 use crate::rules::{Evaluate, NamedStatus, RecordType, Result, Status};
 
+mod coverage;
+mod discovery;
+mod expectations;
+pub(crate) mod fixtures;
+mod snapshot;
+mod watch;
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub struct Test {}
 
@@ -51,11 +71,13 @@ or failure testing.
                 .long(RULES_FILE.0)
                 .short(RULES_FILE.1)
                 .takes_value(true)
+                .conflicts_with(DIRECTORY.0)
                 .help("Provide a rules file"))
             .arg(Arg::with_name(TEST_DATA.0)
                 .long(TEST_DATA.0)
                 .short(TEST_DATA.1)
                 .takes_value(true)
+                .conflicts_with(DIRECTORY.0)
                 .help("Provide a file or dir for data files in JSON or YAML"))
             .arg(Arg::with_name(DIRECTORY.0)
                 .long(DIRECTORY.0)
@@ -75,10 +97,46 @@ or failure testing.
                 .help("Sort by last modified times within a directory"))
             .arg(Arg::with_name(VERBOSE.0).long(VERBOSE.0).short(VERBOSE.1).required(false)
                 .help("Verbose logging"))
+            .arg(Arg::with_name(EXCLUDE.0).long(EXCLUDE.0).short(EXCLUDE.1).takes_value(true).multiple(true)
+                .help("Exclude files and directories matching the pattern when scanning directories for rules and test files. \
+                      Patterns use gitignore syntax relative to the scanned directory, e.g. --exclude node_modules/. Patterns from \
+                      .guardignore files in the current directory, the scanned directory and its subdirectories are honored as well"))
+            .arg(Arg::with_name(CONFIG.0).long(CONFIG.0).short(CONFIG.1).takes_value(true)
+                .help("Provide a project configuration file with default arguments. When not specified, guard.toml or .guardrc is looked up \
+                      starting from the current directory and moving up through its parents. Arguments on the command line override the configuration"))
+            .arg(Arg::with_name(FAIL_ON.0).long(FAIL_ON.0).short(FAIL_ON.1).takes_value(true).use_delimiter(true).multiple(true)
+                .possible_values(&TEST_FAIL_ON_VALUES)
+                .default_value(FAIL_ON_DEFAULT)
+                .help("Controls which outcomes produce a non-zero exit code. --fail-on fail,error (default) or --fail-on fail,skip,error (also fail when rules or rules files have no test expectations) \
+                      or --fail-on fail,untested,error (also fail when rules files in --dir have no test files) or --fail-on none (always exit 0).\
+                      \nExit codes: 2 rules failed to parse, 3 error during evaluation, 1 other errors, 7 test expectations were not met"))
+            .arg(Arg::with_name(COVERAGE.0).long(COVERAGE.0).short(COVERAGE.1).takes_value(true).conflicts_with(PREVIOUS_ENGINE.0)
+                .help("Track which rules, when conditions, disjunction branches and clauses evaluated to PASS and to FAIL across all test cases. \
+                      Prints a coverage report for each rules file and writes coverage in lcov format to the file provided"))
+            .arg(Arg::with_name(SNAPSHOT.0).long(SNAPSHOT.0).short(SNAPSHOT.1).requires_all(&[RULES_FILE.0, TEST_DATA.0])
+                .conflicts_with_all(&[DIRECTORY.0, PREVIOUS_ENGINE.0, COVERAGE.0])
+                .help("Snapshot testing, evaluates the rules file against each data file in --test-data like validate does and compares \
+                      the structured report with the snapshot stored in snapshots/<rules file name>/ next to the rules file"))
+            .arg(Arg::with_name(TEST_PATTERN.0).long(TEST_PATTERN.0).short(TEST_PATTERN.1).takes_value(true).multiple(true).number_of_values(1)
+                .requires(DIRECTORY.0)
+                .help("Glob template for the test files of each rules file found with --dir, {dir} is replaced by the directory of the rules file \
+                      and {stem} by its name without extension, e.g. {dir}/tests/{stem}_tests.yaml. Templates that do not start with {dir} are relative to --dir. \
+                      Can be repeated. Defaults to {dir}/tests/{stem}*.{yaml,yml,json,jsn}"))
+            .arg(Arg::with_name(TEST_FILTER.0).long(TEST_FILTER.0).short(TEST_FILTER.1).takes_value(true).multiple(true).number_of_values(1)
+                .conflicts_with(SNAPSHOT.0)
+                .help("Only run test cases whose name contains the value. Can be repeated to run cases matching any of the values"))
+            .arg(Arg::with_name(UPDATE_SNAPSHOTS.0).long(UPDATE_SNAPSHOTS.0).short(UPDATE_SNAPSHOTS.1).requires(SNAPSHOT.0)
+                .help("Write the evaluated reports as the new snapshots instead of failing on differences"))
+            .arg(Arg::with_name(WATCH.0).long(WATCH.0).short(WATCH.1).conflicts_with_all(&[SNAPSHOT.0, COVERAGE.0, PREVIOUS_ENGINE.0])
+                .help("After testing, keep watching the rules and test files. On each change only the changed files are re-parsed and \
+                      their tests re-run, and the test results that changed since the last run are printed"))
     }
 
     fn execute(&self, app: &ArgMatches<'_>) -> Result<i32> {
-        let mut exit_code = 0;
+        let mut exit_code = ExitCodeTracker::new(
+            fail_on_from(app.values_of(FAIL_ON.0)),
+            TEST_FAILURE_STATUS_CODE,
+        );
         let cmp = if let Some(_ignored) = app.value_of(ALPHABETICAL.0) {
             alpabetical
         } else if let Some(_ignored) = app.value_of(LAST_MODIFIED.0) {
@@ -88,141 +146,82 @@ or failure testing.
         };
         let verbose = app.is_present(VERBOSE.0);
         let new_engine = !app.is_present(PREVIOUS_ENGINE.0);
+        let ignore = IgnoreFilter::new(app.values_of(EXCLUDE.0).into_iter().flatten())?;
+        let coverage_file = app.value_of(COVERAGE.0);
+        let filters = app
+            .values_of(TEST_FILTER.0)
+            .map_or(vec![], |values| values.collect::<Vec<&str>>());
+        let mut coverage = vec![];
+        let watching = app.is_present(WATCH.0);
+        let mut seeds = vec![];
 
         if app.is_present(DIRECTORY_ONLY) {
-            struct GuardFile {
-                prefix: String,
-                file: DirEntry,
-                test_files: Vec<DirEntry>,
-            }
-            let dir = app.value_of(DIRECTORY.0).unwrap();
-            validate_path(dir)?;
-            let walk = walkdir::WalkDir::new(dir);
-            let mut non_guard: Vec<DirEntry> = vec![];
-            let mut ordered_guard_files: BTreeMap<String, Vec<GuardFile>> = BTreeMap::new();
-            for file in walk
-                .follow_links(true)
-                .sort_by_file_name()
-                .into_iter()
-                .flatten()
-            {
-                if file.path().is_file() {
-                    let name = file
-                        .file_name()
-                        .to_str()
-                        .map_or("".to_string(), |s| s.to_string());
-                    if name.ends_with(".guard") || name.ends_with(".ruleset") {
-                        let prefix = name
-                            .strip_suffix(".guard")
-                            .or_else(|| name.strip_suffix(".ruleset"))
-                            .unwrap()
-                            .to_string();
-                        ordered_guard_files
-                            .entry(
-                                file.path()
-                                    .parent()
-                                    .map_or("".to_string(), |p| format!("{}", p.display())),
-                            )
-                            .or_insert(vec![])
-                            .push(GuardFile {
-                                prefix,
-                                file,
-                                test_files: vec![],
-                            });
-                        continue;
-                    } else {
-                        non_guard.push(file);
-                    }
-                }
-            }
-            for file in non_guard {
-                let name = file
-                    .file_name()
-                    .to_str()
-                    .map_or("".to_string(), |s| s.to_string());
-                if name.ends_with(".yaml")
-                    || name.ends_with(".yml")
-                    || name.ends_with(".json")
-                    || name.ends_with(".jsn")
-                {
-                    let parent = file.path().parent();
-                    if parent.map_or(false, |p| p.ends_with("tests")) {
-                        if let Some(candidates) = parent.unwrap().parent().and_then(|grand| {
-                            let grand = format!("{}", grand.display());
-                            ordered_guard_files.get_mut(&grand)
-                        }) {
-                            for guard_file in candidates {
-                                if name.starts_with(&guard_file.prefix) {
-                                    guard_file.test_files.push(file);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            for (_dir, guard_files) in ordered_guard_files {
-                for each_rule_file in guard_files {
-                    if each_rule_file.test_files.is_empty() {
-                        println!(
-                            "Guard File {} did not have any tests associated, skipping.",
-                            each_rule_file.file.path().display()
-                        );
-                        println!("---");
-                        continue;
-                    }
+            let root = app.value_of(DIRECTORY.0).unwrap();
+            validate_path(root)?;
+            let patterns = TestPatterns::new(app.values_of(TEST_PATTERN.0));
+            for each_rule_file in guard_files(root, &patterns, &ignore)? {
+                if each_rule_file.test_files.is_empty() {
                     println!(
-                        "Testing Guard File {}",
+                        "Guard File {} did not have any tests associated, UNTESTED.",
                         each_rule_file.file.path().display()
                     );
-                    let rule_file = File::open(each_rule_file.file.path())?;
-                    let content = read_file_content(rule_file)?;
-                    let span =
-                        crate::rules::parser::Span::new_extra(&content, &each_rule_file.prefix);
-                    match crate::rules::parser::rules_file(span) {
-                        Err(e) => {
-                            eprintln!("Parse Error on ruleset file {}", e);
-                            exit_code = 1;
+                    println!("---");
+                    exit_code.record_untested();
+                    continue;
+                }
+                println!(
+                    "Testing Guard File {}",
+                    each_rule_file.file.path().display()
+                );
+                let rule_file = File::open(each_rule_file.file.path())?;
+                let content = read_file_content(rule_file)?;
+                let span = crate::rules::parser::Span::new_extra(&content, &each_rule_file.prefix);
+                match crate::rules::parser::rules_file(span) {
+                    Err(e) => {
+                        eprintln!("Parse Error on ruleset file {}", e);
+                        exit_code.record_parse_error();
+                    }
+                    Ok(rules) => {
+                        let data_test_files = each_rule_file
+                            .test_files
+                            .iter()
+                            .map(|de| de.path().to_path_buf())
+                            .collect::<Vec<PathBuf>>();
+                        let mut file_coverage = coverage_file.map(|_| {
+                            let path = format!("{}", each_rule_file.file.path().display());
+                            FileCoverage::new(&path, &content, &rules)
+                        });
+                        let mut found = vec![];
+                        if let Err(e) = test_with_data(
+                            &data_test_files,
+                            &rules,
+                            verbose,
+                            new_engine,
+                            &filters,
+                            file_coverage.as_mut(),
+                            watching.then_some(&mut found),
+                            &mut exit_code,
+                        ) {
+                            eprintln!("Evaluation Error on ruleset file {}", e);
+                            exit_code.record_evaluation_error();
                         }
-                        Ok(rules) => {
-                            let data_test_files = each_rule_file
-                                .test_files
-                                .iter()
-                                .map(|de| de.path().to_path_buf())
-                                .collect::<Vec<PathBuf>>();
-                            let test_exit_code =
-                                test_with_data(&data_test_files, &rules, verbose, new_engine)?;
-                            exit_code = if exit_code == 0 {
-                                test_exit_code
-                            } else {
-                                exit_code
-                            }
+                        seeds.extend(found.into_iter().map(|(test_file, results)| {
+                            (each_rule_file.file.path().to_path_buf(), test_file, results)
+                        }));
+                        if let Some(file_coverage) = file_coverage {
+                            file_coverage.print_summary(&mut std::io::stdout())?;
+                            coverage.push(file_coverage);
                         }
                     }
-                    println!("---");
                 }
+                println!("---");
             }
         } else {
             let file = app.value_of(RULES_FILE.0).unwrap();
             let data = app.value_of(TEST_DATA.0).unwrap();
 
             validate_path(file)?;
-            validate_path(data)?;
-            let data_test_files = get_files_with_filter(data, cmp, |entry| {
-                entry
-                    .file_name()
-                    .to_str()
-                    .map(|name| {
-                        name.ends_with(".json")
-                            || name.ends_with(".yaml")
-                            || name.ends_with(".JSON")
-                            || name.ends_with(".YAML")
-                            || name.ends_with(".yml")
-                            || name.ends_with(".jsn")
-                    })
-                    .unwrap_or(false)
-            })?;
+            let data_test_files = get_files_with_filter(data, cmp, &ignore, is_test_file)?;
 
             let path = PathBuf::try_from(file)?;
 
@@ -240,20 +239,47 @@ or failure testing.
                 match rules {
                     Err(e) => {
                         eprintln!("Unable to read rule file content {}", e);
-                        exit_code = 1;
+                        exit_code.record_error();
                     }
                     Ok((context, path)) => {
                         let span = crate::rules::parser::Span::new_extra(&context, &path);
                         match crate::rules::parser::rules_file(span) {
                             Err(e) => {
                                 eprintln!("Parse Error on ruleset file {}", e);
-                                exit_code = 1;
+                                exit_code.record_parse_error();
+                            }
+                            Ok(rules) if app.is_present(SNAPSHOT.0) => {
+                                Snapshots::new(
+                                    Path::new(&path),
+                                    &rules,
+                                    Path::new(data),
+                                    app.is_present(UPDATE_SNAPSHOTS.0),
+                                )
+                                .check(&data_test_files, &mut exit_code)?;
                             }
                             Ok(rules) => {
-                                let curr_exit_code =
-                                    test_with_data(&data_test_files, &rules, verbose, new_engine)?;
-                                if curr_exit_code != 0 {
-                                    exit_code = curr_exit_code;
+                                let mut file_coverage = coverage_file
+                                    .map(|_| FileCoverage::new(&path, &context, &rules));
+                                let mut found = vec![];
+                                if let Err(e) = test_with_data(
+                                    &data_test_files,
+                                    &rules,
+                                    verbose,
+                                    new_engine,
+                                    &filters,
+                                    file_coverage.as_mut(),
+                                    watching.then_some(&mut found),
+                                    &mut exit_code,
+                                ) {
+                                    eprintln!("Evaluation Error on ruleset file {}", e);
+                                    exit_code.record_evaluation_error();
+                                }
+                                seeds.extend(found.into_iter().map(|(test_file, results)| {
+                                    (PathBuf::from(&path), test_file, results)
+                                }));
+                                if let Some(file_coverage) = file_coverage {
+                                    file_coverage.print_summary(&mut std::io::stdout())?;
+                                    coverage.push(file_coverage);
                                 }
                             }
                         }
@@ -262,36 +288,192 @@ or failure testing.
             }
         }
 
-        Ok(exit_code)
+        if let Some(coverage_file) = coverage_file {
+            let mut writer = std::io::BufWriter::new(File::create(coverage_file)?);
+            write_lcov(&coverage, &mut writer)?;
+        }
+
+        if watching {
+            let tests = match app.value_of(DIRECTORY.0) {
+                Some(root) => Tests::Dir {
+                    root,
+                    patterns: TestPatterns::new(app.values_of(TEST_PATTERN.0)),
+                },
+                None => Tests::File {
+                    rules: app.value_of(RULES_FILE.0).unwrap(),
+                    data: app.value_of(TEST_DATA.0).unwrap(),
+                },
+            };
+            let mut session = TestSession::new(tests, &ignore, filters)?;
+            for (rules_file, test_file, results) in seeds {
+                session.seed(&rules_file, &test_file, results);
+            }
+            watch(&mut session, &mut std::io::stdout())?;
+        }
+
+        Ok(exit_code.exit_code())
+    }
+}
+
+struct GuardFile {
+    prefix: String,
+    file: DirEntry,
+    test_files: Vec<DirEntry>,
+}
+
+//
+// The rules files under `root` with their test files, each test file is paired with the first
+// rules file whose test patterns it matches
+//
+fn guard_files(
+    root: &str,
+    patterns: &TestPatterns,
+    ignore: &IgnoreFilter,
+) -> Result<Vec<GuardFile>> {
+    let mut ignore = ignore.rooted_at(Path::new(root))?;
+    let walk = walkdir::WalkDir::new(root);
+    let mut non_guard: Vec<DirEntry> = vec![];
+    let mut ordered_guard_files: BTreeMap<String, Vec<GuardFile>> = BTreeMap::new();
+    for file in walk
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !ignore.is_ignored(entry.path(), entry.file_type().is_dir())
+        })
+        .flatten()
+    {
+        if file.path().is_file() {
+            let name = file
+                .file_name()
+                .to_str()
+                .map_or("".to_string(), |s| s.to_string());
+            if name.ends_with(".guard") || name.ends_with(".ruleset") {
+                let prefix = name
+                    .strip_suffix(".guard")
+                    .or_else(|| name.strip_suffix(".ruleset"))
+                    .unwrap()
+                    .to_string();
+                ordered_guard_files
+                    .entry(
+                        file.path()
+                            .parent()
+                            .map_or("".to_string(), |p| format!("{}", p.display())),
+                    )
+                    .or_insert(vec![])
+                    .push(GuardFile {
+                        prefix,
+                        file,
+                        test_files: vec![],
+                    });
+                continue;
+            } else {
+                non_guard.push(file);
+            }
+        }
+    }
+    ignore.finish()?;
+    let mut guard_files = ordered_guard_files
+        .into_values()
+        .flatten()
+        .map(|guard_file| {
+            let dir = guard_file.file.path().parent().unwrap_or(Path::new(""));
+            let matcher = patterns.matcher(Path::new(root), dir, &guard_file.prefix)?;
+            Ok((guard_file, matcher))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for file in non_guard {
+        if let Some((guard_file, _)) = guard_files
+            .iter_mut()
+            .find(|(_, matcher)| matcher.is_match(file.path()))
+        {
+            guard_file.test_files.push(file);
+        }
     }
+    Ok(guard_files
+        .into_iter()
+        .map(|(guard_file, _)| guard_file)
+        .collect())
+}
+
+fn is_test_file(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map(|name| {
+            name.ends_with(".json")
+                || name.ends_with(".yaml")
+                || name.ends_with(".JSON")
+                || name.ends_with(".YAML")
+                || name.ends_with(".yml")
+                || name.ends_with(".jsn")
+        })
+        .unwrap_or(false)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct TestExpectations {
-    rules: HashMap<String, String>,
+pub(crate) struct TestExpectations {
+    rules: HashMap<String, RuleExpectation>,
 }
 
+//
+// The input fields are not flattened from TestInput, flattening buffers the values which
+// loses YAML tags like !Ref in inlined inputs. The fuzz command reads the same test files,
+// with the statuses it allows in `fuzz`.
+//
 #[derive(Serialize, Deserialize, Debug)]
-struct TestSpec {
-    name: Option<String>,
-    input: serde_yaml::Value,
+pub(crate) struct TestSpec {
+    pub(crate) name: Option<String>,
+    #[serde(default)]
+    input: Option<serde_yaml::Value>,
+    #[serde(default)]
+    input_file: Option<String>,
+    #[serde(default)]
+    merge: Option<serde_json::Value>,
+    #[serde(default)]
+    patch: Option<json_patch::Patch>,
+    #[serde(default)]
+    input_parameters: Option<FileReferences>,
     expectations: TestExpectations,
+    #[serde(default)]
+    pub(crate) fuzz: FuzzExpectations,
+}
+
+impl TestSpec {
+    pub(crate) fn resolve_input(&mut self, dir: &Path) -> Result<PathAwareValue> {
+        TestInput {
+            input: self.input.take(),
+            input_file: self.input_file.take(),
+            merge: self.merge.take(),
+            patch: self.patch.take(),
+            input_parameters: self.input_parameters.take(),
+        }
+        .resolve(dir)
+    }
 }
 
-#[allow(clippy::never_loop)]
+//
+// When `found` is given, the results of each test file that ran to completion are added to it
+// for watch mode to start from
+//
+#[allow(clippy::never_loop, clippy::too_many_arguments)]
 fn test_with_data(
     test_data_files: &[PathBuf],
     rules: &RulesFile<'_>,
     verbose: bool,
     new_engine: bool,
-) -> Result<i32> {
-    let mut exit_code = 0;
+    filters: &[&str],
+    mut coverage: Option<&mut FileCoverage>,
+    mut found: Option<&mut Vec<(PathBuf, TestFileResults)>>,
+    exit_code: &mut ExitCodeTracker,
+) -> Result<()> {
     let mut test_counter = 1;
     for specs in iterate_over(test_data_files, |data, path| {
+        let dir = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
         match serde_yaml::from_str::<Vec<TestSpec>>(&data) {
-            Ok(spec) => Ok(spec),
+            Ok(spec) => Ok((spec, dir, path.to_path_buf())),
             Err(_) => match serde_json::from_str::<Vec<TestSpec>>(&data) {
-                Ok(specs) => Ok(specs),
+                Ok(specs) => Ok((specs, dir, path.to_path_buf())),
                 Err(e) => Err(Error::new(ErrorKind::ParseError(format!(
                     "Unable to process data in file {}, Error {},",
                     path.display(),
@@ -303,21 +485,49 @@ fn test_with_data(
         match specs {
             Err(e) => {
                 eprintln!("Error processing {}", e);
-                exit_code = 1;
+                exit_code.record_error();
             }
-            Ok(specs) => {
-                for each in specs {
+            Ok((specs, dir, test_file)) => {
+                let mut results = TestFileResults::default();
+                for (index, mut each) in specs.into_iter().enumerate() {
+                    if !selected(filters, each.name.as_deref()) {
+                        test_counter += 1;
+                        continue;
+                    }
+                    results.reference(&dir, &each);
                     println!("Test Case #{}", test_counter);
                     if each.name.is_some() {
-                        println!("Name: {}", each.name.unwrap());
+                        println!("Name: {}", each.name.as_ref().unwrap());
                     }
+                    let root = match each.resolve_input(&dir) {
+                        Ok(root) => root,
+                        Err(e) => {
+                            eprintln!("Error processing {}", e);
+                            exit_code.record_error();
+                            test_counter += 1;
+                            continue;
+                        }
+                    };
 
                     let by_result = if new_engine {
                         let mut by_result = HashMap::new();
-                        let root = PathAwareValue::try_from(each.input)?;
                         let mut root_scope = crate::rules::eval_context::root_scope(rules, &root)?;
                         eval_rules_file(rules, &mut root_scope)?;
                         let top = root_scope.reset_recorder().extract();
+                        if let Some(coverage) = coverage.as_mut() {
+                            coverage.record(&top);
+                        }
+                        results.add(index, &each, Outcome::Statuses(rule_statuses(&top)));
+                        let report = if each
+                            .expectations
+                            .rules
+                            .values()
+                            .any(RuleExpectation::has_details)
+                        {
+                            Some(simplifed_json_from_root(&top)?)
+                        } else {
+                            None
+                        };
 
                         let by_rules = top.children.iter().fold(HashMap::new(), |mut acc, rule| {
                             if let Some(RecordType::RuleCheck(NamedStatus { name, .. })) =
@@ -329,16 +539,18 @@ fn test_with_data(
                         });
 
                         for (rule_name, rule) in by_rules {
-                            let expected = match each.expectations.rules.get(rule_name) {
-                                Some(exp) => Status::try_from(exp.as_str())?,
+                            let expectation = match each.expectations.rules.get(rule_name) {
+                                Some(exp) => exp,
                                 None => {
                                     println!(
                                         "  No Test expectation was set for Rule {}",
                                         rule_name
                                     );
+                                    exit_code.record_status(Status::SKIP);
                                     continue;
                                 }
                             };
+                            let expected = expectation.status()?;
 
                             let mut statues: Vec<Status> = Vec::with_capacity(rule.len());
                             let matched = 'matched: loop {
@@ -372,7 +584,35 @@ fn test_with_data(
                                 break 'matched None;
                             };
 
+                            let mismatches = match (&matched, &report) {
+                                (Some(_), Some(report)) => {
+                                    let reports = report
+                                        .not_compliant
+                                        .iter()
+                                        .filter(|each| {
+                                            each.rule().is_some_and(|r| r.name == rule_name)
+                                        })
+                                        .collect::<Vec<_>>();
+                                    expectation.mismatches(&reports)
+                                }
+                                _ => vec![],
+                            };
+
                             match matched {
+                                Some(status) if !mismatches.is_empty() => {
+                                    by_result
+                                        .entry(String::from("FAIL"))
+                                        .or_insert_with(indexmap::IndexSet::new)
+                                        .insert(format!(
+                                            "{}: Expected = {}, Evaluated = {}, with differences\n      {}",
+                                            rule_name,
+                                            status,
+                                            status,
+                                            mismatches.join("\n      ")
+                                        ));
+                                    exit_code.record_status(Status::FAIL);
+                                }
+
                                 Some(status) => {
                                     by_result
                                         .entry(String::from("PASS"))
@@ -388,7 +628,7 @@ fn test_with_data(
                                             "{}: Expected = {}, Evaluated = {:?}",
                                             rule_name, expected, statues
                                         ));
-                                    exit_code = 7;
+                                    exit_code.record_status(Status::FAIL);
                                 }
                             }
                         }
@@ -397,8 +637,24 @@ fn test_with_data(
                             validate::print_verbose_tree(&top);
                         }
                         by_result
+                    } else if each
+                        .expectations
+                        .rules
+                        .values()
+                        .any(RuleExpectation::has_details)
+                    {
+                        //
+                        // The previous engine does not report the paths, messages or clauses
+                        // of failures, these expectations could not be checked
+                        //
+                        eprintln!(
+                            "Error processing Test Case #{}, expectations on paths, messages or failed_clauses are not supported with --{}",
+                            test_counter, PREVIOUS_ENGINE.0
+                        );
+                        exit_code.record_error();
+                        test_counter += 1;
+                        continue;
                     } else {
-                        let root = PathAwareValue::try_from(each.input)?;
                         let context = RootScope::new(rules, &root)?;
                         let stacker = StackTracker::new(&context);
                         rules.evaluate(&root, &stacker)?;
@@ -408,10 +664,10 @@ fn test_with_data(
                         let mut by_result = HashMap::new();
                         for each in &stack[0].children {
                             match expectations.get(&each.context) {
-                                Some(value) => match Status::try_from(value.as_str()) {
+                                Some(value) => match value.status() {
                                     Err(e) => {
                                         eprintln!("Incorrect STATUS provided {}", e);
-                                        exit_code = 1;
+                                        exit_code.record_error();
                                     }
                                     Ok(status) => {
                                         let got = each.status.unwrap();
@@ -423,7 +679,7 @@ fn test_with_data(
                                                     "{}: Expected = {}, Evaluated = {}",
                                                     each.context, status, got
                                                 ));
-                                            exit_code = 7;
+                                            exit_code.record_status(Status::FAIL);
                                         } else {
                                             by_result
                                                 .entry(String::from("PASS"))
@@ -442,7 +698,8 @@ fn test_with_data(
                                     println!(
                                         "  No Test expectation was set for Rule {}",
                                         each.context
-                                    )
+                                    );
+                                    exit_code.record_status(Status::SKIP);
                                 }
                             }
                         }
@@ -451,10 +708,13 @@ fn test_with_data(
                     print_test_case_report(&by_result);
                     test_counter += 1;
                 }
+                if let Some(found) = found.as_mut() {
+                    found.push((test_file, results));
+                }
             }
         }
     }
-    Ok(exit_code)
+    Ok(())
 }
 
 pub(crate) fn print_test_case_report(by_result: &HashMap<String, indexmap::IndexSet<String>>) {
@@ -0,0 +1,603 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::rules::display::ReportDisplay;
+use crate::rules::eval::{
+    access_clause_context, block_clause_context, named_rule_context, type_block_context,
+    RULE_WHEN_BLOCK_CONTEXT, WHEN_BLOCK_CONTEXT,
+};
+use crate::rules::eval_context::EventRecord;
+use crate::rules::exprs::{
+    Conjunctions, GuardAccessClause, GuardClause, Rule, RuleClause, RulesFile, WhenConditions,
+    WhenGuardClause,
+};
+use crate::rules::parser::{rules_file_exprs, Exprs, Span};
+use crate::rules::{RecordType, Result, Status};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Rule,
+    Condition,
+    Disjunction,
+    Branch,
+    Clause,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    kind: Kind,
+    line: usize,
+    label: String,
+    evaluated: u64,
+    passed: u64,
+    failed: u64,
+    branches: Vec<Point>,
+}
+
+impl Point {
+    fn new(kind: Kind, line: usize, label: String) -> Point {
+        Point {
+            kind,
+            line,
+            label,
+            evaluated: 0,
+            passed: 0,
+            failed: 0,
+            branches: vec![],
+        }
+    }
+
+    fn hit(&mut self, status: Status) {
+        self.evaluated += 1;
+        match status {
+            Status::PASS => self.passed += 1,
+            Status::FAIL => self.failed += 1,
+            Status::SKIP => {}
+        }
+    }
+
+    fn missing(&self) -> Vec<&'static str> {
+        let mut missing = vec![];
+        if self.passed == 0 {
+            missing.push("PASS");
+        }
+        if self.failed == 0 {
+            missing.push("FAIL");
+        }
+        missing
+    }
+}
+
+//
+// (rule name, enclosing type or block clause, clause) identifies a coverage point in both the
+// rules file and the evaluation records. Identical clauses in the same block share counts
+//
+type Key = (String, String, String);
+
+///
+/// Coverage of a single rules file accumulated over all evaluated test cases. Coverage points
+/// are the rules, `when` conditions, disjunction branches and guard access clauses of the file,
+/// each tracks how often it was evaluated and how often that evaluated to PASS and to FAIL.
+/// A `when` condition that does not hold counts as FAIL.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FileCoverage {
+    file: String,
+    points: Vec<Point>,
+    index: HashMap<Key, Vec<usize>>,
+}
+
+impl FileCoverage {
+    pub(crate) fn new(file: &str, content: &str, rules: &RulesFile<'_>) -> FileCoverage {
+        let mut coverage = FileCoverage {
+            file: file.to_string(),
+            points: vec![],
+            index: HashMap::new(),
+        };
+        let lines = rule_lines(file, content);
+        let parameterized = rules.parameterized_rules.iter().map(|each| &each.rule);
+        for rule in rules.guard_rules.iter().chain(parameterized) {
+            let line = lines.get(rule.rule_name.as_str()).copied().unwrap_or(1);
+            coverage.add_rule(line, rule);
+        }
+        coverage
+    }
+
+    ///
+    /// Accumulates the evaluation records of a test case, the root record is the one for
+    /// the rules file.
+    ///
+    pub(crate) fn record(&mut self, top: &EventRecord<'_>) {
+        self.visit(top, "", "");
+    }
+
+    pub(crate) fn print_summary(&self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(writer, "Coverage for rules file {}", self.file)?;
+        let categories = [
+            ("Rules", Kind::Rule),
+            ("When conditions", Kind::Condition),
+            ("Disjunction branches", Kind::Branch),
+            ("Clauses", Kind::Clause),
+        ];
+        for (title, kind) in categories {
+            let points = self.points_of(kind);
+            let total = points.len();
+            let count = |check: fn(&Point) -> bool| points.iter().filter(|p| check(p)).count();
+            writeln!(
+                writer,
+                "  {:<21} {}/{} evaluated, {}/{} PASS, {}/{} FAIL",
+                format!("{}:", title),
+                count(|p| p.evaluated > 0),
+                total,
+                count(|p| p.passed > 0),
+                total,
+                count(|p| p.failed > 0),
+                total
+            )?;
+        }
+
+        let mut uncovered = self
+            .points
+            .iter()
+            .flat_map(|each| match each.kind {
+                Kind::Disjunction => each.branches.iter().collect::<Vec<_>>(),
+                _ => vec![each],
+            })
+            .filter(|each| !each.missing().is_empty())
+            .collect::<Vec<_>>();
+        uncovered.sort_by_key(|each| each.line);
+        if !uncovered.is_empty() {
+            writeln!(writer, "  Not evaluated to:")?;
+            for each in uncovered {
+                writeln!(
+                    writer,
+                    "    {}, line {}: {}",
+                    each.missing().join(", "),
+                    each.line,
+                    each.label
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn points_of(&self, kind: Kind) -> Vec<&Point> {
+        self.points
+            .iter()
+            .flat_map(|each| std::iter::once(each).chain(each.branches.iter()))
+            .filter(|each| each.kind == kind)
+            .collect()
+    }
+
+    fn add(&mut self, key: Key, point: Point) {
+        self.index.entry(key).or_default().push(self.points.len());
+        self.points.push(point);
+    }
+
+    fn add_rule(&mut self, line: usize, rule: &Rule<'_>) {
+        let name = &rule.rule_name;
+        self.add(
+            (name.clone(), String::new(), String::new()),
+            Point::new(Kind::Rule, line, format!("rule {}", name)),
+        );
+        if let Some(conditions) = &rule.conditions {
+            self.add_conditions(name, "", conditions);
+        }
+        self.add_conjunctions(name, "", &rule.block.conjunctions);
+    }
+
+    fn add_conditions(&mut self, rule: &str, scope: &str, conditions: &WhenConditions<'_>) {
+        if let Some(first) = conditions.first().and_then(|each| each.first()) {
+            self.add(
+                (
+                    rule.to_string(),
+                    scope.to_string(),
+                    format!("when:{}", first.context()),
+                ),
+                Point::new(
+                    Kind::Condition,
+                    first.line(),
+                    format!("when {}", first.label()),
+                ),
+            );
+        }
+        self.add_conjunctions(rule, scope, conditions);
+    }
+
+    fn add_conjunctions<T: Coverable>(
+        &mut self,
+        rule: &str,
+        scope: &str,
+        conjunctions: &Conjunctions<T>,
+    ) {
+        for disjunctions in conjunctions {
+            if disjunctions.len() > 1 {
+                let first = &disjunctions[0];
+                let mut point = Point::new(Kind::Disjunction, first.line(), first.label());
+                point.branches = disjunctions
+                    .iter()
+                    .map(|each| {
+                        Point::new(Kind::Branch, each.line(), format!("or {}", each.label()))
+                    })
+                    .collect();
+                self.add(
+                    (
+                        rule.to_string(),
+                        scope.to_string(),
+                        format!("or:{}", first.context()),
+                    ),
+                    point,
+                );
+            }
+            for each in disjunctions {
+                each.add_to(rule, scope, self);
+            }
+        }
+    }
+
+    fn add_clause(&mut self, rule: &str, scope: &str, clause: &GuardAccessClause<'_>) {
+        self.add(
+            (
+                rule.to_string(),
+                scope.to_string(),
+                access_clause_context(clause),
+            ),
+            Point::new(
+                Kind::Clause,
+                clause.access_clause.location.line as usize,
+                clause_label(clause),
+            ),
+        );
+    }
+
+    fn hit(&mut self, key: &Key, status: Status) {
+        if let Some(indices) = self.index.get(key) {
+            for idx in indices {
+                self.points[*idx].hit(status);
+            }
+        }
+    }
+
+    fn visit<'r>(&mut self, record: &'r EventRecord<'_>, rule: &'r str, scope: &'r str) {
+        let key = |context: String| (rule.to_string(), scope.to_string(), context);
+        let (rule, scope) = match &record.container {
+            Some(RecordType::RuleCheck(named)) => {
+                self.hit(
+                    &(named.name.to_string(), String::new(), String::new()),
+                    named.status,
+                );
+                (named.name, "")
+            }
+
+            Some(RecordType::RuleCondition(status))
+            | Some(RecordType::TypeCondition(status))
+            | Some(RecordType::WhenCondition(status)) => {
+                let status = match status {
+                    Status::PASS => Status::PASS,
+                    _ => Status::FAIL,
+                };
+                self.hit(&key(format!("when:{}", leading_context(record))), status);
+                (rule, scope)
+            }
+
+            Some(RecordType::Disjunction(check)) => {
+                let key = key(format!("or:{}", leading_context(record)));
+                for idx in self.index.get(&key).into_iter().flatten() {
+                    let point = &mut self.points[*idx];
+                    point.hit(check.status);
+                    for (branch, child) in point.branches.iter_mut().zip(&record.children) {
+                        if let Some(status) = child.container.as_ref().and_then(status_of) {
+                            branch.hit(status);
+                        }
+                    }
+                }
+                (rule, scope)
+            }
+
+            Some(RecordType::GuardClauseBlockCheck(check)) => {
+                self.hit(&key(record.context.clone()), check.status);
+                return;
+            }
+
+            Some(RecordType::TypeCheck(_)) | Some(RecordType::BlockGuardCheck(_)) => {
+                (rule, record.context.as_str())
+            }
+
+            _ => (rule, scope),
+        };
+
+        for child in &record.children {
+            self.visit(child, rule, scope);
+        }
+    }
+}
+
+///
+/// Writes the coverage in the lcov tracefile format. Rules are reported as functions, lines
+/// of rules, conditions and clauses with the number of times they were evaluated, and each
+/// condition, disjunction branch and clause as a pair of branches, taken when it evaluated
+/// to PASS and to FAIL respectively.
+///
+pub(crate) fn write_lcov(files: &[FileCoverage], writer: &mut dyn Write) -> Result<()> {
+    for file in files {
+        writeln!(writer, "TN:")?;
+        writeln!(writer, "SF:{}", file.file)?;
+
+        let rules = file.points_of(Kind::Rule);
+        for rule in &rules {
+            writeln!(writer, "FN:{},{}", rule.line, rule_name(rule))?;
+        }
+        for rule in &rules {
+            writeln!(writer, "FNDA:{},{}", rule.evaluated, rule_name(rule))?;
+        }
+        writeln!(writer, "FNF:{}", rules.len())?;
+        writeln!(
+            writer,
+            "FNH:{}",
+            rules.iter().filter(|each| each.evaluated > 0).count()
+        )?;
+
+        let mut found = 0;
+        let mut hit = 0;
+        for (block, point) in file.points.iter().enumerate() {
+            let outcomes = match point.kind {
+                Kind::Rule => continue,
+                Kind::Disjunction => point.branches.iter().collect::<Vec<_>>(),
+                _ => vec![point],
+            };
+            for (idx, each) in outcomes.iter().enumerate() {
+                for (branch, taken) in [(idx * 2, each.passed), (idx * 2 + 1, each.failed)] {
+                    let taken = if each.evaluated == 0 {
+                        "-".to_string()
+                    } else {
+                        taken.to_string()
+                    };
+                    writeln!(writer, "BRDA:{},{},{},{}", each.line, block, branch, taken)?;
+                    found += 1;
+                    if taken != "-" && taken != "0" {
+                        hit += 1;
+                    }
+                }
+            }
+        }
+        writeln!(writer, "BRF:{}", found)?;
+        writeln!(writer, "BRH:{}", hit)?;
+
+        let mut lines = BTreeMap::new();
+        for point in &file.points {
+            if point.kind != Kind::Disjunction {
+                *lines.entry(point.line).or_insert(0) += point.evaluated;
+            }
+        }
+        for (line, hits) in &lines {
+            writeln!(writer, "DA:{},{}", line, hits)?;
+        }
+        writeln!(writer, "LF:{}", lines.len())?;
+        writeln!(
+            writer,
+            "LH:{}",
+            lines.values().filter(|hits| **hits > 0).count()
+        )?;
+        writeln!(writer, "end_of_record")?;
+    }
+    Ok(())
+}
+
+fn rule_name(point: &Point) -> &str {
+    point.label.strip_prefix("rule ").unwrap_or(&point.label)
+}
+
+//
+// Rules do not carry a location, the lines they start at come from the top level expressions
+// of the rules file. The default rule of clauses outside any rule starts at line 1.
+//
+fn rule_lines(file: &str, content: &str) -> HashMap<String, usize> {
+    let exprs = rules_file_exprs(Span::new_extra(content, file)).unwrap_or_default();
+    let mut lines = HashMap::new();
+    for (location, each) in exprs {
+        let name = match each {
+            Exprs::Rule(rule) => rule.rule_name,
+            Exprs::ParameterizedRule(rule) => rule.rule.rule_name,
+            _ => continue,
+        };
+        lines.entry(name).or_insert(location.line as usize);
+    }
+    lines
+}
+
+fn clause_label(clause: &GuardAccessClause<'_>) -> String {
+    ReportDisplay(clause)
+        .to_string()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+//
+// The context of the first clause evaluated under a record, looking through disjunctions
+//
+fn leading_context(record: &EventRecord<'_>) -> String {
+    match record.children.first() {
+        Some(first) if matches!(first.container, Some(RecordType::Disjunction(_))) => {
+            leading_context(first)
+        }
+        Some(first) => first.context.clone(),
+        None => String::new(),
+    }
+}
+
+fn status_of(record: &RecordType<'_>) -> Option<Status> {
+    match record {
+        RecordType::FileCheck(named) | RecordType::RuleCheck(named) => Some(named.status),
+        RecordType::RuleCondition(status)
+        | RecordType::TypeCondition(status)
+        | RecordType::TypeBlock(status)
+        | RecordType::Filter(status)
+        | RecordType::WhenCondition(status) => Some(*status),
+        RecordType::TypeCheck(check) => Some(check.block.status),
+        RecordType::WhenCheck(check)
+        | RecordType::Disjunction(check)
+        | RecordType::BlockGuardCheck(check)
+        | RecordType::GuardClauseBlockCheck(check) => Some(check.status),
+        RecordType::ClauseValueCheck(_) => None,
+    }
+}
+
+//
+// Clauses that can appear in conjunctions, along with the context of the record their
+// evaluation starts
+//
+trait Coverable {
+    fn context(&self) -> String;
+    fn line(&self) -> usize;
+    fn label(&self) -> String;
+    fn add_to(&self, rule: &str, scope: &str, coverage: &mut FileCoverage);
+}
+
+impl<'loc> Coverable for WhenGuardClause<'loc> {
+    fn context(&self) -> String {
+        match self {
+            WhenGuardClause::Clause(gac) => access_clause_context(gac),
+            WhenGuardClause::NamedRule(gnc) => named_rule_context(gnc),
+            WhenGuardClause::ParameterizedNamedRule(prc) => prc.named_rule.dependent_rule.clone(),
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self {
+            WhenGuardClause::Clause(gac) => gac.access_clause.location.line as usize,
+            WhenGuardClause::NamedRule(gnc) => gnc.location.line as usize,
+            WhenGuardClause::ParameterizedNamedRule(prc) => prc.named_rule.location.line as usize,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            WhenGuardClause::Clause(gac) => clause_label(gac),
+            WhenGuardClause::NamedRule(gnc) => named_rule_label(gnc.negation, &gnc.dependent_rule),
+            WhenGuardClause::ParameterizedNamedRule(prc) => {
+                named_rule_label(prc.named_rule.negation, &prc.named_rule.dependent_rule)
+            }
+        }
+    }
+
+    fn add_to(&self, rule: &str, scope: &str, coverage: &mut FileCoverage) {
+        if let WhenGuardClause::Clause(gac) = self {
+            coverage.add_clause(rule, scope, gac);
+        }
+    }
+}
+
+impl<'loc> Coverable for GuardClause<'loc> {
+    fn context(&self) -> String {
+        match self {
+            GuardClause::Clause(gac) => access_clause_context(gac),
+            GuardClause::NamedRule(gnc) => named_rule_context(gnc),
+            GuardClause::ParameterizedNamedRule(prc) => prc.named_rule.dependent_rule.clone(),
+            GuardClause::BlockClause(block) => block_clause_context(block),
+            GuardClause::WhenBlock(..) => WHEN_BLOCK_CONTEXT.to_string(),
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self {
+            GuardClause::Clause(gac) => gac.access_clause.location.line as usize,
+            GuardClause::NamedRule(gnc) => gnc.location.line as usize,
+            GuardClause::ParameterizedNamedRule(prc) => prc.named_rule.location.line as usize,
+            GuardClause::BlockClause(block) => block.location.line as usize,
+            GuardClause::WhenBlock(conditions, _) => first_line(conditions),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            GuardClause::Clause(gac) => clause_label(gac),
+            GuardClause::NamedRule(gnc) => named_rule_label(gnc.negation, &gnc.dependent_rule),
+            GuardClause::ParameterizedNamedRule(prc) => {
+                named_rule_label(prc.named_rule.negation, &prc.named_rule.dependent_rule)
+            }
+            GuardClause::BlockClause(block) => format!("{} {{ .. }}", block.query),
+            GuardClause::WhenBlock(..) => "when { .. }".to_string(),
+        }
+    }
+
+    fn add_to(&self, rule: &str, scope: &str, coverage: &mut FileCoverage) {
+        match self {
+            GuardClause::Clause(gac) => coverage.add_clause(rule, scope, gac),
+            GuardClause::BlockClause(block) => {
+                let scope = self.context();
+                coverage.add_conjunctions(rule, &scope, &block.block.conjunctions);
+            }
+            GuardClause::WhenBlock(conditions, block) => {
+                coverage.add_conditions(rule, scope, conditions);
+                coverage.add_conjunctions(rule, scope, &block.conjunctions);
+            }
+            GuardClause::NamedRule(_) | GuardClause::ParameterizedNamedRule(_) => {}
+        }
+    }
+}
+
+impl<'loc> Coverable for RuleClause<'loc> {
+    fn context(&self) -> String {
+        match self {
+            RuleClause::Clause(clause) => clause.context(),
+            RuleClause::WhenBlock(..) => RULE_WHEN_BLOCK_CONTEXT.to_string(),
+            RuleClause::TypeBlock(type_block) => type_block_context(type_block),
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self {
+            RuleClause::Clause(clause) => clause.line(),
+            RuleClause::WhenBlock(conditions, _) => first_line(conditions),
+            RuleClause::TypeBlock(type_block) => match &type_block.conditions {
+                Some(conditions) => first_line(conditions),
+                None => first_line(&type_block.block.conjunctions),
+            },
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            RuleClause::Clause(clause) => clause.label(),
+            RuleClause::WhenBlock(..) => "when { .. }".to_string(),
+            RuleClause::TypeBlock(type_block) => format!("{} {{ .. }}", type_block.type_name),
+        }
+    }
+
+    fn add_to(&self, rule: &str, scope: &str, coverage: &mut FileCoverage) {
+        match self {
+            RuleClause::Clause(clause) => clause.add_to(rule, scope, coverage),
+            RuleClause::WhenBlock(conditions, block) => {
+                coverage.add_conditions(rule, scope, conditions);
+                coverage.add_conjunctions(rule, scope, &block.conjunctions);
+            }
+            RuleClause::TypeBlock(type_block) => {
+                let scope = self.context();
+                if let Some(conditions) = &type_block.conditions {
+                    coverage.add_conditions(rule, &scope, conditions);
+                }
+                coverage.add_conjunctions(rule, &scope, &type_block.block.conjunctions);
+            }
+        }
+    }
+}
+
+fn first_line<T: Coverable>(conjunctions: &Conjunctions<T>) -> usize {
+    conjunctions
+        .first()
+        .and_then(|each| each.first())
+        .map_or(1, Coverable::line)
+}
+
+fn named_rule_label(negation: bool, name: &str) -> String {
+    if negation {
+        format!("not {}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+#[path = "coverage_tests.rs"]
+mod coverage_tests;
@@ -0,0 +1,215 @@
+use super::*;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::path_value::PathAwareValue;
+use std::convert::TryFrom;
+
+const RULES: &str = r#"let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+
+rule S3_ENCRYPTION when %buckets !empty {
+    %buckets.Properties.BucketEncryption exists
+    %buckets.Properties.AccessControl == 'Private' or
+    %buckets.Properties.PublicAccessBlockConfiguration exists
+}
+
+rule S3_VERSIONING when S3_ENCRYPTION {
+    %buckets {
+        Properties.VersioningConfiguration.Status == 'Enabled'
+    }
+}
+"#;
+
+fn covered(data: &[&str]) -> Result<FileCoverage> {
+    let rules = RulesFile::try_from(RULES)?;
+    let mut coverage = FileCoverage::new("s3.guard", RULES, &rules);
+    for each in data {
+        let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(each)?)?;
+        let mut scope = root_scope(&rules, &value)?;
+        eval_rules_file(&rules, &mut scope)?;
+        coverage.record(&scope.reset_recorder().extract());
+    }
+    Ok(coverage)
+}
+
+const ENCRYPTED: &str = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketEncryption: {}
+      AccessControl: Private
+      VersioningConfiguration: {Status: Enabled}
+"#;
+
+const UNENCRYPTED: &str = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      AccessControl: PublicRead
+"#;
+
+const BLOCKS: &str = r#"rule check_name(name) {
+    %name == 'logs'
+}
+
+rule typed {
+    AWS::S3::Bucket when Resources exists {
+        Properties.Encrypted == true <<rule typed_in_message must pass>>
+    }
+}
+
+rule typed_block {
+    Resources.* {
+        when Type == 'AWS::S3::Bucket' {
+            Properties.BucketName exists
+        }
+    }
+    when typed {
+        check_name(Resources.*.Properties.BucketName)
+    }
+}
+"#;
+
+const BUCKET: &str = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      Encrypted: true
+      BucketName: logs
+"#;
+
+//
+// Points are keyed on the contexts evaluation records start with, every kind of clause
+// evaluated has to find its point
+//
+#[test]
+fn test_every_kind_of_clause_is_evaluated() -> Result<()> {
+    let rules = RulesFile::try_from(BLOCKS)?;
+    let mut coverage = FileCoverage::new("blocks.guard", BLOCKS, &rules);
+    let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(BUCKET)?)?;
+    let mut scope = root_scope(&rules, &value)?;
+    eval_rules_file(&rules, &mut scope)?;
+    coverage.record(&scope.reset_recorder().extract());
+
+    let mut summary = vec![];
+    coverage.print_summary(&mut summary)?;
+    let summary = String::from_utf8(summary).unwrap();
+    assert!(summary.contains("Rules:                3/3 evaluated, 3/3 PASS"));
+    assert!(summary.contains("When conditions:      3/3 evaluated, 3/3 PASS"));
+    assert!(summary.contains("Clauses:              5/5 evaluated, 5/5 PASS"));
+    let rules = coverage.points_of(Kind::Rule);
+    let lines = rules
+        .iter()
+        .map(|each| (each.label.as_str(), each.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            ("rule typed", 5),
+            ("rule typed_block", 11),
+            ("rule check_name", 1)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_coverage_summary() -> Result<()> {
+    let coverage = covered(&[ENCRYPTED])?;
+    let mut summary = vec![];
+    coverage.print_summary(&mut summary)?;
+    assert_eq!(
+        String::from_utf8(summary).unwrap(),
+        r#"Coverage for rules file s3.guard
+  Rules:                2/2 evaluated, 2/2 PASS, 0/2 FAIL
+  When conditions:      2/2 evaluated, 2/2 PASS, 0/2 FAIL
+  Disjunction branches: 1/2 evaluated, 1/2 PASS, 0/2 FAIL
+  Clauses:              4/5 evaluated, 4/5 PASS, 0/5 FAIL
+  Not evaluated to:
+    FAIL, line 3: rule S3_ENCRYPTION
+    FAIL, line 3: when %buckets not EMPTY
+    FAIL, line 3: %buckets not EMPTY
+    FAIL, line 4: %buckets[*].Properties.BucketEncryption EXISTS
+    FAIL, line 5: or %buckets[*].Properties.AccessControl EQUALS "Private"
+    FAIL, line 5: %buckets[*].Properties.AccessControl EQUALS "Private"
+    PASS, FAIL, line 6: or %buckets[*].Properties.PublicAccessBlockConfiguration EXISTS
+    PASS, FAIL, line 6: %buckets[*].Properties.PublicAccessBlockConfiguration EXISTS
+    FAIL, line 9: rule S3_VERSIONING
+    FAIL, line 9: when S3_ENCRYPTION
+    FAIL, line 11: Properties.VersioningConfiguration.Status EQUALS "Enabled"
+"#
+    );
+
+    let coverage = covered(&[ENCRYPTED, UNENCRYPTED, "Resources: {}"])?;
+    let mut summary = vec![];
+    coverage.print_summary(&mut summary)?;
+    assert_eq!(
+        String::from_utf8(summary).unwrap(),
+        r#"Coverage for rules file s3.guard
+  Rules:                2/2 evaluated, 2/2 PASS, 1/2 FAIL
+  When conditions:      2/2 evaluated, 2/2 PASS, 2/2 FAIL
+  Disjunction branches: 2/2 evaluated, 1/2 PASS, 2/2 FAIL
+  Clauses:              5/5 evaluated, 4/5 PASS, 4/5 FAIL
+  Not evaluated to:
+    PASS, line 6: or %buckets[*].Properties.PublicAccessBlockConfiguration EXISTS
+    PASS, line 6: %buckets[*].Properties.PublicAccessBlockConfiguration EXISTS
+    FAIL, line 9: rule S3_VERSIONING
+    FAIL, line 11: Properties.VersioningConfiguration.Status EQUALS "Enabled"
+"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_lcov() -> Result<()> {
+    let coverage = covered(&[ENCRYPTED, UNENCRYPTED])?;
+    let mut lcov = vec![];
+    write_lcov(&[coverage], &mut lcov)?;
+    //
+    // S3_ENCRYPTION is evaluated again for the when condition of S3_VERSIONING
+    //
+    assert_eq!(
+        String::from_utf8(lcov).unwrap(),
+        r#"TN:
+SF:s3.guard
+FN:3,S3_ENCRYPTION
+FN:9,S3_VERSIONING
+FNDA:4,S3_ENCRYPTION
+FNDA:2,S3_VERSIONING
+FNF:2
+FNH:2
+BRDA:3,1,0,4
+BRDA:3,1,1,0
+BRDA:3,2,0,4
+BRDA:3,2,1,0
+BRDA:4,3,0,2
+BRDA:4,3,1,2
+BRDA:5,4,0,2
+BRDA:5,4,1,2
+BRDA:6,4,2,0
+BRDA:6,4,3,2
+BRDA:5,5,0,2
+BRDA:5,5,1,2
+BRDA:6,6,0,0
+BRDA:6,6,1,2
+BRDA:9,8,0,1
+BRDA:9,8,1,1
+BRDA:11,9,0,1
+BRDA:11,9,1,0
+BRF:18
+BRH:13
+DA:3,12
+DA:4,4
+DA:5,4
+DA:6,2
+DA:9,4
+DA:11,1
+LF:6
+LH:6
+end_of_record
+"#
+    );
+    Ok(())
+}
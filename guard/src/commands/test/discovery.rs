@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use globset::{escape, GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::Result;
+
+pub(crate) const DEFAULT_TEST_PATTERN: &str = "{dir}/tests/{stem}*.{yaml,yml,json,jsn}";
+
+///
+/// Conventions pairing rules files with their test files when testing a directory. Each
+/// pattern is a glob template in which `{dir}` is replaced by the directory of the rules file
+/// and `{stem}` by its file name without the `.guard` or `.ruleset` extension, e.g.
+/// `{dir}/tests/{stem}_tests.yaml`. Patterns that do not start with `{dir}` are relative to
+/// the directory being tested. `*` does not match across directories, `**` does.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TestPatterns {
+    templates: Vec<String>,
+}
+
+impl TestPatterns {
+    pub(crate) fn new<'p>(patterns: Option<impl Iterator<Item = &'p str>>) -> TestPatterns {
+        let templates = match patterns {
+            Some(patterns) => patterns.map(String::from).collect(),
+            None => vec![DEFAULT_TEST_PATTERN.to_string()],
+        };
+        TestPatterns { templates }
+    }
+
+    ///
+    /// The matcher for test files of the rules file at `dir`/`stem`.guard, `root` is the
+    /// directory being tested.
+    ///
+    pub(crate) fn matcher(&self, root: &Path, dir: &Path, stem: &str) -> Result<GlobSet> {
+        let dir = escape(&format!("{}", dir.display()));
+        let stem = escape(stem);
+        let mut builder = GlobSetBuilder::new();
+        for template in &self.templates {
+            let pattern = template.replace("{dir}", &dir).replace("{stem}", &stem);
+            let pattern = if template.starts_with("{dir}") || Path::new(template).is_absolute() {
+                pattern
+            } else {
+                format!("{}", root.join(pattern).display())
+            };
+            let glob = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    Error::new(ErrorKind::IncompatibleError(format!(
+                        "Invalid test pattern {}, Error {}",
+                        template, e
+                    )))
+                })?;
+            builder.add(glob);
+        }
+        builder.build().map_err(|e| {
+            Error::new(ErrorKind::IncompatibleError(format!(
+                "Invalid test patterns, Error {}",
+                e
+            )))
+        })
+    }
+}
+
+///
+/// Whether a test case is selected by the `--filter` values, a case is selected when its name
+/// contains any of them. Unnamed cases are only selected without filters.
+///
+pub(crate) fn selected(filters: &[&str], name: Option<&str>) -> bool {
+    filters.is_empty()
+        || name.is_some_and(|name| filters.iter().any(|filter| name.contains(filter)))
+}
+
+#[cfg(test)]
+#[path = "discovery_tests.rs"]
+mod discovery_tests;
@@ -0,0 +1,67 @@
+use super::*;
+
+fn matches(patterns: &TestPatterns, stem: &str, path: &str) -> bool {
+    patterns
+        .matcher(Path::new("rules"), Path::new("rules/s3"), stem)
+        .unwrap()
+        .is_match(path)
+}
+
+#[test]
+fn test_default_pattern() {
+    let patterns = TestPatterns::new(None::<std::iter::Empty<&str>>);
+    assert!(matches(&patterns, "bucket", "rules/s3/tests/bucket.yaml"));
+    assert!(matches(
+        &patterns,
+        "bucket",
+        "rules/s3/tests/bucket_tests.json"
+    ));
+    assert!(!matches(&patterns, "bucket", "rules/s3/tests/bucket.txt"));
+    assert!(!matches(
+        &patterns,
+        "bucket",
+        "rules/s3/tests/nested/bucket.yaml"
+    ));
+    assert!(!matches(&patterns, "bucket", "rules/s3/bucket.yaml"));
+    assert!(!matches(&patterns, "bucket", "rules/iam/tests/bucket.yaml"));
+}
+
+#[test]
+fn test_configured_patterns() {
+    let patterns = TestPatterns::new(Some(
+        vec!["{dir}/tests/{stem}_tests.yaml", "tests/**/{stem}.yml"].into_iter(),
+    ));
+    assert!(matches(
+        &patterns,
+        "bucket",
+        "rules/s3/tests/bucket_tests.yaml"
+    ));
+    assert!(!matches(&patterns, "bucket", "rules/s3/tests/bucket.yaml"));
+    assert!(matches(&patterns, "bucket", "rules/tests/s3/bucket.yml"));
+    assert!(matches(&patterns, "bucket", "rules/tests/bucket.yml"));
+    assert!(!matches(
+        &patterns,
+        "bucket[1]",
+        "rules/s3/tests/bucket1_tests.yaml"
+    ));
+    assert!(matches(
+        &patterns,
+        "bucket[1]",
+        "rules/s3/tests/bucket[1]_tests.yaml"
+    ));
+
+    let invalid = TestPatterns::new(Some(vec!["{dir}/tests/{stem"].into_iter()));
+    assert!(invalid
+        .matcher(Path::new("rules"), Path::new("rules/s3"), "bucket")
+        .is_err());
+}
+
+#[test]
+fn test_selected() {
+    assert!(selected(&[], None));
+    assert!(selected(&[], Some("bucket encrypted, PASS")));
+    assert!(selected(&["encrypted"], Some("bucket encrypted, PASS")));
+    assert!(selected(&["FAIL", "PASS"], Some("bucket encrypted, PASS")));
+    assert!(!selected(&["FAIL"], Some("bucket encrypted, PASS")));
+    assert!(!selected(&["FAIL"], None));
+}
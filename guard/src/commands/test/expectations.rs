@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::eval_context::{ClauseReport, GuardClauseReport, Messages};
+use crate::rules::{Result, Status};
+
+///
+/// Expectation for a single rule in a test spec. Either just the status
+///
+/// ```yaml
+/// S3_BUCKET_ENCRYPTION: FAIL
+/// ```
+///
+/// or the status along with assertions on why the rule failed
+///
+/// ```yaml
+/// S3_BUCKET_ENCRYPTION:
+///   status: FAIL
+///   paths: [/Resources/MyBucket/Properties]
+///   messages: [S3 Bucket must enable server-side encryption]
+///   failed_clauses: 1
+/// ```
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum RuleExpectation {
+    Status(String),
+    Detailed(DetailedExpectation),
+}
+
+///
+/// `paths` must match the set of failing value paths exactly, each of `messages` must be
+/// contained in a custom message of the rule or one of its failing clauses, and
+/// `failed_clauses` is the number of failing checks reported for the rule.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DetailedExpectation {
+    status: String,
+    #[serde(default)]
+    paths: Option<Vec<String>>,
+    #[serde(default)]
+    messages: Option<Vec<String>>,
+    #[serde(default)]
+    failed_clauses: Option<usize>,
+}
+
+impl RuleExpectation {
+    pub(crate) fn status(&self) -> Result<Status> {
+        match self {
+            RuleExpectation::Status(status) => Status::try_from(status.as_str()),
+            RuleExpectation::Detailed(detailed) => Status::try_from(detailed.status.as_str()),
+        }
+    }
+
+    pub(crate) fn has_details(&self) -> bool {
+        match self {
+            RuleExpectation::Status(_) => false,
+            RuleExpectation::Detailed(detailed) => {
+                detailed.paths.is_some()
+                    || detailed.messages.is_some()
+                    || detailed.failed_clauses.is_some()
+            }
+        }
+    }
+
+    ///
+    /// Checks the detailed assertions against the failure reports for the rule, a rule that
+    /// did not fail has no reports. Returns a line per mismatch, empty when all assertions
+    /// hold.
+    ///
+    pub(crate) fn mismatches(&self, reports: &[&ClauseReport<'_>]) -> Vec<String> {
+        let detailed = match self {
+            RuleExpectation::Status(_) => return vec![],
+            RuleExpectation::Detailed(detailed) => detailed,
+        };
+
+        let mut mismatches = vec![];
+        if let Some(expected) = &detailed.paths {
+            let expected = expected.iter().cloned().collect::<BTreeSet<String>>();
+            let mut got = BTreeSet::new();
+            for each in reports {
+                each.collect_failing_paths(&mut got);
+            }
+            if expected != got {
+                mismatches.push("failing paths (- expected, + evaluated):".to_string());
+                for missing in expected.difference(&got) {
+                    mismatches.push(format!("  - {}", missing));
+                }
+                for unexpected in got.difference(&expected) {
+                    mismatches.push(format!("  + {}", unexpected));
+                }
+            }
+        }
+
+        if let Some(expected) = &detailed.messages {
+            let mut got = vec![];
+            for each in reports {
+                collect_custom_messages(each, &mut got);
+            }
+            for message in expected.iter().map(|each| normalize(each)) {
+                if !got.iter().any(|each| each.contains(&message)) {
+                    mismatches.push(format!("message not found: {}", message));
+                }
+            }
+            if mismatches.iter().any(|each| each.starts_with("message")) {
+                mismatches.push("evaluated messages:".to_string());
+                for each in &got {
+                    mismatches.push(format!("  {}", each));
+                }
+            }
+        }
+
+        if let Some(expected) = detailed.failed_clauses {
+            let got: usize = reports.iter().map(|each| count_failed_clauses(each)).sum();
+            if expected != got {
+                mismatches.push(format!(
+                    "failed clauses: expected = {}, evaluated = {}",
+                    expected, got
+                ));
+            }
+        }
+        mismatches
+    }
+}
+
+//
+// Custom messages are recorded with `;` separators and the indentation from the rules file,
+// they are compared with whitespace collapsed
+//
+fn normalize(message: &str) -> String {
+    message
+        .trim_matches(|c: char| c.is_whitespace() || c == ';')
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn custom_message(messages: &Messages) -> Option<String> {
+    messages
+        .custom_message
+        .as_deref()
+        .map(normalize)
+        .filter(|message| !message.is_empty())
+}
+
+fn collect_custom_messages(report: &ClauseReport<'_>, messages: &mut Vec<String>) {
+    let message = match report {
+        ClauseReport::Rule(rule) => {
+            let message = custom_message(&rule.messages);
+            for each in &rule.checks {
+                collect_custom_messages(each, messages);
+            }
+            message
+        }
+        ClauseReport::Disjunctions(disjunctions) => {
+            for each in &disjunctions.checks {
+                collect_custom_messages(each, messages);
+            }
+            None
+        }
+        ClauseReport::Block(block) => custom_message(&block.messages),
+        ClauseReport::Clause(GuardClauseReport::Unary(unary)) => custom_message(&unary.messages),
+        ClauseReport::Clause(GuardClauseReport::Binary(binary)) => custom_message(&binary.messages),
+    };
+    if let Some(message) = message {
+        if !messages.contains(&message) {
+            messages.push(message);
+        }
+    }
+}
+
+//
+// A disjunction counts as a single clause, it only fails when all of its alternatives do
+//
+fn count_failed_clauses(report: &ClauseReport<'_>) -> usize {
+    match report {
+        ClauseReport::Rule(rule) => rule.checks.iter().map(count_failed_clauses).sum(),
+        ClauseReport::Disjunctions(_) | ClauseReport::Block(_) | ClauseReport::Clause(_) => 1,
+    }
+}
+
+#[cfg(test)]
+#[path = "expectations_tests.rs"]
+mod expectations_tests;
@@ -0,0 +1,103 @@
+use super::*;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::{root_scope, simplifed_json_from_root};
+use crate::rules::exprs::RulesFile;
+use crate::rules::path_value::PathAwareValue;
+
+const RULES: &str = r#"
+let buckets = Resources.*[ Type == 'AWS::S3::Bucket' ]
+
+rule S3_ENCRYPTION when %buckets !empty {
+  %buckets.Properties.BucketEncryption exists
+  <<
+    S3 Bucket must enable server-side encryption
+  >>
+}
+"#;
+
+const DATA: &str = r#"
+Resources:
+  First:
+    Type: AWS::S3::Bucket
+    Properties: {}
+  Second:
+    Type: AWS::S3::Bucket
+    Properties: {}
+"#;
+
+fn expectation(yaml: &str) -> RuleExpectation {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn mismatches_for(expectation: &RuleExpectation) -> Result<Vec<String>> {
+    let rules = RulesFile::try_from(RULES)?;
+    let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(DATA)?)?;
+    let mut scope = root_scope(&rules, &value)?;
+    eval_rules_file(&rules, &mut scope)?;
+    let top = scope.reset_recorder().extract();
+    let report = simplifed_json_from_root(&top)?;
+    let reports = report
+        .not_compliant
+        .iter()
+        .filter(|each| each.rule().is_some_and(|r| r.name == "S3_ENCRYPTION"))
+        .collect::<Vec<_>>();
+    Ok(expectation.mismatches(&reports))
+}
+
+#[test]
+fn test_status_only_expectation() -> Result<()> {
+    let status = expectation("FAIL");
+    assert_eq!(status.status()?, Status::FAIL);
+    assert!(!status.has_details());
+    assert!(mismatches_for(&status)?.is_empty());
+
+    let detailed = expectation("status: SKIP");
+    assert_eq!(detailed.status()?, Status::SKIP);
+    assert!(!detailed.has_details());
+    assert!(serde_yaml::from_str::<RuleExpectation>("{status: FAIL, path: []}").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_matching_details() -> Result<()> {
+    let detailed = expectation(
+        r#"
+status: FAIL
+paths:
+  - /Resources/First/Properties
+  - /Resources/Second/Properties
+messages: [must enable server-side encryption]
+failed_clauses: 2
+"#,
+    );
+    assert!(detailed.has_details());
+    assert_eq!(mismatches_for(&detailed)?, Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_mismatched_details() -> Result<()> {
+    let detailed = expectation(
+        r#"
+status: FAIL
+paths:
+  - /Resources/First/Properties
+  - /Resources/Third/Properties
+messages: [must block public access]
+failed_clauses: 1
+"#,
+    );
+    assert_eq!(
+        mismatches_for(&detailed)?,
+        vec![
+            "failing paths (- expected, + evaluated):",
+            "  - /Resources/Third/Properties",
+            "  + /Resources/Second/Properties",
+            "message not found: must block public access",
+            "evaluated messages:",
+            "  S3 Bucket must enable server-side encryption",
+            "failed clauses: expected = 1, evaluated = 2",
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::files::read_file_content;
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::{Result, Status};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum FileReferences {
+    One(String),
+    Many(Vec<String>),
+}
+
+///
+/// The input for a test case. The document under test is either inlined as `input` or read
+/// from `input_file`, then a JSON merge patch (RFC 7386) in `merge` and a JSON patch
+/// (RFC 6902) in `patch` are applied on top of it, in that order. `input_parameters` are
+/// merged into the document the same way `validate --input-parameters` does. File references
+/// are relative to the directory of the test file.
+///
+/// ```yaml
+/// - name: bucket without encryption
+///   input_file: ../templates/s3.yaml
+///   input_parameters: ../templates/params.yaml
+///   patch:
+///     - op: remove
+///       path: /Resources/Bucket/Properties/BucketEncryption
+///   expectations:
+///     rules:
+///       S3_BUCKET_ENCRYPTION: FAIL
+/// ```
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct TestInput {
+    #[serde(default)]
+    pub(crate) input: Option<serde_yaml::Value>,
+    #[serde(default)]
+    pub(crate) input_file: Option<String>,
+    #[serde(default)]
+    pub(crate) merge: Option<serde_json::Value>,
+    #[serde(default)]
+    pub(crate) patch: Option<json_patch::Patch>,
+    #[serde(default)]
+    pub(crate) input_parameters: Option<FileReferences>,
+}
+
+impl TestInput {
+    pub(crate) fn resolve(self, test_file_dir: &Path) -> Result<PathAwareValue> {
+        let root = match (self.input, &self.input_file) {
+            (Some(_), Some(file)) => {
+                return Err(Error::new(ErrorKind::ParseError(format!(
+                    "Test specifies both input and input_file {}, only one is allowed",
+                    file
+                ))))
+            }
+            (None, None) => {
+                return Err(Error::new(ErrorKind::ParseError(
+                    "Test must specify one of input or input_file".to_string(),
+                )))
+            }
+            (Some(input), None) => PathAwareValue::try_from(input)?,
+            (None, Some(file)) => load(test_file_dir, file)?,
+        };
+
+        let root = if self.merge.is_some() || self.patch.is_some() {
+            let (_, mut document): (String, serde_json::Value) = (&root).try_into()?;
+            if let Some(merge) = &self.merge {
+                json_patch::merge(&mut document, merge);
+            }
+            if let Some(patch) = &self.patch {
+                json_patch::patch(&mut document, patch).map_err(|e| {
+                    Error::new(ErrorKind::IncompatibleError(format!(
+                        "Unable to apply patch to test input, Error {}",
+                        e
+                    )))
+                })?;
+            }
+            PathAwareValue::try_from(document)?
+        } else {
+            root
+        };
+
+        let files = match &self.input_parameters {
+            None => return Ok(root),
+            Some(FileReferences::One(file)) => vec![file],
+            Some(FileReferences::Many(files)) => files.iter().collect(),
+        };
+        let mut parameters: Option<PathAwareValue> = None;
+        for file in files {
+            let value = load(test_file_dir, file)?;
+            parameters = match parameters {
+                Some(current) => Some(current.merge(value)?),
+                None => Some(value),
+            };
+        }
+        match parameters {
+            Some(parameters) => parameters.merge(root),
+            None => Ok(root),
+        }
+    }
+}
+
+///
+/// The rule statuses the fuzz command allows for every input it generates from a test case, e.g.
+///
+/// ```yaml
+/// - name: bucket with encryption
+///   input_file: ../templates/s3.yaml
+///   expectations:
+///     rules:
+///       S3_BUCKET_ENCRYPTION: PASS
+///   fuzz:
+///     rules:
+///       S3_BUCKET_ENCRYPTION: [PASS, FAIL]
+/// ```
+///
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct FuzzExpectations {
+    pub(crate) rules: HashMap<String, AllowedStatuses>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum AllowedStatuses {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl AllowedStatuses {
+    pub(crate) fn statuses(&self) -> Result<Vec<Status>> {
+        match self {
+            AllowedStatuses::One(status) => Ok(vec![Status::try_from(status.as_str())?]),
+            AllowedStatuses::Many(statuses) => statuses
+                .iter()
+                .map(|each| Status::try_from(each.as_str()))
+                .collect(),
+        }
+    }
+}
+
+fn load(test_file_dir: &Path, file: &str) -> Result<PathAwareValue> {
+    let path = test_file_dir.join(file);
+    let content = match File::open(&path) {
+        Ok(file) => read_file_content(file)?,
+        Err(_) => {
+            return Err(Error::new(ErrorKind::FileNotFoundError(format!(
+                "{}",
+                path.display()
+            ))))
+        }
+    };
+    get_path_aware_value_from_data(&content)
+}
+
+#[cfg(test)]
+#[path = "fixtures_tests.rs"]
+mod fixtures_tests;
@@ -0,0 +1,131 @@
+use super::*;
+use std::path::PathBuf;
+
+struct FixtureDir(PathBuf);
+
+impl FixtureDir {
+    fn new(name: &str) -> FixtureDir {
+        let dir =
+            std::env::temp_dir().join(format!("guard-fixtures-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("tests")).unwrap();
+        std::fs::write(
+            dir.join("template.yaml"),
+            r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketName: !Ref Name
+      Tags: [{Key: team, Value: blue}]
+"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("params.yaml"), "PARAMETERS:\n  Stage: dev\n").unwrap();
+        FixtureDir(dir)
+    }
+
+    fn tests(&self) -> PathBuf {
+        self.0.join("tests")
+    }
+}
+
+impl Drop for FixtureDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn resolve(spec: &str, dir: &Path) -> Result<serde_json::Value> {
+    let input: TestInput = serde_yaml::from_str(spec)?;
+    let value = input.resolve(dir)?;
+    let (_, json): (String, serde_json::Value) = (&value).try_into()?;
+    Ok(json)
+}
+
+#[test]
+fn test_inline_input() -> Result<()> {
+    let json = resolve("input: {Resources: {}}", Path::new("."))?;
+    assert_eq!(json, serde_json::json!({"Resources": {}}));
+
+    assert!(resolve("name: no input", Path::new(".")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_input_file_with_overlays() -> Result<()> {
+    let fixtures = FixtureDir::new("overlays");
+    let json = resolve("input_file: ../template.yaml", &fixtures.tests())?;
+    assert_eq!(
+        json,
+        serde_json::json!({"Resources": {"Bucket": {
+            "Type": "AWS::S3::Bucket",
+            "Properties": {"BucketName": {"Ref": "Name"}, "Tags": [{"Key": "team", "Value": "blue"}]}
+        }}})
+    );
+
+    let json = resolve(
+        r#"
+input_file: ../template.yaml
+merge:
+  Resources:
+    Bucket:
+      Properties:
+        BucketName: null
+        VersioningConfiguration: {Status: Enabled}
+patch:
+  - {op: replace, path: /Resources/Bucket/Properties/Tags/0/Value, value: red}
+  - {op: add, path: /Resources/Bucket/Metadata, value: {Owner: infra}}
+"#,
+        &fixtures.tests(),
+    )?;
+    assert_eq!(
+        json,
+        serde_json::json!({"Resources": {"Bucket": {
+            "Type": "AWS::S3::Bucket",
+            "Properties": {
+                "Tags": [{"Key": "team", "Value": "red"}],
+                "VersioningConfiguration": {"Status": "Enabled"}
+            },
+            "Metadata": {"Owner": "infra"}
+        }}})
+    );
+
+    let failed = resolve(
+        r#"
+input_file: ../template.yaml
+patch: [{op: remove, path: /Resources/Missing}]
+"#,
+        &fixtures.tests(),
+    );
+    assert!(failed.is_err());
+
+    let both = resolve(
+        "{input: {}, input_file: ../template.yaml}",
+        &fixtures.tests(),
+    );
+    assert!(both.is_err());
+
+    let missing = resolve("input_file: ../none.yaml", &fixtures.tests());
+    assert!(missing.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_input_parameters() -> Result<()> {
+    let fixtures = FixtureDir::new("parameters");
+    let json = resolve(
+        "{input: {Resources: {}}, input_parameters: ../params.yaml}",
+        &fixtures.tests(),
+    )?;
+    assert_eq!(
+        json,
+        serde_json::json!({"PARAMETERS": {"Stage": "dev"}, "Resources": {}})
+    );
+
+    let conflicting = resolve(
+        "{input: {PARAMETERS: {}}, input_parameters: [../params.yaml]}",
+        &fixtures.tests(),
+    );
+    assert!(conflicting.is_err());
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use similar::TextDiff;
+
+use crate::commands::exit_code::ExitCodeTracker;
+use crate::commands::files::read_file_content;
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::{root_scope, simplifed_json_from_root};
+use crate::rules::exprs::RulesFile;
+use crate::rules::{Result, Status};
+
+///
+/// Snapshot testing compares the structured report of evaluating the rules against each data
+/// file with the report stored from an earlier run. Snapshots for `rules.guard` are kept in
+/// `snapshots/rules/` next to the rules file, one JSON file per data file named after its path
+/// relative to the test data argument.
+///
+pub(crate) struct Snapshots<'a> {
+    rules: &'a RulesFile<'a>,
+    dir: PathBuf,
+    data_root: &'a Path,
+    update: bool,
+}
+
+impl<'a> Snapshots<'a> {
+    pub(crate) fn new(
+        rules_file: &Path,
+        rules: &'a RulesFile<'a>,
+        data_root: &'a Path,
+        update: bool,
+    ) -> Snapshots<'a> {
+        let stem = rules_file
+            .file_stem()
+            .map_or(String::new(), |s| s.to_string_lossy().to_string());
+        let dir = rules_file
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("snapshots")
+            .join(stem);
+        Snapshots {
+            rules,
+            dir,
+            data_root,
+            update,
+        }
+    }
+
+    pub(crate) fn check(
+        &self,
+        data_files: &[PathBuf],
+        exit_code: &mut ExitCodeTracker,
+    ) -> Result<()> {
+        for data_file in data_files {
+            let name = self.name_of(data_file);
+            let evaluated = match self.evaluate(data_file, &name) {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("Error evaluating data file {}, {}", data_file.display(), e);
+                    exit_code.record_evaluation_error();
+                    continue;
+                }
+            };
+
+            let path = self.dir.join(format!("{}.json", name));
+            let stored = std::fs::read_to_string(&path).ok();
+            match stored {
+                Some(stored) if stored == evaluated => {
+                    println!("Snapshot {}: PASS", name);
+                    exit_code.record_status(Status::PASS);
+                }
+
+                Some(_) | None if self.update => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&path, &evaluated)?;
+                    println!("Snapshot {}: written to {}", name, path.display());
+                    exit_code.record_status(Status::PASS);
+                }
+
+                Some(stored) => {
+                    println!(
+                        "Snapshot {}: FAIL, differences (- snapshot, + evaluated):",
+                        name
+                    );
+                    print!("{}", unified_diff(&stored, &evaluated));
+                    exit_code.record_status(Status::FAIL);
+                }
+
+                None => {
+                    println!(
+                        "Snapshot {}: FAIL, no snapshot at {}, run with --update-snapshots to write it",
+                        name,
+                        path.display()
+                    );
+                    exit_code.record_status(Status::FAIL);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn name_of(&self, data_file: &Path) -> String {
+        let relative = match data_file.strip_prefix(self.data_root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => Path::new(data_file.file_name().unwrap_or_default()),
+        };
+        relative
+            .components()
+            .map(|each| each.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn evaluate(&self, data_file: &Path, name: &str) -> Result<String> {
+        let content = read_file_content(File::open(data_file)?)?;
+        let root = get_path_aware_value_from_data(&content)?;
+        let mut scope = root_scope(self.rules, &root)?;
+        eval_rules_file(self.rules, &mut scope)?;
+        let top = scope.reset_recorder().extract();
+        let mut report = simplifed_json_from_root(&top)?;
+        report.name = name;
+        let mut value = serde_json::to_value(&report)?;
+        for set in ["compliant", "not_applicable"] {
+            if let Some(Value::Array(names)) = value.get_mut(set) {
+                names.sort_by(|first, second| first.as_str().cmp(&second.as_str()));
+            }
+        }
+        Ok(format!(
+            "{}\n",
+            serde_json::to_string_pretty(&sorted(value))?
+        ))
+    }
+}
+
+//
+// The report has hash maps and sets, keys are sorted to keep snapshots stable across runs
+//
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(first, _), (second, _)| first.cmp(second));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sorted(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sorted).collect()),
+        rest => rest,
+    }
+}
+
+pub(crate) fn unified_diff(stored: &str, evaluated: &str) -> String {
+    TextDiff::from_lines(stored, evaluated)
+        .unified_diff()
+        .context_radius(3)
+        .header("snapshot", "evaluated")
+        .to_string()
+}
+
+#[cfg(test)]
+#[path = "snapshot_tests.rs"]
+mod snapshot_tests;
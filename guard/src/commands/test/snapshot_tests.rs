@@ -0,0 +1,127 @@
+use super::*;
+use crate::commands::exit_code::fail_on_from;
+use crate::commands::TEST_FAILURE_STATUS_CODE;
+use std::convert::TryFrom;
+
+const RULES: &str = r#"
+rule S3_VERSIONING {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.VersioningConfiguration.Status == 'Enabled'
+}
+
+rule S3_ENCRYPTION {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.BucketEncryption exists
+}
+"#;
+
+struct SnapshotDir(PathBuf);
+
+impl SnapshotDir {
+    fn new(name: &str) -> SnapshotDir {
+        let dir =
+            std::env::temp_dir().join(format!("guard-snapshots-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("s3.guard"), RULES).unwrap();
+        SnapshotDir(dir)
+    }
+
+    fn data(&self, name: &str, content: &str) -> PathBuf {
+        let path = self.0.join("data").join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn check(&self, files: &[PathBuf], update: bool) -> Result<i32> {
+        let rules = RulesFile::try_from(RULES)?;
+        let data_root = self.0.join("data");
+        let snapshots = Snapshots::new(&self.0.join("s3.guard"), &rules, &data_root, update);
+        let mut exit_code = ExitCodeTracker::new(
+            fail_on_from(None::<std::iter::Empty<&str>>),
+            TEST_FAILURE_STATUS_CODE,
+        );
+        snapshots.check(files, &mut exit_code)?;
+        Ok(exit_code.exit_code())
+    }
+}
+
+impl Drop for SnapshotDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+const VERSIONED: &str = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      VersioningConfiguration: {Status: Enabled}
+"#;
+
+#[test]
+fn test_snapshot_lifecycle() -> Result<()> {
+    let dir = SnapshotDir::new("lifecycle");
+    let files = vec![dir.data("bucket.yaml", VERSIONED)];
+
+    assert_eq!(dir.check(&files, false)?, TEST_FAILURE_STATUS_CODE);
+    assert_eq!(dir.check(&files, true)?, 0);
+    let snapshot = dir.0.join("snapshots/s3/bucket.yaml.json");
+    let stored: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&snapshot)?)?;
+    assert_eq!(stored["name"], "bucket.yaml");
+    assert_eq!(stored["status"], "FAIL");
+    assert_eq!(stored["compliant"], serde_json::json!(["S3_VERSIONING"]));
+    assert_eq!(dir.check(&files, false)?, 0);
+
+    dir.data(
+        "bucket.yaml",
+        &format!("{}      BucketEncryption: {{}}\n", VERSIONED),
+    );
+    assert_eq!(dir.check(&files, false)?, TEST_FAILURE_STATUS_CODE);
+    assert_eq!(dir.check(&files, true)?, 0);
+    let stored: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&snapshot)?)?;
+    assert_eq!(stored["status"], "PASS");
+    assert_eq!(
+        stored["compliant"],
+        serde_json::json!(["S3_ENCRYPTION", "S3_VERSIONING"])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_names() {
+    let rules = RulesFile::try_from(RULES).unwrap();
+    let snapshots = Snapshots::new(
+        Path::new("rules/s3.guard"),
+        &rules,
+        Path::new("templates"),
+        false,
+    );
+    assert_eq!(snapshots.dir, Path::new("rules/snapshots/s3"));
+    assert_eq!(
+        snapshots.name_of(Path::new("templates/prod/bucket.yaml")),
+        "prod/bucket.yaml"
+    );
+    assert_eq!(snapshots.name_of(Path::new("templates")), "templates");
+    assert_eq!(
+        snapshots.name_of(Path::new("other/bucket.yaml")),
+        "bucket.yaml"
+    );
+}
+
+#[test]
+fn test_unified_diff() {
+    let diff = unified_diff(
+        "{\n  \"status\": \"FAIL\"\n}\n",
+        "{\n  \"status\": \"PASS\"\n}\n",
+    );
+    assert_eq!(
+        diff,
+        r#"--- snapshot
++++ evaluated
+@@ -1,3 +1,3 @@
+ {
+-  "status": "FAIL"
++  "status": "PASS"
+ }
+"#
+    );
+}
@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::commands::evaluate::{evaluate, Outcome};
+use crate::commands::files::{
+    get_files_with_filter, read_file_content, regular_ordering, IgnoreFilter,
+};
+use crate::commands::test::discovery::{selected, TestPatterns};
+use crate::commands::test::fixtures::FileReferences;
+use crate::commands::test::{guard_files, is_test_file, TestSpec};
+use crate::commands::watch::{absolute, display, RulesCache, Session, Statuses};
+use crate::rules::{Result, Status};
+
+pub(crate) enum Tests<'a> {
+    File {
+        rules: &'a str,
+        data: &'a str,
+    },
+    Dir {
+        root: &'a str,
+        patterns: TestPatterns,
+    },
+}
+
+///
+/// Watch mode for `test`, results are cached for each pair of rules file and test file. A
+/// changed rules file is re-parsed and its tests re-run, a changed test file is re-run against
+/// its rules file, and a changed `input_file` or `input_parameters` file re-runs the test files
+/// referencing it.
+///
+pub(crate) struct TestSession<'a> {
+    tests: Tests<'a>,
+    ignore: &'a IgnoreFilter,
+    filters: Vec<&'a str>,
+    pairs: Vec<(PathBuf, Vec<PathBuf>)>,
+    parsed: RulesCache,
+    references: HashMap<PathBuf, HashSet<PathBuf>>,
+    evaluations: HashMap<(PathBuf, PathBuf), Vec<(String, String)>>,
+}
+
+impl<'a> TestSession<'a> {
+    pub(crate) fn new(
+        tests: Tests<'a>,
+        ignore: &'a IgnoreFilter,
+        filters: Vec<&'a str>,
+    ) -> Result<TestSession<'a>> {
+        let mut session = TestSession {
+            tests,
+            ignore,
+            filters,
+            pairs: vec![],
+            parsed: RulesCache::default(),
+            references: HashMap::new(),
+            evaluations: HashMap::new(),
+        };
+        session.select()?;
+        Ok(session)
+    }
+
+    fn select(&mut self) -> Result<()> {
+        self.pairs = match &self.tests {
+            Tests::File { rules, data } => {
+                let test_files =
+                    get_files_with_filter(data, regular_ordering, self.ignore, is_test_file)?;
+                vec![(
+                    absolute(Path::new(rules)),
+                    test_files.iter().map(|each| absolute(each)).collect(),
+                )]
+            }
+            Tests::Dir { root, patterns } => guard_files(root, patterns, self.ignore)?
+                .into_iter()
+                .map(|guard_file| {
+                    (
+                        absolute(guard_file.file.path()),
+                        guard_file
+                            .test_files
+                            .iter()
+                            .map(|each| absolute(each.path()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        };
+        Ok(())
+    }
+
+    fn run(&mut self, rules_file: &Path, test_file: &Path) -> Vec<(String, String)> {
+        let rules = match self.parsed.get(rules_file) {
+            Ok(rules) => rules,
+            Err(e) => return vec![(String::new(), format!("ERROR {}", e))],
+        };
+        let specs = match File::open(test_file)
+            .and_then(read_file_content)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_yaml::from_str::<Vec<TestSpec>>(&content).or_else(|_| {
+                    serde_json::from_str::<Vec<TestSpec>>(&content).map_err(|e| e.to_string())
+                })
+            }) {
+            Ok(specs) => specs,
+            Err(e) => return vec![(String::new(), format!("ERROR {}", e))],
+        };
+
+        let dir = test_file.parent().unwrap_or_else(|| Path::new(""));
+        let mut results = TestFileResults::default();
+        for (index, mut spec) in specs.into_iter().enumerate() {
+            if !selected(&self.filters, spec.name.as_deref()) {
+                continue;
+            }
+            results.reference(dir, &spec);
+            let outcome = match spec.resolve_input(dir) {
+                Ok(input) => evaluate(&rules, &input),
+                Err(e) => Outcome::Error(e.to_string()),
+            };
+            results.add(index, &spec, outcome);
+        }
+        results.register(test_file, &mut self.references)
+    }
+
+    ///
+    /// Caches the results of a test file from the run before watching, so that watching starts
+    /// from them instead of running the tests again
+    ///
+    pub(crate) fn seed(&mut self, rules_file: &Path, test_file: &Path, results: TestFileResults) {
+        let test_file = absolute(test_file);
+        let results = results.register(&test_file, &mut self.references);
+        self.evaluations
+            .insert((absolute(rules_file), test_file), results);
+    }
+}
+
+///
+/// The results for the test cases in a test file as they are compared between runs, along with
+/// the files the test cases read their inputs from
+///
+#[derive(Default)]
+pub(crate) struct TestFileResults {
+    references: Vec<PathBuf>,
+    results: Vec<(String, String)>,
+}
+
+impl TestFileResults {
+    ///
+    /// Records the input files of a test case, before its input is resolved
+    ///
+    pub(crate) fn reference(&mut self, dir: &Path, spec: &TestSpec) {
+        self.references.extend(
+            spec.input_file
+                .iter()
+                .chain(match &spec.input_parameters {
+                    Some(FileReferences::One(file)) => vec![file],
+                    Some(FileReferences::Many(files)) => files.iter().collect(),
+                    None => vec![],
+                })
+                .map(|file| absolute(&dir.join(file))),
+        );
+    }
+
+    ///
+    /// Adds the result of each rule of the test case at `index` in the test file against its
+    /// expectations
+    ///
+    pub(crate) fn add(&mut self, index: usize, spec: &TestSpec, outcome: Outcome) {
+        let case = match &spec.name {
+            Some(name) => format!("#{} {}", index + 1, name),
+            None => format!("#{}", index + 1),
+        };
+        let statuses = match outcome {
+            Outcome::Statuses(statuses) => statuses,
+            Outcome::Error(e) => {
+                self.results.push((case, format!("ERROR {}", e)));
+                return;
+            }
+            Outcome::Panic(message) => {
+                self.results.push((case, format!("PANIC {}", message)));
+                return;
+            }
+        };
+
+        let by_rule = statuses.into_iter().fold(
+            BTreeMap::<String, Vec<Status>>::new(),
+            |mut acc, (rule, status)| {
+                acc.entry(rule).or_default().push(status);
+                acc
+            },
+        );
+        for (rule, statuses) in by_rule {
+            let expected = spec
+                .expectations
+                .rules
+                .get(&rule)
+                .map(|expectation| expectation.status());
+            let result = match expected {
+                Some(Ok(expected)) if matches(expected, &statuses) => format!("{:?}", expected),
+                Some(Ok(expected)) => format!("{:?}, expected {:?}", statuses[0], expected),
+                Some(Err(e)) => format!("ERROR {}", e),
+                None => format!("{:?}, no expectation", statuses[0]),
+            };
+            self.results.push((format!("{} {}", case, rule), result));
+        }
+    }
+
+    fn register(
+        self,
+        test_file: &Path,
+        references: &mut HashMap<PathBuf, HashSet<PathBuf>>,
+    ) -> Vec<(String, String)> {
+        for each in self.references {
+            references
+                .entry(each)
+                .or_default()
+                .insert(test_file.to_path_buf());
+        }
+        self.results
+    }
+}
+
+//
+// Same as the test command, a SKIP expectation needs all evaluations of the rule to be
+// skipped, other expectations need any of them to match
+//
+fn matches(expected: Status, statuses: &[Status]) -> bool {
+    match expected {
+        Status::SKIP => statuses.iter().all(|each| *each == Status::SKIP),
+        rest => statuses.contains(&rest),
+    }
+}
+
+impl<'a> Session for TestSession<'a> {
+    fn watched(&self) -> Vec<PathBuf> {
+        let mut watched = match &self.tests {
+            Tests::File { rules, data } => {
+                vec![absolute(Path::new(rules)), absolute(Path::new(data))]
+            }
+            Tests::Dir { root, .. } => vec![absolute(Path::new(root))],
+        };
+        watched.extend(self.references.keys().cloned());
+        watched
+    }
+
+    fn changed(&mut self, paths: &[PathBuf]) -> Result<bool> {
+        let roots = self.watched();
+        let mut relevant = false;
+        for path in paths {
+            let known = self.parsed.invalidate(path)
+                || self.pairs.iter().any(|(rules_file, test_files)| {
+                    rules_file == path || test_files.contains(path)
+                });
+            let dependents = self.references.remove(path).unwrap_or_default();
+            self.evaluations.retain(|(rules_file, test_file), _| {
+                rules_file != path && test_file != path && !dependents.contains(test_file)
+            });
+            relevant |= known
+                || !dependents.is_empty()
+                || roots
+                    .iter()
+                    .any(|root| path.starts_with(root) && root.is_dir());
+        }
+        if relevant {
+            self.select()?;
+        }
+        Ok(relevant)
+    }
+
+    fn statuses(&mut self) -> Result<(Statuses, usize)> {
+        let mut statuses = Statuses::new();
+        let mut evaluated = 0;
+        for (rules_file, test_files) in self.pairs.clone() {
+            if test_files.is_empty() {
+                statuses.insert(display(&rules_file), "UNTESTED".to_string());
+                continue;
+            }
+            for test_file in test_files {
+                let key = (rules_file.clone(), test_file.clone());
+                if !self.evaluations.contains_key(&key) {
+                    let results = self.run(&rules_file, &test_file);
+                    evaluated += 1;
+                    self.evaluations.insert(key.clone(), results);
+                }
+                for (case, result) in &self.evaluations[&key] {
+                    let name = if case.is_empty() {
+                        display(&test_file)
+                    } else {
+                        format!("{} {}", display(&test_file), case)
+                    };
+                    statuses.insert(name, result.clone());
+                }
+            }
+        }
+        Ok((statuses, evaluated))
+    }
+}
+
+#[cfg(test)]
+#[path = "watch_tests.rs"]
+mod watch_tests;
@@ -0,0 +1,153 @@
+use super::*;
+
+const RULES: &str = r#"rule S3_LOGGING when Resources.*[ Type == 'AWS::S3::Bucket' ] !empty {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.LoggingConfiguration exists
+}
+"#;
+
+const TESTS: &str = r#"
+- name: logged
+  input_file: ../bucket.yaml
+  expectations:
+    rules:
+      S3_LOGGING: PASS
+- name: empty
+  input: {}
+  expectations:
+    rules:
+      S3_LOGGING: SKIP
+"#;
+
+struct WatchDir(PathBuf);
+
+impl WatchDir {
+    fn new(name: &str) -> WatchDir {
+        let dir =
+            std::env::temp_dir().join(format!("guard-test-watch-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("tests")).unwrap();
+        std::fs::write(dir.join("s3.guard"), RULES).unwrap();
+        std::fs::write(dir.join("tests/s3_tests.yaml"), TESTS).unwrap();
+        std::fs::write(
+            dir.join("bucket.yaml"),
+            "Resources: {B: {Type: AWS::S3::Bucket, Properties: {LoggingConfiguration: {}}}}",
+        )
+        .unwrap();
+        WatchDir(absolute(&dir))
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}", self.0.join(name).display())
+    }
+}
+
+impl Drop for WatchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_statuses_compare_with_expectations() -> Result<()> {
+    let dir = WatchDir::new("expectations");
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let (rules, data) = (dir.path("s3.guard"), dir.path("tests"));
+    let mut session = TestSession::new(
+        Tests::File {
+            rules: &rules,
+            data: &data,
+        },
+        &ignore,
+        vec![],
+    )?;
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    let results = statuses.values().cloned().collect::<Vec<_>>();
+    assert_eq!(results, vec!["PASS", "SKIP"]);
+
+    std::fs::write(
+        dir.0.join("bucket.yaml"),
+        "Resources: {B: {Type: AWS::S3::Bucket}}",
+    )
+    .unwrap();
+    assert!(session.changed(&[dir.0.join("bucket.yaml")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    let results = statuses.values().cloned().collect::<Vec<_>>();
+    assert_eq!(results, vec!["FAIL, expected PASS", "SKIP"]);
+    Ok(())
+}
+
+#[test]
+fn test_dir_session_filters_and_untested() -> Result<()> {
+    let dir = WatchDir::new("dir");
+    std::fs::write(dir.0.join("other.guard"), RULES).unwrap();
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let root = format!("{}", dir.0.display());
+    let mut session = TestSession::new(
+        Tests::Dir {
+            root: &root,
+            patterns: TestPatterns::new(None::<std::iter::Empty<&str>>),
+        },
+        &ignore,
+        vec!["empty"],
+    )?;
+    let (statuses, _) = session.statuses()?;
+    assert_eq!(statuses.len(), 2);
+    assert!(statuses
+        .iter()
+        .any(|(key, status)| key.ends_with("other.guard") && status == "UNTESTED"));
+    assert!(statuses
+        .iter()
+        .any(|(key, status)| key.ends_with("#2 empty S3_LOGGING") && status == "SKIP"));
+
+    std::fs::write(dir.0.join("tests/other_tests.yaml"), TESTS).unwrap();
+    assert!(session.changed(&[dir.0.join("tests/other_tests.yaml")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    assert!(statuses.keys().all(|key| !key.ends_with("other.guard")));
+    Ok(())
+}
+
+#[test]
+fn test_seeded_results_track_input_files() -> Result<()> {
+    let dir = WatchDir::new("seeded");
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let (rules, data) = (dir.path("s3.guard"), dir.path("tests"));
+    let mut session = TestSession::new(
+        Tests::File {
+            rules: &rules,
+            data: &data,
+        },
+        &ignore,
+        vec![],
+    )?;
+    let mut results = TestFileResults::default();
+    for (index, spec) in serde_yaml::from_str::<Vec<TestSpec>>(TESTS)?
+        .iter()
+        .enumerate()
+    {
+        results.reference(&dir.0.join("tests"), spec);
+        results.add(
+            index,
+            spec,
+            Outcome::Statuses(vec![("S3_LOGGING".to_string(), Status::SKIP)]),
+        );
+    }
+    session.seed(
+        &dir.0.join("s3.guard"),
+        &dir.0.join("tests/s3_tests.yaml"),
+        results,
+    );
+
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 0);
+    let results = statuses.values().cloned().collect::<Vec<_>>();
+    assert_eq!(results, vec!["SKIP, expected PASS", "SKIP"]);
+
+    assert!(session.changed(&[dir.0.join("bucket.yaml")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    let results = statuses.values().cloned().collect::<Vec<_>>();
+    assert_eq!(results, vec!["PASS", "SKIP"]);
+    Ok(())
+}
@@ -4,25 +4,34 @@ use std::fmt::Debug;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
+use std::sync::Mutex;
 
 use clap::{App, Arg, ArgGroup, ArgMatches};
 use colored::*;
 use enumflags2::BitFlags;
 use serde::Deserialize;
 
-use Type::CFNTemplate;
-
 use crate::command::Command;
 use crate::commands::aws_meta_appender::MetadataAppender;
-use crate::commands::files::{alpabetical, iterate_over, last_modified};
+use crate::commands::evaluate::{rule_statuses, Outcome};
+use crate::commands::exit_code::{fail_on_from, ExitCodeTracker, FAIL_ON_DEFAULT, FAIL_ON_VALUES};
+use crate::commands::files::{
+    alpabetical, iterate_over, last_modified, regular_ordering, select_files, IgnoreFilter,
+};
+use crate::commands::parallel::{jobs_from, map_ordered, validate_jobs};
 use crate::commands::tracker::{StackTracker, StatusContext};
+use crate::commands::validate::baseline::{BaselineFile, BaselineMatches, BaselineTracker};
+use crate::commands::validate::explain::Explain;
 use crate::commands::validate::summary_table::SummaryType;
 use crate::commands::validate::tf::TfAware;
+use crate::commands::validate::watch::ValidateSession;
+use crate::commands::watch::watch;
 use crate::commands::{
-    ALPHABETICAL, DATA, DATA_FILE_SUPPORTED_EXTENSIONS, INPUT_PARAMETERS, LAST_MODIFIED,
-    OUTPUT_FORMAT, PAYLOAD, PREVIOUS_ENGINE, PRINT_JSON, REQUIRED_FLAGS, RULES,
+    ALPHABETICAL, BASELINE, CONFIG, DATA, DATA_FILE_SUPPORTED_EXTENSIONS, ERROR_STATUS_CODE,
+    EXCLUDE, EXPLAIN, FAILURE_STATUS_CODE, FAIL_ON, INPUT_PARAMETERS, JOBS, LAST_MODIFIED,
+    OUTPUT_FORMAT, PATH, PAYLOAD, PREVIOUS_ENGINE, PRINT_JSON, REQUIRED_FLAGS, RULES,
     RULE_FILE_SUPPORTED_EXTENSIONS, SHOW_CLAUSE_FAILURES, SHOW_SUMMARY, TYPE, VALIDATE, VERBOSE,
+    WATCH, WRITE_BASELINE,
 };
 use crate::rules::errors::{Error, ErrorKind};
 use crate::rules::eval::eval_rules_file;
@@ -32,15 +41,18 @@ use crate::rules::exprs::RulesFile;
 use crate::rules::path_value::traversal::Traversal;
 use crate::rules::path_value::PathAwareValue;
 use crate::rules::values::CmpOperator;
-use crate::rules::{Evaluate, EvaluationContext, EvaluationType, Result, Status};
+use crate::rules::{Evaluate, EvaluationContext, EvaluationType, RecordType, Result, Status};
 
+mod baseline;
 mod cfn;
 mod cfn_reporter;
 mod common;
 mod console_reporter;
+pub(crate) mod explain;
 pub(crate) mod generic_summary;
 mod summary_table;
 mod tf;
+mod watch;
 
 #[derive(Eq, Clone, Debug, PartialEq)]
 pub(crate) struct DataFile {
@@ -49,12 +61,6 @@ pub(crate) struct DataFile {
     name: String,
 }
 
-#[derive(Copy, Eq, Clone, Debug, PartialEq)]
-pub(crate) enum Type {
-    CFNTemplate,
-    Generic,
-}
-
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Eq, Clone, Debug, PartialEq)]
 pub(crate) enum OutputFormatType {
@@ -129,12 +135,14 @@ or rules files.
             .arg(Arg::with_name(RULES.0).long(RULES.0).short(RULES.1).takes_value(true)
                 .help("Provide a rules file or a directory of rules files. Supports passing multiple values by using this option repeatedly.\
                           \nExample:\n --rules rule1.guard --rules ./rules-dir1 --rules rule2.guard\
-                          \nFor directory arguments such as `rules-dir1` above, scanning is only supported for files with following extensions: .guard, .ruleset")
+                          \nFor directory arguments such as `rules-dir1` above, scanning is only supported for files with following extensions: .guard, .ruleset\
+                          \nGlob patterns like 'rules/**/*.guard' are also supported, quote them to prevent expansion by the shell")
                 .multiple(true).conflicts_with("payload"))
             .arg(Arg::with_name(DATA.0).long(DATA.0).short(DATA.1).takes_value(true)
                 .help("Provide a data file or directory of data files in JSON or YAML. Supports passing multiple values by using this option repeatedly.\
                           \nExample:\n --data template1.yaml --data ./data-dir1 --data template2.yaml\
-                          \nFor directory arguments such as `data-dir1` above, scanning is only supported for files with following extensions: .yaml, .yml, .json, .jsn, .template")
+                          \nFor directory arguments such as `data-dir1` above, scanning is only supported for files with following extensions: .yaml, .yml, .json, .jsn, .template\
+                          \nGlob patterns like 'templates/**/*.yaml' are also supported, quote them to prevent expansion by the shell")
                 .multiple(true).conflicts_with("payload"))
             .arg(Arg::with_name(INPUT_PARAMETERS.0).long(INPUT_PARAMETERS.0).short(INPUT_PARAMETERS.1).takes_value(true)
                      .help("Provide a data file or directory of data files in JSON or YAML that specifies any additional parameters to use along with data files to be used as a combined context. \
@@ -167,6 +175,37 @@ or rules files.
             .arg(Arg::with_name(PAYLOAD.0).long(PAYLOAD.0).short(PAYLOAD.1)
                 .help("Provide rules and data in the following JSON format via STDIN,\n{\"rules\":[\"<rules 1>\", \"<rules 2>\", ...], \"data\":[\"<data 1>\", \"<data 2>\", ...]}, where,\n- \"rules\" takes a list of string \
                 version of rules files as its value and\n- \"data\" takes a list of string version of data files as it value.\nWhen --payload is specified --rules and --data cannot be specified."))
+            .arg(Arg::with_name(BASELINE.0).long(BASELINE.0).short(BASELINE.1).takes_value(true).conflicts_with(PREVIOUS_ENGINE.0)
+                .help("Provide a baseline file of accepted failures. Rules whose failures are all listed in the baseline are reported as passing. \
+                      Entries with an `expires` date (YYYY-MM-DD) in the past no longer suppress failures. Expired entries and entries that matched \
+                      no failure are reported on stderr"))
+            .arg(Arg::with_name(WRITE_BASELINE.0).long(WRITE_BASELINE.0).short(WRITE_BASELINE.1).takes_value(true).conflicts_with(PREVIOUS_ENGINE.0)
+                .help("Write all failures found during this run to the specified baseline file. Expiry dates of entries already present in that file are retained"))
+            .arg(Arg::with_name(EXCLUDE.0).long(EXCLUDE.0).short(EXCLUDE.1).takes_value(true).multiple(true)
+                .help("Exclude files and directories matching the pattern when scanning directories for data, input parameter and rules files. \
+                      Patterns use gitignore syntax relative to the scanned directory, e.g. --exclude node_modules/ --exclude 'cdk.out/**'. \
+                      Patterns from .guardignore files in the current directory, each scanned directory and its subdirectories are honored as well"))
+            .arg(Arg::with_name(JOBS.0).long(JOBS.0).short(JOBS.1).takes_value(true).validator(validate_jobs).conflicts_with(PREVIOUS_ENGINE.0)
+                .help("Number of rules and data file pairs to evaluate concurrently, 0 uses all available CPUs. Defaults to 1. \
+                      Output is reported in the same order as with a single job"))
+            .arg(Arg::with_name(WATCH.0).long(WATCH.0).short(WATCH.1).requires_all(&[RULES.0, DATA.0]).conflicts_with(PAYLOAD.0)
+                .help("After validating, keep watching the rules, data and input parameter files. On each change only the changed files are \
+                      re-parsed and re-evaluated, and the rule statuses that changed since the last run are printed"))
+            .arg(Arg::with_name(CONFIG.0).long(CONFIG.0).short(CONFIG.1).takes_value(true)
+                .help("Provide a project configuration file with default arguments. When not specified, guard.toml or .guardrc is looked up \
+                      starting from the current directory and moving up through its parents. Arguments on the command line override the configuration"))
+            .arg(Arg::with_name(FAIL_ON.0).long(FAIL_ON.0).short(FAIL_ON.1).takes_value(true).use_delimiter(true).multiple(true)
+                .possible_values(&FAIL_ON_VALUES)
+                .default_value(FAIL_ON_DEFAULT)
+                .help("Controls which outcomes produce a non-zero exit code. --fail-on fail,error (default) or --fail-on fail,skip,error (also fail when rules were skipped for a data file) or --fail-on none (always exit 0).\
+                      \nExit codes: 2 rules failed to parse, 3 error during evaluation, 1 other errors, 5 rules were not compliant"))
+            .arg(Arg::with_name(EXPLAIN.0).long(EXPLAIN.0).short(EXPLAIN.1).takes_value(true)
+                .conflicts_with_all(&[PREVIOUS_ENGINE.0, BASELINE.0, WRITE_BASELINE.0, WATCH.0])
+                .help("Instead of the report, print a step by step trace of how the named rule was evaluated against each data file: \
+                      each when condition, the values each query resolved to or where it was unresolved, each comparison with its result, \
+                      and where evaluation stopped early"))
+            .arg(Arg::with_name(PATH.0).long(PATH.0).short(PATH.1).takes_value(true).requires(EXPLAIN.0)
+                .help("Only trace the checks of values at or inside this path of the data files, e.g. --path /Resources/MyBucket"))
             .group(ArgGroup::with_name(REQUIRED_FLAGS)
                 .args(&[RULES.0, PAYLOAD.0])
                 .required(true))
@@ -180,44 +219,43 @@ or rules files.
         };
 
         let empty_path = Path::new("");
+        let ignore = IgnoreFilter::new(app.values_of(EXCLUDE.0).into_iter().flatten())?;
         let mut streams: Vec<DataFile> = Vec::new();
+        let mut data_paths: Vec<PathBuf> = Vec::new();
         let data_files: Vec<DataFile> = match app.values_of(DATA.0) {
             Some(list_of_file_or_dir) => {
                 for file_or_dir in list_of_file_or_dir {
-                    validate_path(file_or_dir)?;
-                    let base = PathBuf::from_str(file_or_dir)?;
-                    for file in walkdir::WalkDir::new(base.clone()).into_iter().flatten() {
-                        if file.path().is_file() {
-                            let name = file
-                                .file_name()
-                                .to_str()
-                                .map_or("".to_string(), String::from);
-                            if has_a_supported_extension(&name, &DATA_FILE_SUPPORTED_EXTENSIONS) {
-                                let mut content = String::new();
-                                let mut reader = BufReader::new(File::open(file.path())?);
-                                reader.read_to_string(&mut content)?;
-                                let path = file.path();
-                                let relative = match path.strip_prefix(base.as_path()) {
-                                    Ok(p) => {
-                                        if p != empty_path {
-                                            format!("{}", p.display())
-                                        } else {
-                                            path.file_name().unwrap().to_str().unwrap().to_string()
-                                        }
-                                    }
-                                    Err(_) => format!("{}", path.display()),
-                                };
-                                let path_value = match get_path_aware_value_from_data(&content) {
-                                    Ok(t) => t,
-                                    Err(e) => return Err(e),
-                                };
-                                streams.push(DataFile {
-                                    name: relative,
-                                    path_value,
-                                    content,
-                                });
+                    let selection = select_files(
+                        file_or_dir,
+                        regular_ordering,
+                        &ignore,
+                        has_a_supported_data_extension,
+                    )?;
+                    let base = selection.base;
+                    for path in selection.files {
+                        let mut content = String::new();
+                        let mut reader = BufReader::new(File::open(&path)?);
+                        reader.read_to_string(&mut content)?;
+                        let relative = match path.strip_prefix(base.as_path()) {
+                            Ok(p) => {
+                                if p != empty_path {
+                                    format!("{}", p.display())
+                                } else {
+                                    path.file_name().unwrap().to_str().unwrap().to_string()
+                                }
                             }
-                        }
+                            Err(_) => format!("{}", path.display()),
+                        };
+                        let path_value = match get_path_aware_value_from_data(&content) {
+                            Ok(t) => t,
+                            Err(e) => return Err(e),
+                        };
+                        streams.push(DataFile {
+                            name: relative,
+                            path_value,
+                            content,
+                        });
+                        data_paths.push(path);
                     }
                 }
                 streams
@@ -247,28 +285,24 @@ or rules files.
             Some(list_of_file_or_dir) => {
                 let mut primary_path_value: Option<PathAwareValue> = None;
                 for file_or_dir in list_of_file_or_dir {
-                    validate_path(file_or_dir)?;
-                    let base = PathBuf::from_str(file_or_dir)?;
-                    for file in walkdir::WalkDir::new(base.clone()).into_iter().flatten() {
-                        if file.path().is_file() {
-                            let name = file
-                                .file_name()
-                                .to_str()
-                                .map_or("".to_string(), String::from);
-                            if has_a_supported_extension(&name, &DATA_FILE_SUPPORTED_EXTENSIONS) {
-                                let mut content = String::new();
-                                let mut reader = BufReader::new(File::open(file.path())?);
-                                reader.read_to_string(&mut content)?;
-                                let path_value = match get_path_aware_value_from_data(&content) {
-                                    Ok(t) => t,
-                                    Err(e) => return Err(e),
-                                };
-                                primary_path_value = match primary_path_value {
-                                    Some(current) => Some(current.merge(path_value)?),
-                                    None => Some(path_value),
-                                };
-                            }
-                        }
+                    let selection = select_files(
+                        file_or_dir,
+                        regular_ordering,
+                        &ignore,
+                        has_a_supported_data_extension,
+                    )?;
+                    for path in selection.files {
+                        let mut content = String::new();
+                        let mut reader = BufReader::new(File::open(&path)?);
+                        reader.read_to_string(&mut content)?;
+                        let path_value = match get_path_aware_value_from_data(&content) {
+                            Ok(t) => t,
+                            Err(e) => return Err(e),
+                        };
+                        primary_path_value = match primary_path_value {
+                            Some(current) => Some(current.merge(path_value)?),
+                            None => Some(path_value),
+                        };
                     }
                 }
                 primary_path_value
@@ -278,17 +312,6 @@ or rules files.
 
         let verbose = app.is_present(VERBOSE.0);
 
-        let data_type = match app.value_of(TYPE.0) {
-            Some(t) => {
-                if t == "CFNTemplate" {
-                    CFNTemplate
-                } else {
-                    Type::Generic
-                }
-            }
-            None => Type::Generic,
-        };
-
         let output_type = match app.value_of(OUTPUT_FORMAT.0) {
             Some(o) => {
                 if o == "single-line-summary" {
@@ -323,37 +346,42 @@ or rules files.
         let print_json = app.is_present(PRINT_JSON.0);
         let show_clause_failures = app.is_present(SHOW_CLAUSE_FAILURES.0);
         let new_version_eval_engine = !app.is_present(PREVIOUS_ENGINE.0);
+        let mut baseline = BaselineTracker::new(match app.value_of(BASELINE.0) {
+            Some(file) => Some(BaselineFile::load(Path::new(file))?),
+            None => None,
+        });
 
-        let mut exit_code = 0;
+        let mut exit_code =
+            ExitCodeTracker::new(fail_on_from(app.values_of(FAIL_ON.0)), FAILURE_STATUS_CODE);
+        let evaluator = Evaluator {
+            output: output_type,
+            extra_data: None,
+            verbose,
+            print_json,
+            show_clause_failures,
+            new_engine_version: new_version_eval_engine,
+            summary_table: summary_type,
+            jobs: jobs_from(app.value_of(JOBS.0)),
+            explain: app.value_of(EXPLAIN.0).map(|rule| Explain {
+                rule: rule.to_string(),
+                path: app.value_of(PATH.0).map(String::from),
+            }),
+        };
+        let mut outcomes = Vec::new();
         if app.is_present(RULES.0) {
             let list_of_file_or_dir = app.values_of(RULES.0).unwrap();
             let mut rules = Vec::new();
             for file_or_dir in list_of_file_or_dir {
-                validate_path(file_or_dir)?;
-                let base = PathBuf::from_str(file_or_dir)?;
-                if base.is_file() {
-                    rules.push(base.clone())
-                } else {
-                    for entry in walkdir::WalkDir::new(base.clone())
-                        .sort_by(cmp)
-                        .into_iter()
-                        .flatten()
-                    {
-                        if entry.path().is_file()
-                            && entry
-                                .path()
-                                .file_name()
-                                .and_then(|s| s.to_str())
-                                .map_or(false, |s| {
-                                    has_a_supported_extension(s, &RULE_FILE_SUPPORTED_EXTENSIONS)
-                                })
-                        {
-                            rules.push(entry.path().to_path_buf());
-                        }
-                    }
-                }
+                rules.extend(
+                    select_files(file_or_dir, cmp, &ignore, |entry| {
+                        entry.file_name().to_str().is_some_and(|s| {
+                            has_a_supported_extension(s, &RULE_FILE_SUPPORTED_EXTENSIONS)
+                        })
+                    })?
+                    .files,
+                );
             }
-            for each_file_content in iterate_over(&rules, |content, file| {
+            let contents: Vec<Result<(String, String)>> = iterate_over(&rules, |content, file| {
                 Ok((
                     content,
                     match file.strip_prefix(&file) {
@@ -367,48 +395,54 @@ or rules files.
                         Err(_) => format!("{}", file.display()),
                     },
                 ))
-            }) {
-                match each_file_content {
-                    Err(e) => println!("Unable read content from file {}", e),
+            })
+            .collect();
+            let inputs: Vec<RulesInput<'_>> = contents
+                .iter()
+                .map(|each_file_content| match each_file_content {
+                    Err(e) => RulesInput::Unreadable(e.to_string()),
                     Ok((file_content, rule_file_name)) => {
-                        let span =
-                            crate::rules::parser::Span::new_extra(&file_content, &rule_file_name);
-                        match crate::rules::parser::rules_file(span) {
-                            Err(e) => {
-                                println!(
-                                    "Parsing error handling rule file = {}, Error = {}",
-                                    rule_file_name.underline(),
-                                    e
-                                );
-                                println!("---");
-                                exit_code = 5;
-                                continue;
-                            }
-
-                            Ok(rules) => {
-                                match evaluate_against_data_input(
-                                    data_type,
-                                    output_type,
-                                    extra_data.clone(),
-                                    &data_files,
-                                    &rules,
-                                    &rule_file_name,
-                                    verbose,
-                                    print_json,
-                                    show_clause_failures,
-                                    new_version_eval_engine,
-                                    summary_type,
-                                )? {
-                                    Status::SKIP | Status::PASS => continue,
-                                    Status::FAIL => {
-                                        exit_code = 5;
-                                    }
-                                }
-                            }
+                        match parse_rules(file_content, rule_file_name) {
+                            Err(e) => RulesInput::Unparsable(rule_file_name, e),
+                            Ok(rules) => RulesInput::Parsed(rule_file_name, rules),
                         }
                     }
+                })
+                .collect();
+            if let Some(explain) = &evaluator.explain {
+                if let Err(message) =
+                    explain.check_defined(inputs.iter().filter_map(RulesInput::parsed))
+                {
+                    eprintln!("{}", message);
+                    return Ok(ERROR_STATUS_CODE);
                 }
             }
+            let evaluator = Evaluator {
+                extra_data,
+                ..evaluator
+            };
+            let mut evaluated = Vec::new();
+            evaluator.evaluate(
+                "rule file",
+                &inputs,
+                &data_files,
+                &mut baseline,
+                &mut evaluated,
+                &mut exit_code,
+            )?;
+            if app.is_present(WATCH.0) {
+                outcomes.extend(
+                    evaluated
+                        .into_iter()
+                        .map(|((rules_file, data_file), outcome)| {
+                            (
+                                rules[rules_file].clone(),
+                                data_paths[data_file].clone(),
+                                outcome,
+                            )
+                        }),
+                );
+            }
         } else {
             let mut context = String::new();
             let mut reader = BufReader::new(std::io::stdin());
@@ -433,44 +467,55 @@ or rules files.
                 .enumerate()
                 .map(|(i, rules)| (rules.to_string(), format!("RULES_STDIN[{}]", i + 1)))
                 .collect();
-
-            for (each_rules, location) in rules_collection {
-                match parse_rules(&each_rules, &location) {
-                    Err(e) => {
-                        println!(
-                            "Parsing error handling rules = {}, Error = {}",
-                            location.underline(),
-                            e
-                        );
-                        println!("---");
-                        exit_code = 5;
-                        continue;
-                    }
-
-                    Ok(rules) => {
-                        match evaluate_against_data_input(
-                            data_type,
-                            output_type,
-                            None,
-                            &data_collection,
-                            &rules,
-                            &location,
-                            verbose,
-                            print_json,
-                            show_clause_failures,
-                            new_version_eval_engine,
-                            summary_type,
-                        )? {
-                            Status::SKIP | Status::PASS => continue,
-                            Status::FAIL => {
-                                exit_code = 5;
-                            }
-                        }
-                    }
+            let inputs: Vec<RulesInput<'_>> = rules_collection
+                .iter()
+                .map(
+                    |(each_rules, location)| match parse_rules(each_rules, location) {
+                        Err(e) => RulesInput::Unparsable(location, e),
+                        Ok(rules) => RulesInput::Parsed(location, rules),
+                    },
+                )
+                .collect();
+            if let Some(explain) = &evaluator.explain {
+                if let Err(message) =
+                    explain.check_defined(inputs.iter().filter_map(RulesInput::parsed))
+                {
+                    eprintln!("{}", message);
+                    return Ok(ERROR_STATUS_CODE);
                 }
             }
+            evaluator.evaluate(
+                "rules",
+                &inputs,
+                &data_collection,
+                &mut baseline,
+                &mut Vec::new(),
+                &mut exit_code,
+            )?;
         }
-        Ok(exit_code)
+
+        if let Some(file) = app.value_of(WRITE_BASELINE.0) {
+            baseline.write(Path::new(file))?;
+        }
+        if app.is_present(BASELINE.0) {
+            baseline.print_report(&mut std::io::stderr())?;
+        }
+        if app.is_present(WATCH.0) {
+            let mut session = ValidateSession::new(
+                app.values_of(RULES.0).into_iter().flatten().collect(),
+                app.values_of(DATA.0).into_iter().flatten().collect(),
+                app.values_of(INPUT_PARAMETERS.0)
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                &ignore,
+            )?;
+            for (rules_file, data_file, outcome) in outcomes {
+                session.seed(&rules_file, &data_file, outcome);
+            }
+            watch(&mut session, &mut std::io::stdout())?;
+        }
+        Ok(exit_code.exit_code())
     }
 }
 
@@ -537,22 +582,32 @@ fn indent_spaces(indent: usize) {
 //
 // https://vallentin.dev/2019/05/14/pretty-print-tree
 //
-fn pprint_tree(current: &EventRecord<'_>, prefix: String, last: bool) {
+fn pprint_tree(
+    writer: &mut dyn Write,
+    current: &EventRecord<'_>,
+    prefix: String,
+    last: bool,
+) -> Result<()> {
     let prefix_current = if last { "`- " } else { "|- " };
-    println!("{}{}{}", prefix, prefix_current, current);
+    writeln!(writer, "{}{}{}", prefix, prefix_current, current)?;
 
     let prefix_child = if last { "   " } else { "|  " };
     let prefix = prefix + prefix_child;
     if !current.children.is_empty() {
         let last_child = current.children.len() - 1;
         for (i, child) in current.children.iter().enumerate() {
-            pprint_tree(child, prefix.clone(), i == last_child);
+            pprint_tree(writer, child, prefix.clone(), i == last_child)?;
         }
     }
+    Ok(())
 }
 
 pub(crate) fn print_verbose_tree(root: &EventRecord<'_>) {
-    pprint_tree(root, "".to_string(), true);
+    let _ = write_verbose_tree(&mut std::io::stdout(), root);
+}
+
+pub(crate) fn write_verbose_tree(writer: &mut dyn Write, root: &EventRecord<'_>) -> Result<()> {
+    pprint_tree(writer, root, "".to_string(), true)
 }
 
 pub(super) fn print_context(cxt: &StatusContext, depth: usize) {
@@ -790,65 +845,259 @@ impl<'r> EvaluationContext for ConsoleReporter<'r> {
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-fn evaluate_against_data_input<'r>(
-    _data_type: Type,
+///
+/// A rules file ready for evaluation, or the reason it could not be read or parsed. These are
+/// kept in input order so that errors are reported in between the evaluation output just as
+/// they are encountered.
+///
+enum RulesInput<'r> {
+    Unreadable(String),
+    Unparsable(&'r str, Error),
+    Parsed(&'r str, RulesFile<'r>),
+}
+
+impl<'r> RulesInput<'r> {
+    fn parsed(&self) -> Option<&RulesFile<'r>> {
+        match self {
+            RulesInput::Parsed(_, rules) => Some(rules),
+            _ => None,
+        }
+    }
+}
+
+///
+/// What evaluating a pair of files found besides its output, the baseline entries matching its
+/// failures and the status of each rule before the baseline was applied. Statuses are only
+/// known for the new engine outside of explain mode.
+///
+#[derive(Default)]
+struct Evaluated {
+    matches: BaselineMatches,
+    statuses: Option<Vec<(String, Status)>>,
+}
+
+///
+/// A unit of work when evaluating rules files against data files, referring to the inputs by
+/// their index.
+///
+#[derive(Clone, Copy)]
+enum Work {
+    Report(usize),
+    Evaluate(usize, usize),
+    Done(usize),
+}
+
+#[derive(Debug)]
+struct Evaluator {
     output: OutputFormatType,
     extra_data: Option<PathAwareValue>,
-    data_files: &'r Vec<DataFile>,
-    rules: &RulesFile<'_>,
-    rules_file_name: &'r str,
     verbose: bool,
     print_json: bool,
     show_clause_failures: bool,
     new_engine_version: bool,
     summary_table: BitFlags<SummaryType>,
-) -> Result<Status> {
-    let mut overall = Status::PASS;
-    let mut write_output = Box::new(std::io::stdout()) as Box<dyn Write>;
-    let generic: Box<dyn Reporter> =
-        Box::new(generic_summary::GenericSummary::new()) as Box<dyn Reporter>;
-    let tf: Box<dyn Reporter> = Box::new(TfAware::new_with(generic.as_ref())) as Box<dyn Reporter>;
-    let cfn: Box<dyn Reporter> =
-        Box::new(cfn::CfnAware::new_with(tf.as_ref())) as Box<dyn Reporter>;
-    let reporter: Box<dyn Reporter> = if summary_table.is_empty() {
-        cfn
-    } else {
-        Box::new(summary_table::SummaryTable::new(
-            summary_table,
-            cfn.as_ref(),
-        )) as Box<dyn Reporter>
-    };
-    for file in data_files {
-        if new_engine_version {
-            let each = match &extra_data {
+    jobs: usize,
+    explain: Option<Explain>,
+}
+
+impl Evaluator {
+    ///
+    /// Evaluates every parsed rules file against every data file, running up to `jobs` pairs
+    /// concurrently. Output for each pair is buffered and written in the same order as a
+    /// sequential run. As before, an evaluation error for a pair ends the evaluation of its
+    /// rules file and the remaining data files are not reported for it. Their failures are not
+    /// recorded in the baseline either, it is only updated as results are written. The outcome
+    /// of every pair that was evaluated is added to `outcomes` by the index of its rules file and
+    /// data file, for watch mode to start from.
+    ///
+    fn evaluate(
+        &self,
+        label: &str,
+        inputs: &[RulesInput<'_>],
+        data_files: &[DataFile],
+        baseline: &mut BaselineTracker,
+        outcomes: &mut Vec<((usize, usize), Outcome)>,
+        exit_code: &mut ExitCodeTracker,
+    ) -> Result<()> {
+        let mut work = Vec::with_capacity(inputs.len() * (data_files.len() + 1));
+        for (index, input) in inputs.iter().enumerate() {
+            match input {
+                RulesInput::Parsed(..) => {
+                    work.extend((0..data_files.len()).map(|data| Work::Evaluate(index, data)));
+                    work.push(Work::Done(index));
+                }
+                _ => work.push(Work::Report(index)),
+            }
+        }
+
+        let baseline = Mutex::new(baseline);
+        let mut overall = Status::PASS;
+        let mut errored = false;
+        let mut stdout = std::io::stdout();
+        map_ordered(
+            self.jobs,
+            &work,
+            |each| match *each {
+                Work::Evaluate(rules, data) => match &inputs[rules] {
+                    RulesInput::Parsed(name, rules) => {
+                        let mut output = Vec::new();
+                        let mut evaluated = Evaluated::default();
+                        let status = self.evaluate_data_file(
+                            rules,
+                            name,
+                            &data_files[data],
+                            &baseline,
+                            &mut evaluated,
+                            &mut output,
+                        );
+                        Some((status, output, evaluated))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            |each, result| {
+                if let (Work::Evaluate(rules, data), Some((status, _, evaluated))) =
+                    (*each, &result)
+                {
+                    let outcome = match status {
+                        Ok(_) => evaluated.statuses.clone().map(Outcome::Statuses),
+                        Err(e) => Some(Outcome::Error(e.to_string())),
+                    };
+                    outcomes.extend(outcome.map(|outcome| ((rules, data), outcome)));
+                }
+                match (*each, &inputs[each.index()]) {
+                    (Work::Report(_), RulesInput::Unreadable(e)) => {
+                        println!("Unable read content from file {}", e);
+                        exit_code.record_error();
+                    }
+                    (Work::Report(_), RulesInput::Unparsable(name, e)) => {
+                        println!(
+                            "Parsing error handling {} = {}, Error = {}",
+                            label,
+                            name.underline(),
+                            e
+                        );
+                        println!("---");
+                        exit_code.record_parse_error();
+                    }
+                    (Work::Evaluate(..), RulesInput::Parsed(name, _)) if !errored => {
+                        if let Some((status, output, evaluated)) = result {
+                            stdout.write_all(&output)?;
+                            baseline.lock().unwrap().record(evaluated.matches);
+                            match status {
+                                Ok(Status::FAIL) => overall = Status::FAIL,
+                                Ok(Status::SKIP) if overall != Status::FAIL => {
+                                    overall = Status::SKIP
+                                }
+                                Ok(_) => {}
+                                Err(e) => {
+                                    println!(
+                                        "Evaluation error handling {} = {}, Error = {}",
+                                        label,
+                                        name.underline(),
+                                        e
+                                    );
+                                    println!("---");
+                                    exit_code.record_evaluation_error();
+                                    errored = true;
+                                }
+                            }
+                        }
+                    }
+                    (Work::Done(_), _) => {
+                        if !errored {
+                            exit_code.record_status(overall);
+                        }
+                        overall = Status::PASS;
+                        errored = false;
+                    }
+                    _ => {}
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn evaluate_data_file(
+        &self,
+        rules: &RulesFile<'_>,
+        rules_file_name: &str,
+        file: &DataFile,
+        baseline: &Mutex<&mut BaselineTracker>,
+        evaluated: &mut Evaluated,
+        write_output: &mut dyn Write,
+    ) -> Result<Status> {
+        let generic: Box<dyn Reporter> =
+            Box::new(generic_summary::GenericSummary::new()) as Box<dyn Reporter>;
+        let tf: Box<dyn Reporter> =
+            Box::new(TfAware::new_with(generic.as_ref())) as Box<dyn Reporter>;
+        let cfn: Box<dyn Reporter> =
+            Box::new(cfn::CfnAware::new_with(tf.as_ref())) as Box<dyn Reporter>;
+        let reporter: Box<dyn Reporter> = if self.summary_table.is_empty() {
+            cfn
+        } else {
+            Box::new(summary_table::SummaryTable::new(
+                self.summary_table,
+                cfn.as_ref(),
+            )) as Box<dyn Reporter>
+        };
+        if self.new_engine_version {
+            let each = match &self.extra_data {
                 Some(data) => data.clone().merge(file.path_value.clone())?,
                 None => file.path_value.clone(),
             };
             let traversal = Traversal::from(&each);
             let mut root_scope = root_scope(rules, &each)?;
+            if let Some(explain) = &self.explain {
+                if !explain.defined_in(rules) {
+                    return Ok(Status::SKIP);
+                }
+                root_scope.explain_checks();
+                let status = eval_rules_file(rules, &mut root_scope)?;
+                let root_record = root_scope.reset_recorder().extract();
+                explain.write_trace(write_output, rules_file_name, &file.name, &root_record)?;
+                return Ok(status);
+            }
             let status = eval_rules_file(rules, &mut root_scope)?;
             let root_record = root_scope.reset_recorder().extract();
+            evaluated.statuses = Some(rule_statuses(&root_record));
+            let (root_record, found) =
+                baseline
+                    .lock()
+                    .unwrap()
+                    .filter(rules_file_name, &file.name, &root_record)?;
+            evaluated.matches = found;
+            let status = match &root_record.container {
+                Some(RecordType::FileCheck(named)) => named.status,
+                _ => status,
+            };
             reporter.report_eval(
-                &mut write_output,
+                write_output,
                 status,
                 &root_record,
                 rules_file_name,
                 &file.name,
                 &file.content,
                 &traversal,
-                output,
+                self.output,
             )?;
-            if verbose {
-                print_verbose_tree(&root_record);
-            }
-            if print_json {
-                println!("{}", serde_json::to_string_pretty(&root_record)?)
+            if self.verbose {
+                write_verbose_tree(write_output, &root_record)?;
             }
-            if status == Status::FAIL {
-                overall = Status::FAIL
+            if self.print_json {
+                writeln!(
+                    write_output,
+                    "{}",
+                    serde_json::to_string_pretty(&root_record)?
+                )?;
             }
+            Ok(status)
         } else {
+            //
+            // The previous engine reports straight to stdout, it is only ever run with a
+            // single job
+            //
             let each = &file.path_value;
             let root_context = RootScope::new(rules, each)?;
             let stacker = StackTracker::new(&root_context);
@@ -858,25 +1107,30 @@ fn evaluate_against_data_input<'r>(
                 &renderers,
                 rules_file_name,
                 &file.name,
-                verbose,
-                print_json,
-                show_clause_failures,
+                self.verbose,
+                self.print_json,
+                self.show_clause_failures,
             );
             let appender = MetadataAppender {
                 delegate: &reporter,
                 root_context: each,
             };
             let status = rules.evaluate(each, &appender)?;
-            reporter.report(each, output)?;
-            if status == Status::FAIL {
-                overall = Status::FAIL
-            }
+            reporter.report(each, self.output)?;
+            Ok(status)
         }
     }
-    Ok(overall)
 }
 
-fn get_path_aware_value_from_data(content: &String) -> Result<PathAwareValue> {
+impl Work {
+    fn index(&self) -> usize {
+        match *self {
+            Work::Report(index) | Work::Evaluate(index, _) | Work::Done(index) => index,
+        }
+    }
+}
+
+pub(crate) fn get_path_aware_value_from_data(content: &String) -> Result<PathAwareValue> {
     if content.trim().is_empty() {
         Err(Error::new(ErrorKind::ParseError("blank data".to_string())))
     } else {
@@ -894,6 +1148,13 @@ fn get_path_aware_value_from_data(content: &String) -> Result<PathAwareValue> {
     }
 }
 
+fn has_a_supported_data_extension(entry: &walkdir::DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .is_some_and(|name| has_a_supported_extension(name, &DATA_FILE_SUPPORTED_EXTENSIONS))
+}
+
 fn has_a_supported_extension(name: &str, extensions: &[&str]) -> bool {
     extensions.iter().any(|extension| name.ends_with(extension))
 }
@@ -0,0 +1,351 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::files::read_file_content;
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::eval_context::{simplifed_json_from_root, ClauseReport, EventRecord};
+use crate::rules::{NamedStatus, RecordType, Result, Status};
+
+const BASELINE_FORMAT_VERSION: u32 = 1;
+
+///
+/// Identifies a single failure independent of the run that produced it. A failure is
+/// the combination of the rules file and rule that failed, the data file it was evaluated
+/// against and the path inside the data file that was not compliant.
+///
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub(crate) struct Fingerprint {
+    pub(crate) rules_file: String,
+    pub(crate) rule: String,
+    pub(crate) data_file: String,
+    pub(crate) path: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct BaselineEntry {
+    #[serde(flatten)]
+    pub(crate) fingerprint: Fingerprint,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expires: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct BaselineFile {
+    pub(crate) version: u32,
+    pub(crate) entries: Vec<BaselineEntry>,
+}
+
+impl BaselineFile {
+    pub(crate) fn load(path: &Path) -> Result<BaselineFile> {
+        let content = read_file_content(File::open(path)?)?;
+        let baseline = serde_json::from_str::<BaselineFile>(&content)?;
+        if baseline.version != BASELINE_FORMAT_VERSION {
+            return Err(Error::new(ErrorKind::IncompatibleError(format!(
+                "Baseline file {} has version {}, only version {} is supported",
+                path.display(),
+                baseline.version,
+                BASELINE_FORMAT_VERSION
+            ))));
+        }
+        for each in &baseline.entries {
+            if let Some(expires) = &each.expires {
+                if !is_iso_date(expires) {
+                    return Err(Error::new(ErrorKind::ParseError(format!(
+                        "Baseline entry for rule {} in {} has an invalid expiry date {}, expected YYYY-MM-DD",
+                        each.fingerprint.rule, each.fingerprint.data_file, expires
+                    ))));
+                }
+            }
+        }
+        Ok(baseline)
+    }
+}
+
+///
+/// Keeps track of failures seen during a validate run. Failures are recorded so that
+/// they can be written out as a new baseline, and, when a baseline was supplied, rules
+/// whose failures are all present in the baseline are subtracted from the results.
+///
+#[derive(Debug, Default)]
+pub(crate) struct BaselineTracker {
+    baseline: Option<BaselineFile>,
+    active: HashSet<Fingerprint>,
+    matched: HashSet<Fingerprint>,
+    recorded: BTreeSet<Fingerprint>,
+}
+
+///
+/// The failures found in one evaluation, and the baseline entries they matched. These are kept
+/// apart from the tracker until the evaluation is reported, evaluations whose results are
+/// dropped must not count towards the baseline.
+///
+#[derive(Debug, Default)]
+pub(crate) struct BaselineMatches {
+    matched: Vec<Fingerprint>,
+    recorded: Vec<Fingerprint>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct BaselineReport<'b> {
+    pub(crate) expired: Vec<&'b BaselineEntry>,
+    pub(crate) stale: Vec<&'b BaselineEntry>,
+}
+
+impl BaselineTracker {
+    pub(crate) fn new(baseline: Option<BaselineFile>) -> BaselineTracker {
+        BaselineTracker::new_as_of(baseline, &today())
+    }
+
+    pub(crate) fn new_as_of(baseline: Option<BaselineFile>, today: &str) -> BaselineTracker {
+        let active = baseline.as_ref().map_or(HashSet::new(), |b| {
+            b.entries
+                .iter()
+                .filter(|e| !is_expired(e, today))
+                .map(|e| e.fingerprint.clone())
+                .collect()
+        });
+        BaselineTracker {
+            baseline,
+            active,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Returns the evaluation tree with rules whose failures are all covered by the baseline
+    /// marked as PASS, along with the failures to [`record`](Self::record) once the tree is
+    /// reported. The file status is recomputed to reflect the subtracted rules.
+    ///
+    pub(crate) fn filter<'value>(
+        &self,
+        rules_file: &str,
+        data_file: &str,
+        root: &EventRecord<'value>,
+    ) -> Result<(EventRecord<'value>, BaselineMatches)> {
+        let mut matches = BaselineMatches::default();
+        let mut filtered = root.clone();
+        if !matches!(
+            root.container,
+            Some(RecordType::FileCheck(NamedStatus {
+                status: Status::FAIL,
+                ..
+            }))
+        ) {
+            return Ok((filtered, matches));
+        }
+
+        let report = simplifed_json_from_root(root)?;
+        let mut suppressed = HashSet::new();
+        for each in &report.not_compliant {
+            if let ClauseReport::Rule(rule) = each {
+                let fingerprints = rule_fingerprints(rules_file, data_file, rule.name, each);
+                matches.recorded.extend(fingerprints.iter().cloned());
+                if self.active.is_empty() {
+                    continue;
+                }
+                //
+                // Entries that match a failure are in use even when the rule has other
+                // failures and is not suppressed, they are not stale
+                //
+                let matching = fingerprints
+                    .iter()
+                    .filter(|f| self.active.contains(*f))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let all = matching.len() == fingerprints.len();
+                matches.matched.extend(matching);
+                if all {
+                    suppressed.insert(rule.name);
+                }
+            }
+        }
+
+        if suppressed.is_empty() {
+            return Ok((filtered, matches));
+        }
+
+        let mut fails = 0;
+        let mut passes = 0;
+        for each in &mut filtered.children {
+            if let Some(RecordType::RuleCheck(named)) = &mut each.container {
+                if named.status == Status::FAIL && suppressed.contains(named.name) {
+                    named.status = Status::PASS;
+                }
+                match named.status {
+                    Status::PASS => passes += 1,
+                    Status::FAIL => fails += 1,
+                    Status::SKIP => {}
+                }
+            }
+        }
+
+        if let Some(RecordType::FileCheck(named)) = &mut filtered.container {
+            named.status = if fails > 0 {
+                Status::FAIL
+            } else if passes > 0 {
+                Status::PASS
+            } else {
+                Status::SKIP
+            };
+        }
+        Ok((filtered, matches))
+    }
+
+    pub(crate) fn record(&mut self, matches: BaselineMatches) {
+        self.matched.extend(matches.matched);
+        self.recorded.extend(matches.recorded);
+    }
+
+    ///
+    /// Writes all failures recorded so far as a baseline. Expiry dates of entries already
+    /// present in an existing file at the same location are carried over.
+    ///
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let existing = if path.exists() {
+            Some(BaselineFile::load(path)?)
+        } else {
+            None
+        };
+        let entries = self
+            .recorded
+            .iter()
+            .map(|fingerprint| BaselineEntry {
+                fingerprint: fingerprint.clone(),
+                expires: existing.as_ref().and_then(|b| {
+                    b.entries
+                        .iter()
+                        .find(|e| &e.fingerprint == fingerprint)
+                        .and_then(|e| e.expires.clone())
+                }),
+            })
+            .collect();
+        let baseline = BaselineFile {
+            version: BASELINE_FORMAT_VERSION,
+            entries,
+        };
+        let mut writer = File::create(path)?;
+        serde_json::to_writer_pretty(&mut writer, &baseline)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    pub(crate) fn report(&self) -> BaselineReport<'_> {
+        let mut report = BaselineReport {
+            expired: vec![],
+            stale: vec![],
+        };
+        if let Some(baseline) = &self.baseline {
+            for each in &baseline.entries {
+                if !self.active.contains(&each.fingerprint) {
+                    report.expired.push(each);
+                } else if !self.matched.contains(&each.fingerprint) {
+                    report.stale.push(each);
+                }
+            }
+        }
+        report
+    }
+
+    pub(crate) fn print_report(&self, writer: &mut dyn Write) -> Result<()> {
+        let report = self.report();
+        if !report.expired.is_empty() {
+            writeln!(
+                writer,
+                "Baseline entries expired and no longer suppressing failures: {}",
+                report.expired.len()
+            )?;
+            for each in &report.expired {
+                writeln!(
+                    writer,
+                    "  {} (expired {})",
+                    each.fingerprint,
+                    each.expires.as_ref().map_or("", String::as_str)
+                )?;
+            }
+        }
+        if !report.stale.is_empty() {
+            writeln!(
+                writer,
+                "Baseline entries that did not match any failure in this run: {}",
+                report.stale.len()
+            )?;
+            for each in &report.stale {
+                writeln!(writer, "  {}", each.fingerprint)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} {} {}",
+            self.rules_file, self.rule, self.data_file, self.path
+        )
+    }
+}
+
+fn rule_fingerprints(
+    rules_file: &str,
+    data_file: &str,
+    rule_name: &str,
+    report: &ClauseReport<'_>,
+) -> BTreeSet<Fingerprint> {
+    let mut paths = BTreeSet::new();
+    report.collect_failing_paths(&mut paths);
+    paths
+        .into_iter()
+        .map(|path| Fingerprint {
+            rules_file: rules_file.to_string(),
+            rule: rule_name.to_string(),
+            data_file: data_file.to_string(),
+            path,
+        })
+        .collect()
+}
+
+fn is_expired(entry: &BaselineEntry, today: &str) -> bool {
+    entry
+        .expires
+        .as_ref()
+        .is_some_and(|expires| expires.as_str() < today)
+}
+
+fn is_iso_date(date: &str) -> bool {
+    let parts = date.split('-').collect::<Vec<&str>>();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(parts.iter())
+            .all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+//
+// Civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+//
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+#[path = "baseline_tests.rs"]
+mod baseline_tests;
@@ -0,0 +1,244 @@
+use super::*;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::exprs::RulesFile;
+use crate::rules::path_value::PathAwareValue;
+use std::convert::TryFrom;
+
+const RULES: &str = r#"
+rule encrypted {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.Encrypted == true
+}
+
+rule versioned {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.Versioned == true
+}
+"#;
+
+const DATA: &str = r#"
+Resources:
+  first:
+    Type: AWS::S3::Bucket
+    Properties:
+      Encrypted: false
+      Versioned: true
+  second:
+    Type: AWS::S3::Bucket
+    Properties:
+      Encrypted: false
+      Versioned: false
+"#;
+
+fn fingerprint(rule: &str, path: &str) -> Fingerprint {
+    Fingerprint {
+        rules_file: "s3.guard".to_string(),
+        rule: rule.to_string(),
+        data_file: "template.yaml".to_string(),
+        path: path.to_string(),
+    }
+}
+
+fn entry(rule: &str, path: &str, expires: Option<&str>) -> BaselineEntry {
+    BaselineEntry {
+        fingerprint: fingerprint(rule, path),
+        expires: expires.map(String::from),
+    }
+}
+
+fn evaluate(tracker: &mut BaselineTracker) -> Result<(Status, Vec<(String, Status)>)> {
+    let rules = RulesFile::try_from(RULES)?;
+    let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(DATA)?)?;
+    let mut scope = root_scope(&rules, &value)?;
+    let status = eval_rules_file(&rules, &mut scope)?;
+    assert_eq!(status, Status::FAIL);
+    let record = scope.reset_recorder().extract();
+    let (filtered, matches) = tracker.filter("s3.guard", "template.yaml", &record)?;
+    tracker.record(matches);
+    let file_status = match &filtered.container {
+        Some(RecordType::FileCheck(named)) => named.status,
+        _ => unreachable!(),
+    };
+    let rules = filtered
+        .children
+        .iter()
+        .filter_map(|each| match &each.container {
+            Some(RecordType::RuleCheck(named)) => Some((named.name.to_string(), named.status)),
+            _ => None,
+        })
+        .collect();
+    Ok((file_status, rules))
+}
+
+#[test]
+fn test_records_failures_without_baseline() -> Result<()> {
+    let mut tracker = BaselineTracker::new_as_of(None, "2022-01-01");
+    let (status, _) = evaluate(&mut tracker)?;
+    assert_eq!(status, Status::FAIL);
+    let recorded = tracker.recorded.iter().cloned().collect::<Vec<_>>();
+    assert_eq!(
+        recorded,
+        vec![
+            fingerprint("encrypted", "/Resources/first/Properties/Encrypted"),
+            fingerprint("encrypted", "/Resources/second/Properties/Encrypted"),
+            fingerprint("versioned", "/Resources/second/Properties/Versioned"),
+        ]
+    );
+    assert_eq!(
+        tracker.report(),
+        BaselineReport {
+            expired: vec![],
+            stale: vec![]
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fully_covered_baseline_passes() -> Result<()> {
+    let baseline = BaselineFile {
+        version: 1,
+        entries: vec![
+            entry("encrypted", "/Resources/first/Properties/Encrypted", None),
+            entry("encrypted", "/Resources/second/Properties/Encrypted", None),
+            entry(
+                "versioned",
+                "/Resources/second/Properties/Versioned",
+                Some("2022-01-01"),
+            ),
+        ],
+    };
+    let mut tracker = BaselineTracker::new_as_of(Some(baseline), "2022-01-01");
+    let (status, rules) = evaluate(&mut tracker)?;
+    assert_eq!(status, Status::PASS);
+    assert!(rules.iter().all(|(_, status)| *status == Status::PASS));
+    let report = tracker.report();
+    assert!(report.expired.is_empty());
+    assert!(report.stale.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_partially_covered_rule_still_fails() -> Result<()> {
+    let baseline = BaselineFile {
+        version: 1,
+        entries: vec![
+            entry("encrypted", "/Resources/first/Properties/Encrypted", None),
+            entry("versioned", "/Resources/second/Properties/Versioned", None),
+            entry("versioned", "/Resources/third/Properties/Versioned", None),
+        ],
+    };
+    let mut tracker = BaselineTracker::new_as_of(Some(baseline), "2022-01-01");
+    let (status, rules) = evaluate(&mut tracker)?;
+    assert_eq!(status, Status::FAIL);
+    assert_eq!(
+        rules,
+        vec![
+            ("encrypted".to_string(), Status::FAIL),
+            ("versioned".to_string(), Status::PASS),
+        ]
+    );
+    let report = tracker.report();
+    assert!(report.expired.is_empty());
+    assert_eq!(
+        report.stale,
+        vec![&entry(
+            "versioned",
+            "/Resources/third/Properties/Versioned",
+            None
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_partial_match_is_not_stale() -> Result<()> {
+    let baseline = BaselineFile {
+        version: 1,
+        entries: vec![entry(
+            "encrypted",
+            "/Resources/second/Properties/Encrypted",
+            None,
+        )],
+    };
+    let mut tracker = BaselineTracker::new_as_of(Some(baseline), "2022-01-01");
+    let (status, rules) = evaluate(&mut tracker)?;
+    assert_eq!(status, Status::FAIL);
+    assert!(rules.iter().all(|(_, status)| *status == Status::FAIL));
+    assert_eq!(
+        tracker.report(),
+        BaselineReport {
+            expired: vec![],
+            stale: vec![]
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_expired_entries_do_not_suppress() -> Result<()> {
+    let baseline = BaselineFile {
+        version: 1,
+        entries: vec![entry(
+            "versioned",
+            "/Resources/second/Properties/Versioned",
+            Some("2021-12-31"),
+        )],
+    };
+    let mut tracker = BaselineTracker::new_as_of(Some(baseline), "2022-01-01");
+    let (status, rules) = evaluate(&mut tracker)?;
+    assert_eq!(status, Status::FAIL);
+    assert!(rules.iter().all(|(_, status)| *status == Status::FAIL));
+    let report = tracker.report();
+    assert_eq!(report.expired.len(), 1);
+    assert!(report.stale.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_write_retains_expiry() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("guard-baseline-{}.json", std::process::id()));
+    let existing = BaselineFile {
+        version: 1,
+        entries: vec![
+            entry(
+                "versioned",
+                "/Resources/second/Properties/Versioned",
+                Some("2030-06-30"),
+            ),
+            entry("removed", "/Resources/gone", Some("2030-06-30")),
+        ],
+    };
+    std::fs::write(&path, serde_json::to_string(&existing)?)?;
+
+    let mut tracker = BaselineTracker::new_as_of(None, "2022-01-01");
+    evaluate(&mut tracker)?;
+    tracker.write(&path)?;
+    let written = BaselineFile::load(&path);
+    std::fs::remove_file(&path)?;
+
+    assert_eq!(
+        written?,
+        BaselineFile {
+            version: 1,
+            entries: vec![
+                entry("encrypted", "/Resources/first/Properties/Encrypted", None),
+                entry("encrypted", "/Resources/second/Properties/Encrypted", None),
+                entry(
+                    "versioned",
+                    "/Resources/second/Properties/Versioned",
+                    Some("2030-06-30")
+                ),
+            ]
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_iso_dates() {
+    assert!(is_iso_date("2022-01-31"));
+    assert!(!is_iso_date("2022-1-31"));
+    assert!(!is_iso_date("31/01/2022"));
+    assert_eq!(today().len(), 10);
+    assert!(is_iso_date(&today()));
+}
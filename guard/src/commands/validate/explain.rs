@@ -0,0 +1,315 @@
+use std::io::Write;
+
+use crate::rules::display::ValueOnlyDisplay;
+use crate::rules::eval_context::EventRecord;
+use crate::rules::exprs::RulesFile;
+use crate::rules::values::CmpOperator;
+use crate::rules::{ClauseCheck, QueryResult, RecordType, Result, Status};
+
+#[cfg(test)]
+#[path = "explain_tests.rs"]
+mod explain_tests;
+
+//
+// Values longer than this are cut short in the trace, a resource can take pages to print
+//
+const VALUE_WIDTH: usize = 80;
+
+///
+/// The rule traced with --explain, and with --path the location of the values to show checks for
+///
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Explain {
+    pub(crate) rule: String,
+    pub(crate) path: Option<String>,
+}
+
+impl Explain {
+    ///
+    /// Whether a rules file has a rule with the name traced
+    ///
+    pub(crate) fn defined_in(&self, rules: &RulesFile<'_>) -> bool {
+        rules
+            .guard_rules
+            .iter()
+            .any(|rule| rule.rule_name == self.rule)
+    }
+
+    ///
+    /// The message to show when none of the rules files has the rule, there would be nothing
+    /// to trace
+    ///
+    pub(crate) fn check_defined<'f, 'r: 'f, I>(&self, rules: I) -> std::result::Result<(), String>
+    where
+        I: IntoIterator<Item = &'f RulesFile<'r>>,
+    {
+        let mut rules = rules.into_iter();
+        if rules.any(|each| self.defined_in(each)) {
+            return Ok(());
+        }
+        Err(format!(
+            "There is no rule named {} to explain in the rules files that parsed",
+            self.rule
+        ))
+    }
+
+    ///
+    /// Writes the trace of the rule from the records of evaluating a rules file against a data
+    /// file. The evaluation must have kept the values of the checks that pass.
+    ///
+    pub(crate) fn write_trace(
+        &self,
+        writer: &mut dyn Write,
+        rules_file_name: &str,
+        data_file_name: &str,
+        root: &EventRecord<'_>,
+    ) -> Result<()> {
+        for rule in &root.children {
+            let status = match &rule.container {
+                Some(RecordType::RuleCheck(named)) if named.name == self.rule => named.status,
+                _ => continue,
+            };
+            writeln!(
+                writer,
+                "Rule {} in {} against {}: {}",
+                self.rule, rules_file_name, data_file_name, status
+            )?;
+            self.write_rule(writer, rule)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Writes the trace of the clauses in the record of one evaluation of a rule
+    ///
+    pub(crate) fn write_rule(&self, writer: &mut dyn Write, rule: &EventRecord<'_>) -> Result<()> {
+        let mut shown = 0;
+        for each in &rule.children {
+            if self.shown(each) {
+                self.write_record(writer, each, 1)?;
+                shown += 1;
+            }
+        }
+        if shown == 0 {
+            match &self.path {
+                Some(path) => writeln!(writer, "  no checks of values at {}", path)?,
+                None => writeln!(writer, "  the rule has no clauses")?,
+            }
+        }
+        Ok(())
+    }
+
+    //
+    // With --path only the records with checks of values at or inside the path are shown
+    //
+    fn shown(&self, record: &EventRecord<'_>) -> bool {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return true,
+        };
+        match &record.container {
+            Some(RecordType::ClauseValueCheck(check)) => checked_from(check).is_some_and(|from| {
+                let at = query_path(from);
+                at == *path || at.starts_with(&format!("{}/", path.trim_end_matches('/')))
+            }),
+            _ => record.children.iter().any(|each| self.shown(each)),
+        }
+    }
+
+    fn write_record(
+        &self,
+        writer: &mut dyn Write,
+        record: &EventRecord<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let indent = "  ".repeat(depth);
+        let (line, note) = match &record.container {
+            Some(RecordType::ClauseValueCheck(check)) => {
+                writeln!(writer, "{}{}", indent, check_text(check))?;
+                if let Some(message) = check_message(check) {
+                    writeln!(writer, "{}  {}", indent, message)?;
+                }
+                return Ok(());
+            }
+            //
+            // A rule evaluated for a named rule clause is traced with its own --explain
+            //
+            Some(RecordType::RuleCheck(named)) => {
+                writeln!(writer, "{}rule {}: {}", indent, named.name, named.status)?;
+                return Ok(());
+            }
+            Some(RecordType::RuleCondition(status)) => (
+                format!("when: {}", status),
+                skipped_note(
+                    *status,
+                    "the rule is skipped and its clauses are not evaluated",
+                ),
+            ),
+            Some(RecordType::WhenCondition(status)) => (
+                format!("when: {}", status),
+                skipped_note(*status, "the block is skipped"),
+            ),
+            Some(RecordType::TypeCondition(status)) => (
+                format!("when: {}", status),
+                skipped_note(*status, "the type block is skipped"),
+            ),
+            Some(RecordType::WhenCheck(check)) => (format!("block: {}", check.status), None),
+            Some(RecordType::TypeCheck(check)) => {
+                (format!("{}: {}", check.type_name, check.block.status), None)
+            }
+            Some(RecordType::TypeBlock(status)) => (format!("block: {}", status), None),
+            Some(RecordType::Filter(status)) => (format!("filter: {}", status), None),
+            Some(RecordType::Disjunction(check)) => {
+                (format!("one of: {}", check.status), check.message.clone())
+            }
+            Some(RecordType::BlockGuardCheck(check)) => (
+                format!(
+                    "block at {}: {}",
+                    clause_text(&record.context, "BlockGuardClause#"),
+                    check.status
+                ),
+                check.message.clone(),
+            ),
+            Some(RecordType::GuardClauseBlockCheck(check)) => (
+                format!(
+                    "{}: {}",
+                    clause_text(&record.context, "GuardAccessClause#block"),
+                    check.status
+                ),
+                check.message.clone(),
+            ),
+            Some(RecordType::FileCheck(_)) | None => (record.context.clone(), None),
+        };
+        writeln!(writer, "{}{}", indent, line)?;
+        for each in &record.children {
+            if self.shown(each) {
+                self.write_record(writer, each, depth + 1)?;
+            }
+        }
+        if let Some(note) = note {
+            writeln!(writer, "{}  {}", indent, note)?;
+        }
+        Ok(())
+    }
+}
+
+fn skipped_note(status: Status, skipped: &str) -> Option<String> {
+    match status {
+        Status::PASS => None,
+        _ => Some(format!("the when condition did not pass, {}", skipped)),
+    }
+}
+
+fn clause_text(context: &str, prefix: &str) -> String {
+    context
+        .strip_prefix(prefix)
+        .unwrap_or(context)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn checked_from<'c, 'value>(check: &'c ClauseCheck<'value>) -> Option<&'c QueryResult<'value>> {
+    match check {
+        ClauseCheck::Comparison(check) => Some(&check.from),
+        ClauseCheck::InComparison(check) => Some(&check.from),
+        ClauseCheck::Unary(check) => Some(&check.value.from),
+        ClauseCheck::MissingBlockValue(check) => Some(&check.from),
+        ClauseCheck::Success
+        | ClauseCheck::NoValueForEmptyCheck(_)
+        | ClauseCheck::DependentRule(_) => None,
+    }
+}
+
+fn query_path(result: &QueryResult<'_>) -> String {
+    match result {
+        QueryResult::Literal(value) | QueryResult::Resolved(value) => value.self_path().0.clone(),
+        QueryResult::UnResolved(ur) => ur.traversed_to.self_path().0.clone(),
+    }
+}
+
+fn value_text(result: &QueryResult<'_>) -> String {
+    let text = match result {
+        QueryResult::Literal(value) => format!("{}", ValueOnlyDisplay(value)),
+        //
+        // Literals from the rules are resolved values without a path
+        //
+        QueryResult::Resolved(value) if value.self_path().0.is_empty() => {
+            format!("{}", ValueOnlyDisplay(value))
+        }
+        QueryResult::Resolved(value) => {
+            format!("{} = {}", value.self_path().0, ValueOnlyDisplay(value))
+        }
+        QueryResult::UnResolved(ur) => {
+            return format!(
+                "UnResolved at {} (remaining query {})",
+                ur.traversed_to.self_path().0,
+                ur.remaining_query
+            )
+        }
+    };
+    match text.char_indices().nth(VALUE_WIDTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+fn operator_text((cmp, not): (CmpOperator, bool)) -> String {
+    format!("{}{}", if not { "not " } else { "" }, cmp)
+}
+
+fn check_text(check: &ClauseCheck<'_>) -> String {
+    match check {
+        ClauseCheck::Success => format!("checked: {}", Status::PASS),
+        ClauseCheck::NoValueForEmptyCheck(_) => {
+            format!("no values to check: {}", Status::FAIL)
+        }
+        ClauseCheck::DependentRule(dependent) => {
+            format!("depends on rule {}: {}", dependent.rule, dependent.status)
+        }
+        ClauseCheck::MissingBlockValue(missing) => format!(
+            "{}, no value for the block: {}",
+            value_text(&missing.from),
+            missing.status
+        ),
+        ClauseCheck::Unary(unary) => format!(
+            "{} {}: {}",
+            value_text(&unary.value.from),
+            operator_text(unary.comparison),
+            unary.value.status
+        ),
+        ClauseCheck::Comparison(comparison) => format!(
+            "{} {}{}: {}",
+            value_text(&comparison.from),
+            operator_text(comparison.comparison),
+            comparison
+                .to
+                .as_ref()
+                .map_or(String::new(), |to| format!(" {}", value_text(to))),
+            comparison.status
+        ),
+        ClauseCheck::InComparison(comparison) => format!(
+            "{} {} {}: {}",
+            value_text(&comparison.from),
+            operator_text(comparison.comparison),
+            match comparison.to.as_slice() {
+                [list] => value_text(list),
+                values => format!(
+                    "[{}]",
+                    values.iter().map(value_text).collect::<Vec<_>>().join(", ")
+                ),
+            },
+            comparison.status
+        ),
+    }
+}
+
+fn check_message<'c>(check: &'c ClauseCheck<'_>) -> Option<&'c str> {
+    match check {
+        ClauseCheck::Comparison(check) => check.message.as_deref(),
+        ClauseCheck::InComparison(check) => check.message.as_deref(),
+        ClauseCheck::Unary(check) => check.value.message.as_deref(),
+        ClauseCheck::MissingBlockValue(check) => check.message.as_deref(),
+        _ => None,
+    }
+}
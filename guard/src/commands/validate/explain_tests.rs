@@ -0,0 +1,105 @@
+use super::*;
+use crate::rules::eval::eval_rules_file;
+use crate::rules::eval_context::root_scope;
+use crate::rules::path_value::PathAwareValue;
+use std::convert::TryFrom;
+
+const RULES: &str = r#"
+rule encrypted when Resources.*[ Type == 'AWS::EC2::Volume' ] not empty {
+    Resources.*[ Type == 'AWS::EC2::Volume' ].Properties.Size > 500 or
+    Resources.*[ Type == 'AWS::EC2::Volume' ].Properties.Size < 300 or
+    Resources.*[ Type == 'AWS::EC2::Volume' ].Properties.Encrypted == true
+    Resources.*[ Type == 'AWS::EC2::Volume' ].Properties.KmsKeyId exists
+}
+
+rule tables when Resources.*[ Type == 'AWS::DynamoDB::Table' ] not empty {
+    Resources.*[ Type == 'AWS::DynamoDB::Table' ].Properties.SSESpecification exists
+}
+"#;
+
+const DATA: &str = r#"
+Resources:
+  first:
+    Type: AWS::EC2::Volume
+    Properties:
+      Size: 200
+  second:
+    Type: AWS::EC2::Volume
+    Properties:
+      Size: 250
+      KmsKeyId: key
+"#;
+
+fn trace(rule: &str, path: Option<&str>) -> Result<String> {
+    let rules = RulesFile::try_from(RULES)?;
+    let value = PathAwareValue::try_from(serde_yaml::from_str::<serde_yaml::Value>(DATA)?)?;
+    let mut scope = root_scope(&rules, &value)?;
+    scope.explain_checks();
+    eval_rules_file(&rules, &mut scope)?;
+    let record = scope.reset_recorder().extract();
+    let explain = Explain {
+        rule: rule.to_string(),
+        path: path.map(String::from),
+    };
+    let mut output = Vec::new();
+    explain.write_trace(&mut output, "volumes.guard", "template.yaml", &record)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_trace_has_values_of_passing_and_failing_checks() -> Result<()> {
+    let trace = trace("encrypted", None)?;
+    assert!(trace.starts_with(&format!(
+        "Rule encrypted in volumes.guard against template.yaml: {}\n  when: {}\n",
+        Status::FAIL,
+        Status::PASS
+    )));
+    assert!(trace.contains(&format!(
+        "/Resources/first/Properties/Size = 200 LESS THAN 300: {}",
+        Status::PASS
+    )));
+    assert!(trace.contains(&format!(
+        "/Resources/second/Properties/Size = 250 GREATER THAN 500: {}",
+        Status::FAIL
+    )));
+    assert!(trace.contains(&format!(
+        "UnResolved at /Resources/first/Properties (remaining query KmsKeyId) EXISTS: {}",
+        Status::FAIL
+    )));
+    assert!(trace.contains("alternative 2 of 3 passed, the rest are not evaluated"));
+    assert!(!trace.contains("Encrypted"));
+    Ok(())
+}
+
+#[test]
+fn test_trace_of_skipped_rule() -> Result<()> {
+    let trace = trace("tables", None)?;
+    assert!(trace.starts_with(&format!(
+        "Rule tables in volumes.guard against template.yaml: {}\n",
+        Status::SKIP
+    )));
+    assert!(trace.contains(
+        "the when condition did not pass, the rule is skipped and its clauses are not evaluated"
+    ));
+    assert!(!trace.contains("SSESpecification"));
+
+    let rules = RulesFile::try_from(RULES)?;
+    let missing = Explain {
+        rule: String::from("buckets"),
+        path: None,
+    };
+    assert!(missing.check_defined(std::iter::once(&rules)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_trace_at_path() -> Result<()> {
+    let second = trace("encrypted", Some("/Resources/second"))?;
+    assert!(second.contains("/Resources/second/Properties/Size = 250"));
+    assert!(second.contains("/Resources/second/Properties/KmsKeyId = \"key\" EXISTS"));
+    assert!(!second.contains("/Resources/first"));
+
+    let third = trace("encrypted", Some("/Resources/third"))?;
+    assert!(third.ends_with("  no checks of values at /Resources/third\n"));
+    Ok(())
+}
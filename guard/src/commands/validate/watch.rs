@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::commands::evaluate::{evaluate, Outcome};
+use crate::commands::files::{read_file_content, regular_ordering, select_files, IgnoreFilter};
+use crate::commands::validate::{
+    get_path_aware_value_from_data, has_a_supported_data_extension, has_a_supported_extension,
+};
+use crate::commands::watch::{absolute, display, RulesCache, Session, Statuses};
+use crate::commands::RULE_FILE_SUPPORTED_EXTENSIONS;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::Result;
+
+///
+/// Watch mode for `validate`, evaluations are cached for each pair of rules file and data file.
+/// A changed rules file is re-parsed and evaluated against all data files, a changed data file
+/// is evaluated against all rules files, and a changed input parameters file re-evaluates
+/// everything.
+///
+pub(crate) struct ValidateSession<'a> {
+    rules: Vec<&'a str>,
+    data: Vec<&'a str>,
+    parameters: Vec<&'a str>,
+    ignore: &'a IgnoreFilter,
+    roots: Vec<PathBuf>,
+    rules_files: Vec<PathBuf>,
+    data_files: Vec<PathBuf>,
+    parameter_files: Vec<PathBuf>,
+    parsed: RulesCache,
+    merged_parameters: Option<std::result::Result<Option<PathAwareValue>, String>>,
+    documents: HashMap<PathBuf, std::result::Result<PathAwareValue, String>>,
+    evaluations: HashMap<(PathBuf, PathBuf), Vec<(String, String)>>,
+}
+
+impl<'a> ValidateSession<'a> {
+    pub(crate) fn new(
+        rules: Vec<&'a str>,
+        data: Vec<&'a str>,
+        parameters: Vec<&'a str>,
+        ignore: &'a IgnoreFilter,
+    ) -> Result<ValidateSession<'a>> {
+        let mut session = ValidateSession {
+            rules,
+            data,
+            parameters,
+            ignore,
+            roots: vec![],
+            rules_files: vec![],
+            data_files: vec![],
+            parameter_files: vec![],
+            parsed: RulesCache::default(),
+            merged_parameters: None,
+            documents: HashMap::new(),
+            evaluations: HashMap::new(),
+        };
+        session.select()?;
+        Ok(session)
+    }
+
+    fn select(&mut self) -> Result<()> {
+        let mut roots = vec![];
+        let mut rules_files = vec![];
+        for each in &self.rules {
+            let selection = select_files(each, regular_ordering, self.ignore, |entry| {
+                entry.file_name().to_str().is_some_and(|name| {
+                    has_a_supported_extension(name, &RULE_FILE_SUPPORTED_EXTENSIONS)
+                })
+            })?;
+            roots.push(absolute(&selection.base));
+            rules_files.extend(selection.files.iter().map(|file| absolute(file)));
+        }
+        let mut data_files = vec![];
+        for each in &self.data {
+            let selection = select_files(
+                each,
+                regular_ordering,
+                self.ignore,
+                has_a_supported_data_extension,
+            )?;
+            roots.push(absolute(&selection.base));
+            data_files.extend(selection.files.iter().map(|file| absolute(file)));
+        }
+        let mut parameter_files = vec![];
+        for each in &self.parameters {
+            let selection = select_files(
+                each,
+                regular_ordering,
+                self.ignore,
+                has_a_supported_data_extension,
+            )?;
+            roots.push(absolute(&selection.base));
+            parameter_files.extend(selection.files.iter().map(|file| absolute(file)));
+        }
+        self.roots = roots;
+        self.rules_files = rules_files;
+        self.data_files = data_files;
+        self.parameter_files = parameter_files;
+        Ok(())
+    }
+
+    ///
+    /// Caches the outcome of evaluating a pair of files in the run before watching, so that
+    /// watching starts from it instead of evaluating the pair again
+    ///
+    pub(crate) fn seed(&mut self, rules_file: &Path, data_file: &Path, outcome: Outcome) {
+        self.evaluations.insert(
+            (absolute(rules_file), absolute(data_file)),
+            outcome_statuses(outcome),
+        );
+    }
+
+    fn document(&mut self, path: &Path) -> std::result::Result<PathAwareValue, String> {
+        if self.merged_parameters.is_none() {
+            self.merged_parameters = Some(merge_parameters(&self.parameter_files));
+        }
+        let parameters = self.merged_parameters.clone().unwrap();
+        self.documents
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let document = load(path)?;
+                match parameters? {
+                    Some(parameters) => parameters.merge(document).map_err(|e| e.to_string()),
+                    None => Ok(document),
+                }
+            })
+            .clone()
+    }
+}
+
+impl<'a> Session for ValidateSession<'a> {
+    fn watched(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+
+    fn changed(&mut self, paths: &[PathBuf]) -> Result<bool> {
+        let mut relevant = false;
+        for path in paths {
+            if self.parameter_files.contains(path) {
+                self.merged_parameters = None;
+                self.documents.clear();
+                self.evaluations.clear();
+                relevant = true;
+                continue;
+            }
+            let known = self.parsed.invalidate(path)
+                || self.documents.remove(path).is_some()
+                || self.rules_files.contains(path)
+                || self.data_files.contains(path);
+            self.evaluations
+                .retain(|(rules, data), _| rules != path && data != path);
+            relevant |= known || self.roots.iter().any(|root| path.starts_with(root));
+        }
+        if relevant {
+            self.select()?;
+        }
+        Ok(relevant)
+    }
+
+    fn statuses(&mut self) -> Result<(Statuses, usize)> {
+        let mut statuses = Statuses::new();
+        let mut evaluated = 0;
+        for rules_file in self.rules_files.clone() {
+            let missing = self
+                .data_files
+                .iter()
+                .filter(|data_file| {
+                    !self
+                        .evaluations
+                        .contains_key(&(rules_file.clone(), data_file.to_path_buf()))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let documents = missing
+                .into_iter()
+                .map(|data_file| {
+                    let document = self.document(&data_file);
+                    (data_file, document)
+                })
+                .collect::<Vec<_>>();
+            let rules = match self.parsed.get(&rules_file) {
+                Ok(rules) => rules,
+                Err(e) => {
+                    statuses.insert(display(&rules_file), format!("ERROR {}", e));
+                    continue;
+                }
+            };
+            for (data_file, document) in documents {
+                let results = match document {
+                    Ok(document) => outcome_statuses(evaluate(&rules, &document)),
+                    Err(e) => vec![(String::new(), format!("ERROR {}", e))],
+                };
+                evaluated += 1;
+                self.evaluations
+                    .insert((rules_file.clone(), data_file), results);
+            }
+            for data_file in &self.data_files {
+                let key = (rules_file.clone(), data_file.clone());
+                for (rule, status) in &self.evaluations[&key] {
+                    let name = if rule.is_empty() {
+                        format!("{} {}", display(&rules_file), display(data_file))
+                    } else {
+                        format!("{} {} {}", display(&rules_file), rule, display(data_file))
+                    };
+                    statuses.insert(name, status.clone());
+                }
+            }
+        }
+        Ok((statuses, evaluated))
+    }
+}
+
+pub(crate) fn outcome_statuses(outcome: Outcome) -> Vec<(String, String)> {
+    match outcome {
+        Outcome::Statuses(statuses) => statuses
+            .into_iter()
+            .map(|(rule, status)| (rule, format!("{:?}", status)))
+            .collect(),
+        Outcome::Error(e) => vec![(String::new(), format!("ERROR {}", e))],
+        Outcome::Panic(message) => vec![(String::new(), format!("PANIC {}", message))],
+    }
+}
+
+fn load(path: &Path) -> std::result::Result<PathAwareValue, String> {
+    File::open(path)
+        .and_then(read_file_content)
+        .map_err(|e| e.to_string())
+        .and_then(|content| get_path_aware_value_from_data(&content).map_err(|e| e.to_string()))
+}
+
+fn merge_parameters(files: &[PathBuf]) -> std::result::Result<Option<PathAwareValue>, String> {
+    let mut merged: Option<PathAwareValue> = None;
+    for file in files {
+        let value = load(file)?;
+        merged = Some(match merged {
+            Some(current) => current.merge(value).map_err(|e| e.to_string())?,
+            None => value,
+        });
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+#[path = "watch_tests.rs"]
+mod watch_tests;
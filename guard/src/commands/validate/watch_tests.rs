@@ -0,0 +1,115 @@
+use super::*;
+use crate::rules::Status;
+
+const RULES: &str = r#"rule S3_LOGGING when Resources.*[ Type == 'AWS::S3::Bucket' ] !empty {
+    Resources.*[ Type == 'AWS::S3::Bucket' ].Properties.LoggingConfiguration exists
+}
+"#;
+
+const NOT_LOGGED: &str = "Resources:\n  B:\n    Type: AWS::S3::Bucket\n    Properties: {}\n";
+const LOGGED: &str =
+    "Resources:\n  B:\n    Type: AWS::S3::Bucket\n    Properties: {LoggingConfiguration: {}}\n";
+
+struct WatchDir(PathBuf);
+
+impl WatchDir {
+    fn new(name: &str) -> WatchDir {
+        let dir = std::env::temp_dir().join(format!(
+            "guard-validate-watch-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("s3.guard"), RULES).unwrap();
+        std::fs::write(dir.join("data/a.yaml"), NOT_LOGGED).unwrap();
+        std::fs::write(dir.join("data/b.yaml"), LOGGED).unwrap();
+        WatchDir(absolute(&dir))
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}", self.0.join(name).display())
+    }
+}
+
+impl Drop for WatchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn status_of<'s>(statuses: &'s Statuses, data: &str) -> Option<&'s str> {
+    statuses
+        .iter()
+        .find(|(key, _)| key.contains("S3_LOGGING") && key.ends_with(data))
+        .map(|(_, status)| status.as_str())
+}
+
+#[test]
+fn test_only_changed_data_is_evaluated() -> Result<()> {
+    let dir = WatchDir::new("data");
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let (rules, data) = (dir.path("s3.guard"), dir.path("data"));
+    let mut session = ValidateSession::new(vec![&rules], vec![&data], vec![], &ignore)?;
+
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 2);
+    assert_eq!(status_of(&statuses, "a.yaml"), Some("FAIL"));
+    assert_eq!(status_of(&statuses, "b.yaml"), Some("PASS"));
+
+    std::fs::write(dir.0.join("data/a.yaml"), LOGGED).unwrap();
+    assert!(session.changed(&[dir.0.join("data/a.yaml")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    assert_eq!(status_of(&statuses, "a.yaml"), Some("PASS"));
+
+    assert!(!session.changed(&[PathBuf::from("/elsewhere/c.yaml")])?);
+    assert_eq!(session.statuses()?.1, 0);
+    Ok(())
+}
+
+#[test]
+fn test_changed_rules_and_new_data_files() -> Result<()> {
+    let dir = WatchDir::new("rules");
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let (rules, data) = (dir.path("s3.guard"), dir.path("data"));
+    let mut session = ValidateSession::new(vec![&rules], vec![&data], vec![], &ignore)?;
+    session.statuses()?;
+
+    std::fs::write(
+        dir.0.join("s3.guard"),
+        "rule S3_LOGGING { Resources exists ",
+    )
+    .unwrap();
+    assert!(session.changed(&[dir.0.join("s3.guard")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 0);
+    assert_eq!(statuses.len(), 1);
+    assert!(statuses.values().all(|status| status.starts_with("ERROR")));
+
+    std::fs::write(dir.0.join("s3.guard"), RULES).unwrap();
+    std::fs::write(dir.0.join("data/c.yaml"), NOT_LOGGED).unwrap();
+    assert!(session.changed(&[dir.0.join("s3.guard"), dir.0.join("data/c.yaml")])?);
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 3);
+    assert_eq!(status_of(&statuses, "c.yaml"), Some("FAIL"));
+    Ok(())
+}
+
+#[test]
+fn test_seeded_pairs_are_not_evaluated() -> Result<()> {
+    let dir = WatchDir::new("seeded");
+    let ignore = IgnoreFilter::new(std::iter::empty())?;
+    let (rules, data) = (dir.path("s3.guard"), dir.path("data"));
+    let mut session = ValidateSession::new(vec![&rules], vec![&data], vec![], &ignore)?;
+    session.seed(
+        &dir.0.join("s3.guard"),
+        &dir.0.join("data/a.yaml"),
+        Outcome::Statuses(vec![("S3_LOGGING".to_string(), Status::FAIL)]),
+    );
+
+    let (statuses, evaluated) = session.statuses()?;
+    assert_eq!(evaluated, 1);
+    assert_eq!(status_of(&statuses, "a.yaml"), Some("FAIL"));
+    assert_eq!(status_of(&statuses, "b.yaml"), Some("PASS"));
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::commands::files::read_file_content;
+use crate::commands::{DATA_FILE_SUPPORTED_EXTENSIONS, RULE_FILE_SUPPORTED_EXTENSIONS};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::exprs::RulesFile;
+use crate::rules::Result;
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+///
+/// Statuses from a run in watch mode, keyed by a description of what was evaluated, e.g. the
+/// rules file, data file and rule name, and compared between runs to show what changed.
+///
+pub(crate) type Statuses = BTreeMap<String, String>;
+
+///
+/// The state a command keeps between runs in watch mode. Parsed files and evaluation results
+/// are cached, a change only drops the cache entries for the changed files so that the next
+/// run re-parses and re-evaluates just those.
+///
+pub(crate) trait Session {
+    ///
+    /// The files and directories to watch, asked for after the first run
+    ///
+    fn watched(&self) -> Vec<PathBuf>;
+
+    ///
+    /// Drops cached state for the changed files, returns false when none of them matter to
+    /// this session
+    ///
+    fn changed(&mut self, paths: &[PathBuf]) -> Result<bool>;
+
+    ///
+    /// The statuses for all evaluations, re-evaluating the ones that are not cached, along with
+    /// the number of evaluations that were run
+    ///
+    fn statuses(&mut self) -> Result<(Statuses, usize)>;
+}
+
+///
+/// Watches the session's files and prints the status changes after each change until the
+/// process is stopped. Changes within a short interval are handled together. The session is
+/// expected to be seeded with the results of the run that just finished, so the statuses to
+/// compare the first change against come from its cache and only missing pairs are evaluated.
+///
+pub(crate) fn watch(session: &mut dyn Session, writer: &mut dyn Write) -> Result<()> {
+    let (mut previous, _) = session.statuses()?;
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+    for path in session.watched() {
+        //
+        // Files are watched through their directory, editors often save by replacing the
+        // file which ends a watch on the file itself
+        //
+        let (target, mode) = if path.is_dir() {
+            (path, RecursiveMode::Recursive)
+        } else {
+            match path.parent() {
+                Some(parent) if parent != Path::new("") => {
+                    (parent.to_path_buf(), RecursiveMode::NonRecursive)
+                }
+                _ => (PathBuf::from("."), RecursiveMode::NonRecursive),
+            }
+        };
+        watcher.watch(&target, mode).map_err(watch_error)?;
+    }
+
+    writeln!(writer, "Watching for changes, press Ctrl-C to stop")?;
+    while let Ok(event) = receiver.recv() {
+        let mut changed = changed_paths(event);
+        loop {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(event) => changed.extend(changed_paths(event)),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+        changed.sort();
+        changed.dedup();
+        if changed.is_empty() || !session.changed(&changed)? {
+            continue;
+        }
+
+        let (current, evaluated) = session.statuses()?;
+        writeln!(
+            writer,
+            "Re-evaluated {} pairs of files after changes to {}",
+            evaluated,
+            changed
+                .iter()
+                .map(|each| display(each))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        print_changes(&previous, &current, writer)?;
+        previous = current;
+    }
+    Ok(())
+}
+
+fn watch_error(e: notify::Error) -> Error {
+    match (e.kind, e.paths.first()) {
+        (notify::ErrorKind::PathNotFound, Some(path)) => {
+            Error::new(ErrorKind::FileNotFoundError(path.display().to_string()))
+        }
+        (notify::ErrorKind::Io(io), _) => Error::new(ErrorKind::IoError(io)),
+        (kind, _) => Error::new(ErrorKind::IoError(std::io::Error::other(format!(
+            "unable to watch for changes, {}",
+            notify::Error::new(kind)
+        )))),
+    }
+}
+
+//
+// Reading files during evaluation shows up as access events, and possibly as changes to
+// their access time, these must not trigger another run
+//
+fn changed_paths(event: notify::Result<Event>) -> Vec<PathBuf> {
+    match event {
+        Ok(event) => match event.kind {
+            EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_)) => vec![],
+            _ => event
+                .paths
+                .into_iter()
+                .filter(|path| is_watched_file(path))
+                .collect(),
+        },
+        Err(_) => vec![],
+    }
+}
+
+fn is_watched_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            DATA_FILE_SUPPORTED_EXTENSIONS
+                .iter()
+                .chain(RULE_FILE_SUPPORTED_EXTENSIONS.iter())
+                .any(|ext| name.ends_with(ext))
+        })
+}
+
+///
+/// Prints one line for each status that changed, appeared or disappeared between runs
+///
+pub(crate) fn print_changes(
+    previous: &Statuses,
+    current: &Statuses,
+    writer: &mut dyn Write,
+) -> Result<usize> {
+    let mut changes = 0;
+    for (key, status) in current {
+        match previous.get(key) {
+            Some(before) if before == status => {}
+            Some(before) => {
+                changes += 1;
+                writeln!(writer, "  {}: {} -> {}", key, before, status)?;
+            }
+            None => {
+                changes += 1;
+                writeln!(writer, "  {}: {} (new)", key, status)?;
+            }
+        }
+    }
+    for (key, status) in previous {
+        if !current.contains_key(key) {
+            changes += 1;
+            writeln!(writer, "  {}: {} (removed)", key, status)?;
+        }
+    }
+    if changes == 0 {
+        writeln!(writer, "  No status changes")?;
+    }
+    Ok(changes)
+}
+
+pub(crate) fn display(path: &Path) -> String {
+    match std::env::current_dir() {
+        Ok(cwd) => format!("{}", path.strip_prefix(&cwd).unwrap_or(path).display()),
+        Err(_) => format!("{}", path.display()),
+    }
+}
+
+///
+/// Paths are compared in absolute form, the watcher reports absolute paths
+///
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    })
+}
+
+///
+/// Rules file contents kept across runs, a change drops the entry for the file so that the next
+/// run reads it again. Parsing is cheap, the rules are parsed from the cached content each time
+/// they are evaluated.
+///
+#[derive(Default)]
+pub(crate) struct RulesCache {
+    files: HashMap<PathBuf, std::result::Result<(String, String), String>>,
+}
+
+impl RulesCache {
+    pub(crate) fn get(&mut self, path: &Path) -> std::result::Result<RulesFile<'_>, String> {
+        let (content, name) = self
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                File::open(path)
+                    .and_then(read_file_content)
+                    .map(|content| (content, display(path)))
+                    .map_err(|e| format!("Unable to read rules file, Error {}", e))
+            })
+            .as_ref()
+            .map_err(Clone::clone)?;
+        let span = crate::rules::parser::Span::new_extra(content, name);
+        crate::rules::parser::rules_file(span).map_err(|e| format!("Parse Error {}", e))
+    }
+
+    pub(crate) fn invalidate(&mut self, path: &Path) -> bool {
+        self.files.remove(path).is_some()
+    }
+}
+
+#[cfg(test)]
+#[path = "watch_tests.rs"]
+mod watch_tests;
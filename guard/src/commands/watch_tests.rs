@@ -0,0 +1,64 @@
+use super::*;
+use notify::event::{AccessKind, CreateKind, MetadataKind};
+
+fn statuses(entries: &[(&str, &str)]) -> Statuses {
+    entries
+        .iter()
+        .map(|(key, status)| (key.to_string(), status.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_print_changes() -> Result<()> {
+    let previous = statuses(&[("s3.guard R a.yaml", "FAIL"), ("s3.guard R b.yaml", "PASS")]);
+    let current = statuses(&[("s3.guard R a.yaml", "PASS"), ("s3.guard R c.yaml", "SKIP")]);
+    let mut out = Vec::new();
+    assert_eq!(print_changes(&previous, &current, &mut out)?, 3);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "  s3.guard R a.yaml: FAIL -> PASS\n  s3.guard R c.yaml: SKIP (new)\n  s3.guard R b.yaml: PASS (removed)\n"
+    );
+
+    let mut out = Vec::new();
+    assert_eq!(print_changes(&current, &current, &mut out)?, 0);
+    assert_eq!(String::from_utf8(out).unwrap(), "  No status changes\n");
+    Ok(())
+}
+
+#[test]
+fn test_changed_paths_ignore_reads_and_other_files() {
+    let event = |kind: EventKind, path: &str| Ok(Event::new(kind).add_path(PathBuf::from(path)));
+    assert_eq!(
+        changed_paths(event(
+            EventKind::Create(CreateKind::File),
+            "/rules/s3.guard"
+        )),
+        vec![PathBuf::from("/rules/s3.guard")]
+    );
+    assert!(changed_paths(event(
+        EventKind::Create(CreateKind::File),
+        "/rules/.s3.guard.swp"
+    ))
+    .is_empty());
+    assert!(changed_paths(event(EventKind::Access(AccessKind::Any), "/data/a.yaml")).is_empty());
+    assert!(changed_paths(event(
+        EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime)),
+        "/data/a.yaml"
+    ))
+    .is_empty());
+}
+
+#[test]
+fn test_rules_cache_reparses_after_invalidate() {
+    let path = std::env::temp_dir().join(format!("guard-watch-{}.guard", std::process::id()));
+    std::fs::write(&path, "rule r { Resources exists }").unwrap();
+    let mut cache = RulesCache::default();
+    assert!(cache.get(&path).is_ok());
+
+    std::fs::write(&path, "rule r { Resources exists ").unwrap();
+    assert!(cache.get(&path).is_ok());
+    assert!(cache.invalidate(&path));
+    assert!(cache.get(&path).is_err());
+    assert!(!cache.invalidate(Path::new("/not/cached.guard")));
+    std::fs::remove_file(&path).unwrap();
+}
@@ -8,7 +8,7 @@ mod rules;
 mod utils;
 
 use crate::command::Command;
-use crate::commands::{APP_NAME, APP_VERSION};
+use crate::commands::{APP_NAME, APP_VERSION, ERROR_STATUS_CODE};
 use rules::errors::Error;
 use std::process::exit;
 
@@ -29,6 +29,11 @@ fn main() -> Result<(), Error> {
     commands.push(Box::new(crate::commands::validate::Validate::new()));
     commands.push(Box::new(crate::commands::rulegen::Rulegen::new()));
     commands.push(Box::new(crate::commands::migrate::Migrate::new()));
+    commands.push(Box::new(crate::commands::fuzz::Fuzz::new()));
+    commands.push(Box::new(crate::commands::lsp::Lsp::new()));
+    commands.push(Box::new(crate::commands::fmt::Fmt::new()));
+    commands.push(Box::new(crate::commands::lint::Lint::new()));
+    commands.push(Box::new(crate::commands::repl::Repl::new()));
 
     let mappings = commands.iter().map(|s| (s.name(), s)).fold(
         HashMap::with_capacity(commands.len()),
@@ -42,14 +47,22 @@ fn main() -> Result<(), Error> {
         app = app.subcommand(each.command());
     }
 
-    let app = app.get_matches();
+    let args = match crate::commands::config::apply_project_config(&app, std::env::args().collect())
+    {
+        Ok(args) => args,
+        Err(e) => {
+            println!("Error occurred {}", e);
+            exit(ERROR_STATUS_CODE);
+        }
+    };
+    let app = app.get_matches_from(args);
     match app.subcommand() {
         (name, Some(value)) => {
             if let Some(command) = mappings.get(name) {
                 match (*command).execute(value) {
                     Err(e) => {
                         println!("Error occurred {}", e);
-                        exit(-1);
+                        exit(ERROR_STATUS_CODE);
                     }
                     Ok(code) => exit(code),
                 }
@@ -0,0 +1,302 @@
+use crate::migrate::violation::{
+    code_chars, guard_value, identifier, literal, rule_name, string_literal, swapped, Check,
+    Clause, PolicyReport, Segment, Violation,
+};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::Result;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, space0};
+use nom::combinator::{all_consuming, map, map_res, opt, value};
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use serde_json::Value;
+
+#[cfg(test)]
+#[path = "cfn_nag_tests.rs"]
+mod cfn_nag_tests;
+
+//
+// cfn-nag rules in JSON, an array of rules or an object with a "rules" array:
+//
+//   {
+//     "id": "F1",
+//     "type": "FAILING_VIOLATION",
+//     "message": "EBS volume should have server-side encryption enabled",
+//     "jq": "[.Resources | with_entries(.value.LogicalResourceId = .key)[] | select(.Type == \"AWS::EC2::Volume\" and .Properties.Encrypted != true)] | map(.LogicalResourceId)"
+//   }
+//
+// The resources the one select(...) of the jq expression picks are the ones in violation. Its
+// terms are joined with "and", and each compares a property path with a value, checks a path
+// with "|not", or is a path on its own. Rules without a jq expression, which cfn-nag implements
+// in Ruby, and other expressions are left as TODOs with their id and message.
+//
+// jq gives null for missing properties, so comparisons that hold for null hold for missing
+// properties too, e.g. `.Properties.Encrypted != true` picks volumes without Encrypted.
+//
+
+///
+/// Migrates each rule in a file of cfn-nag rules
+///
+pub(crate) fn report_policies(content: &str) -> Result<Vec<PolicyReport>> {
+    let rules = match serde_json::from_str::<Value>(content)? {
+        Value::Array(rules) => rules,
+        Value::Object(mut rules) => match rules.remove("rules") {
+            Some(Value::Array(rules)) => rules,
+            Some(_) => {
+                return Err(Error::new(ErrorKind::ParseError(String::from(
+                    "the rules of cfn-nag rules are expected to be an array",
+                ))))
+            }
+            None => vec![Value::Object(rules)],
+        },
+        _ => return Err(Error::new(ErrorKind::ParseError(String::from(
+            "cfn-nag rules are expected to be an array of rules, or an object with a rules array",
+        )))),
+    };
+
+    let mut reports = vec![];
+    for (idx, rule) in rules.iter().enumerate() {
+        let text = serde_json::to_string(rule)?;
+        let field = |name| rule.get(name).and_then(Value::as_str);
+        let id = match field("id") {
+            Some(id) => id,
+            None => {
+                reports.push(PolicyReport::unsupported(
+                    format!("rule_{}", idx + 1),
+                    1,
+                    &text,
+                    String::from("the rule has no id"),
+                ));
+                continue;
+            }
+        };
+        let line = content
+            .find(&format!("\"{}\"", id))
+            .map_or(1, |offset| content[..offset].lines().count().max(1));
+        let jq = match field("jq") {
+            Some(jq) => jq,
+            None => {
+                reports.push(PolicyReport::unsupported(
+                    rule_name(id),
+                    line,
+                    &text,
+                    String::from(
+                        "the rule has no jq expression, cfn-nag rules in Ruby are not migrated",
+                    ),
+                ));
+                continue;
+            }
+        };
+        reports.push(match violation(jq) {
+            Ok(mut violation) => {
+                violation.message = field("message").map(|message| format!("{} {}", id, message));
+                let mut caveats = vec![];
+                if field("type").is_some_and(|kind| kind.ends_with("WARNING")) {
+                    caveats.push(String::from(
+                        "WARNING rules fail like FAILING_VIOLATION rules in Guard",
+                    ));
+                }
+                PolicyReport::migrated(rule_name(id), line, &text, violation, caveats)
+            }
+            Err(reason) => PolicyReport::unsupported(rule_name(id), line, &text, reason),
+        });
+    }
+    Ok(reports)
+}
+
+fn violation(jq: &str) -> std::result::Result<Violation, String> {
+    let selects = jq.matches("select(").count();
+    let start = match jq.find("select(") {
+        Some(start) if selects == 1 && jq[..start].contains(".Resources") => {
+            start + "select(".len()
+        }
+        _ => {
+            return Err(String::from(
+                "the jq expression does not select from .Resources once",
+            ))
+        }
+    };
+    let condition = &jq[start..];
+    let end = code_chars(condition)
+        .into_iter()
+        .find(|(_, ch, depth)| *ch == ')' && *depth == -1)
+        .map_or(condition.len(), |(idx, _, _)| idx);
+    let condition = &condition[..end];
+
+    let mut resource_type = None;
+    let mut conditions = vec![];
+    for text in terms(condition)? {
+        let term = match all_consuming(term)(text) {
+            Ok((_, term)) => term,
+            Err(_) => return Err(format!("`{}` has no equivalent in Guard rules", text)),
+        };
+        let (segments, checks) = match term {
+            Term::Compare(segments, "==", Value::String(name))
+                if segments == [Segment::Key(String::from("Type"))] =>
+            {
+                match resource_type {
+                    None => resource_type = Some(name),
+                    Some(_) => return Err(String::from("checks more than one Type of resource")),
+                }
+                continue;
+            }
+            Term::Compare(segments, operator, value) => (segments, checks(operator, &value)),
+            Term::Not(segments) => (
+                segments,
+                vec![
+                    Check::NotExists,
+                    Check::Compare("==", String::from("false")),
+                ],
+            ),
+            Term::Truthy(segments) => (segments, vec![Check::Compare("!=", String::from("false"))]),
+        };
+        if checks.len() > 1 && segments.contains(&Segment::Any) {
+            return Err(format!(
+                "`{}` can hold for a missing property inside a list, which has no equivalent in Guard rules",
+                text
+            ));
+        }
+        conditions.push(
+            checks
+                .into_iter()
+                .map(|check| Clause::new(&segments, check))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        );
+    }
+    match resource_type {
+        Some(resource_type) => Ok(Violation {
+            resource_type,
+            conditions,
+            message: None,
+        }),
+        None => Err(String::from(
+            "the select does not check the .Type of a resource",
+        )),
+    }
+}
+
+//
+// The terms of a select condition joined by "and", or why they can't be migrated
+//
+fn terms(condition: &str) -> std::result::Result<Vec<&str>, String> {
+    let mut terms = vec![];
+    let mut start = 0;
+    let chars = code_chars(condition);
+    for (pos, (idx, ch, depth)) in chars.iter().enumerate() {
+        if *depth != 0 || !ch.is_whitespace() || *idx < start {
+            continue;
+        }
+        let word = chars[pos + 1..]
+            .iter()
+            .take_while(|(_, ch, depth)| *depth == 0 && ch.is_ascii_alphabetic())
+            .map(|(_, ch, _)| *ch)
+            .collect::<String>();
+        match word.as_str() {
+            "and" => {
+                terms.push(condition[start..*idx].trim());
+                start = idx + 1 + word.len();
+            }
+            "or" => {
+                return Err(String::from(
+                    "the select has terms joined with \"or\", which are not migrated",
+                ))
+            }
+            _ => {}
+        }
+    }
+    terms.push(condition[start..].trim());
+    Ok(terms
+        .into_iter()
+        .map(|term| {
+            term.strip_prefix('(')
+                .and_then(|term| term.strip_suffix(')'))
+                .map_or(term, str::trim)
+        })
+        .collect())
+}
+
+//
+// A path from a resource, e.g. .Properties.Tags[].Key
+//
+fn path(input: &str) -> IResult<&str, Vec<Segment>> {
+    many1(alt((
+        map(preceded(char('.'), identifier), |key| {
+            Segment::Key(key.to_string())
+        }),
+        preceded(
+            opt(char('.')),
+            alt((
+                value(Segment::Any, tag("[]")),
+                map(
+                    delimited(char('['), map_res(digit1, str::parse), char(']')),
+                    Segment::Index,
+                ),
+                map(
+                    delimited(
+                        char('['),
+                        map_res(string_literal, serde_json::from_str),
+                        char(']'),
+                    ),
+                    Segment::Key,
+                ),
+            )),
+        ),
+    )))(input)
+}
+
+fn operator(input: &str) -> IResult<&str, &'static str> {
+    delimited(
+        space0,
+        alt((
+            value("==", tag("==")),
+            value("!=", tag("!=")),
+            value("<=", tag("<=")),
+            value(">=", tag(">=")),
+            value("<", tag("<")),
+            value(">", tag(">")),
+        )),
+        space0,
+    )(input)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Term {
+    Compare(Vec<Segment>, &'static str, Value),
+    Not(Vec<Segment>),
+    Truthy(Vec<Segment>),
+}
+
+fn term(input: &str) -> IResult<&str, Term> {
+    alt((
+        map(
+            tuple((path, operator, literal)),
+            |(path, operator, value)| Term::Compare(path, operator, value),
+        ),
+        map(
+            tuple((literal, operator, path)),
+            |(value, operator, path)| Term::Compare(path, swapped(operator), value),
+        ),
+        map(
+            terminated(path, pair(delimited(space0, char('|'), space0), tag("not"))),
+            Term::Not,
+        ),
+        map(path, Term::Truthy),
+    ))(input)
+}
+
+//
+// The checks of which any one holds for a resource a comparison picks
+//
+fn checks(operator: &'static str, value: &Value) -> Vec<Check> {
+    match (operator, value) {
+        ("==", Value::Null) => vec![Check::NotExists],
+        ("!=", Value::Null) => vec![Check::Exists],
+        ("!=", _) | ("<", _) | ("<=", _) => vec![
+            Check::NotExists,
+            Check::Compare(operator, guard_value(value)),
+        ],
+        _ => vec![Check::Compare(operator, guard_value(value))],
+    }
+}
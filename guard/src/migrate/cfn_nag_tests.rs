@@ -0,0 +1,114 @@
+use super::*;
+
+use crate::migrate::report::{LineStatus, Reported};
+use crate::migrate::violation::violation_rules;
+
+const RULES: &str = r#"[
+  {
+    "id": "F1",
+    "type": "FAILING_VIOLATION",
+    "message": "EBS volume should have server-side encryption enabled",
+    "jq": "[.Resources | with_entries(.value.LogicalResourceId = .key)[] | select(.Type == \"AWS::EC2::Volume\" and .Properties.Encrypted != true)] | map(.LogicalResourceId)"
+  },
+  {
+    "id": "W35",
+    "type": "WARNING",
+    "message": "S3 Bucket should have access logging configured",
+    "jq": "[.Resources | with_entries(.value.LogicalResourceId = .key)[] | select(.Type == \"AWS::S3::Bucket\" and .Properties.LoggingConfiguration == null)] | map(.LogicalResourceId)"
+  },
+  {
+    "id": "W12",
+    "type": "WARNING",
+    "message": "IAM policy should not allow * resource",
+    "jq": "[.Resources | with_entries(.value.LogicalResourceId = .key)[] | select(.Type == \"AWS::IAM::Role\" and (.Properties.Path|not) and .Properties.Policies[].PolicyName == \"admin\" and 3600 < .Properties.MaxSessionDuration)] | map(.LogicalResourceId)"
+  }
+]"#;
+
+#[test]
+fn test_cfn_nag_rules() -> Result<()> {
+    let reports = report_policies(RULES)?;
+    let statuses = reports
+        .iter()
+        .map(|report| {
+            (
+                report.line(),
+                report.name.as_str(),
+                report.status.to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let warning = "migrated with caveats, WARNING rules fail like FAILING_VIOLATION rules in Guard";
+    assert_eq!(
+        statuses,
+        vec![
+            (3, "F1", String::from("migrated")),
+            (9, "W35", String::from(warning)),
+            (15, "W12", String::from(warning)),
+        ]
+    );
+    let expected = r#"let aws_ec2_volume = Resources.*[ Type == "AWS::EC2::Volume" ]
+rule F1 WHEN %aws_ec2_volume NOT EMPTY {
+    %aws_ec2_volume[ Properties.Encrypted NOT EXISTS OR Properties.Encrypted != true ] EMPTY <<F1 EBS volume should have server-side encryption enabled>>
+}
+
+let aws_s3_bucket = Resources.*[ Type == "AWS::S3::Bucket" ]
+rule W35 WHEN %aws_s3_bucket NOT EMPTY {
+    %aws_s3_bucket[ Properties.LoggingConfiguration NOT EXISTS ] EMPTY <<W35 S3 Bucket should have access logging configured>>
+}
+
+let aws_iam_role = Resources.*[ Type == "AWS::IAM::Role" ]
+rule W12 WHEN %aws_iam_role NOT EMPTY {
+    %aws_iam_role[ Properties.Path NOT EXISTS OR Properties.Path == false SOME Properties.Policies[*].PolicyName == 'admin' Properties.MaxSessionDuration > 3600 ] EMPTY <<W12 IAM policy should not allow * resource>>
+}
+
+"#;
+    assert_eq!(violation_rules(&reports)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_cfn_nag_rules_not_migrated() -> Result<()> {
+    let rules = r#"{ "rules": [
+  { "id": "F2", "message": "Ruby rule" },
+  { "id": "F3", "jq": ".Resources[] | select(.Type == \"AWS::IAM::User\" or .Type == \"AWS::IAM::Group\")" },
+  { "id": "F4", "jq": ".Resources[] | select(.Properties.Size > 10)" },
+  { "id": "F5", "jq": ".Resources[] | select(.Type == \"AWS::EC2::Volume\" and (.Properties.Size | tonumber) > 10)" },
+  { "id": "F6", "jq": ".Parameters[] | select(.Type == \"String\")" },
+  { "id": "F7", "jq": ".Resources[] | select(.Type == \"AWS::IAM::Role\" and .Properties.Policies[].PolicyName != \"admin\")" },
+  { "message": "no id" }
+] }"#;
+    let reports = report_policies(rules)?;
+    let reasons = reports
+        .iter()
+        .map(|report| match &report.status {
+            LineStatus::Unsupported(reason) => (report.name.as_str(), reason.as_str()),
+            _ => (report.name.as_str(), "migrated"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reasons,
+        vec![
+            ("F2", "the rule has no jq expression, cfn-nag rules in Ruby are not migrated"),
+            ("F3", "the select has terms joined with \"or\", which are not migrated"),
+            ("F4", "the select does not check the .Type of a resource"),
+            ("F5", "`(.Properties.Size | tonumber) > 10` has no equivalent in Guard rules"),
+            ("F6", "the jq expression does not select from .Resources once"),
+            (
+                "F7",
+                "`.Properties.Policies[].PolicyName != \"admin\"` can hold for a missing property inside a list, which has no equivalent in Guard rules"
+            ),
+            ("rule_7", "the rule has no id"),
+        ]
+    );
+    assert_eq!(reports[0].text, r#"{"id":"F2","message":"Ruby rule"}"#);
+    assert_eq!(reports[0].line, 2);
+    Ok(())
+}
+
+#[test]
+fn test_cfn_nag_rules_errors() {
+    assert!(report_policies("not json").is_err());
+    assert!(report_policies(r#"{ "rules": {} }"#).is_err());
+    assert!(report_policies(r#""F1""#).is_err());
+    assert!(report_policies("[]").is_ok_and(|reports| reports.is_empty()));
+}
@@ -0,0 +1,210 @@
+use crate::migrate::parser::{CmpOperator, OldGuardValues, PropertyComparison, Rule, RuleLineType};
+use crate::rules::display::value_text;
+use crate::rules::path_value::PathAwareValue;
+use crate::rules::values::Value;
+use crate::rules::Status;
+use std::collections::HashMap;
+
+#[cfg(test)]
+#[path = "evaluate_tests.rs"]
+mod evaluate_tests;
+
+//
+// Evaluates 1.0 rules the way the 1.0 tool did, to compare them with the migrated rules.
+//
+//   * every resource of the rule's type is checked, a rule passes when there are none
+//   * property paths start at Properties, or at the resource for paths starting with "."
+//   * "*" in a path matches every entry of a map or list, and every value matched must pass
+//   * a property that is missing fails the check
+//   * values are compared as text, so `Size == 100` matches both 100 and "100"
+//   * "<", ">", "<=" and ">=" compare numbers, and fail for anything else
+//   * a line with |OR| passes for a resource when any rule for its type passes
+//   * a WHEN ... CHECK rule only applies to resources that satisfy the WHEN comparison
+//   * %{NAME} is read from the environment
+//
+
+///
+/// The values assigned with let, by variable name
+///
+pub(crate) fn variables(lines: &[RuleLineType]) -> HashMap<String, OldGuardValues> {
+    lines
+        .iter()
+        .filter_map(|line| match line {
+            RuleLineType::Assignment(assignment) => {
+                Some((assignment.var_name.clone(), assignment.value.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+///
+/// The status of a 1.0 rule line against a template. Lines that are not clauses are skipped.
+///
+pub(crate) fn evaluate_line(
+    line: &RuleLineType,
+    variables: &HashMap<String, OldGuardValues>,
+    template: &PathAwareValue,
+) -> Status {
+    let clause = match line {
+        RuleLineType::Clause(clause) => clause,
+        _ => return Status::SKIP,
+    };
+    let resources = template
+        .as_map()
+        .and_then(|map| map.values.get("Resources"))
+        .and_then(PathAwareValue::as_map)
+        .map_or(vec![], |resources| resources.values.values().collect());
+
+    let mut status = Status::SKIP;
+    for resource in resources {
+        let resource_type = resource
+            .as_map()
+            .and_then(|map| map.values.get("Type"))
+            .and_then(PathAwareValue::as_string);
+        let mut applies = false;
+        let mut passed = false;
+        for rule in &clause.rules {
+            match rule {
+                Rule::Basic(basic) if Some(basic.type_name.type_name.as_str()) == resource_type => {
+                    applies = true;
+                    passed |= compare(resource, &basic.property_comparison, variables);
+                }
+                Rule::Conditional(conditional)
+                    if Some(conditional.type_name.type_name.as_str()) == resource_type
+                        && compare(resource, &conditional.when_condition, variables) =>
+                {
+                    applies = true;
+                    passed |= compare(resource, &conditional.check_condition, variables);
+                }
+                _ => {}
+            }
+        }
+        if applies {
+            if !passed {
+                return Status::FAIL;
+            }
+            status = Status::PASS;
+        }
+    }
+    status
+}
+
+fn compare(
+    resource: &PathAwareValue,
+    comparison: &PropertyComparison,
+    variables: &HashMap<String, OldGuardValues>,
+) -> bool {
+    let expected = match resolve(&comparison.comparison_value, variables, 0) {
+        Some(expected) => expected,
+        None => return false,
+    };
+    let values = retrieve(resource, &comparison.property_path);
+    !values.is_empty()
+        && values
+            .iter()
+            .all(|each| holds(comparison.operator, &expected, &text(each)))
+}
+
+//
+// The value a variable has, following variables assigned from other variables
+//
+fn resolve(
+    value: &OldGuardValues,
+    variables: &HashMap<String, OldGuardValues>,
+    depth: usize,
+) -> Option<Value> {
+    match value {
+        OldGuardValues::Value(value) => Some(value.clone()),
+        OldGuardValues::VariableAccess(name) if depth < variables.len() => {
+            resolve(variables.get(name)?, variables, depth + 1)
+        }
+        OldGuardValues::VariableAccess(_) => None,
+        OldGuardValues::EnvironmentVariable(name) => std::env::var(name).ok().map(Value::String),
+    }
+}
+
+fn retrieve<'v>(resource: &'v PathAwareValue, path: &str) -> Vec<&'v PathAwareValue> {
+    let (start, path) = match path.strip_prefix('.') {
+        Some(path) => (Some(resource), path),
+        None => (
+            resource
+                .as_map()
+                .and_then(|map| map.values.get("Properties")),
+            path,
+        ),
+    };
+    let mut current = start.into_iter().collect::<Vec<_>>();
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        current = current
+            .into_iter()
+            .flat_map(|value| match (key, value) {
+                ("*", PathAwareValue::Map((_, map))) => map.values.values().collect(),
+                ("*", PathAwareValue::List((_, list))) => list.iter().collect(),
+                (key, PathAwareValue::Map((_, map))) => map.values.get(key).into_iter().collect(),
+                (key, PathAwareValue::List((_, list))) => key
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| list.get(idx))
+                    .into_iter()
+                    .collect(),
+                _ => vec![],
+            })
+            .collect();
+    }
+    current
+}
+
+fn holds(operator: CmpOperator, expected: &Value, actual: &str) -> bool {
+    match operator {
+        CmpOperator::Eq => equals(expected, actual),
+        CmpOperator::Ne => !equals(expected, actual),
+        CmpOperator::In => members(expected).iter().any(|each| equals(each, actual)),
+        CmpOperator::NotIn => !members(expected).iter().any(|each| equals(each, actual)),
+        CmpOperator::Gt | CmpOperator::Lt | CmpOperator::Le | CmpOperator::Ge => {
+            match (actual.parse::<f64>(), value_string(expected).parse::<f64>()) {
+                (Ok(actual), Ok(expected)) => match operator {
+                    CmpOperator::Gt => actual > expected,
+                    CmpOperator::Lt => actual < expected,
+                    CmpOperator::Le => actual <= expected,
+                    _ => actual >= expected,
+                },
+                _ => false,
+            }
+        }
+    }
+}
+
+fn equals(expected: &Value, actual: &str) -> bool {
+    match expected {
+        Value::Regex(regex) => regex::Regex::new(regex).is_ok_and(|regex| regex.is_match(actual)),
+        expected => value_string(expected) == actual,
+    }
+}
+
+fn members(expected: &Value) -> Vec<Value> {
+    match expected {
+        Value::List(list) => list.clone(),
+        single => vec![single.clone()],
+    }
+}
+
+//
+// The text a template value was compared as
+//
+fn text(value: &PathAwareValue) -> String {
+    match value {
+        PathAwareValue::String((_, text)) => text.clone(),
+        PathAwareValue::Int((_, number)) => number.to_string(),
+        PathAwareValue::Float((_, number)) => number.to_string(),
+        PathAwareValue::Bool((_, flag)) => flag.to_string(),
+        other => value_text(other),
+    }
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+use crate::commands::validate::get_path_aware_value_from_data;
+use crate::migrate::parser::parse_rules_file;
+use crate::rules::Result;
+
+const TEMPLATE: &str = r#"
+Resources:
+  Data:
+    Type: AWS::EC2::Volume
+    Properties:
+      Encrypted: true
+      Size: "100"
+      AvailabilityZone: us-east-1a
+      Tags:
+        - { Key: team, Value: storage }
+        - { Key: owner, Value: storage }
+  Logs:
+    Type: AWS::EC2::Volume
+    Properties:
+      Encrypted: "true"
+      Size: 50
+      AvailabilityZone: us-east-1b
+  Web:
+    Type: AWS::EC2::Instance
+    Properties:
+      InstanceType: m2.large
+    DeletionPolicy: Retain
+"#;
+
+fn statuses(rules: &str) -> Result<Vec<Status>> {
+    let lines = parse_rules_file(&rules.to_string(), &String::from("rules.ruleset"))?;
+    let template = get_path_aware_value_from_data(&TEMPLATE.to_string())?;
+    let variables = variables(&lines);
+    Ok(lines
+        .iter()
+        .filter(|line| matches!(line, RuleLineType::Clause(_)))
+        .map(|line| evaluate_line(line, &variables, &template))
+        .collect())
+}
+
+#[test]
+fn test_evaluate_compares_text() -> Result<()> {
+    let rules = r#"AWS::EC2::Volume Encrypted == true
+AWS::EC2::Volume Size == 100
+AWS::EC2::Volume Size IN [100, 50]
+AWS::EC2::Volume AvailabilityZone == /^us-east-1/
+AWS::EC2::Volume AvailabilityZone != us-east-1a
+AWS::EC2::Volume AvailabilityZone NOT_IN [eu-west-1a, eu-west-1b]
+AWS::EC2::Volume Size >= 50
+AWS::EC2::Volume Size < 100
+AWS::EC2::Volume AvailabilityZone > 1
+AWS::S3::Bucket BucketName == logs
+"#;
+    assert_eq!(
+        statuses(rules)?,
+        vec![
+            Status::PASS,
+            Status::FAIL,
+            Status::PASS,
+            Status::PASS,
+            Status::FAIL,
+            Status::PASS,
+            Status::PASS,
+            Status::FAIL,
+            Status::FAIL,
+            Status::SKIP,
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_paths_and_rules() -> Result<()> {
+    let rules = r#"AWS::EC2::Volume Tags.*.Value == storage
+AWS::EC2::Volume Tags.*.Key == team
+AWS::EC2::Volume Tags.1.Key == owner
+AWS::EC2::Instance .DeletionPolicy == Retain
+AWS::EC2::Instance DeletionPolicy == Retain
+AWS::EC2::Volume WHEN Size == 100 CHECK Tags.0.Key == team
+AWS::EC2::Volume WHEN Size == 100 CHECK Tags.0.Key == owner
+AWS::EC2::Volume WHEN Size == 1 CHECK Tags.0.Key == owner
+AWS::EC2::Volume Size == 100 |OR| AWS::EC2::Volume Size == 50
+AWS::EC2::Volume Size == 100 |OR| AWS::EC2::Instance InstanceType == m2.large
+"#;
+    assert_eq!(
+        statuses(rules)?,
+        vec![
+            Status::FAIL,
+            Status::FAIL,
+            Status::FAIL,
+            Status::PASS,
+            Status::FAIL,
+            Status::PASS,
+            Status::FAIL,
+            Status::SKIP,
+            Status::PASS,
+            Status::FAIL,
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_variables() -> Result<()> {
+    std::env::set_var("CFN_GUARD_MIGRATE_TEST_ZONES", "us-east-1a");
+    let rules = r#"let zones = [us-east-1a, us-east-1b]
+let encrypted = true
+let same = %encrypted
+let loop = %loop
+AWS::EC2::Volume AvailabilityZone IN %zones
+AWS::EC2::Volume Encrypted == %same
+AWS::EC2::Volume Encrypted == %loop
+AWS::EC2::Volume Encrypted == %missing
+AWS::EC2::Instance .Properties.InstanceType != %{CFN_GUARD_MIGRATE_TEST_ZONES}
+AWS::EC2::Instance InstanceType == %{CFN_GUARD_MIGRATE_TEST_UNSET}
+"#;
+    assert_eq!(
+        statuses(rules)?,
+        vec![
+            Status::PASS,
+            Status::PASS,
+            Status::FAIL,
+            Status::FAIL,
+            Status::PASS,
+            Status::FAIL,
+        ]
+    );
+    Ok(())
+}
@@ -1 +1,6 @@
+pub(crate) mod cfn_nag;
+pub(crate) mod evaluate;
 pub(crate) mod parser;
+pub(crate) mod rego;
+pub(crate) mod report;
+pub(crate) mod violation;
@@ -1,4 +1,4 @@
-use crate::rules::errors::Error;
+use crate::rules::errors::{Error, ErrorKind};
 use crate::rules::parser::{
     parse_value, type_name, var_name, var_name_access, IResult, ParserError, Span,
 };
@@ -22,12 +22,14 @@ mod parser_tests;
 pub enum OldGuardValues {
     Value(Value),
     VariableAccess(String),
+    EnvironmentVariable(String),
 }
 impl Display for OldGuardValues {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             OldGuardValues::Value(value) => write!(f, "{}", value),
             OldGuardValues::VariableAccess(s) => write!(f, "%{}", s),
+            OldGuardValues::EnvironmentVariable(s) => write!(f, "%{{{}}}", s),
         }
     }
 }
@@ -39,6 +41,9 @@ impl Hash for OldGuardValues {
             OldGuardValues::VariableAccess(s) => {
                 s.hash(state);
             }
+            OldGuardValues::EnvironmentVariable(s) => {
+                s.hash(state);
+            }
         }
     }
 }
@@ -238,6 +243,10 @@ pub(in crate::migrate) fn parse_old_guard_value(input: Span) -> IResult<Span, Ol
         map(terminated(parse_value, space0), |v| {
             OldGuardValues::Value(v)
         }),
+        map(
+            delimited(space0, delimited(tag("%{"), var_name, tag("}")), space0),
+            OldGuardValues::EnvironmentVariable,
+        ),
         map(parse_variable_dereference, |s| {
             OldGuardValues::VariableAccess(s)
         }),
@@ -270,7 +279,31 @@ pub(crate) fn assignment(input: Span) -> IResult<Span, Assignment> {
         cut(preceded(space0, tag("="))),
     ))(input)?;
     let (remaining, value) = parse_old_guard_value(input)?;
-    Ok((remaining, Assignment { value, var_name }))
+    Ok((
+        remaining,
+        Assignment {
+            value: bare_list(value),
+            var_name,
+        },
+    ))
+}
+
+// lists for assignments, IN and NOT_IN can be written without quotes, e.g. [us-east-1a, us-east-1b]
+// returns the list of strings for a value taken as a bare string
+fn bare_list(value: OldGuardValues) -> OldGuardValues {
+    match value {
+        OldGuardValues::Value(Value::String(list))
+            if list.starts_with('[') && list.ends_with(']') =>
+        {
+            OldGuardValues::Value(Value::List(
+                list[1..list.len() - 1]
+                    .split(',')
+                    .map(|each| Value::String(each.trim().to_string()))
+                    .collect(),
+            ))
+        }
+        value => value,
+    }
 }
 
 // value_operator = "==" / "!=" / "<" / ">" / "<=" / ">=" / %s"IN" / %s"NOT_IN"
@@ -314,6 +347,11 @@ pub(in crate::migrate) fn property_comparison(input: Span) -> IResult<Span, Prop
     // comparison value
     let (remaining, comparison_value) =
         cut(preceded(space1, parse_old_guard_value))(remaining_for_value)?;
+
+    let comparison_value = match operator {
+        CmpOperator::In | CmpOperator::NotIn => bare_list(comparison_value),
+        _ => comparison_value,
+    };
     Ok((
         remaining,
         PropertyComparison {
@@ -440,3 +478,31 @@ pub(crate) fn parse_rules_file(
     }
     Ok(rule_lines)
 }
+
+// parses each line like parse_rules_file, but on its own, so that a line that can't be parsed
+// doesn't stop the lines after it from being migrated
+// returns each line with its ruleline enum, or why it could not be parsed
+pub(crate) fn parse_rule_lines<'a>(
+    input: &'a str,
+    file_name: &str,
+) -> Vec<(&'a str, Result<RuleLineType, Error>)> {
+    let mut rule_lines = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let context = format!("{}:{}", file_name, i);
+
+        let line_span = Span::new_extra(line, context.as_str());
+        let parsed = match rule_line(line_span) {
+            Ok((_result, parsed_rule_line)) => Ok(parsed_rule_line),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                Err(Error::new(ErrorKind::ParseError(format!(
+                    "could not be parsed at column {}, near `{}`",
+                    e.span.get_utf8_column(),
+                    e.span.fragment().trim()
+                ))))
+            }
+            Err(e) => Err(Error::from(e)),
+        };
+        rule_lines.push((line, parsed));
+    }
+    rule_lines
+}
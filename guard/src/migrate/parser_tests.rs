@@ -584,3 +584,45 @@ fn test_disjunction_conditional_clauses() {
 fn make_empty_span(offset: usize) -> Span<'static> {
     unsafe { Span::new_from_raw_offset(offset, 1, "", "") }
 }
+
+#[test]
+fn test_parse_rule_lines() {
+    let example = "let version = %{POLICY_VERSION}\n\
+     AWS::EC2::Volume AvailabilityZone IN [us-east-1a, us-east-1b]\n\
+     AWS::EC2::Volume Size IS_SET\n\
+     AWS::EC2::Volume Size NOT_IN [\"1\", 2]\n";
+
+    let lines = parse_rule_lines(example, "file_name");
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0].1.as_ref().unwrap(),
+        &RuleLineType::Assignment(Assignment {
+            var_name: String::from("version"),
+            value: OldGuardValues::EnvironmentVariable(String::from("POLICY_VERSION")),
+        })
+    );
+
+    let availability_zone = match lines[1].1.as_ref().unwrap() {
+        RuleLineType::Clause(clause) => match &clause.rules[0] {
+            Rule::Basic(rule) => rule.property_comparison.comparison_value.clone(),
+            rule => panic!("unexpected rule {:?}", rule),
+        },
+        line => panic!("unexpected line {:?}", line),
+    };
+    assert_eq!(
+        availability_zone,
+        OldGuardValues::Value(Value::List(vec![
+            Value::String(String::from("us-east-1a")),
+            Value::String(String::from("us-east-1b")),
+        ]))
+    );
+
+    assert_eq!(lines[2].0, "AWS::EC2::Volume Size IS_SET");
+    assert!(lines[2]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("could not be parsed at column 23, near `IS_SET`"));
+    assert!(lines[3].1.is_ok());
+}
@@ -0,0 +1,478 @@
+use crate::migrate::violation::{
+    code_chars, guard_value, identifier, literal, rule_name, string_literal, swapped, Check,
+    Clause, PolicyReport, Segment, Violation,
+};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, space0, space1};
+use nom::combinator::{all_consuming, map, map_res, opt, rest, value};
+use nom::multi::{many0, separated_nonempty_list};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(test)]
+#[path = "rego_tests.rs"]
+mod rego_tests;
+
+//
+// The subset of Rego that is migrated, deny (or violation, or warn) rules over the resources
+// of a CloudFormation template:
+//
+//   deny[msg] {
+//       resource := input.Resources[name]          # or some name, resource in input.Resources
+//       resource.Type == "AWS::S3::Bucket"
+//       not resource.Properties.BucketEncryption   # or resource.Properties.X == "value"
+//       count(resource.Properties.Tags) == 0       # or count(...) > 0, as EMPTY and NOT EMPTY
+//       msg := sprintf("%s is not encrypted", [name])
+//   }
+//
+// Each statement in the body checks the one resource, named or as input.Resources[_], and
+// becomes a condition of the violation. package and import lines are skipped, and any other
+// rule or statement is left as a TODO.
+//
+
+//
+// A reference, e.g. input.Resources[_].Properties.Tags[i].Key
+//
+#[derive(Debug, PartialEq, Clone)]
+enum Part {
+    Key(String),
+    Index(usize),
+    Var(String),
+}
+
+type Reference = (String, Vec<Part>);
+
+#[derive(Debug, PartialEq, Clone)]
+enum Statement {
+    Some,
+    Resource(String),
+    Message(String, String),
+    Compare(Reference, &'static str, serde_json::Value),
+    NotCompare(Reference, &'static str, serde_json::Value),
+    Count(Reference, &'static str, serde_json::Value),
+    Not(Reference),
+    Truthy(Reference),
+}
+
+///
+/// Migrates each rule in a Rego policy file
+///
+pub(crate) fn report_policies(content: &str) -> Vec<PolicyReport> {
+    let mut counts = HashMap::new();
+    let mut reports = vec![];
+    for (line, text) in rule_texts(content) {
+        let head_end = code_chars(&text)
+            .into_iter()
+            .find(|(_, ch, _)| *ch == '{')
+            .map_or(text.len(), |(idx, _, _)| idx);
+        let head = text[..head_end].trim();
+        let (kind, message_var) = match all_consuming(rule_head)(head) {
+            Ok((_, head)) => head,
+            Err(_) => {
+                let name = head
+                    .split(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_')
+                    .next()
+                    .unwrap_or("");
+                reports.push(PolicyReport::unsupported(
+                    rule_name(name),
+                    line,
+                    &text,
+                    String::from("only deny, violation and warn rules are migrated"),
+                ));
+                continue;
+            }
+        };
+        let count = counts.entry(kind.to_string()).or_insert(0);
+        *count += 1;
+        let name = format!("{}_{}", kind, count);
+        let body = match text.rfind('}') {
+            Some(body_end) if body_end > head_end => &text[head_end + 1..body_end],
+            _ => "",
+        };
+        reports.push(match violation(body, message_var) {
+            Ok((violation, mut caveats)) => {
+                if kind == "warn" {
+                    caveats.push(String::from("warn rules fail like deny rules in Guard"));
+                }
+                PolicyReport::migrated(name, line, &text, violation, caveats)
+            }
+            Err(reason) => PolicyReport::unsupported(name, line, &text, reason),
+        });
+    }
+    reports
+}
+
+//
+// The rules of a file with the lines they start on, without comments
+//
+fn rule_texts(content: &str) -> Vec<(usize, String)> {
+    let mut rules = vec![];
+    let mut current: Option<(usize, String)> = None;
+    let mut depth = 0;
+    for (idx, line) in content.lines().enumerate() {
+        let chars = code_chars(line);
+        let code = match chars.iter().find(|(_, ch, _)| *ch == '#') {
+            Some((comment, _, _)) => &line[..*comment],
+            None => line,
+        };
+        let (text, start) = match current.take() {
+            Some((start, text)) => (text, start),
+            None => {
+                let trimmed = code.trim();
+                if trimmed.is_empty()
+                    || trimmed.starts_with("package ")
+                    || trimmed.starts_with("import ")
+                {
+                    continue;
+                }
+                (String::new(), idx + 1)
+            }
+        };
+        depth += code_chars(code)
+            .iter()
+            .map(|(_, ch, _)| match ch {
+                '{' => 1,
+                '}' => -1,
+                _ => 0,
+            })
+            .sum::<i32>();
+        let text = format!("{}{}\n", text, code.trim_end());
+        if depth > 0 {
+            current = Some((start, text));
+        } else {
+            depth = 0;
+            rules.push((start, text));
+        }
+    }
+    rules.extend(current);
+    rules
+}
+
+//
+// The statements of a rule body, separated by new lines or semicolons
+//
+fn statements(body: &str) -> Vec<&str> {
+    let mut statements = vec![];
+    let mut start = 0;
+    for (idx, ch, depth) in code_chars(body) {
+        if depth == 0 && (ch == '\n' || ch == ';') {
+            statements.push(body[start..idx].trim());
+            start = idx + 1;
+        }
+    }
+    statements.push(body[start..].trim());
+    statements.retain(|statement| !statement.is_empty());
+    statements
+}
+
+fn violation(
+    body: &str,
+    message_var: Option<&str>,
+) -> std::result::Result<(Violation, Vec<String>), String> {
+    let mut resources = BTreeSet::new();
+    let mut implicit = vec![];
+    let mut resource_type = None;
+    let mut conditions = vec![];
+    let mut message = None;
+    for text in statements(body) {
+        let unsupported = || format!("`{}` has no equivalent in Guard rules", text);
+        let statement = match all_consuming(statement)(text) {
+            Ok((_, statement)) => statement,
+            Err(_) => return Err(unsupported()),
+        };
+        let reference = match &statement {
+            Statement::Some => continue,
+            Statement::Resource(name) => {
+                resources.insert(name.clone());
+                continue;
+            }
+            Statement::Message(name, text) if Some(name.as_str()) == message_var => {
+                message = Some(text.clone());
+                continue;
+            }
+            Statement::Message(..) => return Err(unsupported()),
+            Statement::Compare(reference, ..)
+            | Statement::NotCompare(reference, ..)
+            | Statement::Count(reference, ..)
+            | Statement::Not(reference)
+            | Statement::Truthy(reference) => reference,
+        };
+        let parts = match reference {
+            (name, parts) if resources.contains(name) => parts.clone(),
+            (name, parts) if name == "input" && parts.len() >= 2 => match &parts[..2] {
+                [Part::Key(key), Part::Var(var)] if key == "Resources" => {
+                    implicit.push(var.clone());
+                    parts[2..].to_vec()
+                }
+                _ => return Err(unsupported()),
+            },
+            _ => return Err(unsupported()),
+        };
+        if parts == [Part::Key(String::from("Type"))] {
+            match &statement {
+                Statement::Compare(_, "==", serde_json::Value::String(name))
+                    if resource_type.is_none() =>
+                {
+                    resource_type = Some(name.clone());
+                    continue;
+                }
+                Statement::Compare(_, "==", serde_json::Value::String(_)) => {
+                    return Err(String::from("checks more than one Type of resource"))
+                }
+                _ => return Err(unsupported()),
+            }
+        }
+        let check = match statement {
+            Statement::Compare(_, operator, value) => {
+                vec![Check::Compare(operator, guard_value(&value))]
+            }
+            Statement::NotCompare(_, operator, value) => vec![
+                Check::NotExists,
+                Check::Compare(negated(operator), guard_value(&value)),
+            ],
+            Statement::Count(_, operator, value) => match (operator, value.as_u64()) {
+                ("==", Some(0)) => vec![Check::Empty],
+                (">", Some(0)) | ("!=", Some(0)) => vec![Check::NotEmpty],
+                _ => return Err(unsupported()),
+            },
+            Statement::Not(_) => vec![
+                Check::NotExists,
+                Check::Compare("==", String::from("false")),
+            ],
+            _ => vec![Check::Compare("!=", String::from("false"))],
+        };
+        let segments = parts
+            .into_iter()
+            .map(|part| match part {
+                Part::Key(key) => Segment::Key(key),
+                Part::Index(idx) => Segment::Index(idx),
+                Part::Var(_) => Segment::Any,
+            })
+            .collect::<Vec<_>>();
+        if check.contains(&Check::Empty) || check.contains(&Check::NotEmpty) {
+            if segments.contains(&Segment::Any) {
+                return Err(unsupported());
+            }
+            //
+            // count() of a value that is not there is undefined in Rego, where Guard takes it to
+            // be empty
+            //
+            if check == [Check::Empty] {
+                conditions.push(vec![Clause::new(&segments, Check::Exists)?]);
+            }
+        }
+        if check.len() > 1 && segments.contains(&Segment::Any) {
+            return Err(format!(
+                "`{}` negates a check over every value at a path, which has no equivalent in Guard rules",
+                text
+            ));
+        }
+        conditions.push(
+            check
+                .into_iter()
+                .map(|check| Clause::new(&segments, check))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        );
+    }
+
+    if resources.len() + (!implicit.is_empty() as usize) > 1 {
+        return Err(String::from("checks more than one resource"));
+    }
+    let resource_type = match resource_type {
+        Some(resource_type) => resource_type,
+        None => return Err(String::from("does not check the Type of a resource")),
+    };
+    let mut caveats = vec![];
+    let keys = implicit.iter().collect::<BTreeSet<_>>();
+    if keys.len() > 1 || (implicit.len() > 1 && keys.contains(&String::from("_"))) {
+        caveats.push(String::from(
+            "each input.Resources[_] is taken to be the same resource",
+        ));
+    }
+    Ok((
+        Violation {
+            resource_type,
+            conditions,
+            message,
+        },
+        caveats,
+    ))
+}
+
+fn negated(operator: &'static str) -> &'static str {
+    match operator {
+        "==" => "!=",
+        "!=" => "==",
+        "<" => ">=",
+        ">=" => "<",
+        ">" => "<=",
+        _ => ">",
+    }
+}
+
+//
+// The kind of rule, deny, violation or warn, and the variable with its message
+//
+fn rule_head(input: &str) -> IResult<&str, (&str, Option<&str>)> {
+    terminated(
+        pair(
+            alt((tag("deny"), tag("violation"), tag("warn"))),
+            alt((
+                map(
+                    delimited(
+                        pair(space0, char('[')),
+                        delimited(space0, identifier, space0),
+                        char(']'),
+                    ),
+                    Some,
+                ),
+                map(
+                    preceded(tuple((space1, tag("contains"), space1)), identifier),
+                    Some,
+                ),
+                value(None, space0),
+            )),
+        ),
+        opt(preceded(space1, tag("if"))),
+    )(input)
+}
+
+fn reference(input: &str) -> IResult<&str, Reference> {
+    map(
+        pair(
+            identifier,
+            many0(alt((
+                map(preceded(char('.'), identifier), |key| {
+                    Part::Key(key.to_string())
+                }),
+                map(
+                    delimited(
+                        char('['),
+                        map_res(string_literal, serde_json::from_str),
+                        char(']'),
+                    ),
+                    Part::Key,
+                ),
+                map(
+                    delimited(char('['), map_res(digit1, str::parse), char(']')),
+                    Part::Index,
+                ),
+                map(delimited(char('['), identifier, char(']')), |var| {
+                    Part::Var(var.to_string())
+                }),
+            ))),
+        ),
+        |(name, parts)| (name.to_string(), parts),
+    )(input)
+}
+
+fn operator(input: &str) -> IResult<&str, &'static str> {
+    delimited(
+        space0,
+        alt((
+            value("==", tag("==")),
+            value("!=", tag("!=")),
+            value("<=", tag("<=")),
+            value(">=", tag(">=")),
+            value("<", tag("<")),
+            value(">", tag(">")),
+            value("==", terminated(char('='), space0)),
+        )),
+        space0,
+    )(input)
+}
+
+fn assign(input: &str) -> IResult<&str, &str> {
+    delimited(space0, alt((tag(":="), tag("="))), space0)(input)
+}
+
+fn statement(input: &str) -> IResult<&str, Statement> {
+    alt((
+        map(
+            preceded(
+                pair(tag("some"), space1),
+                alt((
+                    preceded(
+                        pair(identifier, delimited(space0, char(','), space0)),
+                        terminated(
+                            identifier,
+                            tuple((space1, tag("in"), space1, tag("input.Resources"))),
+                        ),
+                    ),
+                    terminated(
+                        identifier,
+                        tuple((space1, tag("in"), space1, tag("input.Resources"))),
+                    ),
+                )),
+            ),
+            |name| Statement::Resource(name.to_string()),
+        ),
+        value(
+            Statement::Some,
+            preceded(
+                pair(tag("some"), space1),
+                separated_nonempty_list(delimited(space0, char(','), space0), identifier),
+            ),
+        ),
+        map(
+            terminated(
+                identifier,
+                tuple((assign, tag("input.Resources["), identifier, char(']'))),
+            ),
+            |name| Statement::Resource(name.to_string()),
+        ),
+        map(
+            pair(
+                identifier,
+                preceded(
+                    assign,
+                    alt((
+                        string_literal,
+                        terminated(
+                            preceded(pair(tag("sprintf("), space0), string_literal),
+                            rest,
+                        ),
+                    )),
+                ),
+            ),
+            |(name, text)| {
+                let text = serde_json::from_str(text).unwrap_or_else(|_| text.to_string());
+                Statement::Message(name.to_string(), text)
+            },
+        ),
+        map(
+            preceded(
+                pair(tag("not"), space1),
+                tuple((reference, operator, literal)),
+            ),
+            |(reference, operator, value)| Statement::NotCompare(reference, operator, value),
+        ),
+        map(
+            preceded(pair(tag("not"), space1), reference),
+            Statement::Not,
+        ),
+        map(
+            tuple((
+                delimited(
+                    pair(tag("count("), space0),
+                    reference,
+                    pair(space0, char(')')),
+                ),
+                operator,
+                literal,
+            )),
+            |(reference, operator, value)| Statement::Count(reference, operator, value),
+        ),
+        map(
+            tuple((reference, operator, literal)),
+            |(reference, operator, value)| Statement::Compare(reference, operator, value),
+        ),
+        map(
+            tuple((literal, operator, reference)),
+            |(value, operator, reference)| Statement::Compare(reference, swapped(operator), value),
+        ),
+        map(reference, Statement::Truthy),
+    ))(input)
+}
@@ -0,0 +1,232 @@
+use super::*;
+
+use crate::commands::common_test_helpers::status;
+use crate::migrate::report::{todo_markers, LineStatus};
+use crate::migrate::violation::violation_rules;
+use crate::rules::{Result, Status};
+
+const POLICY: &str = r#"package cfn.storage
+
+import future.keywords.in
+
+# buckets are encrypted
+deny[msg] {
+    bucket := input.Resources[name]
+    bucket.Type == "AWS::S3::Bucket"
+    not bucket.Properties.BucketEncryption
+    msg := sprintf("%s is not encrypted", [name])
+}
+
+deny[msg] {
+    input.Resources[_].Type == "AWS::EC2::Volume"
+    100 < input.Resources[_].Properties.Size
+    msg := "volumes are 100 GiB or less"
+}
+
+warn contains msg if {
+    some name, role in input.Resources
+    role.Type == "AWS::IAM::Role"; role.Properties.Policies[_].PolicyName == "admin"
+}
+"#;
+
+#[test]
+fn test_rego_policies() -> Result<()> {
+    let reports = report_policies(POLICY);
+    let statuses = reports
+        .iter()
+        .map(|report| (report.line, report.name.as_str(), report.status.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            (6, "deny_1", String::from("migrated")),
+            (
+                13,
+                "deny_2",
+                String::from(
+                    "migrated with caveats, each input.Resources[_] is taken to be the same resource"
+                )
+            ),
+            (
+                19,
+                "warn_1",
+                String::from("migrated with caveats, warn rules fail like deny rules in Guard")
+            ),
+        ]
+    );
+    let expected = r#"let aws_s3_bucket = Resources.*[ Type == "AWS::S3::Bucket" ]
+rule deny_1 WHEN %aws_s3_bucket NOT EMPTY {
+    %aws_s3_bucket[ Properties.BucketEncryption NOT EXISTS OR Properties.BucketEncryption == false ] EMPTY <<%s is not encrypted>>
+}
+
+let aws_ec2_volume = Resources.*[ Type == "AWS::EC2::Volume" ]
+rule deny_2 WHEN %aws_ec2_volume NOT EMPTY {
+    %aws_ec2_volume[ Properties.Size > 100 ] EMPTY <<volumes are 100 GiB or less>>
+}
+
+let aws_iam_role = Resources.*[ Type == "AWS::IAM::Role" ]
+rule warn_1 WHEN %aws_iam_role NOT EMPTY {
+    %aws_iam_role[ SOME Properties.Policies[*].PolicyName == 'admin' ] EMPTY
+}
+
+"#;
+    assert_eq!(violation_rules(&reports)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_rego_policies_evaluate_like_rego() -> Result<()> {
+    let rules = violation_rules(&report_policies(POLICY))?;
+    let template = r#"
+Resources:
+  Logs:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketEncryption: { ServerSideEncryptionConfiguration: [] }
+  Data:
+    Type: AWS::EC2::Volume
+    Properties: { Size: 100 }
+  Role:
+    Type: AWS::IAM::Role
+    Properties:
+      Policies: [{ PolicyName: read }, { PolicyName: write }]
+"#;
+    assert_eq!(status(&rules, template)?, Status::PASS);
+    assert_eq!(
+        status(&rules, &template.replace("Size: 100", "Size: 200"))?,
+        Status::FAIL
+    );
+    assert_eq!(
+        status(
+            &rules,
+            &template.replace("PolicyName: write", "PolicyName: admin")
+        )?,
+        Status::FAIL
+    );
+    assert_eq!(
+        status(
+            &rules,
+            &template.replace("BucketEncryption:", "BucketLogging:")
+        )?,
+        Status::FAIL
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rego_count_checks() -> Result<()> {
+    let policy = r#"package cfn
+
+deny[msg] {
+    r := input.Resources[_]
+    r.Type == "AWS::S3::Bucket"
+    count(r.Properties.Tags) == 0
+    msg := "buckets are tagged"
+}
+
+deny[msg] {
+    r := input.Resources[_]
+    r.Type == "AWS::S3::Bucket"
+    count(r.Properties.CorsConfiguration.CorsRules) > 0
+}
+"#;
+    let rules = violation_rules(&report_policies(policy))?;
+    assert_eq!(
+        rules,
+        r#"let aws_s3_bucket = Resources.*[ Type == "AWS::S3::Bucket" ]
+rule deny_1 WHEN %aws_s3_bucket NOT EMPTY {
+    %aws_s3_bucket[ Properties.Tags EXISTS Properties.Tags EMPTY ] EMPTY <<buckets are tagged>>
+}
+
+rule deny_2 WHEN %aws_s3_bucket NOT EMPTY {
+    %aws_s3_bucket[ Properties.CorsConfiguration.CorsRules NOT EMPTY ] EMPTY
+}
+
+"#
+    );
+    let template = r#"
+Resources:
+  Logs:
+    Type: AWS::S3::Bucket
+    Properties:
+      Tags: [{ Key: team, Value: platform }]
+"#;
+    assert_eq!(status(&rules, template)?, Status::PASS);
+    assert_eq!(
+        status(
+            &rules,
+            &template.replace("Tags: [{ Key: team, Value: platform }]", "Tags: []")
+        )?,
+        Status::FAIL
+    );
+    assert_eq!(
+        status(&rules, &template.replace("Tags:", "Metadata:"))?,
+        Status::PASS
+    );
+    assert_eq!(
+        status(
+            &rules,
+            &format!(
+                "{}      CorsConfiguration: {{ CorsRules: [{{}}] }}\n",
+                template
+            )
+        )?,
+        Status::FAIL
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rego_policies_not_migrated() -> Result<()> {
+    let policy = r#"package cfn
+
+deny[msg] {
+    r := input.Resources[_]
+    r.Type == "AWS::S3::Bucket"
+    count(r.Properties.Tags) > 2   # many tags
+    msg := "tags"
+}
+deny[msg] { input.Resources[_].Properties.Size > 100 }
+deny[msg] {
+    a := input.Resources[_]; b := input.Resources[_]
+    a.Type == "AWS::S3::Bucket"
+}
+deny[msg] { r := input.Resources[_]; r.Type == "AWS::S3::Bucket"; not r.Properties.Tags[_].Key == "team" }
+is_public(r) {
+    r.Properties.AccessControl == "PublicRead"
+}
+"#;
+    let reports = report_policies(policy);
+    let reasons = reports
+        .iter()
+        .map(|report| match &report.status {
+            LineStatus::Unsupported(reason) => (report.line, reason.as_str()),
+            _ => (report.line, "migrated"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reasons,
+        vec![
+            (3, "`count(r.Properties.Tags) > 2` has no equivalent in Guard rules"),
+            (9, "does not check the Type of a resource"),
+            (10, "checks more than one resource"),
+            (
+                14,
+                "`not r.Properties.Tags[_].Key == \"team\"` negates a check over every value at a path, which has no equivalent in Guard rules"
+            ),
+            (15, "only deny, violation and warn rules are migrated"),
+        ]
+    );
+    assert!(todo_markers(&reports).starts_with(
+        r#"# TODO line 3 was not migrated, `count(r.Properties.Tags) > 2` has no equivalent in Guard rules
+# deny[msg] {
+#     r := input.Resources[_]
+#     r.Type == "AWS::S3::Bucket"
+#     count(r.Properties.Tags) > 2
+#     msg := "tags"
+# }
+# TODO line 9 was not migrated"#
+    ));
+    assert_eq!(violation_rules(&reports)?, "");
+    Ok(())
+}
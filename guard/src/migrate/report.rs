@@ -0,0 +1,250 @@
+use crate::migrate::parser::{parse_rule_lines, OldGuardValues, Rule, RuleLineType};
+use crate::rules::errors::{Error, ErrorKind};
+use crate::rules::values::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fmt::Display;
+
+#[cfg(test)]
+#[path = "report_tests.rs"]
+mod report_tests;
+
+///
+/// How a 1.0 rule line was migrated
+///
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum LineStatus {
+    Migrated,
+    /// Migrated, with how the migrated rule can behave differently from the 1.0 rule
+    MigratedWithCaveats(Vec<String>),
+    /// Not migrated, with why
+    Unsupported(String),
+}
+
+impl Display for LineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineStatus::Migrated => write!(f, "migrated"),
+            LineStatus::MigratedWithCaveats(caveats) => {
+                write!(f, "migrated with caveats, {}", caveats.join("; "))
+            }
+            LineStatus::Unsupported(reason) => write!(f, "unsupported, {}", reason),
+        }
+    }
+}
+
+///
+/// A line of a 1.0 rules file with an assignment or rules, and how it was migrated
+///
+#[derive(Debug, Clone)]
+pub(crate) struct LineReport {
+    /// The line number, starting from 1
+    pub(crate) line: usize,
+    pub(crate) text: String,
+    /// The parsed line, None when it could not be parsed
+    pub(crate) parsed: Option<RuleLineType>,
+    pub(crate) status: LineStatus,
+}
+
+impl LineReport {
+    pub(crate) fn is_migrated(&self) -> bool {
+        !matches!(self.status, LineStatus::Unsupported(_))
+    }
+}
+
+///
+/// Something migrated from a rules file, a line or a policy spanning lines, and how
+///
+pub(crate) trait Reported {
+    /// The line it starts on, from 1
+    fn line(&self) -> usize;
+    fn text(&self) -> &str;
+    fn status(&self) -> &LineStatus;
+}
+
+impl Reported for LineReport {
+    fn line(&self) -> usize {
+        self.line
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn status(&self) -> &LineStatus {
+        &self.status
+    }
+}
+
+///
+/// Parses each line of a 1.0 rules file and reports how it is migrated. Comments and empty
+/// lines are left out.
+///
+pub(crate) fn report_lines(content: &str, file_name: &str) -> Vec<LineReport> {
+    let mut reports = vec![];
+    let mut assigned = HashMap::new();
+    let mut not_migrated = BTreeSet::new();
+    for (idx, (text, parsed)) in parse_rule_lines(content, file_name).into_iter().enumerate() {
+        let (parsed, status) = match parsed {
+            Ok(RuleLineType::Comment(_)) | Ok(RuleLineType::EmptyLine) => continue,
+            Ok(parsed) => {
+                let status = line_status(&parsed, &assigned, &not_migrated);
+                if let RuleLineType::Assignment(assignment) = &parsed {
+                    if matches!(status, LineStatus::Unsupported(_)) {
+                        not_migrated.insert(assignment.var_name.clone());
+                    } else {
+                        assigned.insert(assignment.var_name.clone(), assignment.value.clone());
+                    }
+                }
+                (Some(parsed), status)
+            }
+            Err(Error(ErrorKind::ParseError(reason))) => (None, LineStatus::Unsupported(reason)),
+            Err(e) => (None, LineStatus::Unsupported(e.to_string())),
+        };
+        reports.push(LineReport {
+            line: idx + 1,
+            text: text.trim().to_string(),
+            parsed,
+            status,
+        });
+    }
+    reports
+}
+
+fn line_status(
+    line: &RuleLineType,
+    assigned: &HashMap<String, OldGuardValues>,
+    not_migrated: &BTreeSet<String>,
+) -> LineStatus {
+    let mut values = vec![];
+    let mut types = BTreeSet::new();
+    match line {
+        RuleLineType::Assignment(assignment) => values.push(&assignment.value),
+        RuleLineType::Clause(clause) => {
+            for rule in &clause.rules {
+                match rule {
+                    Rule::Basic(basic) => {
+                        types.insert(basic.type_name.type_name.as_str());
+                        values.push(&basic.property_comparison.comparison_value);
+                    }
+                    Rule::Conditional(conditional) => {
+                        types.insert(conditional.type_name.type_name.as_str());
+                        values.push(&conditional.when_condition.comparison_value);
+                        values.push(&conditional.check_condition.comparison_value);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut typed_values = vec![];
+    for value in values {
+        let value = match value {
+            OldGuardValues::EnvironmentVariable(name) => {
+                return LineStatus::Unsupported(format!(
+                    "environment variable %{{{}}} has no equivalent in 2.0 rules",
+                    name
+                ))
+            }
+            OldGuardValues::VariableAccess(name) if not_migrated.contains(name) => {
+                return LineStatus::Unsupported(format!("uses %{}, which was not migrated", name))
+            }
+            OldGuardValues::VariableAccess(name) => match assigned.get(name) {
+                Some(OldGuardValues::Value(value)) => value,
+                Some(_) => continue,
+                None if matches!(line, RuleLineType::Clause(_)) => {
+                    return LineStatus::Unsupported(format!(
+                        "uses %{}, which is not assigned",
+                        name
+                    ))
+                }
+                None => continue,
+            },
+            OldGuardValues::Value(value) => value,
+        };
+        if matches!(line, RuleLineType::Clause(_)) && is_typed(value) {
+            typed_values.push(value.to_string());
+        }
+    }
+
+    let mut caveats = vec![];
+    if !typed_values.is_empty() {
+        caveats.push(format!(
+            "compares {} by type, 1.0 compared values as text",
+            typed_values.join(", ")
+        ));
+    }
+    if types.len() > 1 {
+        caveats.push(format!(
+            "the |OR| spans {}, it is checked within the rules for each type",
+            types.into_iter().collect::<Vec<_>>().join(" and ")
+        ));
+    }
+    if caveats.is_empty() {
+        LineStatus::Migrated
+    } else {
+        LineStatus::MigratedWithCaveats(caveats)
+    }
+}
+
+//
+// Numbers and booleans are compared by type in 2.0 rules, where 1.0 compared their text, so
+// `Size == 100` no longer matches a Size of "100"
+//
+fn is_typed(value: &Value) -> bool {
+    match value {
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) => true,
+        Value::List(list) => list.iter().any(is_typed),
+        _ => false,
+    }
+}
+
+///
+/// The `# TODO` comments for the lines that were not migrated, to be put in the migrated rules
+///
+pub(crate) fn todo_markers<R: Reported>(reports: &[R]) -> String {
+    let mut markers = String::new();
+    for report in reports {
+        if let LineStatus::Unsupported(reason) = report.status() {
+            markers.push_str(&format!(
+                "# TODO line {} was not migrated, {}\n",
+                report.line(),
+                reason
+            ));
+            for line in report.text().lines() {
+                markers.push_str(&format!("# {}\n", line));
+            }
+        }
+    }
+    if !markers.is_empty() {
+        markers.push('\n');
+    }
+    markers
+}
+
+///
+/// The line reports for each line, followed by a summary
+///
+pub(crate) fn report_text<R: Reported>(file_name: &str, reports: &[R]) -> String {
+    let mut text = String::new();
+    let (mut migrated, mut caveats, mut unsupported) = (0, 0, 0);
+    for report in reports {
+        match report.status() {
+            LineStatus::Migrated => migrated += 1,
+            LineStatus::MigratedWithCaveats(_) => caveats += 1,
+            LineStatus::Unsupported(_) => unsupported += 1,
+        }
+        text.push_str(&format!(
+            "{}:{}: {}\n",
+            file_name,
+            report.line(),
+            report.status()
+        ));
+    }
+    text.push_str(&format!(
+        "{} migrated, {} migrated with caveats, {} unsupported\n",
+        migrated, caveats, unsupported
+    ));
+    text
+}
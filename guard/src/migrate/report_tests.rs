@@ -0,0 +1,75 @@
+use super::*;
+
+#[test]
+fn test_report_lines() {
+    let rules = r#"# volumes
+let encrypted = true
+let version = %{POLICY_VERSION}
+
+AWS::EC2::Volume Encrypted == %encrypted
+AWS::EC2::Volume AvailabilityZone IN [us-east-1a, us-east-1b]
+AWS::EC2::Volume Size == 100 |OR| AWS::EC2::Instance InstanceType == m2.large
+AWS::IAM::Role AssumeRolePolicyDocument.Version == %version
+AWS::IAM::Role Path == %missing
+AWS::EC2::Volume Size IS_SET
+"#;
+    let reports = report_lines(rules, "rules.ruleset");
+    let statuses = reports
+        .iter()
+        .map(|report| (report.line, report.status.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            (2, String::from("migrated")),
+            (
+                3,
+                String::from(
+                    "unsupported, environment variable %{POLICY_VERSION} has no equivalent in 2.0 rules"
+                )
+            ),
+            (
+                5,
+                String::from("migrated with caveats, compares true by type, 1.0 compared values as text")
+            ),
+            (6, String::from("migrated")),
+            (
+                7,
+                String::from(
+                    "migrated with caveats, compares 100 by type, 1.0 compared values as text; \
+                     the |OR| spans AWS::EC2::Instance and AWS::EC2::Volume, it is checked within the rules for each type"
+                )
+            ),
+            (8, String::from("unsupported, uses %version, which was not migrated")),
+            (9, String::from("unsupported, uses %missing, which is not assigned")),
+            (
+                10,
+                String::from("unsupported, could not be parsed at column 23, near `IS_SET`")
+            ),
+        ]
+    );
+    assert!(reports[0].parsed.is_some() && reports[7].parsed.is_none());
+    assert_eq!(reports[7].text, "AWS::EC2::Volume Size IS_SET");
+    assert_eq!(
+        reports.iter().filter(|report| report.is_migrated()).count(),
+        4
+    );
+}
+
+#[test]
+fn test_report_text_and_todo_markers() {
+    let rules = "AWS::S3::Bucket BucketName == /^prod-/\n  AWS::S3::Bucket Size IS_SET  \n";
+    let reports = report_lines(rules, "rules.ruleset");
+    assert_eq!(
+        report_text("rules.ruleset", &reports),
+        "rules.ruleset:1: migrated\n\
+         rules.ruleset:2: unsupported, could not be parsed at column 24, near `IS_SET`\n\
+         1 migrated, 0 migrated with caveats, 1 unsupported\n"
+    );
+    assert_eq!(
+        todo_markers(&reports),
+        "# TODO line 2 was not migrated, could not be parsed at column 24, near `IS_SET`\n\
+         # AWS::S3::Bucket Size IS_SET\n\n"
+    );
+    assert_eq!(todo_markers(&reports[..1]), "");
+}
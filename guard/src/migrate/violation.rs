@@ -0,0 +1,352 @@
+use crate::migrate::parser::TypeName;
+use crate::migrate::report::{LineStatus, Reported};
+use crate::rules::display::quoted;
+use crate::rules::Result;
+use nom::branch::alt;
+use nom::bytes::complete::{escaped, is_not, tag, take_while, take_while1};
+use nom::character::complete::{anychar, char, digit1};
+use nom::combinator::{map_res, opt, recognize};
+use nom::sequence::{delimited, pair, tuple};
+use nom::IResult;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Write;
+
+#[cfg(test)]
+#[path = "violation_tests.rs"]
+mod violation_tests;
+
+//
+// Rego and cfn-nag policies describe the resources that are in violation, where Guard rules
+// describe the resources that comply. Policies from both are migrated to the conditions a
+// resource in violation satisfies, and the rule for them checks that no resource of the type
+// satisfies them all:
+//
+//   rule deny_1 WHEN %aws_s3_bucket NOT EMPTY {
+//       %aws_s3_bucket[ Properties.BucketName NOT EXISTS ] EMPTY
+//   }
+//
+
+///
+/// A key, index or wildcard in the path to a property of a resource
+///
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Check {
+    /// A comparison operator and the value compared with, as Guard text
+    Compare(&'static str, String),
+    Exists,
+    NotExists,
+    Empty,
+    NotEmpty,
+}
+
+///
+/// A check of one property of a resource
+///
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Clause {
+    /// The path from the resource, e.g. `Properties.Tags[*].Key`
+    pub(crate) path: String,
+    /// Whether one value at the path is enough, for paths with wildcards
+    pub(crate) some: bool,
+    pub(crate) check: Check,
+}
+
+impl Clause {
+    ///
+    /// The clause for a path, with the reason when it has no Guard equivalent
+    ///
+    pub(crate) fn new(segments: &[Segment], check: Check) -> std::result::Result<Self, String> {
+        let mut path = String::new();
+        let mut some = false;
+        for segment in segments {
+            match segment {
+                Segment::Key(key) if is_identifier(key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                }
+                Segment::Key(key) => {
+                    return Err(format!(
+                        "the key {:?} has no equivalent in a Guard query",
+                        key
+                    ))
+                }
+                Segment::Index(idx) => path.push_str(&format!("[{}]", idx)),
+                Segment::Any => {
+                    some = true;
+                    path.push_str("[*]");
+                }
+            }
+        }
+        if path.is_empty() {
+            return Err(String::from("checks the resource itself"));
+        }
+        Ok(Clause { path, some, check })
+    }
+}
+
+impl Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.some {
+            write!(f, "SOME ")?;
+        }
+        match &self.check {
+            Check::Compare(operator, value) => write!(f, "{} {} {}", self.path, operator, value),
+            Check::Exists => write!(f, "{} EXISTS", self.path),
+            Check::NotExists => write!(f, "{} NOT EXISTS", self.path),
+            Check::Empty => write!(f, "{} EMPTY", self.path),
+            Check::NotEmpty => write!(f, "{} NOT EMPTY", self.path),
+        }
+    }
+}
+
+///
+/// The resources of a type that are in violation of a policy
+///
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Violation {
+    pub(crate) resource_type: String,
+    /// The conditions a resource in violation satisfies, each satisfied by any of its clauses
+    pub(crate) conditions: Vec<Vec<Clause>>,
+    pub(crate) message: Option<String>,
+}
+
+///
+/// A policy from a Rego or cfn-nag rules file, and how it was migrated
+///
+#[derive(Debug, Clone)]
+pub(crate) struct PolicyReport {
+    /// The name of the migrated rule
+    pub(crate) name: String,
+    /// The line the policy starts on, from 1
+    pub(crate) line: usize,
+    pub(crate) text: String,
+    /// The violation it was migrated to, None when it was not migrated
+    pub(crate) violation: Option<Violation>,
+    pub(crate) status: LineStatus,
+}
+
+impl PolicyReport {
+    pub(crate) fn unsupported(name: String, line: usize, text: &str, reason: String) -> Self {
+        PolicyReport {
+            name,
+            line,
+            text: text.trim().to_string(),
+            violation: None,
+            status: LineStatus::Unsupported(reason),
+        }
+    }
+
+    pub(crate) fn migrated(
+        name: String,
+        line: usize,
+        text: &str,
+        violation: Violation,
+        caveats: Vec<String>,
+    ) -> Self {
+        PolicyReport {
+            name,
+            line,
+            text: text.trim().to_string(),
+            violation: Some(violation),
+            status: if caveats.is_empty() {
+                LineStatus::Migrated
+            } else {
+                LineStatus::MigratedWithCaveats(caveats)
+            },
+        }
+    }
+}
+
+impl Reported for PolicyReport {
+    fn line(&self) -> usize {
+        self.line
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn status(&self) -> &LineStatus {
+        &self.status
+    }
+}
+
+///
+/// A rule name for a policy name or id, e.g. `W35` or `s3-bucket-logging`
+///
+pub(crate) fn rule_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    if name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("rule_{}", name)
+    }
+}
+
+///
+/// The characters of text outside of strings, with their offsets and the depth of the brackets
+/// they are in. A bracket has the depth of the text around it.
+///
+pub(crate) fn code_chars(text: &str) -> Vec<(usize, char, i32)> {
+    let mut chars = vec![];
+    let (mut depth, mut in_string, mut escaped) = (0, false, false);
+    for (idx, ch) in text.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '(' | '[' | '{' => {
+                chars.push((idx, ch, depth));
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                chars.push((idx, ch, depth));
+            }
+            _ => chars.push((idx, ch, depth)),
+        }
+    }
+    chars
+}
+
+fn is_identifier(key: &str) -> bool {
+    key.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+///
+/// The rules for the migrated policies, with a let for the resources of each type before the
+/// first rule for it
+///
+pub(crate) fn violation_rules(reports: &[PolicyReport]) -> Result<String> {
+    let mut rules = String::new();
+    let mut assigned = HashSet::new();
+    for report in reports {
+        let violation = match &report.violation {
+            Some(violation) => violation,
+            None => continue,
+        };
+        let type_name = TypeName {
+            type_name: violation.resource_type.clone(),
+        };
+        if assigned.insert(type_name.to_string()) {
+            writeln!(
+                rules,
+                "let {} = Resources.*[ Type == \"{}\" ]",
+                type_name, violation.resource_type
+            )?;
+        }
+        writeln!(
+            rules,
+            "rule {} WHEN %{} NOT EMPTY {{",
+            report.name, type_name
+        )?;
+        let filter = violation
+            .conditions
+            .iter()
+            .map(|clauses| {
+                clauses
+                    .iter()
+                    .map(Clause::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" OR ")
+            })
+            .collect::<Vec<_>>();
+        write!(rules, "    %{}", type_name)?;
+        if !filter.is_empty() {
+            write!(rules, "[ {} ]", filter.join(" "))?;
+        }
+        write!(rules, " EMPTY")?;
+        if let Some(message) = &violation.message {
+            write!(
+                rules,
+                " <<{}>>",
+                message.replace(">>", "> >").replace('\n', " ")
+            )?;
+        }
+        writeln!(rules, "\n}}\n")?;
+    }
+    Ok(rules)
+}
+
+//
+// Identifiers, literals and operators, which Rego and jq write the same way
+//
+
+pub(crate) fn identifier(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_while1(|ch: char| ch.is_ascii_alphabetic() || ch == '_'),
+        take_while(|ch: char| ch.is_ascii_alphanumeric() || ch == '_'),
+    ))(input)
+}
+
+pub(crate) fn string_literal(input: &str) -> IResult<&str, &str> {
+    recognize(delimited(
+        char('"'),
+        opt(escaped(is_not("\"\\"), '\\', anychar)),
+        char('"'),
+    ))(input)
+}
+
+pub(crate) fn literal(input: &str) -> IResult<&str, serde_json::Value> {
+    map_res(
+        alt((
+            string_literal,
+            recognize(tuple((
+                opt(char('-')),
+                digit1,
+                opt(pair(char('.'), digit1)),
+            ))),
+            tag("true"),
+            tag("false"),
+            tag("null"),
+        )),
+        serde_json::from_str,
+    )(input)
+}
+
+///
+/// The Guard text for a literal
+///
+pub(crate) fn guard_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => quoted(text),
+        other => other.to_string(),
+    }
+}
+
+///
+/// The operator for a comparison with its sides swapped, e.g. `<` for `5 > .Size`
+///
+pub(crate) fn swapped(operator: &'static str) -> &'static str {
+    match operator {
+        "<" => ">",
+        ">" => "<",
+        "<=" => ">=",
+        ">=" => "<=",
+        other => other,
+    }
+}
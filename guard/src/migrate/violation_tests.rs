@@ -0,0 +1,107 @@
+use super::*;
+
+#[test]
+fn test_clause_and_rule_names() {
+    let clause = Clause::new(
+        &[
+            Segment::Key(String::from("Properties")),
+            Segment::Key(String::from("Tags")),
+            Segment::Any,
+            Segment::Key(String::from("Key")),
+        ],
+        Check::Compare("==", String::from("'team'")),
+    );
+    assert_eq!(
+        clause.map(|clause| clause.to_string()),
+        Ok(String::from("SOME Properties.Tags[*].Key == 'team'"))
+    );
+    let clause = Clause::new(
+        &[
+            Segment::Key(String::from("Properties")),
+            Segment::Key(String::from("Volumes")),
+            Segment::Index(0),
+        ],
+        Check::NotExists,
+    );
+    assert_eq!(
+        clause.map(|clause| clause.to_string()),
+        Ok(String::from("Properties.Volumes[0] NOT EXISTS"))
+    );
+    assert!(Clause::new(&[Segment::Key(String::from("aws:tag"))], Check::Exists).is_err());
+    assert!(Clause::new(&[], Check::Exists).is_err());
+
+    assert_eq!(rule_name("W35"), "W35");
+    assert_eq!(rule_name("s3-bucket-logging"), "s3_bucket_logging");
+    assert_eq!(rule_name("1"), "rule_1");
+}
+
+#[test]
+fn test_violation_rules() -> Result<()> {
+    let encrypted = Clause::new(
+        &[
+            Segment::Key(String::from("Properties")),
+            Segment::Key(String::from("Encrypted")),
+        ],
+        Check::NotExists,
+    )
+    .unwrap();
+    let violation = |conditions, message| Violation {
+        resource_type: String::from("AWS::EC2::Volume"),
+        conditions,
+        message,
+    };
+    let reports = vec![
+        PolicyReport::migrated(
+            String::from("deny_1"),
+            3,
+            "deny[msg] { ... }",
+            violation(
+                vec![vec![
+                    encrypted.clone(),
+                    Clause {
+                        check: Check::Compare("==", String::from("false")),
+                        ..encrypted.clone()
+                    },
+                ]],
+                Some(String::from("volumes must be encrypted >> now")),
+            ),
+            vec![],
+        ),
+        PolicyReport::unsupported(
+            String::from("deny_2"),
+            9,
+            "deny[msg] { ... }",
+            String::from("no reason"),
+        ),
+        PolicyReport::migrated(
+            String::from("deny_3"),
+            12,
+            "deny[msg] { ... }",
+            violation(vec![], None),
+            vec![String::from("a caveat")],
+        ),
+    ];
+    let expected = r#"let aws_ec2_volume = Resources.*[ Type == "AWS::EC2::Volume" ]
+rule deny_1 WHEN %aws_ec2_volume NOT EMPTY {
+    %aws_ec2_volume[ Properties.Encrypted NOT EXISTS OR Properties.Encrypted == false ] EMPTY <<volumes must be encrypted > > now>>
+}
+
+rule deny_3 WHEN %aws_ec2_volume NOT EMPTY {
+    %aws_ec2_volume EMPTY
+}
+
+"#;
+    assert_eq!(violation_rules(&reports)?, expected);
+    assert_eq!(
+        reports
+            .iter()
+            .map(|report| report.status().to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "migrated",
+            "unsupported, no reason",
+            "migrated with caveats, a caveat"
+        ]
+    );
+    Ok(())
+}
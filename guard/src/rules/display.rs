@@ -1,5 +1,5 @@
 use crate::rules::eval_context::EventRecord;
-use crate::rules::exprs::SliceDisplay;
+use crate::rules::exprs::{GuardAccessClause, GuardNamedRuleClause, LetValue, SliceDisplay};
 use crate::rules::path_value::PathAwareValue;
 use crate::rules::values::{CmpOperator, RangeType, LOWER_INCLUSIVE, UPPER_INCLUSIVE};
 use crate::rules::{BlockCheck, ClauseCheck, QueryResult, RecordType, Status};
@@ -9,6 +9,141 @@ pub(crate) fn display_comparison((cmp, not): (CmpOperator, bool)) -> String {
     format!("{} {}", if not { "not" } else { "" }, cmp)
 }
 
+///
+/// A string literal in Guard source, single quoted unless the text only contains single quotes
+///
+pub(crate) fn quoted(text: &str) -> String {
+    let quote = if text.contains('\'') && !text.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    format!(
+        "{}{}{}",
+        quote,
+        text.replace(quote, &format!("\\{}", quote)),
+        quote
+    )
+}
+
+fn float_text(value: f64) -> String {
+    //
+    // An exponent needs its sign to be read back as a float
+    //
+    let text = format!("{:?}", value);
+    match text.find('e') {
+        Some(idx) if !text[idx + 1..].starts_with('-') => {
+            format!("{}e+{}", &text[..idx], &text[idx + 1..])
+        }
+        _ => text,
+    }
+}
+
+fn range_text<T: PartialOrd, F: Fn(&T) -> String>(range: &RangeType<T>, each: F) -> String {
+    format!(
+        "r{}{},{}{}",
+        if range.inclusive & LOWER_INCLUSIVE != 0 {
+            '['
+        } else {
+            '('
+        },
+        each(&range.lower),
+        each(&range.upper),
+        if range.inclusive & UPPER_INCLUSIVE != 0 {
+            ']'
+        } else {
+            ')'
+        }
+    )
+}
+
+///
+/// The Guard source for a literal value, the parser reads it back as the same value
+///
+pub(crate) fn value_text(value: &PathAwareValue) -> String {
+    match value {
+        PathAwareValue::Null(_) => "null".to_string(),
+        PathAwareValue::String((_, text)) => quoted(text),
+        PathAwareValue::Regex((_, regex)) => format!("/{}/", regex.replace('/', "\\/")),
+        PathAwareValue::Bool((_, flag)) => flag.to_string(),
+        PathAwareValue::Int((_, number)) => number.to_string(),
+        PathAwareValue::Float((_, number)) => float_text(*number),
+        PathAwareValue::Char((_, ch)) => ch.to_string(),
+        PathAwareValue::List((_, list)) => format!(
+            "[{}]",
+            list.iter().map(value_text).collect::<Vec<_>>().join(", ")
+        ),
+        PathAwareValue::Map((_, map)) => format!(
+            "{{{}}}",
+            map.values
+                .iter()
+                .map(|(key, each)| {
+                    let plain = !key.is_empty()
+                        && key
+                            .chars()
+                            .all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_');
+                    let key = if plain { key.clone() } else { quoted(key) };
+                    format!("{}: {}", key, value_text(each))
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        PathAwareValue::RangeInt((_, range)) => range_text(range, i64::to_string),
+        PathAwareValue::RangeFloat((_, range)) => range_text(range, |each| float_text(*each)),
+        PathAwareValue::RangeChar((_, range)) => range_text(range, char::to_string),
+    }
+}
+
+///
+/// Displays a clause the way evaluation records and reports name it, e.g.
+/// ` Properties.Encrypted EQUALS  true`. The clause's own Display is its Guard source.
+///
+pub(crate) struct ReportDisplay<'a, T>(pub(crate) &'a T);
+
+impl<'a> Display for ReportDisplay<'a, GuardAccessClause<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let access = &self.0.access_clause;
+        let (operator, not) = access.comparator;
+        write!(
+            f,
+            "{} {} {}{}  ",
+            if self.0.negation { "not" } else { "" },
+            SliceDisplay(&access.query.query),
+            if not { "not " } else { "" },
+            operator
+        )?;
+        match &access.compare_with {
+            Some(with) => ReportDisplay(with).fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Display for ReportDisplay<'a, GuardNamedRuleClause<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rule({}@{})", self.0.dependent_rule, self.0.location)
+    }
+}
+
+impl<'a> Display for ReportDisplay<'a, LetValue<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            LetValue::Value(value) => ValueOnlyDisplay(value).fmt(f),
+            LetValue::AccessClause(query) => SliceDisplay(&query.query).fmt(f),
+            LetValue::FunctionCall(call) => {
+                write!(f, "{}(", call.name)?;
+                for (idx, each) in call.parameters.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    ReportDisplay(each).fmt(f)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
 fn write_range<T: Display + PartialOrd>(
     formatter: &mut Formatter<'_>,
     range: &RangeType<T>,
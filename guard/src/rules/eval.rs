@@ -1,5 +1,6 @@
 use super::exprs::*;
 use super::*;
+use crate::rules::display::ReportDisplay;
 use crate::rules::eval::operators::Comparator;
 use crate::rules::eval_context::{block_scope, ValueScope};
 use crate::rules::path_value::compare_eq;
@@ -89,6 +90,21 @@ where
     }
 }
 
+//
+// The record for a check that passed. Reports only show the values of checks that failed, the
+// values of the ones that passed are only kept to explain an evaluation
+//
+fn passed_check<'value, 'loc: 'value>(
+    eval_context: &dyn EvalContext<'value, 'loc>,
+    check: impl FnOnce() -> ClauseCheck<'value>,
+) -> RecordType<'value> {
+    RecordType::ClauseValueCheck(if eval_context.explaining() {
+        check()
+    } else {
+        ClauseCheck::Success
+    })
+}
+
 fn record_unary_clause<'eval, 'value, 'loc: 'value, O>(
     operation: O,
     cmp: (CmpOperator, bool),
@@ -119,8 +135,13 @@ where
                         })),
                     )?;
                 } else {
-                    eval_context
-                        .end_record(&context, RecordType::ClauseValueCheck(ClauseCheck::Success))?;
+                    let record = passed_check(eval_context, || {
+                        ClauseCheck::Unary(UnaryValueCheck {
+                            value: check,
+                            comparison: cmp,
+                        })
+                    });
+                    eval_context.end_record(&context, record)?;
                 }
                 Ok(result)
             }
@@ -238,10 +259,18 @@ fn unary_operation<'r, 'l: 'r, 'loc: 'l>(
 
                     match status {
                         Status::PASS => {
-                            eval_context.end_record(
-                                &context,
-                                RecordType::ClauseValueCheck(ClauseCheck::Success),
-                            )?;
+                            let record = passed_check(eval_context, || {
+                                ClauseCheck::Unary(UnaryValueCheck {
+                                    comparison: cmp,
+                                    value: ValueCheck {
+                                        status: Status::PASS,
+                                        message: None,
+                                        custom_message: custom_message.clone(),
+                                        from: result.clone(),
+                                    },
+                                })
+                            });
+                            eval_context.end_record(&context, record)?;
                         }
                         Status::FAIL => {
                             eval_context.end_record(
@@ -657,7 +686,17 @@ fn report_value<'r, 'value: 'r, 'loc: 'value>(
 
     Ok(if outcome {
         eval_context.start_record(&context)?;
-        eval_context.end_record(&context, RecordType::ClauseValueCheck(ClauseCheck::Success))?;
+        let record = passed_check(eval_context, || {
+            ClauseCheck::Comparison(ComparisonClauseCheck {
+                status: Status::PASS,
+                message: None,
+                custom_message,
+                comparison: cmp,
+                from: lhs_value.clone(),
+                to: rhs_value,
+            })
+        });
+        eval_context.end_record(&context, record)?;
         (lhs_value, Status::PASS)
     } else {
         eval_context.start_record(&context)?;
@@ -744,10 +783,19 @@ fn report_at_least_one<'r, 'value: 'r, 'loc: 'value>(
             _ => false,
         });
         match found {
-            Some(_) => {
+            Some((_, rhs)) => {
                 eval_context.start_record(&context)?;
-                eval_context
-                    .end_record(&context, RecordType::ClauseValueCheck(ClauseCheck::Success))?;
+                let record = passed_check(eval_context, || {
+                    ClauseCheck::Comparison(ComparisonClauseCheck {
+                        status: Status::PASS,
+                        message: None,
+                        custom_message: custom_message.clone(),
+                        comparison: cmp,
+                        from: QueryResult::Resolved(*lhs),
+                        to: Some(rhs.clone()),
+                    })
+                });
+                eval_context.end_record(&context, record)?;
                 statues.push((QueryResult::Resolved(*lhs), Status::PASS))
             }
             None => {
@@ -859,43 +907,76 @@ fn binary_operation<'value, 'loc: 'value>(
                     }
 
                     operators::ValueEvalResult::ComparisonResult(
-                        operators::ComparisonResult::Success(cmp),
-                    ) => match cmp {
+                        operators::ComparisonResult::Success(compared),
+                    ) => match compared {
                         operators::Compare::ListIn(lin) => {
                             eval_context.start_record(&context)?;
-                            eval_context.end_record(
-                                &context,
-                                RecordType::ClauseValueCheck(ClauseCheck::Success),
-                            )?;
+                            let record = passed_check(eval_context, || {
+                                ClauseCheck::InComparison(InComparisonCheck {
+                                    status: Status::PASS,
+                                    message: None,
+                                    custom_message: custom_message.clone(),
+                                    comparison: cmp,
+                                    from: QueryResult::Resolved(lin.lhs),
+                                    to: vec![QueryResult::Resolved(lin.rhs)],
+                                })
+                            });
+                            eval_context.end_record(&context, record)?;
                             statues.push((QueryResult::Resolved(lin.lhs), Status::PASS));
                         }
 
                         operators::Compare::QueryIn(qin) => {
+                            let rhs = qin
+                                .rhs
+                                .iter()
+                                .map(|e| QueryResult::Resolved(*e))
+                                .collect::<Vec<_>>();
                             for each in qin.lhs {
                                 eval_context.start_record(&context)?;
-                                eval_context.end_record(
-                                    &context,
-                                    RecordType::ClauseValueCheck(ClauseCheck::Success),
-                                )?;
+                                let record = passed_check(eval_context, || {
+                                    ClauseCheck::InComparison(InComparisonCheck {
+                                        status: Status::PASS,
+                                        message: None,
+                                        custom_message: custom_message.clone(),
+                                        comparison: cmp,
+                                        from: QueryResult::Resolved(each),
+                                        to: rhs.clone(),
+                                    })
+                                });
+                                eval_context.end_record(&context, record)?;
                                 statues.push((QueryResult::Resolved(each), Status::PASS));
                             }
                         }
 
                         operators::Compare::Value(pair) => {
                             eval_context.start_record(&context)?;
-                            eval_context.end_record(
-                                &context,
-                                RecordType::ClauseValueCheck(ClauseCheck::Success),
-                            )?;
+                            let record = passed_check(eval_context, || {
+                                ClauseCheck::Comparison(ComparisonClauseCheck {
+                                    status: Status::PASS,
+                                    message: None,
+                                    custom_message: custom_message.clone(),
+                                    comparison: cmp,
+                                    from: QueryResult::Resolved(pair.lhs),
+                                    to: Some(QueryResult::Resolved(pair.rhs)),
+                                })
+                            });
+                            eval_context.end_record(&context, record)?;
                             statues.push((QueryResult::Resolved(pair.lhs), Status::PASS));
                         }
 
                         operators::Compare::ValueIn(val) => {
                             eval_context.start_record(&context)?;
-                            eval_context.end_record(
-                                &context,
-                                RecordType::ClauseValueCheck(ClauseCheck::Success),
-                            )?;
+                            let record = passed_check(eval_context, || {
+                                ClauseCheck::InComparison(InComparisonCheck {
+                                    status: Status::PASS,
+                                    message: None,
+                                    custom_message: custom_message.clone(),
+                                    comparison: cmp,
+                                    from: QueryResult::Resolved(val.lhs),
+                                    to: vec![QueryResult::Resolved(val.rhs)],
+                                })
+                            });
+                            eval_context.end_record(&context, record)?;
                             statues.push((QueryResult::Resolved(val.lhs), Status::PASS));
                         }
                     },
@@ -1106,7 +1187,7 @@ pub(in crate::rules) fn eval_guard_access_clause<'value, 'loc: 'value>(
     resolver: &mut dyn EvalContext<'value, 'loc>,
 ) -> Result<Status> {
     let all = gac.access_clause.query.match_all;
-    let blk_context = format!("GuardAccessClause#block{}", gac);
+    let blk_context = access_clause_context(gac);
     resolver.start_record(&blk_context)?;
 
     let statues = if gac.access_clause.comparator.0.is_unary() {
@@ -1114,7 +1195,7 @@ pub(in crate::rules) fn eval_guard_access_clause<'value, 'loc: 'value>(
             &gac.access_clause.query.query,
             gac.access_clause.comparator,
             gac.negation,
-            format!("{}", gac),
+            ReportDisplay(gac).to_string(),
             gac.access_clause.custom_message.clone(),
             resolver,
         )
@@ -1160,7 +1241,7 @@ pub(in crate::rules) fn eval_guard_access_clause<'value, 'loc: 'value>(
             &gac.access_clause.query.query,
             &rhs,
             gac.access_clause.comparator,
-            format!("{}", gac),
+            ReportDisplay(gac).to_string(),
             gac.access_clause.custom_message.clone(),
             resolver,
         )
@@ -1236,7 +1317,7 @@ pub(in crate::rules) fn eval_guard_named_clause<'value, 'loc: 'value>(
     gnc: &'value GuardNamedRuleClause<'loc>,
     resolver: &mut dyn EvalContext<'value, 'loc>,
 ) -> Result<Status> {
-    let context = format!("{}", gnc);
+    let context = named_rule_context(gnc);
     resolver.start_record(&context)?;
 
     match resolver.rule_status(&gnc.dependent_rule) {
@@ -1259,8 +1340,15 @@ pub(in crate::rules) fn eval_guard_named_clause<'value, 'loc: 'value>(
             };
             match status {
                 Status::PASS => {
-                    resolver
-                        .end_record(&context, RecordType::ClauseValueCheck(ClauseCheck::Success))?;
+                    let record = passed_check(resolver, || {
+                        ClauseCheck::DependentRule(MissingValueCheck {
+                            rule: &gnc.dependent_rule,
+                            status: Status::PASS,
+                            message: None,
+                            custom_message: gnc.custom_message.clone(),
+                        })
+                    });
+                    resolver.end_record(&context, record)?;
                 }
                 Status::FAIL => {
                     resolver.end_record(
@@ -1312,7 +1400,7 @@ pub(in crate::rules) fn eval_guard_block_clause<'value, 'loc: 'value>(
     block_clause: &'value BlockGuardClause<'loc>,
     resolver: &mut dyn EvalContext<'value, 'loc>,
 ) -> Result<Status> {
-    let context = format!("BlockGuardClause#{}", block_clause.location);
+    let context = block_clause_context(block_clause);
     let match_all = block_clause.query.match_all;
     resolver.start_record(&context)?;
     let block_values = match resolver.query(&block_clause.query.query) {
@@ -1579,6 +1667,10 @@ impl<'eval, 'value, 'loc: 'value> RecordTracer<'value>
         };
         self.parent.end_record(context, record)
     }
+
+    fn explaining(&self) -> bool {
+        self.parent.explaining()
+    }
 }
 
 pub(in crate::rules) fn eval_parameterized_rule_call<'value, 'loc: 'value>(
@@ -1632,12 +1724,9 @@ pub(in crate::rules) fn eval_guard_clause<'value, 'loc: 'value>(
         GuardClause::Clause(gac) => eval_guard_access_clause(gac, resolver),
         GuardClause::NamedRule(gnc) => eval_guard_named_clause(gnc, resolver),
         GuardClause::BlockClause(bc) => eval_guard_block_clause(bc, resolver),
-        GuardClause::WhenBlock(conditions, block) => eval_when_condition_block(
-            "GuardConditionClause".to_string(),
-            conditions,
-            block,
-            resolver,
-        ),
+        GuardClause::WhenBlock(conditions, block) => {
+            eval_when_condition_block(WHEN_BLOCK_CONTEXT.to_string(), conditions, block, resolver)
+        }
         GuardClause::ParameterizedNamedRule(prc) => eval_parameterized_rule_call(&prc, resolver),
     }
 }
@@ -1659,10 +1748,10 @@ pub(in crate::rules) fn eval_type_block_clause<'value, 'loc: 'value>(
     type_block: &'value TypeBlock<'loc>,
     resolver: &mut dyn EvalContext<'value, 'loc>,
 ) -> Result<Status> {
-    let context = format!("TypeBlock#{}", type_block.type_name);
+    let context = type_block_context(type_block);
     resolver.start_record(&context)?;
     let block = if let Some(conditions) = &type_block.conditions {
-        let when_context = format!("TypeBlock#{}/When", type_block.type_name);
+        let when_context = format!("{}/When", context);
         resolver.start_record(&when_context)?;
         match eval_conjunction_clauses(conditions, resolver, eval_when_clause) {
             Ok(status) => {
@@ -1819,9 +1908,12 @@ pub(in crate::rules) fn eval_rule_clause<'value, 'loc: 'value>(
     match rule_clause {
         RuleClause::Clause(gc) => eval_guard_clause(gc, resolver),
         RuleClause::TypeBlock(tb) => eval_type_block_clause(tb, resolver),
-        RuleClause::WhenBlock(conditions, block) => {
-            eval_when_condition_block("RuleClause".to_string(), conditions, block, resolver)
-        }
+        RuleClause::WhenBlock(conditions, block) => eval_when_condition_block(
+            RULE_WHEN_BLOCK_CONTEXT.to_string(),
+            conditions,
+            block,
+            resolver,
+        ),
     }
 }
 
@@ -1903,6 +1995,29 @@ impl<'loc> std::fmt::Display for RulesFile<'loc> {
     }
 }
 
+//
+// The contexts that evaluation records of clauses start with. Coverage for the test command
+// matches records to the clauses of a rules file with these.
+//
+pub(crate) const WHEN_BLOCK_CONTEXT: &str = "GuardConditionClause";
+pub(crate) const RULE_WHEN_BLOCK_CONTEXT: &str = "RuleClause";
+
+pub(crate) fn access_clause_context(gac: &GuardAccessClause<'_>) -> String {
+    format!("GuardAccessClause#block{}", ReportDisplay(gac))
+}
+
+pub(crate) fn named_rule_context(gnc: &GuardNamedRuleClause<'_>) -> String {
+    ReportDisplay(gnc).to_string()
+}
+
+pub(crate) fn block_clause_context(block_clause: &BlockGuardClause<'_>) -> String {
+    format!("BlockGuardClause#{}", block_clause.location)
+}
+
+pub(crate) fn type_block_context(type_block: &TypeBlock<'_>) -> String {
+    format!("TypeBlock#{}", type_block.type_name)
+}
+
 pub(crate) fn eval_rules_file<'value, 'loc: 'value>(
     rule: &'value RulesFile<'loc>,
     resolver: &mut dyn EvalContext<'value, 'loc>,
@@ -1974,16 +2089,25 @@ where
             if multiple_ors_present {
                 resolver.start_record(&context)?;
             }
-            for disjunction in conjunction {
+            for (idx, disjunction) in conjunction.iter().enumerate() {
                 match eval_fn(disjunction, resolver) {
                     Ok(status) => match status {
                         Status::PASS => {
                             num_passes += 1;
                             if multiple_ors_present {
+                                let remaining = idx + 1 < conjunction.len();
                                 resolver.end_record(
                                     &context,
                                     RecordType::Disjunction(BlockCheck {
-                                        message: None,
+                                        message: if resolver.explaining() && remaining {
+                                            Some(format!(
+                                                "alternative {} of {} passed, the rest are not evaluated",
+                                                idx + 1,
+                                                conjunction.len()
+                                            ))
+                                        } else {
+                                            None
+                                        },
                                         at_least_one_matches: true,
                                         status: Status::PASS,
                                     }),
@@ -15,7 +15,7 @@ use crate::rules::{
 use inflector::cases::*;
 use lazy_static::lazy_static;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 pub(crate) struct Scope<'value, 'loc: 'value> {
     root: &'value PathAwareValue,
@@ -38,6 +38,7 @@ pub(crate) struct RootScope<'value, 'loc: 'value> {
     rules_status: HashMap<&'value str, Status>,
     parameterized_rules: HashMap<&'value str, &'value ParameterizedRule<'loc>>,
     recorder: RecordTracker<'value>,
+    explain: bool,
 }
 
 impl<'value, 'loc: 'value> RootScope<'value, 'loc> {
@@ -60,6 +61,13 @@ impl<'value, 'loc: 'value> RootScope<'value, 'loc> {
             },
         )
     }
+
+    ///
+    /// Records the values checked by the checks that pass as well, to explain the evaluation
+    ///
+    pub(crate) fn explain_checks(&mut self) {
+        self.explain = true;
+    }
 }
 
 pub(crate) fn reset_with<'value, 'loc: 'value>(
@@ -87,6 +95,7 @@ pub(crate) fn reset_with<'value, 'loc: 'value>(
             final_event: None,
             events: vec![],
         },
+        explain: root_scope.explain,
     }
 }
 
@@ -956,6 +965,7 @@ pub(crate) fn root_scope_with<'value, 'loc: 'value>(
             final_event: None,
             events: vec![],
         },
+        explain: false,
     })
 }
 
@@ -1159,6 +1169,10 @@ impl<'value, 'loc: 'value> RecordTracer<'value> for RootScope<'value, 'loc> {
     fn end_record(&mut self, context: &str, record: RecordType<'value>) -> Result<()> {
         self.recorder.end_record(context, record)
     }
+
+    fn explaining(&self) -> bool {
+        self.explain
+    }
 }
 
 impl<'value, 'loc: 'value, 'eval> EvalContext<'value, 'loc> for ValueScope<'value, 'eval, 'loc> {
@@ -1202,6 +1216,10 @@ impl<'value, 'loc: 'value, 'eval> RecordTracer<'value> for ValueScope<'value, 'e
     fn end_record(&mut self, context: &str, record: RecordType<'value>) -> Result<()> {
         self.parent.end_record(context, record)
     }
+
+    fn explaining(&self) -> bool {
+        self.parent.explaining()
+    }
 }
 
 impl<'value, 'loc: 'value, 'eval> EvalContext<'value, 'loc> for BlockScope<'value, 'loc, 'eval> {
@@ -1272,6 +1290,10 @@ impl<'value, 'loc: 'value, 'eval> RecordTracer<'value> for BlockScope<'value, 'l
     fn end_record(&mut self, context: &str, record: RecordType<'value>) -> Result<()> {
         self.parent.end_record(context, record)
     }
+
+    fn explaining(&self) -> bool {
+        self.parent.explaining()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Default)]
@@ -1512,6 +1534,34 @@ impl<'value> ClauseReport<'value> {
             Self::Clause(_) => format!("{}/C[{:p}]", parent, self),
         }
     }
+
+    ///
+    /// Adds the paths of the values that failed in the report, a rule that failed without
+    /// checking values adds an empty path
+    ///
+    pub(crate) fn collect_failing_paths(&self, paths: &mut BTreeSet<String>) {
+        match self {
+            Self::Rule(rule) => {
+                if rule.checks.is_empty() {
+                    paths.insert(String::new());
+                }
+                for each in &rule.checks {
+                    each.collect_failing_paths(paths);
+                }
+            }
+            Self::Disjunctions(disjunctions) => {
+                for each in &disjunctions.checks {
+                    each.collect_failing_paths(paths);
+                }
+            }
+            Self::Block(_) | Self::Clause(_) => {
+                paths.insert(
+                    self.value_from()
+                        .map_or(String::new(), |v| v.self_path().0.clone()),
+                );
+            }
+        }
+    }
 }
 
 impl<'value> ValueComparisons<'value> for ClauseReport<'value> {
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::Formatter;
 
+use crate::rules::display::ReportDisplay;
 use crate::rules::errors::{Error, ErrorKind};
 use crate::rules::exprs::{
     AccessQuery, Block, Conjunctions, GuardAccessClause, LetExpr, LetValue, Rule, RulesFile,
@@ -461,7 +462,7 @@ impl<'loc> Evaluate for GuardAccessClause<'loc> {
         };
 
         if let Some(r) = result {
-            let guard_loc = format!("{}", self);
+            let guard_loc = ReportDisplay(self).to_string();
             let mut auto_reporter =
                 AutoReport::new(EvaluationType::Clause, var_resolver, &guard_loc);
             let message = match &clause.access_clause.custom_message {
@@ -489,7 +490,7 @@ impl<'loc> Evaluate for GuardAccessClause<'loc> {
 
         let lhs = match lhs {
             None => {
-                let guard_loc = format!("{}", self);
+                let guard_loc = ReportDisplay(self).to_string();
                 let mut auto_reporter =
                     AutoReport::new(EvaluationType::Clause, var_resolver, &guard_loc);
                 if all {
@@ -669,7 +670,7 @@ impl<'loc> Evaluate for GuardAccessClause<'loc> {
         };
 
         for (outcome, from, to) in outcomes {
-            let guard_loc = format!("{}", self);
+            let guard_loc = ReportDisplay(self).to_string();
             let mut auto_reporter =
                 AutoReport::new(EvaluationType::Clause, var_resolver, &guard_loc);
             auto_reporter.status(if outcome { Status::PASS } else { Status::FAIL });
@@ -687,19 +688,13 @@ impl<'loc> Evaluate for GuardAccessClause<'loc> {
     }
 }
 
-impl<'loc> std::fmt::Display for GuardNamedRuleClause<'loc> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Rule({}@{})", self.dependent_rule, self.location)
-    }
-}
-
 impl<'loc> Evaluate for GuardNamedRuleClause<'loc> {
     fn evaluate<'s>(
         &self,
         _context: &'s PathAwareValue,
         var_resolver: &'s dyn EvaluationContext,
     ) -> Result<Status> {
-        let guard_loc = format!("{}", self);
+        let guard_loc = ReportDisplay(self).to_string();
         let mut auto_reporter = AutoReport::new(EvaluationType::Clause, var_resolver, &guard_loc);
         let status = invert_status(
             match var_resolver.rule_status(&self.dependent_rule)? {
@@ -1,12 +1,12 @@
 use crate::rules::values::*;
 
-use crate::rules::display::ValueOnlyDisplay;
+use crate::rules::display::{quoted, value_text};
 use crate::rules::path_value::PathAwareValue;
 use serde::{Deserialize, Serialize};
-use std::fmt::Formatter;
+use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 
-#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash)]
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash, Default)]
 pub(crate) struct FileLocation<'loc> {
     pub(crate) line: u32,
     pub(crate) column: u32,
@@ -26,11 +26,75 @@ impl<'loc> std::fmt::Display for FileLocation<'loc> {
 
 #[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize, Hash)]
 pub(crate) enum LetValue<'loc> {
-    Value(PathAwareValue),
+    Value(#[serde(with = "literal_value")] PathAwareValue),
     AccessClause(AccessQuery<'loc>),
     FunctionCall(FunctionExpr<'loc>),
 }
 
+//
+// Values are written the way PathAwareValue serializes them, where regexes, ranges and
+// characters are strings. Values holding any of these also carry their Guard source as
+// `literal`, which is read back instead of `value` when present.
+//
+mod literal_value {
+    use std::convert::{TryFrom, TryInto};
+
+    use serde::ser::SerializeStruct;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::rules::display::value_text;
+    use crate::rules::path_value::PathAwareValue;
+
+    #[derive(Deserialize)]
+    struct Literal {
+        value: serde_json::Value,
+        #[serde(default)]
+        literal: Option<String>,
+    }
+
+    pub(super) fn serialize<S>(value: &PathAwareValue, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (path, json): (String, serde_json::Value) =
+            value.try_into().map_err(serde::ser::Error::custom)?;
+        let literal = needs_literal(value);
+        let mut state =
+            serializer.serialize_struct("PathAwareValue", if literal { 3 } else { 2 })?;
+        state.serialize_field("path", &path)?;
+        state.serialize_field("value", &json)?;
+        if literal {
+            state.serialize_field("literal", &value_text(value))?;
+        }
+        state.end()
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<PathAwareValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let read = Literal::deserialize(deserializer)?;
+        match read.literal {
+            Some(literal) => PathAwareValue::try_from(literal.as_str()),
+            None => PathAwareValue::try_from(read.value),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+
+    fn needs_literal(value: &PathAwareValue) -> bool {
+        match value {
+            PathAwareValue::List((_, list)) => list.iter().any(needs_literal),
+            PathAwareValue::Map((_, map)) => map.values.values().any(needs_literal),
+            PathAwareValue::Regex(_)
+            | PathAwareValue::Char(_)
+            | PathAwareValue::RangeInt(_)
+            | PathAwareValue::RangeFloat(_)
+            | PathAwareValue::RangeChar(_) => true,
+            _ => false,
+        }
+    }
+}
+
 ///
 /// This expression encapsulates assignment expressions inside a block expression
 /// or at the file let. An assignment can either be a direct Value object or access
@@ -41,6 +105,8 @@ pub(crate) enum LetValue<'loc> {
 pub(crate) struct LetExpr<'loc> {
     pub(crate) var: String,
     pub(crate) value: LetValue<'loc>,
+    #[serde(default)]
+    pub(crate) location: FileLocation<'loc>,
 }
 
 ///
@@ -146,6 +212,7 @@ pub(crate) struct AccessClause<'loc> {
     pub(crate) comparator: (CmpOperator, bool),
     pub(crate) compare_with: Option<LetValue<'loc>>,
     pub(crate) custom_message: Option<String>,
+    #[serde(default)]
     pub(crate) location: FileLocation<'loc>,
 }
 
@@ -188,6 +255,7 @@ pub(crate) struct GuardNamedRuleClause<'loc> {
     pub(crate) dependent_rule: String,
     pub(crate) negation: bool,
     pub(crate) custom_message: Option<String>,
+    #[serde(default)]
     pub(crate) location: FileLocation<'loc>,
 }
 
@@ -195,6 +263,7 @@ pub(crate) struct GuardNamedRuleClause<'loc> {
 pub(crate) struct BlockGuardClause<'loc> {
     pub(crate) query: AccessQuery<'loc>,
     pub(crate) block: Block<'loc, GuardClause<'loc>>,
+    #[serde(default)]
     pub(crate) location: FileLocation<'loc>,
     pub(crate) not_empty: bool,
 }
@@ -215,6 +284,7 @@ pub(crate) struct ParameterizedNamedRuleClause<'loc> {
 pub(crate) struct FunctionExpr<'loc> {
     pub(crate) parameters: Vec<LetValue<'loc>>,
     pub(crate) name: String,
+    #[serde(default)]
     pub(crate) location: FileLocation<'loc>,
 }
 
@@ -296,94 +366,277 @@ impl<'a, T: std::fmt::Display + 'a> std::fmt::Display for SliceDisplay<'a, T> {
     }
 }
 
+//
+// Expressions display as Guard source on a single line, the parser reads them back as the
+// same expression
+//
+
+//
+// Words that are read as keywords or values when they start a query, keys named like
+// these are quoted
+//
+const RESERVED: [&str; 26] = [
+    "this", "THIS", "some", "SOME", "when", "WHEN", "not", "NOT", "rule", "let", "keys", "KEYS",
+    "or", "OR", "true", "True", "false", "False", "null", "NULL", "in", "IN", "exists", "EXISTS",
+    "empty", "EMPTY",
+];
+
+///
+/// Displays conjunctions as Guard source, disjunctions are joined with "or" and conjunctions
+/// with a space
+///
+pub(crate) struct ConjunctionsDisplay<'a, T>(pub(crate) &'a Conjunctions<T>);
+
+impl<'a, T: std::fmt::Display> std::fmt::Display for ConjunctionsDisplay<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, disjunctions) in self.0.iter().enumerate() {
+            if idx != 0 {
+                f.write_str(" ")?;
+            }
+            for (idx, each) in disjunctions.iter().enumerate() {
+                if idx != 0 {
+                    f.write_str(" or ")?;
+                }
+                each.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'loc, T: std::fmt::Display> std::fmt::Display for Block<'loc, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for each in &self.assignments {
+            write!(f, "{} ", each)?;
+        }
+        ConjunctionsDisplay(&self.conjunctions).fmt(f)
+    }
+}
+
 impl<'loc> std::fmt::Display for GuardClause<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            GuardClause::Clause(individual) => individual.fmt(f)?,
-            GuardClause::BlockClause(block) => block.fmt(f)?,
-            _ => unimplemented!(),
+            GuardClause::Clause(individual) => individual.fmt(f),
+            GuardClause::BlockClause(block) => block.fmt(f),
+            GuardClause::NamedRule(named) => named.fmt(f),
+            GuardClause::ParameterizedNamedRule(parameterized) => parameterized.fmt(f),
+            GuardClause::WhenBlock(conditions, block) => write!(
+                f,
+                "when {} {{ {} }}",
+                ConjunctionsDisplay(conditions),
+                block
+            ),
         }
-        Ok(())
     }
 }
 
+impl<'loc> std::fmt::Display for WhenGuardClause<'loc> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhenGuardClause::Clause(individual) => individual.fmt(f),
+            WhenGuardClause::NamedRule(named) => named.fmt(f),
+            WhenGuardClause::ParameterizedNamedRule(parameterized) => parameterized.fmt(f),
+        }
+    }
+}
+
+impl<'loc> std::fmt::Display for GuardNamedRuleClause<'loc> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.negation {
+            f.write_str("not ")?;
+        }
+        f.write_str(&self.dependent_rule)?;
+        write_message(f, &self.custom_message)
+    }
+}
+
+impl<'loc> std::fmt::Display for ParameterizedNamedRuleClause<'loc> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let named = &self.named_rule;
+        if named.negation {
+            f.write_str("not ")?;
+        }
+        write_call(f, &named.dependent_rule, &self.parameters)?;
+        write_message(f, &named.custom_message)
+    }
+}
+
+fn write_message(f: &mut Formatter<'_>, message: &Option<String>) -> std::fmt::Result {
+    match message {
+        Some(message) => write!(f, " <<{}>>", message),
+        None => Ok(()),
+    }
+}
+
+fn write_call(f: &mut Formatter<'_>, name: &str, parameters: &[LetValue<'_>]) -> std::fmt::Result {
+    write!(f, "{}(", name)?;
+    for (idx, each) in parameters.iter().enumerate() {
+        if idx != 0 {
+            f.write_str(", ")?;
+        }
+        each.fmt(f)?;
+    }
+    f.write_str(")")
+}
+
 impl<'loc> std::fmt::Display for BlockGuardClause<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {{ ", self.query)?;
-        for each in &self.block.conjunctions {
-            let len = each.len();
-            for idx in 0..len - 2 {
-                write!(f, "{} or ", each[idx])?;
-            }
-            write!(f, "{}; ", each[len])?;
+        self.query.fmt(f)?;
+        if self.not_empty {
+            f.write_str(" !empty")?;
         }
-        write!(f, " }}")?;
-        Ok(())
+        write!(f, " {{ {} }}", self.block)
     }
 }
 
 impl<'loc> std::fmt::Display for GuardAccessClause<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {}",
-            if self.negation { "not" } else { "" },
-            self.access_clause
-        )?;
-        Ok(())
+        if self.negation {
+            f.write_str("not ")?;
+        }
+        self.access_clause.fmt(f)
     }
 }
 
 impl<'loc> std::fmt::Display for AccessClause<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {} {}",
-            self.query,
-            display_comparator(self.comparator),
-            match &self.compare_with {
-                Some(value) => format!("{}", value),
-                None => "".to_string(),
-            }
-        )?;
-        Ok(())
+        write!(f, "{} {}", self.query, display_comparator(self.comparator))?;
+        if let Some(with) = &self.compare_with {
+            write!(f, " {}", with)?;
+        }
+        write_message(f, &self.custom_message)
     }
 }
 
 impl<'loc> std::fmt::Display for AccessQuery<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", SliceDisplay(&self.query))?;
-        Ok(())
+        QueryPartsDisplay(self, 0..self.query.len()).fmt(f)
     }
 }
 
-impl<'loc> std::fmt::Display for FunctionExpr<'loc> {
+///
+/// Displays the parts of a query in the range as Guard source, the `some` keyword belongs to
+/// the first part
+///
+pub(crate) struct QueryPartsDisplay<'a, 'loc>(
+    pub(crate) &'a AccessQuery<'loc>,
+    pub(crate) std::ops::Range<usize>,
+);
+
+impl<'a, 'loc> std::fmt::Display for QueryPartsDisplay<'a, 'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}(", self.name)?;
-        let last_index = self.parameters.len() - 1;
-        for (idx, each_param) in self.parameters.iter().enumerate() {
-            write!(f, "{}", each_param)?;
-            if idx != last_index {
-                write!(f, ", ")?;
+        let QueryPartsDisplay(query, range) = self;
+        if !query.match_all && range.start == 0 {
+            f.write_str("some ")?;
+        }
+        let parts = &query.query;
+        for (idx, part) in parts.iter().enumerate().take(range.end).skip(range.start) {
+            match part {
+                QueryPart::This => f.write_str("this")?,
+                QueryPart::Key(key) if idx == 0 => {
+                    if key.starts_with('%') || (is_name(key) && !RESERVED.contains(&key.as_str())) {
+                        f.write_str(key)?;
+                    } else {
+                        f.write_str(&quoted(key))?;
+                    }
+                }
+                QueryPart::Key(key) => {
+                    if key.starts_with('%') || is_name(key) {
+                        write!(f, ".{}", key)?;
+                    } else {
+                        write!(f, "[{}]", quoted(key))?;
+                    }
+                }
+                //
+                // The parser adds `[*]` after a variable that is followed by other parts
+                //
+                QueryPart::AllIndices(None)
+                    if idx == 1 && parts.len() > 2 && parts[0].is_variable() => {}
+                QueryPart::AllIndices(None) => f.write_str("[*]")?,
+                QueryPart::AllIndices(Some(name)) => write!(f, "[{}]", name)?,
+                QueryPart::AllValues(None) => f.write_str(".*")?,
+                QueryPart::AllValues(Some(name)) => write!(f, "[ {} ]", name)?,
+                QueryPart::Index(index) => write!(f, "[{}]", index)?,
+                QueryPart::Filter(name, conjunctions) => {
+                    f.write_str("[ ")?;
+                    if let Some(name) = name {
+                        write!(f, "{} | ", name)?;
+                    }
+                    write!(f, "{} ]", ConjunctionsDisplay(conjunctions))?;
+                }
+                QueryPart::MapKeyFilter(name, filter) => {
+                    let comparator = match filter.comparator {
+                        (CmpOperator::In, negated) => (CmpOperator::In, negated),
+                        (_, negated) => (CmpOperator::Eq, negated),
+                    };
+                    f.write_str("[ ")?;
+                    if let Some(name) = name {
+                        write!(f, "{} | ", name)?;
+                    }
+                    write!(
+                        f,
+                        "keys {} {} ]",
+                        display_comparator(comparator),
+                        filter.compare_with
+                    )?;
+                }
             }
         }
-        write!(f, ")")?;
         Ok(())
     }
 }
 
+//
+// A name that can be written without quotes, letters followed by letters, digits or `_`
+//
+fn is_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+impl<'loc> std::fmt::Display for FunctionExpr<'loc> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_call(f, &self.name, &self.parameters)
+    }
+}
+
 impl<'loc> std::fmt::Display for LetValue<'loc> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            LetValue::AccessClause(acc) => acc.fmt(f)?,
-            LetValue::Value(v) => write!(f, "{}", ValueOnlyDisplay(v))?,
-            LetValue::FunctionCall(call_expr) => write!(f, "{}", call_expr)?,
+            LetValue::AccessClause(acc) => acc.fmt(f),
+            LetValue::Value(v) => f.write_str(&value_text(v)),
+            LetValue::FunctionCall(call_expr) => call_expr.fmt(f),
         }
-        Ok(())
     }
 }
 
-pub(crate) fn display_comparator(cmp: (CmpOperator, bool)) -> String {
-    let (op, not) = cmp;
-    format!("{}{} ", if not { "not " } else { "" }, op)
+impl<'loc> std::fmt::Display for LetExpr<'loc> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "let {} = {}", self.var, self.value)
+    }
+}
+
+///
+/// The Guard source for a comparison operator, unary operators are negated with `!`
+///
+pub(crate) fn display_comparator((operator, negated): (CmpOperator, bool)) -> String {
+    let unary = match operator {
+        CmpOperator::Eq => return if negated { "!=" } else { "==" }.to_string(),
+        CmpOperator::In => return if negated { "not in" } else { "in" }.to_string(),
+        CmpOperator::Gt => return ">".to_string(),
+        CmpOperator::Ge => return ">=".to_string(),
+        CmpOperator::Lt => return "<".to_string(),
+        CmpOperator::Le => return "<=".to_string(),
+        CmpOperator::Exists => "exists",
+        CmpOperator::Empty => "empty",
+        CmpOperator::IsString => "is_string",
+        CmpOperator::IsList => "is_list",
+        CmpOperator::IsMap => "is_struct",
+        CmpOperator::IsBool => "is_bool",
+        CmpOperator::IsInt => "is_int",
+    };
+    format!("{}{}", if negated { "!" } else { "" }, unary)
 }
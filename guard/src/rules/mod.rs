@@ -292,6 +292,13 @@ struct ParameterRuleResult<'value, 'loc> {
 pub(crate) trait RecordTracer<'value> {
     fn start_record(&mut self, context: &str) -> Result<()>;
     fn end_record(&mut self, context: &str, record: RecordType<'value>) -> Result<()>;
+    ///
+    /// Whether checks that pass are recorded with the values they checked, to explain an
+    /// evaluation. Otherwise only their success is recorded.
+    ///
+    fn explaining(&self) -> bool {
+        false
+    }
 }
 
 pub(crate) trait EvalContext<'value, 'loc: 'value>: RecordTracer<'value> {
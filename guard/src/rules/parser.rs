@@ -1348,7 +1348,7 @@ fn clauses(input: Span) -> IResult<Span, Conjunctions<GuardClause>> {
     )
 }
 
-fn let_assignment_expr(input: Span) -> IResult<Span, String> {
+pub(crate) fn let_assignment_expr(input: Span) -> IResult<Span, String> {
     let (input, _let_keyword) = tag("let")(input)?;
     let (input, (var_name, _eq_sign)) = tuple((
         //
@@ -1369,6 +1369,11 @@ fn let_assignment_expr(input: Span) -> IResult<Span, String> {
 }
 
 fn assignment(input: Span) -> IResult<Span, LetExpr> {
+    let location = FileLocation {
+        file_name: input.extra,
+        line: input.location_line(),
+        column: input.get_utf8_column() as u32,
+    };
     let (input, var_name) = let_assignment_expr(input)?;
 
     match parse_value(input) {
@@ -1377,6 +1382,7 @@ fn assignment(input: Span) -> IResult<Span, LetExpr> {
             LetExpr {
                 var: var_name,
                 value: LetValue::Value(PathAwareValue::try_from(value).unwrap()),
+                location,
             },
         )),
 
@@ -1392,6 +1398,7 @@ fn assignment(input: Span) -> IResult<Span, LetExpr> {
                 LetExpr {
                     var: var_name,
                     value: LetValue::AccessClause(access),
+                    location,
                 },
             ))
         }
@@ -1743,7 +1750,7 @@ where
 }
 
 #[derive(Clone, PartialEq, Debug)]
-enum Exprs<'loc> {
+pub(crate) enum Exprs<'loc> {
     Assignment(LetExpr<'loc>),
     DefaultTypeBlock(Disjunctions<TypeBlock<'loc>>),
     DefaultWhenBlock(WhenConditions<'loc>, Block<'loc, GuardClause<'loc>>),
@@ -1752,27 +1759,55 @@ enum Exprs<'loc> {
     ParameterizedRule(ParameterizedRule<'loc>),
 }
 
+//
+// A single top level expression in a rules file
+//
+fn expr(input: Span) -> IResult<Span, Exprs> {
+    alt((
+        map(assignment, Exprs::Assignment),
+        map(parameterized_rule_block, Exprs::ParameterizedRule),
+        map(rule_block, Exprs::Rule),
+        map(type_block_clauses, Exprs::DefaultTypeBlock),
+        when_block(single_clauses, alt((clause, rule_clause)), |c, b| {
+            Exprs::DefaultWhenBlock(
+                c,
+                Block {
+                    assignments: b.0,
+                    conjunctions: b.1,
+                },
+            )
+        }),
+        map(default_clauses, Exprs::DefaultClause),
+    ))(input)
+}
+
+///
+/// The top level expressions of a rules file in the order they appear, each with the location
+/// it starts at. `rules_file` groups these into assignments, the default rule and the named
+/// rules, this keeps them as they were written, e.g. for printing them back out.
+///
+pub(crate) fn rules_file_exprs(input: Span) -> Result<Vec<(FileLocation, Exprs)>, Error> {
+    let mut exprs = vec![];
+    let (mut rest, _) = zero_or_more_ws_or_comment(input)?;
+    while !rest.fragment().is_empty() {
+        let location = FileLocation {
+            file_name: rest.extra,
+            line: rest.location_line(),
+            column: rest.get_utf8_column() as u32,
+        };
+        let (remaining, each) = expr(rest)?;
+        exprs.push((location, each));
+        rest = zero_or_more_ws_or_comment(remaining)?.0;
+    }
+    Ok(exprs)
+}
+
 //
 // Rules File
 //
 pub(crate) fn rules_file(input: Span) -> Result<RulesFile, Error> {
     let exprs = all_consuming(fold_many1(
-        remove_whitespace_comments(alt((
-            map(assignment, Exprs::Assignment),
-            map(parameterized_rule_block, Exprs::ParameterizedRule),
-            map(rule_block, Exprs::Rule),
-            map(type_block_clauses, Exprs::DefaultTypeBlock),
-            when_block(single_clauses, alt((clause, rule_clause)), |c, b| {
-                Exprs::DefaultWhenBlock(
-                    c,
-                    Block {
-                        assignments: b.0,
-                        conjunctions: b.1,
-                    },
-                )
-            }),
-            map(default_clauses, Exprs::DefaultClause),
-        ))),
+        remove_whitespace_comments(expr),
         Vec::new(),
         |mut acc, expr| {
             acc.push(expr);
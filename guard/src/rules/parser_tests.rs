@@ -2277,6 +2277,11 @@ fn test_clauses() {
             },
             LetExpr {
                 var: String::from("x"),
+                location: FileLocation {
+                    line: 1,
+                    column: 1,
+                    file_name: "",
+                },
                 value: LetValue::Value(PathAwareValue::try_from(Value::Int(10)).unwrap())
             }
             )))]
@@ -2291,6 +2296,11 @@ fn test_clauses() {
             },
             LetExpr {
                 var: String::from("x"),
+                location: FileLocation {
+                    line: 1,
+                    column: 1,
+                    file_name: "",
+                },
                 value: LetValue::Value(PathAwareValue::try_from(Value::List(vec![
                                                                             Value::Int(10), Value::Int(20)
                 ])).unwrap())
@@ -2307,6 +2317,11 @@ fn test_clauses() {
             },
             LetExpr {
                 var: String::from("x"),
+                location: FileLocation {
+                    line: 1,
+                    column: 1,
+                    file_name: "",
+                },
                 value: LetValue::AccessClause(AccessQuery{ query: vec![
                     QueryPart::Key(String::from("engine"))], match_all: true })
             }
@@ -2322,6 +2337,11 @@ fn test_clauses() {
             },
             LetExpr {
                 var: String::from("engines"),
+                location: FileLocation {
+                    line: 1,
+                    column: 1,
+                    file_name: "",
+                },
                 value: LetValue::AccessClause(AccessQuery{ query: vec![
                     QueryPart::Key(String::from("%engines"))], match_all: true })
             }
@@ -2349,6 +2369,11 @@ fn test_clauses() {
             },
             LetExpr {
                 var: String::from("aurora_dbs"),
+                location: FileLocation {
+                    line: 1,
+                    column: 1,
+                    file_name: "",
+                },
                 value: LetValue::AccessClause(AccessQuery {
                     query: vec![
                         QueryPart::Key(String::from("resources")),
@@ -2400,6 +2425,11 @@ r##"let ENGINE_LOGS = {
         },
         LetExpr {
             var: String::from("ENGINE_LOGS"),
+            location: FileLocation {
+                line: 1,
+                column: 1,
+                file_name: "",
+            },
             value: LetValue::Value(PathAwareValue::try_from(r##"
         {
             'mariadb':       ["audit", "error", "general", "slowquery"],
@@ -2480,6 +2510,11 @@ fn test_type_block() {
                 block: Block {
                     assignments: vec![LetExpr {
                         var: String::from("keyName"),
+                        location: FileLocation {
+                            line: 2,
+                            column: 17,
+                            file_name: "",
+                        },
                         value: LetValue::AccessClause(AccessQuery {
                             query: vec![QueryPart::Key(String::from("keyName"))],
                             match_all: true,
@@ -2768,6 +2803,11 @@ fn test_rule_block() {
             block: Block {
                 assignments: vec![LetExpr {
                     var: String::from("ec2_instance_types"),
+                    location: FileLocation {
+                        line: 2,
+                        column: 5,
+                        file_name: "",
+                    },
                     value: LetValue::Value(
                         PathAwareValue::try_from(Value::List(vec![
                             Value::Regex("^t*".to_string()),
@@ -2859,6 +2899,11 @@ fn test_rule_block() {
                             block: Block {
                                 assignments: vec![LetExpr {
                                     var: "volumes".to_string(),
+                                    location: FileLocation {
+                                        line: 15,
+                                        column: 9,
+                                        file_name: "",
+                                    },
                                     value: LetValue::AccessClause(AccessQuery {
                                         query: vec![QueryPart::Key(
                                             "block_device_mappings".to_string(),
@@ -9,7 +9,10 @@ mod tests {
 
     use cfn_guard;
     use cfn_guard::commands::validate::Validate;
-    use cfn_guard::commands::{DATA, INPUT_PARAMETERS, RULES, VALIDATE};
+    use cfn_guard::commands::{
+        BASELINE, DATA, ERROR_STATUS_CODE, EVALUATION_ERROR_STATUS_CODE, EXCLUDE, FAIL_ON,
+        INPUT_PARAMETERS, JOBS, PARSING_ERROR_STATUS_CODE, RULES, VALIDATE, WRITE_BASELINE,
+    };
 
     use crate::utils;
 
@@ -196,6 +199,72 @@ mod tests {
         assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
     }
 
+    #[test]
+    fn test_explain_unknown_rule() {
+        let data_arg = utils::get_full_path_for_resource_file(
+            "resources/data-dir/s3-public-read-prohibited-template-non-compliant.yaml",
+        );
+        let rules_arg = utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_public_read_prohibited.guard",
+        );
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            "--explain",
+            "nosuch",
+        ];
+        assert_eq!(
+            ERROR_STATUS_CODE,
+            utils::cfn_guard_test_command(Validate::new(), args)
+        );
+    }
+
+    #[test]
+    fn test_single_data_file_single_rules_file_with_baseline() {
+        let data_arg = utils::get_full_path_for_resource_file(
+            "resources/data-dir/s3-public-read-prohibited-template-non-compliant.yaml",
+        );
+        let rules_arg = utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_public_read_prohibited.guard",
+        );
+        let baseline =
+            std::env::temp_dir().join(format!("functional-baseline-{}.json", std::process::id()));
+        let baseline_arg = baseline.display().to_string();
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let write_baseline_option = format!("--{}", WRITE_BASELINE.0);
+        let baseline_option = format!("--{}", BASELINE.0);
+
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &write_baseline_option,
+            &baseline_arg,
+        ];
+        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
+
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &baseline_option,
+            &baseline_arg,
+        ];
+        let status = utils::cfn_guard_test_command(Validate::new(), args);
+        std::fs::remove_file(&baseline).unwrap();
+        assert_eq!(0, status);
+    }
+
     #[test]
     fn test_data_dir_single_rules_file() {
         let data_arg = utils::get_full_path_for_resource_file("resources/data-dir/");
@@ -208,6 +277,50 @@ mod tests {
         assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
     }
 
+    #[test]
+    fn test_data_dir_rules_dir_with_jobs() {
+        let data_arg = utils::get_full_path_for_resource_file("resources/data-dir/");
+        let rules_arg = utils::get_full_path_for_resource_file("resources/rules-dir/");
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let jobs_option = format!("--{}", JOBS.0);
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &jobs_option,
+            "4",
+        ];
+        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
+    }
+
+    #[test]
+    fn test_data_glob_with_exclude_single_rules_file() {
+        let data_arg =
+            utils::get_full_path_for_resource_file("resources/data-dir/s3-public-read-*.yaml");
+        let rules_arg = utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_public_read_prohibited.guard",
+        );
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let exclude_option = format!("--{}", EXCLUDE.0);
+        let args = vec![VALIDATE, &data_option, &data_arg, &rules_option, &rules_arg];
+        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
+
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &exclude_option,
+            "*-non-compliant.yaml",
+        ];
+        assert_eq!(0, utils::cfn_guard_test_command(Validate::new(), args));
+    }
+
     #[test]
     fn test_single_data_file_rules_dir() {
         let data_arg = utils::get_full_path_for_resource_file(
@@ -474,8 +587,55 @@ mod tests {
         let data_option = format!("-{}", DATA.1);
         let rules_option = format!("-{}", RULES.1);
         let args = vec![VALIDATE, &data_option, &data_arg, &rules_option, &rules_arg];
-        // -1 status code equates to Error being thrown
-        assert_eq!(-1, utils::cfn_guard_test_command(Validate::new(), args));
+        assert_eq!(
+            EVALUATION_ERROR_STATUS_CODE,
+            utils::cfn_guard_test_command(Validate::new(), args)
+        );
+    }
+
+    #[test]
+    fn test_single_data_file_malformed_rules_file_fail_on_none() {
+        let data_arg = utils::get_full_path_for_resource_file(
+            "resources/s3-server-side-encryption-template-non-compliant-2.yaml",
+        );
+        let rules_arg = utils::get_full_path_for_resource_file("resources/malformed-rule.guard");
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let fail_on_option = format!("--{}", FAIL_ON.0);
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &fail_on_option,
+            "none",
+        ];
+        assert_eq!(0, utils::cfn_guard_test_command(Validate::new(), args));
+    }
+
+    #[test]
+    fn test_single_data_file_single_rules_file_fail_on_skip() {
+        let data_arg = utils::get_full_path_for_resource_file("resources/db_resource.yaml");
+        let rules_arg = utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_public_read_prohibited.guard",
+        );
+        let data_option = format!("-{}", DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let fail_on_option = format!("--{}", FAIL_ON.0);
+        let args = vec![VALIDATE, &data_option, &data_arg, &rules_option, &rules_arg];
+        assert_eq!(0, utils::cfn_guard_test_command(Validate::new(), args));
+
+        let args = vec![
+            VALIDATE,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rules_arg,
+            &fail_on_option,
+            "fail,skip",
+        ];
+        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
     }
 
     #[test]
@@ -516,7 +676,6 @@ mod tests {
 
     #[test]
     fn test_single_data_file_blank_rules_file() {
-        // The parsing exits with status code 5 = FAIL for allowing other rules to get evaluated even when one of them fails to get parsed
         let data_arg = utils::get_full_path_for_resource_file(
             "resources/s3-server-side-encryption-template-non-compliant-2.yaml",
         );
@@ -524,12 +683,15 @@ mod tests {
         let data_option = format!("-{}", DATA.1);
         let rules_option = format!("-{}", RULES.1);
         let args = vec![VALIDATE, &data_option, &data_arg, &rules_option, &rules_arg];
-        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
+        assert_eq!(
+            PARSING_ERROR_STATUS_CODE,
+            utils::cfn_guard_test_command(Validate::new(), args)
+        );
     }
 
     #[test]
     fn test_single_data_file_blank_and_valid_rules_file() {
-        // The parsing exits with status code 5 = FAIL for allowing other rules to get evaluated even when one of them fails to get parsed
+        // Parse errors take precedence over rule failures, other rules still get evaluated
         let data_arg = utils::get_full_path_for_resource_file(
             "resources/s3-server-side-encryption-template-non-compliant-2.yaml",
         );
@@ -548,7 +710,10 @@ mod tests {
             &rules_option,
             &rules_arg2,
         ];
-        assert_eq!(5, utils::cfn_guard_test_command(Validate::new(), args));
+        assert_eq!(
+            PARSING_ERROR_STATUS_CODE,
+            utils::cfn_guard_test_command(Validate::new(), args)
+        );
     }
 
     #[test]
@@ -661,7 +826,10 @@ mod tests {
 #[cfg(test)]
 mod test_test_command {
     use cfn_guard::commands::test::Test;
-    use cfn_guard::commands::{RULES, TEST, TEST_DATA};
+    use cfn_guard::commands::{
+        COVERAGE, ERROR_STATUS_CODE, FAIL_ON, PARSING_ERROR_STATUS_CODE, RULES, SNAPSHOT, TEST,
+        TEST_DATA, TEST_FAILURE_STATUS_CODE, TEST_FILTER, TEST_PATTERN, UPDATE_SNAPSHOTS,
+    };
     use cfn_guard::Error;
     use rstest::rstest;
 
@@ -704,4 +872,308 @@ mod test_test_command {
         assert_eq!(0, crate::utils::cfn_guard_test_command(Test::new(), args));
         Ok(())
     }
+
+    #[test]
+    fn test_test_data_file_with_detailed_expectations() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled_detailed.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![TEST, &data_option, &test_data_arg, &rules_option, &rule_arg];
+
+        assert_eq!(0, crate::utils::cfn_guard_test_command(Test::new(), args));
+    }
+
+    #[test]
+    fn test_test_data_file_with_detailed_expectations_and_previous_engine() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled_detailed.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![
+            TEST,
+            &data_option,
+            &test_data_arg,
+            &rules_option,
+            &rule_arg,
+            "--previous-engine",
+        ];
+
+        assert_eq!(
+            ERROR_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Test::new(), args)
+        );
+    }
+
+    #[test]
+    fn test_test_data_file_with_input_file_fixtures() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled_fixtures.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![TEST, &data_option, &test_data_arg, &rules_option, &rule_arg];
+
+        assert_eq!(0, crate::utils::cfn_guard_test_command(Test::new(), args));
+    }
+
+    #[test]
+    fn test_test_data_file_continues_after_missing_input_file() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled_missing_input.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![TEST, &data_option, &test_data_arg, &rules_option, &rule_arg];
+
+        assert_eq!(
+            ERROR_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Test::new(), args)
+        );
+    }
+
+    #[test]
+    fn test_test_data_file_with_coverage() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+        );
+        let lcov =
+            std::env::temp_dir().join(format!("functional-coverage-{}.lcov", std::process::id()));
+        let lcov_arg = lcov.display().to_string();
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let coverage_option = format!("--{}", COVERAGE.0);
+
+        let args = vec![
+            TEST,
+            &data_option,
+            &test_data_arg,
+            &rules_option,
+            &rule_arg,
+            &coverage_option,
+            &lcov_arg,
+        ];
+
+        assert_eq!(0, crate::utils::cfn_guard_test_command(Test::new(), args));
+        let content = std::fs::read_to_string(&lcov).unwrap();
+        std::fs::remove_file(&lcov).unwrap();
+        assert!(content.starts_with(&format!("TN:\nSF:{}\n", rule_arg)));
+        assert!(content.contains("FNDA:"));
+        assert!(content.ends_with("end_of_record\n"));
+    }
+
+    #[test]
+    fn test_data_dir_snapshots() {
+        let dir = std::env::temp_dir().join(format!("functional-snapshots-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rule_arg = dir.join("s3.guard").display().to_string();
+        std::fs::copy(
+            crate::utils::get_full_path_for_resource_file(
+                "resources/rules-dir/s3_bucket_server_side_encryption_enabled.guard",
+            ),
+            &rule_arg,
+        )
+        .unwrap();
+        let data_arg = crate::utils::get_full_path_for_resource_file("resources/data-dir/");
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let snapshot_option = format!("--{}", SNAPSHOT.0);
+        let update_option = format!("--{}", UPDATE_SNAPSHOTS.0);
+        let args = vec![
+            TEST,
+            &data_option,
+            &data_arg,
+            &rules_option,
+            &rule_arg,
+            &snapshot_option,
+        ];
+
+        let missing = crate::utils::cfn_guard_test_command(Test::new(), args.clone());
+        let mut update = args.clone();
+        update.push(&update_option);
+        let updated = crate::utils::cfn_guard_test_command(Test::new(), update);
+        let matched = crate::utils::cfn_guard_test_command(Test::new(), args);
+        let written = dir
+            .join("snapshots/s3/s3-server-side-encryption-template-compliant.yaml.json")
+            .exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(TEST_FAILURE_STATUS_CODE, missing);
+        assert_eq!(0, updated);
+        assert_eq!(0, matched);
+        assert!(written);
+    }
+
+    #[test]
+    fn test_dir_with_test_pattern_and_filter() {
+        let dir_arg = crate::utils::get_full_path_for_resource_file("resources/");
+        let pattern_option = format!("--{}", TEST_PATTERN.0);
+        let filter_option = format!("--{}", TEST_FILTER.0);
+        let fail_on_option = format!("--{}", FAIL_ON.0);
+        let args = vec![
+            TEST,
+            "--dir",
+            &dir_arg,
+            &pattern_option,
+            "test-data-dir/{stem}.yaml",
+            &filter_option,
+            "KMS",
+        ];
+        assert_eq!(
+            0,
+            crate::utils::cfn_guard_test_command(Test::new(), args.clone())
+        );
+
+        let mut untested = args;
+        untested.push(&fail_on_option);
+        untested.push("fail,untested,error");
+        assert_eq!(
+            TEST_FAILURE_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Test::new(), untested)
+        );
+    }
+
+    #[test]
+    fn test_test_data_file_blank_rules_file() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/test-data-dir/s3_bucket_server_side_encryption_enabled.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file("resources/blank-rule.guard");
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![TEST, &data_option, &test_data_arg, &rules_option, &rule_arg];
+
+        assert_eq!(
+            PARSING_ERROR_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Test::new(), args)
+        );
+    }
+}
+
+#[cfg(test)]
+mod fuzz_command_tests {
+    use cfn_guard::commands::fuzz::Fuzz;
+    use cfn_guard::commands::{
+        ERROR_STATUS_CODE, FUZZ, MAX_INPUTS, RULES, TEST_DATA, TEST_FAILURE_STATUS_CODE,
+    };
+
+    #[test]
+    fn test_fuzz_reports_contradicted_expectations() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/fuzz-dir/s3_bucket_logging_enabled_fuzz.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/fuzz-dir/s3_bucket_logging_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+        let max_inputs_option = format!("--{}", MAX_INPUTS.0);
+
+        let args = vec![FUZZ, &data_option, &test_data_arg, &rules_option, &rule_arg];
+        assert_eq!(
+            TEST_FAILURE_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Fuzz::new(), args.clone())
+        );
+
+        let mut unmodified = args;
+        unmodified.push(&max_inputs_option);
+        unmodified.push("0");
+        assert_eq!(
+            0,
+            crate::utils::cfn_guard_test_command(Fuzz::new(), unmodified)
+        );
+    }
+
+    #[test]
+    fn test_fuzz_continues_after_test_case_errors() {
+        let test_data_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/fuzz-dir/missing_input_fuzz.yaml",
+        );
+        let rule_arg = crate::utils::get_full_path_for_resource_file(
+            "resources/fuzz-dir/s3_bucket_logging_enabled.guard",
+        );
+        let data_option = format!("-{}", TEST_DATA.1);
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![FUZZ, &data_option, &test_data_arg, &rules_option, &rule_arg];
+        assert_eq!(
+            ERROR_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Fuzz::new(), args)
+        );
+    }
+}
+
+#[cfg(test)]
+mod fmt_command_tests {
+    use cfn_guard::commands::fmt::Fmt;
+    use cfn_guard::commands::{CHECK, FAILURE_STATUS_CODE, FMT, RULES, SUCCESS_STATUS_CODE};
+
+    #[test]
+    fn test_fmt_check_reports_unformatted_rules() {
+        let formatted_arg =
+            crate::utils::get_full_path_for_resource_file("resources/fmt-dir/formatted.guard");
+        let dir_arg = crate::utils::get_full_path_for_resource_file("resources/fmt-dir/");
+        let rules_option = format!("-{}", RULES.1);
+        let check_option = format!("--{}", CHECK.0);
+
+        let args = vec![FMT, &check_option, &rules_option, &formatted_arg];
+        assert_eq!(
+            SUCCESS_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Fmt::new(), args)
+        );
+
+        let args = vec![FMT, &check_option, &rules_option, &dir_arg];
+        assert_eq!(
+            FAILURE_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Fmt::new(), args)
+        );
+    }
+}
+
+#[cfg(test)]
+mod lint_command_tests {
+    use cfn_guard::commands::lint::Lint;
+    use cfn_guard::commands::{FAILURE_STATUS_CODE, LINT, RULES, SUCCESS_STATUS_CODE};
+
+    #[test]
+    fn test_lint_fails_only_on_errors() {
+        let warnings_arg =
+            crate::utils::get_full_path_for_resource_file("resources/lint-dir/warnings.guard");
+        let dir_arg = crate::utils::get_full_path_for_resource_file("resources/lint-dir/");
+        let rules_option = format!("-{}", RULES.1);
+
+        let args = vec![LINT, &rules_option, &warnings_arg];
+        assert_eq!(
+            SUCCESS_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Lint::new(), args)
+        );
+
+        let args = vec![LINT, &rules_option, &dir_arg];
+        assert_eq!(
+            FAILURE_STATUS_CODE,
+            crate::utils::cfn_guard_test_command(Lint::new(), args)
+        );
+    }
 }